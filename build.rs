@@ -32,6 +32,97 @@ fn compute_k_table() -> [f64; 64] {
         .unwrap()
 }
 
+fn compute_atanh_table() -> [f64; 64] {
+    (1..=64)
+        .map(|i| f64::atanh(f64::powf(2.0, -i as f64)))
+        .collect::<Vec<f64>>()
+        .try_into()
+        .unwrap()
+}
+
+fn compute_kh_table() -> [f64; 64] {
+    // Iterations 4, 13, 40, ... are repeated to ensure convergence
+    let mut k = 1.0;
+    let mut repeat = 4;
+    (1..=64)
+        .map(|i| {
+            let f = 1.0 / f64::sqrt(1.0 - f64::powf(2.0, -2.0 * i as f64));
+            k *= f;
+            if i == repeat {
+                k *= f;
+                repeat = 3 * repeat + 1;
+            }
+            k
+        })
+        .collect::<Vec<f64>>()
+        .try_into()
+        .unwrap()
+}
+
+/// Base 2 logarithm of the size of the fixed-point tables
+const FIXED_LOG2_N: usize = 12;
+
+/// Round to the nearest fixed-point value, with the given number of fractional bits
+fn to_fixed(x: f64, frac_bits: u32) -> i64 {
+    let max = (1i64 << frac_bits) - 1;
+    let min = -(1i64 << frac_bits);
+    ((x * (1i64 << frac_bits) as f64).round() as i64).clamp(min, max)
+}
+
+/// Hashes a value into a uniformly distributed pseudo-random number, like the fixed-point types
+fn dither(x: u64) -> u64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Round to one of the two nearest fixed-point values at random, the nearest one being the most likely
+///
+/// The probability of rounding up is the fractional part, so that the errors average out.
+fn to_fixed_dithered(x: f64, frac_bits: u32, seed: u64) -> i64 {
+    let max = (1i64 << frac_bits) - 1;
+    let min = -(1i64 << frac_bits);
+    let x = x * (1i64 << frac_bits) as f64;
+    let u = (dither(seed) >> 11) as f64 / (1u64 << 53) as f64;
+    let bits = if x - x.floor() > u { x.floor() as i64 + 1 } else { x.floor() as i64 };
+    bits.clamp(min, max)
+}
+
+fn compute_sine_table(n: usize) -> Vec<f64> {
+    (0..n / 4)
+        .map(|i| f64::sin(2.0 * std::f64::consts::PI * i as f64 / n as f64))
+        .collect()
+}
+
+fn compute_window_table(n: usize, a: &[f64]) -> Vec<f64> {
+    // Periodic windows are symmetric, only the first half is required
+    (0..=n / 2)
+        .map(|i| {
+            let theta = 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+            a.iter()
+                .enumerate()
+                .map(|(k, a)| if k % 2 == 0 { *a } else { -*a } * f64::cos(k as f64 * theta))
+                .sum()
+        })
+        .collect()
+}
+
+fn write_fixed_table(f: &mut File, name: &str, table: &[f64]) {
+    let q15 = table.iter().map(|&x| to_fixed(x, 15) as i16).collect::<Vec<_>>();
+    let q31 = table.iter().map(|&x| to_fixed(x, 31) as i32).collect::<Vec<_>>();
+    writeln!(f, "const {}_Q15: [i16; {}] = {:?};", name, q15.len(), q15).unwrap();
+    writeln!(f, "const {}_Q31: [i32; {}] = {:?};", name, q31.len(), q31).unwrap();
+}
+
+fn write_dithered_table(f: &mut File, name: &str, table: &[f64], n: usize) {
+    // Seeded like the tables that the fixed-point types compute for the sizes that are not generated
+    let seed = |i: usize| ((n << 32) | i) as u64;
+    let q15 = table.iter().enumerate().map(|(i, &x)| to_fixed_dithered(x, 15, seed(i)) as i16).collect::<Vec<_>>();
+    let q31 = table.iter().enumerate().map(|(i, &x)| to_fixed_dithered(x, 31, seed(i)) as i32).collect::<Vec<_>>();
+    writeln!(f, "const {}_DITHERED_Q15: [i16; {}] = {:?};", name, q15.len(), q15).unwrap();
+    writeln!(f, "const {}_DITHERED_Q31: [i32; {}] = {:?};", name, q31.len(), q31).unwrap();
+}
+
 /******************************************************************************/
 
 fn main() {
@@ -56,4 +147,40 @@ fn main() {
         k_table
     )
     .unwrap();
+    let atanh_table = compute_atanh_table();
+    writeln!(
+        &mut f,
+        "const ATANH_TABLE: [f64; {}] = {:?};",
+        atanh_table.len(),
+        atanh_table
+    )
+    .unwrap();
+    let kh_table = compute_kh_table();
+    writeln!(
+        &mut f,
+        "const KH_TABLE: [f64; {}] = {:?};",
+        kh_table.len(),
+        kh_table
+    )
+    .unwrap();
+
+    let n = 1 << FIXED_LOG2_N;
+    let dest_path = Path::new(&out_dir).join("fixed_twiddles.rs");
+    let mut f = File::create(dest_path).unwrap();
+    writeln!(&mut f, "const FIXED_TABLE_N: usize = {};", n).unwrap();
+    let sine_table = compute_sine_table(n);
+    write_fixed_table(&mut f, "SINE", &sine_table);
+    write_dithered_table(&mut f, "SINE", &sine_table, n);
+
+    let dest_path = Path::new(&out_dir).join("fixed_windows.rs");
+    let mut f = File::create(dest_path).unwrap();
+    writeln!(&mut f, "const FIXED_TABLE_N: usize = {};", n).unwrap();
+    write_fixed_table(&mut f, "HANN", &compute_window_table(n, &[0.5, 0.5]));
+    write_fixed_table(&mut f, "HAMMING", &compute_window_table(n, &[0.54, 0.46]));
+    write_fixed_table(&mut f, "BLACKMAN", &compute_window_table(n, &[0.42, 0.5, 0.08]));
+    write_fixed_table(
+        &mut f,
+        "FLAT_TOP",
+        &compute_window_table(n, &[0.21557895, 0.41663158, 0.277263158, 0.083578947, 0.006947368]),
+    );
 }
@@ -0,0 +1,214 @@
+/* embfft | adaptive.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Frequency-domain adaptive filtering
+//!
+//! A time-domain LMS filter of L taps costs 2 × L multiplications per sample, which is too slow on a
+//! microcontroller for the long filters of echo cancellation or active noise control. The frequency-domain
+//! block LMS algorithm (FBLMS) filters and adapts blocks of N / 2 samples with a few FFTs, for a cost that
+//! grows with log2(N) instead.
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::{EmbFft, EmbIfft, Error, Status};
+
+/******************************************************************************/
+
+/// Frequency-domain adaptive filter of N / 2 taps
+///
+/// Each block of N / 2 reference samples is transformed together with the previous block (overlap-save),
+/// filtered by the weight spectrum, and subtracted from the desired signal. The error then updates the
+/// weights, with a step normalized by the power of each bin, and constrained to N / 2 taps so that the
+/// filter stays linear rather than circular.
+/// For example:
+/// ```
+/// use embfft::adaptive::AdaptiveFilter;
+///
+/// // Echo of the reference, delayed by 2 samples and halved
+/// let mut filter = AdaptiveFilter::<f32, 16>::new(0.5, 0.5).unwrap();
+/// let reference: [(f32, f32); 8] = core::array::from_fn(|i| ((i % 3) as f32 - 1.0, 0.0));
+/// let mut microphone: [(f32, f32); 8] = core::array::from_fn(|i| (0.5 * reference[(i + 6) % 8].0, 0.0));
+///
+/// // The microphone signal is replaced by the residual echo
+/// filter.process(&reference, &mut microphone).unwrap();
+/// ```
+pub struct AdaptiveFilter<T, const N: usize> {
+    weights: [(T, T); N],
+    power: [T; N],
+    frame: [(T, T); N],
+    step: T,
+    alpha: T
+}
+
+impl<T: Float<N>, const N: usize> AdaptiveFilter<T, N> {
+    /// Initializes the filter, with all its weights cleared
+    ///
+    /// `step` is the normalized step size, and `alpha` the weight of each new block in the power estimate
+    /// of the bins, both between 0 and 1.
+    /// Fails with [`Error::InvalidParameter`] if either is out of range.
+    pub fn new(step: T, alpha: T) -> Result<Self, Error> {
+        if step <= T::ZERO || step > T::ONE || alpha <= T::ZERO || alpha > T::ONE {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            weights: [(T::ZERO, T::ZERO); N],
+            power: [T::ZERO; N],
+            frame: [(T::ZERO, T::ZERO); N],
+            step,
+            alpha
+        })
+    }
+
+    /// Clears the weights, the power estimate and the history
+    pub fn reset(&mut self) {
+        self.weights = [(T::ZERO, T::ZERO); N];
+        self.power = [T::ZERO; N];
+        self.frame = [(T::ZERO, T::ZERO); N];
+    }
+
+    /// Filters the next block of N / 2 reference samples, and adapts the weights
+    ///
+    /// The `desired` block is replaced in place by the error, i.e. the desired signal minus the filtered
+    /// reference. This is a blocking function, which performs three FFTs and two IFFTs, and returns the
+    /// status flags of the processing.
+    /// Fails with [`Error::InvalidLength`] if either block length is not N / 2.
+    pub fn process(&mut self, reference: &[(T, T)], desired: &mut [(T, T)]) -> Result<Status, Error> {
+        self.run(reference, desired, true)
+    }
+
+    /// Filters the next block of N / 2 reference samples, without adapting the weights
+    ///
+    /// This is typically used while the desired signal contains more than the echo, such as during double
+    /// talk. Otherwise, this function behaves as [`AdaptiveFilter::process()`].
+    pub fn filter(&mut self, reference: &[(T, T)], desired: &mut [(T, T)]) -> Result<Status, Error> {
+        self.run(reference, desired, false)
+    }
+
+    /// Returns the impulse response of the filter, whose N / 2 first samples are the taps
+    ///
+    /// This is a blocking function, which performs an IFFT.
+    pub fn impulse_response(&self) -> [(T, T); N] {
+        let mut response = self.weights;
+        EmbIfft::new_unordered(&mut response).ifft();
+        response
+    }
+
+    fn run(&mut self, reference: &[(T, T)], desired: &mut [(T, T)], adapt: bool) -> Result<Status, Error> {
+        let half = N / 2;
+        if reference.len() != half || desired.len() != half {
+            return Err(Error::InvalidLength);
+        }
+
+        // Overlap-save: the previous block followed by the new one
+        self.frame.copy_within(half.., 0);
+        self.frame[half..].copy_from_slice(reference);
+        let mut spectrum = self.frame;
+        let mut fft = EmbFft::new_unordered(&mut spectrum);
+        fft.fft();
+        let mut status = fft.status();
+
+        // Filtering, only the last N / 2 samples of the circular convolution are valid
+        let mut output = [(T::ZERO, T::ZERO); N];
+        for ((y, x), w) in output.iter_mut().zip(spectrum.iter()).zip(self.weights.iter()) {
+            *y = (x.0 * w.0 - x.1 * w.1, x.0 * w.1 + x.1 * w.0);
+        }
+        let mut ifft = EmbIfft::new_unordered(&mut output);
+        ifft.ifft();
+        status |= ifft.status();
+        for (d, y) in desired.iter_mut().zip(output[half..].iter()) {
+            *d = (d.0 - y.0, d.1 - y.1);
+            status |= d.0.status() | d.1.status();
+        }
+        if !adapt {
+            return Ok(status);
+        }
+
+        // Gradient: correlation of the error with the reference, normalized by the power of each bin
+        let mut gradient = [(T::ZERO, T::ZERO); N];
+        gradient[half..].copy_from_slice(desired);
+        let mut fft = EmbFft::new_unordered(&mut gradient);
+        fft.fft();
+        status |= fft.status();
+        for ((g, x), p) in gradient.iter_mut().zip(spectrum.iter()).zip(self.power.iter_mut()) {
+            *p = *p + self.alpha * (x.0 * x.0 + x.1 * x.1 - *p);
+            let e = *g;
+            *g = if *p > T::ZERO {
+                ((x.0 * e.0 + x.1 * e.1) / *p, (x.0 * e.1 - x.1 * e.0) / *p)
+            } else {
+                (T::ZERO, T::ZERO)
+            };
+        }
+
+        // Constraint: the gradient is limited to the N / 2 first taps
+        let mut ifft = EmbIfft::new_unordered(&mut gradient);
+        ifft.ifft();
+        status |= ifft.status();
+        gradient[half..].fill((T::ZERO, T::ZERO));
+        let mut fft = EmbFft::new_unordered(&mut gradient);
+        fft.fft();
+        status |= fft.status();
+        for (w, g) in self.weights.iter_mut().zip(gradient.iter()) {
+            *w = (w.0 + self.step * g.0, w.1 + self.step * g.1);
+            status |= w.0.status() | w.1.status();
+        }
+        Ok(status)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth::Lfsr;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_errors() {
+        assert!(AdaptiveFilter::<f32, 16>::new(0.0, 0.5).is_err());
+        assert!(AdaptiveFilter::<f32, 16>::new(0.5, 1.5).is_err());
+        let mut filter = AdaptiveFilter::<f32, 16>::new(0.5, 0.5).unwrap();
+        assert_eq!(filter.process(&[(0.0, 0.0); 8], &mut [(0.0, 0.0); 7]), Err(Error::InvalidLength));
+        assert_eq!(filter.filter(&[(0.0, 0.0); 16], &mut [(0.0, 0.0); 8]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_identification() {
+        // Unknown echo path of 6 taps, identified by a filter of 8 taps
+        let path = [0.0, 0.8, -0.4, 0.0, 0.2, 0.1];
+        let mut filter = AdaptiveFilter::<f64, 16>::new(0.5, 0.2).unwrap();
+        let mut lfsr = Lfsr::new(1);
+        let mut history = [0.0; 6];
+        let mut residual = 0.0;
+        for _ in 0..200 {
+            let mut reference = [(0.0, 0.0); 8];
+            let mut desired = [(0.0, 0.0); 8];
+            for (x, d) in reference.iter_mut().zip(desired.iter_mut()) {
+                history.copy_within(..5, 1);
+                history[0] = lfsr.uniform();
+                *x = (history[0], 0.0);
+                *d = (path.iter().zip(history.iter()).map(|(h, x)| h * x).sum(), 0.0);
+            }
+            assert!(!filter.process(&reference, &mut desired).unwrap().is_corrupted());
+            residual = desired.iter().map(|e| e.0 * e.0 + e.1 * e.1).sum();
+        }
+        assert!(residual < 1e-12, "residual echo {residual}");
+
+        let response = filter.impulse_response();
+        for (i, h) in response[..8].iter().enumerate() {
+            assert_abs_diff_eq!(h.0, path.get(i).copied().unwrap_or(0.0), epsilon = 1e-6);
+            assert_abs_diff_eq!(h.1, 0.0, epsilon = 1e-6);
+        }
+
+        // Without adaptation, the weights are kept
+        let mut desired = [(1.0, 0.0); 8];
+        filter.filter(&[(0.0, 0.0); 8], &mut desired).unwrap();
+        assert_eq!(filter.impulse_response(), response);
+
+        filter.reset();
+        assert_eq!(filter.impulse_response(), [(0.0, 0.0); 16]);
+    }
+}
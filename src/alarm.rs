@@ -0,0 +1,254 @@
+/* embfft | alarm.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Spectrum deviation alarms
+//!
+//! Predictive maintenance learns the spectrum of a healthy machine, then watches the band levels for
+//! deviations, which reveal wear long before a failure.
+
+/******************************************************************************/
+
+use crate::band::band_power_excluding;
+use crate::common::Float;
+use crate::error::Error;
+use crate::mask::ExclusionMask;
+use crate::math;
+use crate::units::{Hertz, SampleRate};
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Comparator of the band levels of successive spectra with a learned baseline, with hysteresis
+///
+/// The spectra are those of real signals, in natural order, computed with the window `W`, like those of
+/// the [`band`](crate::band) functions. The alarm of a band is raised once its level deviates from the
+/// baseline by more than `on` dB, in either direction, for `attack` successive spectra, and cleared once
+/// the deviation falls below `off` dB for `release` successive spectra.
+///
+/// For example:
+/// ```
+/// use embfft::alarm::SpectrumAlarm;
+/// use embfft::units::{Hertz, SampleRate};
+/// use embfft::window::Rectangular;
+///
+/// let bands = [(Hertz(0.0), Hertz(2.0)), (Hertz(2.0), Hertz(4.0))];
+/// let mut alarm = SpectrumAlarm::new(bands, SampleRate::new(8.0).unwrap(), 6.0, 3.0).unwrap();
+/// let mut data = [(1.0f32, 0.0); 8];
+/// alarm.learn::<f32, Rectangular, 8>(&data);
+/// assert!(!alarm.update::<f32, Rectangular, 8>(&data).unwrap());
+///
+/// data[1] = (4.0, 0.0);
+/// assert!(alarm.update::<f32, Rectangular, 8>(&data).unwrap());
+/// assert_eq!(alarm.alarms(), &[true, false]);
+/// ```
+pub struct SpectrumAlarm<const B: usize> {
+    bands: [(Hertz, Hertz); B],
+    sample_rate: SampleRate,
+    on: f64,
+    off: f64,
+    attack: usize,
+    release: usize,
+    baseline: [f64; B],
+    learned: usize,
+    deltas: [f64; B],
+    alarms: [bool; B],
+    counts: [usize; B]
+}
+
+impl<const B: usize> SpectrumAlarm<B> {
+    /// Initializes a comparator for B bands, without any baseline
+    ///
+    /// `on` and `off` are the deviations that raise and clear the alarms, in dB. Fails with
+    /// [`Error::InvalidParameter`] if a band is not between 0 and the Nyquist frequency, or if `off` is
+    /// greater than `on`.
+    pub fn new(bands: [(Hertz, Hertz); B], sample_rate: SampleRate, on: f64, off: f64) -> Result<Self, Error> {
+        if bands.iter().any(|(lo, hi)| lo.0.is_nan() || *lo < Hertz(0.0) || lo > hi || *hi > sample_rate.nyquist()) {
+            return Err(Error::InvalidParameter);
+        }
+        if on.is_nan() || off.is_nan() || off > on {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            bands,
+            sample_rate,
+            on,
+            off,
+            attack: 1,
+            release: 1,
+            baseline: [0.0; B],
+            learned: 0,
+            deltas: [0.0; B],
+            alarms: [false; B],
+            counts: [0; B]
+        })
+    }
+
+    /// Sets the number of successive spectra required to raise or clear an alarm
+    ///
+    /// Values of 0 are treated as 1.
+    pub fn with_hold(mut self, attack: usize, release: usize) -> Self {
+        self.attack = attack.max(1);
+        self.release = release.max(1);
+        self
+    }
+
+    /// Forgets the baseline and the alarms
+    pub fn reset(&mut self) {
+        self.baseline = [0.0; B];
+        self.learned = 0;
+        self.clear();
+    }
+
+    /// Clears the alarms, keeping the baseline
+    pub fn clear(&mut self) {
+        self.deltas = [0.0; B];
+        self.alarms = [false; B];
+        self.counts = [0; B];
+    }
+
+    /// Adds a spectrum of the healthy state to the baseline, which is the mean power of each band
+    pub fn learn<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N]) {
+        self.learn_excluding::<T, W, N>(spectrum, &ExclusionMask::new());
+    }
+
+    /// Adds a spectrum of the healthy state to the baseline, without the bins excluded by a mask
+    ///
+    /// The same mask must be given to [`SpectrumAlarm::update_excluding()`].
+    pub fn learn_excluding<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N], mask: &ExclusionMask<N>) {
+        self.learned += 1;
+        let weight = 1.0 / self.learned as f64;
+        for (baseline, (lo, hi)) in self.baseline.iter_mut().zip(self.bands.iter()) {
+            let power = band_power_excluding::<T, W, N>(spectrum, *lo, *hi, self.sample_rate, mask).unwrap_or(0.0);
+            *baseline += weight * (power - *baseline);
+        }
+    }
+
+    /// Returns the number of spectra learned in the baseline
+    pub fn learned(&self) -> usize {
+        self.learned
+    }
+
+    /// Compares a new spectrum to the baseline, and returns whether any alarm is raised
+    ///
+    /// Fails with [`Error::NotDone`] if no baseline was learned.
+    pub fn update<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N]) -> Result<bool, Error> {
+        self.update_excluding::<T, W, N>(spectrum, &ExclusionMask::new())
+    }
+
+    /// Compares a new spectrum to the baseline, without the bins excluded by a mask
+    ///
+    /// Fails like [`SpectrumAlarm::update()`].
+    pub fn update_excluding<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N], mask: &ExclusionMask<N>) -> Result<bool, Error> {
+        if self.learned == 0 {
+            return Err(Error::NotDone);
+        }
+        for b in 0..B {
+            let (lo, hi) = self.bands[b];
+            let power = band_power_excluding::<T, W, N>(spectrum, lo, hi, self.sample_rate, mask).unwrap_or(0.0);
+            let delta = math::power_to_db(power / self.baseline[b]);
+            let deviation = if delta < 0.0 { -delta } else { delta };
+            self.deltas[b] = delta;
+
+            // Count the successive spectra that would change the state
+            let (change, hold) = if self.alarms[b] { (deviation < self.off, self.release) } else { (deviation > self.on, self.attack) };
+            self.counts[b] = if change { self.counts[b] + 1 } else { 0 };
+            if self.counts[b] >= hold {
+                self.alarms[b] = !self.alarms[b];
+                self.counts[b] = 0;
+            }
+        }
+        Ok(self.alarms.iter().any(|&alarm| alarm))
+    }
+
+    /// Returns the alarm state of each band
+    pub fn alarms(&self) -> &[bool; B] {
+        &self.alarms
+    }
+
+    /// Returns the last deviation of each band from the baseline, in dB
+    pub fn deltas(&self) -> &[f64; B] {
+        &self.deltas
+    }
+
+    /// Returns the baseline power of each band
+    pub fn baseline(&self) -> &[f64; B] {
+        &self.baseline
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::Rectangular;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_alarm() {
+        let fs = SampleRate::new(16.0).unwrap();
+        let bands = [(Hertz(0.5), Hertz(2.5)), (Hertz(4.5), Hertz(6.5))];
+        assert!(SpectrumAlarm::new([(Hertz(0.0), Hertz(9.0))], fs, 6.0, 3.0).is_err());
+        assert!(SpectrumAlarm::new(bands, fs, 3.0, 6.0).is_err());
+
+        let mut alarm = SpectrumAlarm::new(bands, fs, 6.0, 3.0).unwrap().with_hold(2, 2);
+        let spectrum = |a: f64, b: f64| {
+            let mut data = [(0.0f64, 0.0); 16];
+            (data[2], data[14]) = ((a, 0.0), (a, 0.0));
+            (data[5], data[11]) = ((b, 0.0), (b, 0.0));
+            data
+        };
+        assert_eq!(alarm.update::<f64, Rectangular, 16>(&spectrum(1.0, 1.0)), Err(Error::NotDone));
+        alarm.learn::<f64, Rectangular, 16>(&spectrum(1.0, 1.0));
+        alarm.learn::<f64, Rectangular, 16>(&spectrum(3.0, 1.0));
+        assert_eq!(alarm.learned(), 2);
+
+        // A deviation of 10 dB in the first band, which must persist for 2 spectra
+        let louder = spectrum(50f64.sqrt(), 1.0);
+        assert!(!alarm.update::<f64, Rectangular, 16>(&louder).unwrap());
+        assert_abs_diff_eq!(alarm.deltas()[0], 10.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(alarm.deltas()[1], 0.0, epsilon = 1e-9);
+        assert!(alarm.update::<f64, Rectangular, 16>(&louder).unwrap());
+        assert_eq!(alarm.alarms(), &[true, false]);
+
+        // The hysteresis keeps the alarm while the deviation is above 3 dB
+        let lower = spectrum((5.0 * 10f64.powf(0.4)).sqrt(), 1.0);
+        for _ in 0..3 {
+            assert!(alarm.update::<f64, Rectangular, 16>(&lower).unwrap());
+        }
+        let baseline = spectrum(5f64.sqrt(), 1.0);
+        assert!(alarm.update::<f64, Rectangular, 16>(&baseline).unwrap());
+        assert!(!alarm.update::<f64, Rectangular, 16>(&baseline).unwrap());
+
+        // A loss of level is a deviation as well
+        let quieter = spectrum(5f64.sqrt(), 0.1);
+        alarm.update::<f64, Rectangular, 16>(&quieter).unwrap();
+        assert!(alarm.update::<f64, Rectangular, 16>(&quieter).unwrap());
+        assert_eq!(alarm.alarms(), &[false, true]);
+        assert_abs_diff_eq!(alarm.deltas()[1], -20.0, epsilon = 1e-9);
+
+        alarm.reset();
+        assert_eq!(alarm.learned(), 0);
+        assert_eq!(alarm.alarms(), &[false, false]);
+    }
+
+    #[test]
+    fn test_mask() {
+        let fs = SampleRate::new(16.0).unwrap();
+        let mut alarm = SpectrumAlarm::new([(Hertz(0.5), Hertz(4.5))], fs, 6.0, 3.0).unwrap();
+        let mut mask = ExclusionMask::new();
+        mask.exclude_frequency(Hertz(3.0), fs, 0).unwrap();
+        let mut data = [(0.0f64, 0.0); 16];
+        (data[2], data[14]) = ((1.0, 0.0), (1.0, 0.0));
+        alarm.learn_excluding::<f64, Rectangular, 16>(&data, &mask);
+
+        // A spur appearing in the excluded bin does not raise the alarm
+        (data[3], data[13]) = ((10.0, 0.0), (10.0, 0.0));
+        assert!(!alarm.update_excluding::<f64, Rectangular, 16>(&data, &mask).unwrap());
+        assert_eq!(alarm.deltas()[0], 0.0);
+        assert!(alarm.update::<f64, Rectangular, 16>(&data).unwrap());
+    }
+}
@@ -0,0 +1,398 @@
+/* embfft | average.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Averaging of successive acquisitions
+
+/******************************************************************************/
+
+use crate::common::{count_inv, Float};
+use crate::error::Error;
+
+/******************************************************************************/
+
+/// Time-synchronous (coherent) averaging
+///
+/// Averages complex spectra, or time frames, that are aligned on a trigger. Unlike the averaging of
+/// magnitudes, the uncorrelated noise averages out, improving the signal to noise ratio by √M for M
+/// acquisitions of a repetitive signal.
+///
+/// For example:
+/// ```
+/// let mut avg = embfft::average::CoherentAverage::<f32, 8>::new();
+/// for _ in 0..4 {
+///     let mut data = [(1.0f32, 0.0); 8];
+///     embfft::EmbFft::new(&mut data).fft();
+///     avg.add(&data);
+/// }
+///
+/// let mut result = [(0.0, 0.0); 8];
+/// avg.average(&mut result).unwrap();
+/// assert_eq!(result[0], (8.0, 0.0));
+/// ```
+pub struct CoherentAverage<T, const N: usize> {
+    sum: [(T, T); N],
+    count: usize
+}
+
+impl<T: Float<N>, const N: usize> CoherentAverage<T, N> {
+    /// Initializes an empty accumulator
+    pub fn new() -> Self {
+        Self {
+            sum: [(T::ZERO, T::ZERO); N],
+            count: 0
+        }
+    }
+
+    /// Discards all the accumulated acquisitions
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Accumulates a new acquisition
+    pub fn add(&mut self, data: &[(T, T); N]) {
+        for (s, x) in self.sum.iter_mut().zip(data) {
+            s.0 = s.0 + x.0;
+            s.1 = s.1 + x.1;
+        }
+        self.count += 1;
+    }
+
+    /// Returns the number of accumulated acquisitions
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Computes the average of the accumulated acquisitions
+    ///
+    /// Fails with [`Error::NotDone`] if no acquisition was accumulated yet.
+    pub fn average(&self, output: &mut [(T, T); N]) -> Result<(), Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        let count_inv = count_inv(self.count);
+        for (y, s) in output.iter_mut().zip(&self.sum) {
+            *y = (s.0 * count_inv, s.1 * count_inv);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float<N>, const N: usize> Default for CoherentAverage<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-bin running statistics across frames
+///
+/// Maintains the minimum, maximum, mean and variance of each bin with Welford's algorithm, which is
+/// numerically stable and does not store any history. The values can be magnitudes, powers or any other
+/// per-bin quantity. The mean update scales each deviation by the reciprocal of the frame count, which
+/// loses precision with the fixed-point types as the count grows.
+///
+/// For example:
+/// ```
+/// let mut stats = embfft::average::BinStatistics::<f32, 4>::new();
+/// stats.add(&[1.0, 2.0, 3.0, 4.0]);
+/// stats.add(&[3.0, 2.0, 1.0, 0.0]);
+///
+/// assert_eq!(stats.mean().unwrap(), &[2.0; 4]);
+/// assert_eq!(stats.max().unwrap(), &[3.0, 2.0, 3.0, 4.0]);
+/// let mut variance = [0.0; 4];
+/// stats.variance(&mut variance).unwrap();
+/// assert_eq!(variance, [1.0, 0.0, 1.0, 4.0]);
+/// ```
+pub struct BinStatistics<T, const N: usize> {
+    min: [T; N],
+    max: [T; N],
+    mean: [T; N],
+    m2: [T; N],
+    count: usize
+}
+
+impl<T: Float<N>, const N: usize> BinStatistics<T, N> {
+    /// Initializes empty statistics
+    pub fn new() -> Self {
+        Self {
+            min: [T::ZERO; N],
+            max: [T::ZERO; N],
+            mean: [T::ZERO; N],
+            m2: [T::ZERO; N],
+            count: 0
+        }
+    }
+
+    /// Discards all the accumulated frames
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Accumulates the values of a new frame
+    pub fn add(&mut self, values: &[T; N]) {
+        self.count += 1;
+        let count_inv = count_inv(self.count);
+        for (i, &x) in values.iter().enumerate() {
+            if self.count == 1 || x < self.min[i] {
+                self.min[i] = x;
+            }
+            if self.count == 1 || x > self.max[i] {
+                self.max[i] = x;
+            }
+            let delta = x - self.mean[i];
+            self.mean[i] = self.mean[i] + delta * count_inv;
+            self.m2[i] = self.m2[i] + delta * (x - self.mean[i]);
+        }
+    }
+
+    /// Accumulates the power of each bin of a spectrum
+    pub fn add_power(&mut self, data: &[(T, T); N]) {
+        self.add(&core::array::from_fn(|i| data[i].0 * data[i].0 + data[i].1 * data[i].1));
+    }
+
+    /// Returns the number of accumulated frames
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the minimum of each bin
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet.
+    pub fn min(&self) -> Result<&[T; N], Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        Ok(&self.min)
+    }
+
+    /// Returns the maximum of each bin
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet.
+    pub fn max(&self) -> Result<&[T; N], Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        Ok(&self.max)
+    }
+
+    /// Returns the mean of each bin
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet.
+    pub fn mean(&self) -> Result<&[T; N], Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        Ok(&self.mean)
+    }
+
+    /// Computes the population variance of each bin
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet.
+    pub fn variance(&self, output: &mut [T; N]) -> Result<(), Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        let count_inv = count_inv(self.count);
+        for (y, &m2) in output.iter_mut().zip(&self.m2) {
+            *y = m2 * count_inv;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float<N>, const N: usize> Default for BinStatistics<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Exponential averaging with a per-bin weight adapted to the measured variance
+///
+/// The weight of each new frame moves between `alpha_max` for the bins whose values are stable, which
+/// settle quickly, and `alpha_min` for the bins whose values fluctuate, which are smoothed more. The
+/// fluctuation is measured by the ratio `variance / (variance + mean²)`, between 0 and 1. This suits
+/// instrument displays, which must be both responsive and stable. Divisions are involved, which requires a
+/// floating-point type.
+///
+/// For example:
+/// ```
+/// let mut avg = embfft::average::AdaptiveAverage::<f32, 2>::new(0.05, 0.5).unwrap();
+/// for i in 0..16 {
+///     let noisy = if i % 2 == 0 { 0.0 } else { 2.0 };
+///     avg.update(&[1.0, noisy]);
+/// }
+/// assert_eq!(avg.average().unwrap()[0], 1.0);
+/// assert!(avg.alpha(1).unwrap() < avg.alpha(0).unwrap());
+/// ```
+pub struct AdaptiveAverage<T, const N: usize> {
+    average: [T; N],
+    variance: [T; N],
+    alpha_min: T,
+    alpha_max: T,
+    started: bool
+}
+
+impl<T: Float<N>, const N: usize> AdaptiveAverage<T, N> {
+    /// Initializes an empty average
+    ///
+    /// Fails with [`Error::InvalidParameter`] unless `0 < alpha_min <= alpha_max <= 1`.
+    pub fn new(alpha_min: T, alpha_max: T) -> Result<Self, Error> {
+        if alpha_min <= T::ZERO || alpha_min > alpha_max || alpha_max > T::ONE {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { average: [T::ZERO; N], variance: [T::ZERO; N], alpha_min, alpha_max, started: false })
+    }
+
+    /// Discards the average
+    pub fn reset(&mut self) {
+        self.average = [T::ZERO; N];
+        self.variance = [T::ZERO; N];
+        self.started = false;
+    }
+
+    /// Returns the weight of the next frame for bin k
+    fn weight(&self, k: usize) -> T {
+        let m2 = self.average[k] * self.average[k];
+        let total = self.variance[k] + m2;
+        if total > T::ZERO {
+            self.alpha_max - (self.alpha_max - self.alpha_min) * (self.variance[k] / total)
+        } else {
+            self.alpha_max
+        }
+    }
+
+    /// Updates the average with the values of a new frame, such as magnitudes or powers
+    pub fn update(&mut self, values: &[T; N]) {
+        if !self.started {
+            self.average = *values;
+            self.started = true;
+            return;
+        }
+        for (k, &x) in values.iter().enumerate() {
+            let e = x - self.average[k];
+            // The variance follows quickly, so that the weight reacts to the changes
+            self.variance[k] = self.variance[k] + self.alpha_max * (e * e - self.variance[k]);
+            self.average[k] = self.average[k] + self.weight(k) * e;
+        }
+    }
+
+    /// Updates the average with the power of each bin of a spectrum
+    pub fn update_power(&mut self, data: &[(T, T); N]) {
+        self.update(&core::array::from_fn(|i| data[i].0 * data[i].0 + data[i].1 * data[i].1));
+    }
+
+    /// Returns the average of each bin
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was processed yet.
+    pub fn average(&self) -> Result<&[T; N], Error> {
+        if !self.started {
+            return Err(Error::NotDone);
+        }
+        Ok(&self.average)
+    }
+
+    /// Returns the weight that the next frame will have for bin k
+    ///
+    /// Fails with [`Error::OutOfRange`] if k is not lower than N.
+    pub fn alpha(&self, k: usize) -> Result<T, Error> {
+        if k >= N {
+            return Err(Error::OutOfRange);
+        }
+        Ok(self.weight(k))
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coherent_average() {
+        let mut avg = CoherentAverage::<f64, 4>::new();
+        let mut output = [(0.0, 0.0); 4];
+        assert_eq!(avg.average(&mut output), Err(Error::NotDone));
+
+        // The alternating noise cancels out
+        for i in 0..4 {
+            let noise = if i % 2 == 0 { 0.5 } else { -0.5 };
+            avg.add(&[(1.0 + noise, 2.0), (3.0, 4.0 - noise), (5.0, 6.0), (7.0, 8.0)]);
+        }
+        assert_eq!(avg.count(), 4);
+        avg.average(&mut output).unwrap();
+        assert_eq!(output, [(1.0, 2.0), (3.0, 4.0), (5.0, 6.0), (7.0, 8.0)]);
+
+        avg.reset();
+        assert_eq!(avg.count(), 0);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        use crate::fixed_point::Q15;
+
+        // The counts cannot be represented, the sums must not saturate the divisor
+        let mut avg = CoherentAverage::<Q15, 2>::new();
+        for _ in 0..4 {
+            avg.add(&[(Q15::from_f64(0.1), Q15::from_f64(-0.2)), (Q15::from_f64(0.0), Q15::from_f64(0.05))]);
+        }
+        let mut output = [(Q15::from_f64(0.0), Q15::from_f64(0.0)); 2];
+        avg.average(&mut output).unwrap();
+        assert_eq!(output, [(Q15::from_f64(0.1), Q15::from_f64(-0.2)), (Q15::from_f64(0.0), Q15::from_f64(0.05))]);
+
+        let mut stats = BinStatistics::<Q15, 1>::new();
+        for x in [0.1, 0.2, 0.3, 0.4] {
+            stats.add(&[Q15::from_f64(x)]);
+        }
+        assert!((stats.mean().unwrap()[0].to_f64() - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_bin_statistics() {
+        let mut stats = BinStatistics::<f64, 2>::default();
+        let mut variance = [0.0; 2];
+        assert_eq!(stats.min(), Err(Error::NotDone));
+        assert_eq!(stats.variance(&mut variance), Err(Error::NotDone));
+
+        // Large offset, where the naive sum of squares would lose the variance
+        for x in [4.0, 7.0, 13.0, 16.0] {
+            stats.add(&[1e9 + x, -x]);
+        }
+        assert_eq!(stats.count(), 4);
+        assert_eq!(stats.min().unwrap(), &[1e9 + 4.0, -16.0]);
+        assert_eq!(stats.max().unwrap(), &[1e9 + 16.0, -4.0]);
+        assert_eq!(stats.mean().unwrap(), &[1e9 + 10.0, -10.0]);
+        stats.variance(&mut variance).unwrap();
+        assert_eq!(variance, [22.5, 22.5]);
+
+        stats.reset();
+        stats.add_power(&[(3.0, 4.0), (0.0, -2.0)]);
+        assert_eq!(stats.mean().unwrap(), &[25.0, 4.0]);
+    }
+
+    #[test]
+    fn test_adaptive_average() {
+        assert!(AdaptiveAverage::<f64, 2>::new(0.0, 0.5).is_err());
+        assert!(AdaptiveAverage::<f64, 2>::new(0.6, 0.5).is_err());
+        assert!(AdaptiveAverage::<f64, 2>::new(0.1, 1.5).is_err());
+
+        let mut avg = AdaptiveAverage::<f64, 2>::new(0.01, 0.5).unwrap();
+        assert_eq!(avg.average(), Err(Error::NotDone));
+        assert_eq!(avg.alpha(2), Err(Error::OutOfRange));
+        avg.update_power(&[(1.0, 0.0), (0.0, 1.0)]);
+        assert_eq!(avg.average().unwrap(), &[1.0, 1.0]);
+
+        // A stable bin settles with the largest weight, a noisy bin is smoothed
+        for i in 0..32 {
+            avg.update(&[2.0, if i % 2 == 0 { 0.0 } else { 2.0 }]);
+        }
+        assert!((avg.average().unwrap()[0] - 2.0).abs() < 1e-6);
+        assert!((avg.average().unwrap()[1] - 1.0).abs() < 0.2);
+        assert!((avg.alpha(0).unwrap() - 0.5).abs() < 1e-6);
+        assert!(avg.alpha(1).unwrap() < 0.3);
+
+        avg.reset();
+        assert_eq!(avg.average(), Err(Error::NotDone));
+    }
+}
@@ -0,0 +1,278 @@
+/* embfft | band.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Band power measurements
+//!
+//! The spectra are those of real signals, in natural order, computed with the window `W`. The power is
+//! the mean square value of the signal in the band, consistent with the one-sided power spectral
+//! density: a sine wave of amplitude A has a power of A² / 2.
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::mask::ExclusionMask;
+use crate::units::{Hertz, SampleRate};
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Returns the factor that converts a squared magnitude to power, for the window `W`
+pub(crate) fn power_scale<T: Float<N>, W: Window<T, N>, const N: usize>() -> f64 {
+    let mut sum = 0.0;
+    for w in W::TABLE.iter() {
+        let w = w.to_f64();
+        sum += w * w;
+    }
+    1.0 / (N as f64 * sum)
+}
+
+/// Returns the one-sided power of bin k, for a real signal
+pub(crate) fn bin_power<T: Float<N>, const N: usize>(spectrum: &[(T, T); N], k: usize, scale: f64) -> f64 {
+    let (re, im) = (spectrum[k].0.to_f64(), spectrum[k].1.to_f64());
+    let power = (re * re + im * im) * scale;
+    if k == 0 || 2 * k == N { power } else { 2.0 * power }
+}
+
+/// Returns the frequency range covered by bin k, in bins
+pub(crate) fn bin_range<const N: usize>(k: usize) -> (f64, f64) {
+    let k = k as f64;
+    let lo = if k == 0.0 { 0.0 } else { k - 0.5 };
+    let hi = if 2.0 * k == N as f64 { k } else { k + 0.5 };
+    (lo, hi)
+}
+
+/// Computes the power of the signal between `f_lo` and `f_hi`
+///
+/// The bins that are partially inside the band are weighted by the fraction of their width that
+/// overlaps it. With a tapering window, the power of a tone leaks into the neighbouring bins, so the band
+/// should extend a few bins beyond the tones it must include.
+/// Fails with [`Error::InvalidParameter`] if the band is not between 0 and the Nyquist frequency.
+///
+/// For example:
+/// ```
+/// use embfft::units::{Hertz, SampleRate};
+/// use embfft::window::Hann;
+///
+/// let mut data: [(f32, f32); 64] = core::array::from_fn(|i| {
+///     let phase = 2.0 * core::f32::consts::PI * 1000.0 * i as f32 / 8000.0;
+///     (2.0 * phase.cos(), 0.0)
+/// });
+/// embfft::EmbFft::<f32, 64, Hann>::windowed(&mut data).fft();
+/// let fs = SampleRate::new(8000.0).unwrap();
+/// let power = embfft::band::band_power::<f32, Hann, 64>(&data, Hertz(800.0), Hertz(1200.0), fs).unwrap();
+/// assert!((power - 2.0).abs() < 1e-4);
+/// ```
+pub fn band_power<T: Float<N>, W: Window<T, N>, const N: usize>(spectrum: &[(T, T); N], f_lo: Hertz, f_hi: Hertz, sample_rate: SampleRate) -> Result<f64, Error> {
+    band_power_excluding::<T, W, N>(spectrum, f_lo, f_hi, sample_rate, &ExclusionMask::new())
+}
+
+/// Computes the power of the signal between `f_lo` and `f_hi`, without the bins excluded by a mask
+///
+/// Fails like [`band_power()`].
+pub fn band_power_excluding<T: Float<N>, W: Window<T, N>, const N: usize>(spectrum: &[(T, T); N], f_lo: Hertz, f_hi: Hertz, sample_rate: SampleRate, mask: &ExclusionMask<N>) -> Result<f64, Error> {
+    if f_lo < Hertz(0.0) || f_lo > f_hi || f_hi > sample_rate.nyquist() {
+        return Err(Error::InvalidParameter);
+    }
+    let (lo, hi) = (sample_rate.to_bin::<N>(f_lo), sample_rate.to_bin::<N>(f_hi));
+
+    let scale = power_scale::<T, W, N>();
+    let mut power = 0.0;
+    for k in 0..=N / 2 {
+        let (a, b) = bin_range::<N>(k);
+        let overlap = (if b < hi { b } else { hi }) - (if a > lo { a } else { lo });
+        if overlap > 0.0 && !mask.is_excluded(k) {
+            power += bin_power(spectrum, k, scale) * overlap / (b - a);
+        }
+    }
+    Ok(power)
+}
+
+/// Computes the energy of the signal between `f_lo` and `f_hi`, over the duration of the frame
+///
+/// This is the band power multiplied by the frame duration, N / `sample_rate`.
+/// Fails like [`band_power()`].
+pub fn band_energy<T: Float<N>, W: Window<T, N>, const N: usize>(spectrum: &[(T, T); N], f_lo: Hertz, f_hi: Hertz, sample_rate: SampleRate) -> Result<f64, Error> {
+    Ok(band_power::<T, W, N>(spectrum, f_lo, f_hi, sample_rate)? / sample_rate.bin_width::<N>().0)
+}
+
+/// Computes the band that contains the given fraction of the total power, typically 0.99
+///
+/// Returns the lower and upper frequencies of the band, each tail containing half of the remaining
+/// power. The power is considered uniform within each bin.
+/// Fails with [`Error::InvalidParameter`] if the fraction is not between 0 and 1, or if the spectrum is
+/// null.
+///
+/// For example:
+/// ```
+/// use embfft::units::{Hertz, SampleRate};
+///
+/// // Flat spectrum, from DC to the Nyquist frequency
+/// let mut data = [(0.0f32, 0.0); 64];
+/// data[0] = (1.0, 0.0);
+/// embfft::EmbFft::new(&mut data).fft();
+///
+/// let fs = SampleRate::new(64.0).unwrap();
+/// let (lo, hi) = embfft::band::occupied_bandwidth::<f32, embfft::window::Rectangular, 64>(&data, 0.5, fs).unwrap();
+/// assert!((lo - Hertz(8.0)).0.abs() < 1e-4 && (hi - Hertz(24.0)).0.abs() < 1e-4);
+/// ```
+pub fn occupied_bandwidth<T: Float<N>, W: Window<T, N>, const N: usize>(spectrum: &[(T, T); N], fraction: f64, sample_rate: SampleRate) -> Result<(Hertz, Hertz), Error> {
+    if fraction <= 0.0 || fraction > 1.0 {
+        return Err(Error::InvalidParameter);
+    }
+    let scale = power_scale::<T, W, N>();
+    let mut total = 0.0;
+    for k in 0..=N / 2 {
+        total += bin_power(spectrum, k, scale);
+    }
+    if total == 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+    let tail = total * (1.0 - fraction) / 2.0;
+
+    // Walks from the lowest bin up, and from the highest bin down, until the tail is reached
+    let mut lo = 0.0;
+    let mut sum = 0.0;
+    for k in 0..=N / 2 {
+        let p = bin_power(spectrum, k, scale);
+        if sum + p > tail {
+            let (a, b) = bin_range::<N>(k);
+            lo = a + (tail - sum) / p * (b - a);
+            break;
+        }
+        sum += p;
+    }
+    let mut hi = N as f64 / 2.0;
+    let mut sum = 0.0;
+    for k in (0..=N / 2).rev() {
+        let p = bin_power(spectrum, k, scale);
+        if sum + p > tail {
+            let (a, b) = bin_range::<N>(k);
+            hi = b - (tail - sum) / p * (b - a);
+            break;
+        }
+        sum += p;
+    }
+    Ok((sample_rate.bin_frequency::<N>(lo), sample_rate.bin_frequency::<N>(hi)))
+}
+
+/// Computes the half-power (-3 dB) bandwidth around the peak at bin k
+///
+/// Returns the lower and upper frequencies where the power falls below half of the peak power,
+/// interpolated linearly between the bins, or the edges of the spectrum if it never does.
+/// Fails with [`Error::OutOfRange`] if k is greater than N / 2, and with [`Error::InvalidParameter`] if
+/// the peak is null.
+pub fn half_power_bandwidth<T: Float<N>, const N: usize>(spectrum: &[(T, T); N], k: usize, sample_rate: SampleRate) -> Result<(Hertz, Hertz), Error> {
+    if 2 * k > N {
+        return Err(Error::OutOfRange);
+    }
+    let power = |i: usize| {
+        let (re, im) = (spectrum[i].0.to_f64(), spectrum[i].1.to_f64());
+        re * re + im * im
+    };
+    let half = power(k) / 2.0;
+    if half == 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    let mut lo = 0.0;
+    for i in (0..k).rev() {
+        if power(i) < half {
+            lo = i as f64 + (half - power(i)) / (power(i + 1) - power(i));
+            break;
+        }
+    }
+    let mut hi = N as f64 / 2.0;
+    for i in k + 1..=N / 2 {
+        if power(i) < half {
+            hi = i as f64 - (half - power(i)) / (power(i - 1) - power(i));
+            break;
+        }
+    }
+    Ok((sample_rate.bin_frequency::<N>(lo), sample_rate.bin_frequency::<N>(hi)))
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use crate::window::{Hann, Rectangular};
+    use approx::assert_abs_diff_eq;
+
+    fn fs(x: f64) -> SampleRate {
+        SampleRate::new(x).unwrap()
+    }
+
+    fn tone<W: Window<f64, 64>>(bin: f64, amplitude: f64) -> [(f64, f64); 64] {
+        let mut data = core::array::from_fn(|i| {
+            (amplitude * cordic::cos(2.0 * core::f64::consts::PI * bin * i as f64 / 64.0), 0.0)
+        });
+        crate::EmbFft::<f64, 64, W>::windowed(&mut data).fft();
+        data
+    }
+
+    #[test]
+    fn test_band_power() {
+        let data = tone::<Rectangular>(8.0, 1.0);
+        assert_eq!(band_power::<f64, Rectangular, 64>(&data, Hertz(0.0), Hertz(40.0), fs(64.0)), Err(Error::InvalidParameter));
+        assert_eq!(band_power::<f64, Rectangular, 64>(&data, Hertz(9.0), Hertz(8.0), fs(64.0)), Err(Error::InvalidParameter));
+        assert_eq!(SampleRate::new(0.0), Err(Error::InvalidParameter));
+
+        assert_abs_diff_eq!(band_power::<f64, Rectangular, 64>(&data, Hertz(0.0), Hertz(32.0), fs(64.0)).unwrap(), 0.5, epsilon = 1e-12);
+        assert_abs_diff_eq!(band_power::<f64, Rectangular, 64>(&data, Hertz(7.75), Hertz(8.25), fs(64.0)).unwrap(), 0.25, epsilon = 1e-12);
+        assert_abs_diff_eq!(band_power::<f64, Rectangular, 64>(&data, Hertz(8.5), Hertz(32.0), fs(64.0)).unwrap(), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(band_energy::<f64, Rectangular, 64>(&data, Hertz(0.0), Hertz(32.0), fs(128.0)).unwrap(), 0.25, epsilon = 1e-12);
+
+        // DC and Nyquist bins are not doubled
+        let data = tone::<Rectangular>(0.0, 1.0);
+        assert_abs_diff_eq!(band_power::<f64, Rectangular, 64>(&data, Hertz(0.0), Hertz(0.25), fs(64.0)).unwrap(), 0.5, epsilon = 1e-12);
+        let data = tone::<Rectangular>(32.0, 2.0);
+        assert_abs_diff_eq!(band_power::<f64, Rectangular, 64>(&data, Hertz(31.0), Hertz(32.0), fs(64.0)).unwrap(), 4.0, epsilon = 1e-12);
+
+        // The leakage of the window is included in the band
+        let data = tone::<Hann>(10.0, 3.0);
+        assert_abs_diff_eq!(band_power::<f64, Hann, 64>(&data, Hertz(8.5), Hertz(11.5), fs(64.0)).unwrap(), 4.5, epsilon = 1e-9);
+
+        // The tone is not counted in an excluded bin
+        let mut mask = ExclusionMask::new();
+        mask.exclude(8).unwrap();
+        let data = tone::<Rectangular>(8.0, 1.0);
+        assert_abs_diff_eq!(band_power_excluding::<f64, Rectangular, 64>(&data, Hertz(0.0), Hertz(32.0), fs(64.0), &mask).unwrap(), 0.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_occupied_bandwidth() {
+        let data = tone::<Hann>(10.0, 1.0);
+        assert!(occupied_bandwidth::<f64, Hann, 64>(&data, 1.5, fs(64.0)).is_err());
+        assert!(occupied_bandwidth::<f64, Hann, 64>(&[(0.0, 0.0); 64], 0.99, fs(64.0)).is_err());
+
+        // Hann leaks 1/6 of the power in each neighbour
+        let (lo, hi) = occupied_bandwidth::<f64, Hann, 64>(&data, 0.99, fs(6400.0)).unwrap();
+        assert_abs_diff_eq!(lo.0, 850.0 + 100.0 * 0.005 / (1.0 / 6.0), epsilon = 1e-6);
+        assert_abs_diff_eq!(hi.0, 1150.0 - 100.0 * 0.005 / (1.0 / 6.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_half_power_bandwidth() {
+        let mut data = [(0.0, 0.0); 16];
+        data[3] = (0.0, 0.5);
+        data[4] = (1.0, 0.0);
+        data[5] = (0.5, 0.5);
+        data[6] = (0.25, 0.0);
+        assert_eq!(half_power_bandwidth(&data, 9, fs(16.0)), Err(Error::OutOfRange));
+        assert!(half_power_bandwidth(&data, 1, fs(16.0)).is_err());
+
+        // Powers are 0.25 and 0.5 on either side of the peak
+        let (lo, hi) = half_power_bandwidth(&data, 4, fs(160.0)).unwrap();
+        assert_abs_diff_eq!(lo.0, 30.0 + 10.0 / 3.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(hi.0, 50.0, epsilon = 1e-12);
+
+        // The edges are reached
+        let (lo, hi) = half_power_bandwidth(&[(1.0, 0.0); 16], 4, fs(16.0)).unwrap();
+        assert_eq!((lo, hi), (Hertz(0.0), Hertz(8.0)));
+    }
+}
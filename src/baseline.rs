@@ -0,0 +1,310 @@
+/* embfft | baseline.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Robust learning of a baseline spectrum
+//!
+//! The spectrum of a healthy machine is learned on the device, over a training period that inevitably
+//! contains a few transients, such as a door slam or a tool drop. The mean of the levels is pulled by these
+//! outliers, so the [`BaselineLearner`] tracks the median and the median absolute deviation (MAD) of the
+//! level of each bin instead. Both are estimated on the fly, without storing the training spectra.
+//!
+//! The resulting [`Baseline`] scores new spectra bin by bin, and with the `embedded-storage` feature, it
+//! is a `storage::Record` that can be kept in flash memory by a `storage::SpectrumLog`, to survive a
+//! reset.
+//! For example:
+//! ```
+//! use embfft::baseline::BaselineLearner;
+//! use embfft::window::Rectangular;
+//!
+//! let mut learner = BaselineLearner::<5>::new(0.1).unwrap();
+//! for i in 0..100 {
+//!     // A transient every 10 spectra
+//!     let level = if i % 10 == 9 { 100.0 } else { 1.0 };
+//!     learner.learn::<f32, Rectangular, 8>(&[(level, 0.0); 8]).unwrap();
+//! }
+//!
+//! let baseline = learner.baseline().unwrap();
+//! assert!(baseline.mad[1] < 0.5);
+//! assert!(baseline.z_scores::<f32, Rectangular, 8>(&[(1.0, 0.0); 8]).unwrap()[1].abs() < 1.0);
+//! ```
+
+/******************************************************************************/
+
+use crate::band::{bin_power, power_scale};
+use crate::common::Float;
+use crate::error::Error;
+use crate::mask::ExclusionMask;
+use crate::math;
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Robust statistics of the levels of the K first bins of a spectrum, in dB
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Baseline<const K: usize> {
+    /// Median level of each bin, in dB
+    pub median: [f32; K],
+    /// Median absolute deviation of the level of each bin, in dB
+    pub mad: [f32; K],
+    /// Smallest deviation considered significant, in dB, which bounds the MAD from below in the scores
+    pub resolution: f32,
+    /// Number of spectra learned
+    pub spectra: u32
+}
+
+/// Returns the level of bin k, in dB
+fn bin_level<T: Float<N>, const N: usize>(spectrum: &[(T, T); N], k: usize, scale: f64) -> f64 {
+    let power = bin_power(spectrum, k, scale);
+    math::power_to_db(if power > 1e-30 { power } else { 1e-30 })
+}
+
+impl<const K: usize> Baseline<K> {
+    /// Returns the robust z-score of each bin of a spectrum, i.e. its deviation from the median in
+    /// standard deviations, estimated as 1.4826 × the MAD
+    ///
+    /// The spectrum is that of a real signal, in natural order, computed with the window `W`.
+    /// Fails with [`Error::InvalidLength`] if K is greater than N / 2 + 1.
+    pub fn z_scores<T: Float<N>, W: Window<T, N>, const N: usize>(&self, spectrum: &[(T, T); N]) -> Result<[f64; K], Error> {
+        self.z_scores_excluding::<T, W, N>(spectrum, &ExclusionMask::new())
+    }
+
+    /// Returns the robust z-score of each bin of a spectrum, the bins excluded by a mask scoring 0
+    ///
+    /// Fails like [`Baseline::z_scores()`].
+    pub fn z_scores_excluding<T: Float<N>, W: Window<T, N>, const N: usize>(&self, spectrum: &[(T, T); N], mask: &ExclusionMask<N>) -> Result<[f64; K], Error> {
+        if K > N / 2 + 1 {
+            return Err(Error::InvalidLength);
+        }
+        let scale = power_scale::<T, W, N>();
+        let resolution = self.resolution as f64;
+        Ok(core::array::from_fn(|k| {
+            if mask.is_excluded(k) {
+                return 0.0;
+            }
+            let mad = self.mad[k] as f64;
+            let sigma = 1.4826 * if mad > resolution { mad } else { resolution };
+            (bin_level(spectrum, k, scale) - self.median[k] as f64) / sigma
+        }))
+    }
+}
+
+#[cfg(feature = "embedded-storage")]
+impl<const K: usize> crate::storage::Record for Baseline<K> {
+    const SIZE: usize = 8 * K + 8;
+
+    fn encode(&self, put: &mut dyn FnMut(&[u8])) {
+        self.median.encode(put);
+        self.mad.encode(put);
+        self.resolution.encode(put);
+        put(&self.spectra.to_le_bytes());
+    }
+
+    fn decode(take: &mut dyn FnMut(&mut [u8])) -> Self {
+        let median = <[f32; K]>::decode(take);
+        let mad = <[f32; K]>::decode(take);
+        let resolution = f32::decode(take);
+        let mut spectra = [0; 4];
+        take(&mut spectra);
+        Self { median, mad, resolution, spectra: u32::from_le_bytes(spectra) }
+    }
+}
+
+/******************************************************************************/
+
+/// Learner of the [`Baseline`] of the K first bins of successive spectra
+///
+/// Each new level moves the median and the MAD estimates towards it by a fixed step, which decreases as
+/// 1 / √n with the number n of spectra learned, and is proportional to the MAD so that it adapts to the
+/// spread of each bin. An outlier thus moves the estimates no more than any other spectrum. The estimates
+/// settle within about half the MAD of the exact values after a few hundred spectra.
+pub struct BaselineLearner<const K: usize> {
+    baseline: Baseline<K>
+}
+
+impl<const K: usize> BaselineLearner<K> {
+    /// Initializes a learner, without any spectrum
+    ///
+    /// `resolution` is the smallest deviation considered significant, in dB, which also sets the smallest
+    /// step of the estimates.
+    /// Fails with [`Error::InvalidParameter`] if it is not positive.
+    pub fn new(resolution: f32) -> Result<Self, Error> {
+        if resolution.is_nan() || resolution <= 0.0 {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            baseline: Baseline { median: [0.0; K], mad: [0.0; K], resolution, spectra: 0 }
+        })
+    }
+
+    /// Resumes the learning of a baseline, e.g. one loaded from flash memory
+    pub fn resume(baseline: Baseline<K>) -> Self {
+        Self { baseline }
+    }
+
+    /// Forgets all the spectra learned
+    pub fn reset(&mut self) {
+        self.baseline.median = [0.0; K];
+        self.baseline.mad = [0.0; K];
+        self.baseline.spectra = 0;
+    }
+
+    /// Returns the number of spectra learned
+    pub fn spectra(&self) -> u32 {
+        self.baseline.spectra
+    }
+
+    /// Adds a spectrum of the training period
+    ///
+    /// The spectrum is that of a real signal, in natural order, computed with the window `W`.
+    /// Fails with [`Error::InvalidLength`] if K is greater than N / 2 + 1.
+    pub fn learn<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N]) -> Result<(), Error> {
+        self.learn_excluding::<T, W, N>(spectrum, &ExclusionMask::new())
+    }
+
+    /// Adds a spectrum of the training period, without the bins excluded by a mask
+    ///
+    /// The excluded bins keep their estimates, so that a spur present during the training does not end up
+    /// in the baseline. Fails like [`BaselineLearner::learn()`].
+    pub fn learn_excluding<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N], mask: &ExclusionMask<N>) -> Result<(), Error> {
+        if K > N / 2 + 1 {
+            return Err(Error::InvalidLength);
+        }
+        let scale = power_scale::<T, W, N>();
+        let baseline = &mut self.baseline;
+        baseline.spectra = baseline.spectra.saturating_add(1);
+        let gain = (1.0 / math::sqrt(baseline.spectra as f64)) as f32;
+        for k in (0..K).filter(|&k| !mask.is_excluded(k)) {
+            let level = bin_level(spectrum, k, scale) as f32;
+            if baseline.spectra == 1 {
+                baseline.median[k] = level;
+                continue;
+            }
+
+            // Sign steps, which converge to the medians of the level and of its deviation
+            let (median, mad) = (&mut baseline.median[k], &mut baseline.mad[k]);
+            let step = gain * (*mad + baseline.resolution);
+            if level > *median {
+                *median += step;
+            } else if level < *median {
+                *median -= step;
+            }
+            let deviation = if level > *median { level - *median } else { *median - level };
+            if deviation > *mad {
+                *mad += step;
+            } else if deviation < *mad {
+                *mad = if *mad > step { *mad - step } else { 0.0 };
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the baseline learned so far
+    ///
+    /// Fails with [`Error::NotDone`] if no spectrum was learned.
+    pub fn baseline(&self) -> Result<Baseline<K>, Error> {
+        if self.baseline.spectra == 0 {
+            return Err(Error::NotDone);
+        }
+        Ok(self.baseline)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth::Lfsr;
+    use crate::window::Rectangular;
+
+    #[test]
+    fn test_errors() {
+        assert!(BaselineLearner::<4>::new(0.0).is_err());
+        assert!(BaselineLearner::<4>::new(f32::NAN).is_err());
+        let mut learner = BaselineLearner::<6>::new(0.1).unwrap();
+        assert_eq!(learner.baseline(), Err(Error::NotDone));
+        assert_eq!(learner.learn::<f32, Rectangular, 8>(&[(1.0, 0.0); 8]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_robust() {
+        // Levels of 0 dB ± 3 dB at bin 1, with a transient of +30 dB in one spectrum out of 10
+        let mut learner = BaselineLearner::<3>::new(0.1).unwrap();
+        let mut lfsr = Lfsr::new(1);
+        for i in 0..500 {
+            let db = if i % 10 == 3 { 30.0 } else { 3.0 * lfsr.uniform() };
+            // Bin 1 counts twice in the one-sided power of a 4-bin spectrum
+            let magnitude = (8.0 * 10f64.powf(db / 10.0)).sqrt();
+            learner.learn::<f64, Rectangular, 4>(&[(0.0, 0.0), (magnitude, 0.0), (0.0, 0.0), (0.0, 0.0)]).unwrap();
+        }
+        assert_eq!(learner.spectra(), 500);
+
+        // The median of the levels is 1/3 dB and their MAD 1.6 dB, while their mean is pulled up to 3 dB
+        let baseline = learner.baseline().unwrap();
+        assert!((baseline.median[1] - 0.33).abs() < 1.0, "median {}", baseline.median[1]);
+        assert!((baseline.mad[1] - 1.6).abs() < 0.8, "mad {}", baseline.mad[1]);
+        assert_eq!(baseline.mad[0], 0.0);
+
+        // A level of 20 dB scores about 8 standard deviations
+        let magnitude = (8.0 * 100.0f64).sqrt();
+        let scores = baseline.z_scores::<f64, Rectangular, 4>(&[(0.0, 0.0), (magnitude, 0.0), (0.0, 0.0), (0.0, 0.0)]).unwrap();
+        let expected = (20.0 - baseline.median[1] as f64) / (1.4826 * baseline.mad[1] as f64);
+        assert!((scores[1] - expected).abs() < 1e-5);
+        assert!(scores[1] > 5.0);
+        assert_eq!(scores[0], 0.0);
+
+        learner.reset();
+        assert_eq!(learner.baseline(), Err(Error::NotDone));
+        let mut learner = BaselineLearner::resume(baseline);
+        assert_eq!(learner.baseline(), Ok(baseline));
+        learner.learn::<f64, Rectangular, 4>(&[(0.0, 0.0); 4]).unwrap();
+        assert_eq!(learner.spectra(), 501);
+    }
+
+    #[test]
+    fn test_mask() {
+        // A spur at bin 2 during the training, and at bin 3 afterwards
+        let mut mask = ExclusionMask::new();
+        mask.exclude_range(2, 3).unwrap();
+        let mut learner = BaselineLearner::<4>::new(0.1).unwrap();
+        let mut data = [(1.0f64, 0.0); 8];
+        data[2] = (100.0, 0.0);
+        for _ in 0..20 {
+            learner.learn_excluding::<f64, Rectangular, 8>(&data, &mask).unwrap();
+        }
+        let baseline = learner.baseline().unwrap();
+        assert_eq!((baseline.median[2], baseline.mad[2]), (0.0, 0.0));
+
+        let mut data = [(1.0f64, 0.0); 8];
+        data[3] = (100.0, 0.0);
+        let scores = baseline.z_scores_excluding::<f64, Rectangular, 8>(&data, &mask).unwrap();
+        assert!(scores[1].abs() < 1.0);
+        assert_eq!((scores[2], scores[3]), (0.0, 0.0));
+        assert!(baseline.z_scores::<f64, Rectangular, 8>(&data).unwrap()[3] > 100.0);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-storage")]
+    fn test_record() {
+        use crate::storage::Record;
+
+        let baseline = Baseline { median: [1.0, -2.0], mad: [0.5, 0.25], resolution: 0.1, spectra: 42 };
+        let mut bytes = [0; 24];
+        let mut len = 0;
+        baseline.encode(&mut |b: &[u8]| {
+            bytes[len..len + b.len()].copy_from_slice(b);
+            len += b.len();
+        });
+        assert_eq!(len, Baseline::<2>::SIZE);
+
+        let mut pos = 0;
+        let decoded = Baseline::<2>::decode(&mut |b: &mut [u8]| {
+            b.copy_from_slice(&bytes[pos..pos + b.len()]);
+            pos += b.len();
+        });
+        assert_eq!(decoded, baseline);
+    }
+}
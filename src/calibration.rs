@@ -0,0 +1,298 @@
+/* embfft | calibration.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Per-bin calibration of the spectra
+//!
+//! The corrections are either measured with a known reference signal, or relative to another channel: a
+//! [`ChannelMismatch`] compares two channels driven by a shared stimulus, such as the I and Q paths of a
+//! receiver or the microphones of a stereo pair, and corrects one to match the other.
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+
+/******************************************************************************/
+
+/// Complex correction of each bin, compensating the gain and phase of a sensor or analog front-end
+///
+/// For example:
+/// ```
+/// use embfft::calibration::CalibrationTable;
+///
+/// // The front-end halves the level of every bin
+/// let table = CalibrationTable::from_response(&[(0.5f32, 0.0); 8]).unwrap();
+///
+/// let mut data = [(1.0f32, -0.5); 8];
+/// table.apply(&mut data);
+/// assert_eq!(data[3], (2.0, -1.0));
+/// ```
+pub struct CalibrationTable<T, const N: usize> {
+    correction: [(T, T); N]
+}
+
+impl<T: Float<N>, const N: usize> CalibrationTable<T, N> {
+    /// Initializes the table from stored complex corrections, in natural order
+    pub fn new(correction: &[(T, T); N]) -> Self {
+        Self { correction: *correction }
+    }
+
+    /// Initializes a table that leaves the spectra unchanged
+    pub fn unity() -> Self {
+        Self { correction: [(T::ONE, T::ZERO); N] }
+    }
+
+    /// Initializes the table from a gain and a phase in radians for each bin
+    pub fn from_gain_phase(gain_phase: &[(f64, f64); N]) -> Self {
+        Self {
+            correction: core::array::from_fn(|k| {
+                let (gain, phase) = gain_phase[k];
+                let (sin, cos) = math::sin_cos(phase);
+                (T::from_f64(gain * cos), T::from_f64(gain * sin))
+            })
+        }
+    }
+
+    /// Builds the table from the spectrum measured with a known reference signal
+    ///
+    /// Each correction is `reference / measured`, so that the corrected measurement matches the reference.
+    /// Fails with [`Error::InvalidParameter`] if a measured bin is null while its reference is not.
+    pub fn from_measurement(measured: &[(T, T); N], reference: &[(T, T); N]) -> Result<Self, Error> {
+        let mut correction = [(T::ONE, T::ZERO); N];
+        for (c, (m, r)) in correction.iter_mut().zip(core::iter::zip(measured, reference)) {
+            let (m, r) = ((m.0.to_f64(), m.1.to_f64()), (r.0.to_f64(), r.1.to_f64()));
+            let m2 = m.0 * m.0 + m.1 * m.1;
+            if m2 == 0.0 {
+                if r != (0.0, 0.0) {
+                    return Err(Error::InvalidParameter);
+                }
+                // Nothing to correct, the bin is kept as is
+                continue;
+            }
+            *c = (T::from_f64((r.0 * m.0 + r.1 * m.1) / m2), T::from_f64((r.1 * m.0 - r.0 * m.1) / m2));
+        }
+        Ok(Self { correction })
+    }
+
+    /// Builds the table from the measured frequency response of the front-end, which is inverted
+    ///
+    /// Fails with [`Error::InvalidParameter`] if a bin of the response is null.
+    pub fn from_response(response: &[(T, T); N]) -> Result<Self, Error> {
+        Self::from_measurement(response, &[(T::ONE, T::ZERO); N])
+    }
+
+    /// Returns the complex corrections, in natural order
+    pub fn correction(&self) -> &[(T, T); N] {
+        &self.correction
+    }
+
+    /// Corrects a spectrum in natural order, in place
+    pub fn apply(&self, data: &mut [(T, T); N]) {
+        for (x, c) in core::iter::zip(data.iter_mut(), &self.correction) {
+            *x = (x.0 * c.0 - x.1 * c.1, x.0 * c.1 + x.1 * c.0);
+        }
+    }
+}
+
+/// Gain and phase mismatch of a channel, relative to a reference channel
+///
+/// Both channels receive a shared stimulus, a broadband noise or a sweep, and their spectra are averaged
+/// over several frames as the cross spectrum C·R* and the auto spectrum |R|² of the reference. Their ratio
+/// is the relative response of the channel, which is not biased by the noise of the channel, and whose
+/// inverse is the correction that makes the channel match the reference.
+/// For example:
+/// ```
+/// use embfft::calibration::ChannelMismatch;
+///
+/// // The second channel has a gain of 0.5 and a phase lag of 90° in every bin
+/// let reference = [(1.0f32, 0.5); 8];
+/// let channel = [(0.25f32, -0.5); 8];
+///
+/// let mut mismatch = ChannelMismatch::new();
+/// mismatch.add(&reference, &channel);
+/// let (gain, phase) = mismatch.gain_phase(3).unwrap();
+/// assert!((gain - 0.5).abs() < 1e-6);
+/// assert!((phase + core::f64::consts::FRAC_PI_2).abs() < 1e-6);
+///
+/// let mut data = channel;
+/// mismatch.table().unwrap().apply(&mut data);
+/// assert!((data[3].0 - 1.0).abs() < 1e-6 && (data[3].1 - 0.5).abs() < 1e-6);
+/// ```
+pub struct ChannelMismatch<T, const N: usize> {
+    cross: [(T, T); N],
+    power: [T; N],
+    count: usize
+}
+
+impl<T: Float<N>, const N: usize> ChannelMismatch<T, N> {
+    /// Initializes an empty accumulator
+    pub fn new() -> Self {
+        Self {
+            cross: [(T::ZERO, T::ZERO); N],
+            power: [T::ZERO; N],
+            count: 0
+        }
+    }
+
+    /// Discards all the accumulated frames
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Accumulates the spectra of a frame of both channels, in natural order
+    pub fn add(&mut self, reference: &[(T, T); N], channel: &[(T, T); N]) {
+        for (k, (r, c)) in core::iter::zip(reference, channel).enumerate() {
+            self.cross[k].0 = self.cross[k].0 + c.0 * r.0 + c.1 * r.1;
+            self.cross[k].1 = self.cross[k].1 + c.1 * r.0 - c.0 * r.1;
+            self.power[k] = self.power[k] + r.0 * r.0 + r.1 * r.1;
+        }
+        self.count += 1;
+    }
+
+    /// Returns the number of accumulated frames
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the relative response of the channel at bin k, or `None` if the stimulus had no energy in
+    /// that bin
+    fn response(&self, k: usize) -> Option<(f64, f64)> {
+        let power = self.power[k].to_f64();
+        let (re, im) = (self.cross[k].0.to_f64() / power, self.cross[k].1.to_f64() / power);
+        if power == 0.0 || (re == 0.0 && im == 0.0) { None } else { Some((re, im)) }
+    }
+
+    /// Returns the gain of the channel relative to the reference at bin k, and its phase in radians
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet, with [`Error::OutOfRange`] if k is
+    /// not lower than N, and with [`Error::InvalidParameter`] if the stimulus had no energy in that bin.
+    pub fn gain_phase(&self, k: usize) -> Result<(f64, f64), Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        } else if k >= N {
+            return Err(Error::OutOfRange);
+        }
+        let (re, im) = self.response(k).ok_or(Error::InvalidParameter)?;
+        Ok((math::sqrt(re * re + im * im), math::atan2(im, re)))
+    }
+
+    /// Builds the table that corrects the spectra of the channel to match the reference
+    ///
+    /// The bins in which the stimulus had no energy are left unchanged.
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet.
+    pub fn table(&self) -> Result<CalibrationTable<T, N>, Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        let correction = core::array::from_fn(|k| match self.response(k) {
+            Some((re, im)) => {
+                let m2 = re * re + im * im;
+                (T::from_f64(re / m2), T::from_f64(-im / m2))
+            },
+            None => (T::ONE, T::ZERO)
+        });
+        Ok(CalibrationTable { correction })
+    }
+}
+
+impl<T: Float<N>, const N: usize> Default for ChannelMismatch<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use crate::synth::Lfsr;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_from_measurement() {
+        let reference: [(f64, f64); 4] = [(1.0, 0.0), (0.0, 2.0), (0.0, 0.0), (-1.0, 1.0)];
+        let measured = [(0.5, 0.5), (0.0, -1.0), (0.0, 0.0), (3.0, 0.0)];
+        let table = CalibrationTable::from_measurement(&measured, &reference).unwrap();
+        assert_eq!(table.correction()[2], (1.0, 0.0));
+
+        let mut data = measured;
+        table.apply(&mut data);
+        for (x, y) in core::iter::zip(data, reference) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-12);
+        }
+
+        let measured = [(0.5, 0.5), (0.0, 0.0), (0.0, 0.0), (3.0, 0.0)];
+        assert_eq!(CalibrationTable::from_measurement(&measured, &reference).err(), Some(Error::InvalidParameter));
+        assert!(CalibrationTable::from_response(&measured).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_mismatch() {
+        let mut mismatch = ChannelMismatch::<f64, 64>::new();
+        assert_eq!(mismatch.gain_phase(0), Err(Error::NotDone));
+        assert!(mismatch.table().is_err());
+
+        // The channel has a gain slope, a delay of 0.3 sample and its own noise, the stimulus is white noise
+        let mut lfsr = Lfsr::new(7);
+        let response = |k: usize| {
+            let f = if k < 32 { k as f64 } else { k as f64 - 64.0 };
+            let (s, c) = cordic::sin_cos(-2.0 * core::f64::consts::PI * 0.3 * f / 64.0);
+            let gain = 0.8 + 0.2 * k as f64 / 64.0;
+            (gain * c, gain * s)
+        };
+        for _ in 0..200 {
+            let mut reference = [(0.0, 0.0); 64];
+            for x in reference.iter_mut() {
+                *x = (lfsr.uniform(), 0.0);
+            }
+            crate::EmbFft::new(&mut reference).fft();
+            let channel: [(f64, f64); 64] = core::array::from_fn(|k| {
+                let (h, r) = (response(k), reference[k]);
+                (h.0 * r.0 - h.1 * r.1 + 0.05 * lfsr.uniform(), h.0 * r.1 + h.1 * r.0 + 0.05 * lfsr.uniform())
+            });
+            mismatch.add(&reference, &channel);
+        }
+        assert_eq!(mismatch.count(), 200);
+        assert_eq!(mismatch.gain_phase(64), Err(Error::OutOfRange));
+        for k in [1, 10, 31, 50] {
+            let h = response(k);
+            let (gain, phase) = mismatch.gain_phase(k).unwrap();
+            assert_abs_diff_eq!(gain, (h.0 * h.0 + h.1 * h.1).sqrt(), epsilon = 5e-3);
+            assert_abs_diff_eq!(phase, h.1.atan2(h.0), epsilon = 5e-3);
+        }
+
+        let table = mismatch.table().unwrap();
+        let mut data: [(f64, f64); 64] = core::array::from_fn(response);
+        table.apply(&mut data);
+        for x in data.iter() {
+            assert_abs_diff_eq!(x.0, 1.0, epsilon = 1e-2);
+            assert_abs_diff_eq!(x.1, 0.0, epsilon = 1e-2);
+        }
+
+        // Without stimulus, the bins are left unchanged
+        mismatch.reset();
+        mismatch.add(&[(0.0, 0.0); 64], &[(1.0, 0.0); 64]);
+        assert_eq!(mismatch.gain_phase(5), Err(Error::InvalidParameter));
+        assert_eq!(mismatch.table().unwrap().correction(), &[(1.0, 0.0); 64]);
+    }
+
+    #[test]
+    fn test_gain_phase() {
+        let table = CalibrationTable::<f64, 4>::from_gain_phase(&[(2.0, core::f64::consts::FRAC_PI_2); 4]);
+        let mut data = [(1.0, 0.0); 4];
+        table.apply(&mut data);
+        assert_abs_diff_eq!(data[1].0, 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(data[1].1, 2.0, epsilon = 1e-12);
+
+        let mut data = [(0.25, -3.0); 4];
+        CalibrationTable::unity().apply(&mut data);
+        assert_eq!(data, [(0.25, -3.0); 4]);
+    }
+}
@@ -5,7 +5,9 @@
 
 /******************************************************************************/
 
-use core::ops::{Add, Mul, Neg, Sub};
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::status::Status;
 
 /******************************************************************************/
 
@@ -53,33 +55,171 @@ impl<const N: usize> Base<N> {
 /******************************************************************************/
 
 /// A trait that allows generic implementations for float types
+///
+/// It is implemented for f32, f64 and the [`fixed_point`](crate::fixed_point) types, and can be implemented
+/// for other number types to run the transforms on them directly.
 pub trait Float<const N: usize>:
-    Copy + Add<Output = Self> + Mul<Output = Self> + Neg<Output = Self> + Sub<Output = Self>
+    Copy + PartialOrd + Add<Output = Self> + Div<Output = Self> + Mul<Output = Self> + Neg<Output = Self> + Sub<Output = Self>
 {
+    /// The value 0
     const ZERO: Self;
+    /// The value 1, or the closest one to it
+    const ONE: Self;
+    /// The value 1 / N, used to scale the inverse transforms
     const N_INV: Self;
+    /// The values of sin(2πi / N) for i below N / 4, the other ones are unused
     const SINE_TABLE: [Self; N];
+
+    /// Converts an integer, typically a count, to this type
+    fn from_usize(x: usize) -> Self;
+
+    /// Converts a double precision floating-point number to this type
+    fn from_f64(x: f64) -> Self;
+
+    /// Converts this type to a double precision floating-point number
+    fn to_f64(self) -> f64;
+
+    /// Returns the distance from this value to the next representable one, away from zero
+    fn ulp(self) -> f64;
+
+    /// Reports abnormal values
+    fn status(self) -> Status;
+
+    /// Adjusts a value computed by a butterfly of the given stage, from 0 to log2(N) - 1
+    ///
+    /// This hook lets a type apply its own scaling or rounding between the stages of the transforms, e.g. a
+    /// shift for block floating-point, or a stochastic rounding. The value is returned unchanged by default.
+    fn scale_stage(self, _stage: usize) -> Self {
+        self
+    }
+
+    /// Computes a × b + c × d, as in the multiplications by the twiddle factors
+    ///
+    /// A type can override it to accumulate the products with a higher precision before rounding.
+    fn sum_of_products(a: Self, b: Self, c: Self, d: Self) -> Self {
+        a * b + c * d
+    }
+
+    /// Computes a × b - c × d, as in the multiplications by the twiddle factors
+    ///
+    /// A type can override it like [`Float::sum_of_products()`].
+    fn difference_of_products(a: Self, b: Self, c: Self, d: Self) -> Self {
+        a * b - c * d
+    }
+}
+
+/// Computes the reciprocal of a count, typically to average a sum
+///
+/// The fixed-point types cannot represent the counts, but their reciprocals are in range.
+pub(crate) fn count_inv<T: Float<N>, const N: usize>(count: usize) -> T {
+    T::from_f64(1.0 / count as f64)
 }
 
 macro_rules! gen_float_impl {
-    ($T: ty) => {
+    ($T: ty, $iter: expr) => {
         impl<const N: usize> Float<N> for $T {
             const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
             const N_INV: Self = 1.0 / N as $T;
             const SINE_TABLE: [Self; N] = {
                 // TODO: the size should be N / 4...
                 let mut table = [0.0; N];
                 let mut i = 1;
                 while i < N / 4 {
-                    table[i] =
-                        crate::cordic::sin(2.0 * core::f64::consts::PI * i as f64 / N as f64) as $T;
+                    let alpha = 2.0 * core::f64::consts::PI * i as f64 / N as f64;
+                    table[i] = crate::cordic::sin_cos_n(alpha, $iter).0 as $T;
                     i += 1;
                 }
                 table
             };
+
+            fn from_usize(x: usize) -> Self {
+                x as $T
+            }
+
+            fn from_f64(x: f64) -> Self {
+                x as $T
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn ulp(self) -> f64 {
+                let x = if self < 0.0 { -self } else { self };
+                (<$T>::from_bits(x.to_bits() + 1) - x) as f64
+            }
+
+            fn status(self) -> Status {
+                if self.is_nan() {
+                    Status::INVALID
+                } else if self.is_infinite() {
+                    Status::OVERFLOW
+                } else if self.is_subnormal() {
+                    Status::DENORMAL
+                } else {
+                    Status::OK
+                }
+            }
         }
     };
 }
 
-gen_float_impl!(f32);
-gen_float_impl!(f64);
+// The f32 tables do not need the full f64 accuracy
+gen_float_impl!(f32, 40);
+gen_float_impl!(f64, crate::cordic::ITERATIONS);
+
+/// Half precision, whose products are accumulated in single precision
+#[cfg(feature = "half")]
+impl<const N: usize> Float<N> for half::f16 {
+    const ZERO: Self = half::f16::ZERO;
+    const ONE: Self = half::f16::ONE;
+    const N_INV: Self = half::f16::from_f64_const(1.0 / N as f64);
+    const SINE_TABLE: [Self; N] = {
+        let mut table = [half::f16::ZERO; N];
+        let mut i = 1;
+        while i < N / 4 {
+            let alpha = 2.0 * core::f64::consts::PI * i as f64 / N as f64;
+            table[i] = half::f16::from_f64_const(crate::cordic::sin_cos_n(alpha, 40).0);
+            i += 1;
+        }
+        table
+    };
+
+    fn from_usize(x: usize) -> Self {
+        half::f16::from_f64(x as f64)
+    }
+
+    fn from_f64(x: f64) -> Self {
+        half::f16::from_f64(x)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.to_f64()
+    }
+
+    fn ulp(self) -> f64 {
+        let x = half::f16::from_bits(self.to_bits() & 0x7FFF);
+        half::f16::from_bits(x.to_bits() + 1).to_f64() - x.to_f64()
+    }
+
+    fn status(self) -> Status {
+        if self.is_nan() {
+            Status::INVALID
+        } else if self.is_infinite() {
+            Status::OVERFLOW
+        } else if !self.is_normal() && self.to_bits() & 0x7FFF != 0 {
+            Status::DENORMAL
+        } else {
+            Status::OK
+        }
+    }
+
+    fn sum_of_products(a: Self, b: Self, c: Self, d: Self) -> Self {
+        half::f16::from_f32(a.to_f32() * b.to_f32() + c.to_f32() * d.to_f32())
+    }
+
+    fn difference_of_products(a: Self, b: Self, c: Self, d: Self) -> Self {
+        half::f16::from_f32(a.to_f32() * b.to_f32() - c.to_f32() * d.to_f32())
+    }
+}
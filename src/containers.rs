@@ -0,0 +1,202 @@
+/* embfft | containers.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Fixed-capacity containers for the processing results
+//!
+//! Requires the `heapless` feature.
+
+/******************************************************************************/
+
+use heapless::{Deque, Vec};
+
+use crate::mask::ExclusionMask;
+
+/******************************************************************************/
+
+/// Local maximum of a spectrum
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Peak<T> {
+    /// Index of the bin
+    pub bin: usize,
+    /// Value of the bin
+    pub value: T
+}
+
+/// Peaks of a spectrum, strongest first
+pub type PeakList<T, const M: usize> = Vec<Peak<T>, M>;
+
+/// Bin that exceeded a threshold
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Detection<T> {
+    /// Index of the frame, counted since the creation of the history
+    pub frame: usize,
+    /// Index of the bin
+    pub bin: usize,
+    /// Value of the bin
+    pub value: T
+}
+
+/// Detections, oldest first
+pub type DetectionList<T, const M: usize> = Vec<Detection<T>, M>;
+
+/// Finds the M strongest local maxima of a spectrum that reach a threshold
+///
+/// For example:
+/// ```
+/// let spectrum = [0.0, 3.0, 1.0, 0.5, 4.0, 2.0, 0.0, 1.0];
+/// let peaks = embfft::containers::find_peaks::<f32, 8, 2>(&spectrum, 1.0);
+/// assert_eq!(peaks.iter().map(|p| p.bin).collect::<Vec<_>>(), [4, 1]);
+/// ```
+pub fn find_peaks<T: Copy + PartialOrd, const N: usize, const M: usize>(spectrum: &[T; N], threshold: T) -> PeakList<T, M> {
+    find_peaks_excluding(spectrum, threshold, &ExclusionMask::new())
+}
+
+/// Finds the M strongest local maxima of a spectrum that reach a threshold, ignoring the excluded bins
+///
+/// The excluded bins are neither reported nor compared to their neighbours.
+pub fn find_peaks_excluding<T: Copy + PartialOrd, const N: usize, const M: usize>(spectrum: &[T; N], threshold: T, mask: &ExclusionMask<N>) -> PeakList<T, M> {
+    let mut peaks = PeakList::new();
+    for (bin, &value) in spectrum.iter().enumerate() {
+        let rising = bin == 0 || mask.is_excluded(bin - 1) || value > spectrum[bin - 1];
+        let falling = bin == N - 1 || mask.is_excluded(bin + 1) || value >= spectrum[bin + 1];
+        if mask.is_excluded(bin) || value < threshold || !rising || !falling {
+            continue;
+        }
+
+        // Keep the list sorted, dropping the weakest peak if needed
+        let idx = peaks.iter().position(|p: &Peak<T>| p.value < value).unwrap_or(peaks.len());
+        if idx == M {
+            continue;
+        }
+        if peaks.is_full() {
+            peaks.pop();
+        }
+        let _ = peaks.insert(idx, Peak { bin, value });
+    }
+    peaks
+}
+
+/// History of the last M spectra
+///
+/// For example:
+/// ```
+/// let mut history = embfft::containers::SpectrumHistory::<f32, 4, 2>::new();
+/// history.push(&[0.0, 1.0, 0.0, 0.0]);
+/// history.push(&[0.0, 0.0, 5.0, 0.0]);
+/// history.push(&[3.0, 0.0, 0.0, 0.0]);
+///
+/// let detections = history.detect::<4>(2.0);
+/// assert_eq!(detections.iter().map(|d| (d.frame, d.bin)).collect::<Vec<_>>(), [(1, 2), (2, 0)]);
+/// ```
+pub struct SpectrumHistory<T, const N: usize, const M: usize> {
+    frames: Deque<[T; N], M>,
+    count: usize
+}
+
+impl<T: Copy + PartialOrd, const N: usize, const M: usize> SpectrumHistory<T, N, M> {
+    /// Initializes an empty history
+    pub fn new() -> Self {
+        Self { frames: Deque::new(), count: 0 }
+    }
+
+    /// Discards all the spectra
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Adds a new spectrum, discarding the oldest one if the history is full
+    pub fn push(&mut self, spectrum: &[T; N]) {
+        if self.frames.is_full() {
+            self.frames.pop_front();
+        }
+        let _ = self.frames.push_back(*spectrum);
+        self.count += 1;
+    }
+
+    /// Returns the number of spectra in the history
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Checks if the history is empty
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the most recent spectrum
+    pub fn latest(&self) -> Option<&[T; N]> {
+        self.frames.back()
+    }
+
+    /// Iterates over the spectra, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &[T; N]> {
+        self.frames.iter()
+    }
+
+    /// Lists the first L bins of the history that reach a threshold
+    pub fn detect<const L: usize>(&self, threshold: T) -> DetectionList<T, L> {
+        let mut detections = DetectionList::new();
+        let first = self.count - self.frames.len();
+        for (i, spectrum) in self.frames.iter().enumerate() {
+            for (bin, &value) in spectrum.iter().enumerate() {
+                if value >= threshold && detections.push(Detection { frame: first + i, bin, value }).is_err() {
+                    return detections;
+                }
+            }
+        }
+        detections
+    }
+}
+
+impl<T: Copy + PartialOrd, const N: usize, const M: usize> Default for SpectrumHistory<T, N, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_peaks() {
+        let spectrum = [5.0, 1.0, 2.0, 2.0, 1.0, 3.0, 0.0, 4.0];
+        let peaks = find_peaks::<f64, 8, 3>(&spectrum, 2.0);
+        let expected = [Peak { bin: 0, value: 5.0 }, Peak { bin: 7, value: 4.0 }, Peak { bin: 5, value: 3.0 }];
+        assert_eq!(peaks.as_slice(), expected);
+
+        // The plateau counts as a single peak
+        let peaks = find_peaks::<f64, 8, 8>(&spectrum, 0.0);
+        assert_eq!(peaks.len(), 4);
+        assert_eq!(peaks[3], Peak { bin: 2, value: 2.0 });
+
+        // The excluded bins are not reported, even if higher than their neighbours
+        let mut mask = ExclusionMask::new();
+        mask.exclude(0).unwrap();
+        mask.exclude(6).unwrap();
+        let peaks = find_peaks_excluding::<f64, 8, 8>(&spectrum, 0.0, &mask);
+        assert_eq!(peaks.iter().map(|p| p.bin).collect::<Vec<_, 8>>(), [7, 5, 2]);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = SpectrumHistory::<u8, 2, 2>::new();
+        assert!(history.is_empty());
+        assert_eq!(history.latest(), None);
+        for i in 0..3 {
+            history.push(&[i, 2 * i]);
+        }
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.latest(), Some(&[2, 4]));
+        assert_eq!(history.iter().next(), Some(&[1, 2]));
+
+        let detections = history.detect::<2>(2);
+        assert_eq!(detections.as_slice(), [Detection { frame: 1, bin: 1, value: 2 }, Detection { frame: 2, bin: 0, value: 2 }]);
+    }
+}
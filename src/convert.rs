@@ -0,0 +1,273 @@
+/* embfft | convert.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Conversions between sample formats
+//!
+//! A typical pipeline reads i16 samples from an ADC, transforms them in fixed-point, and post-processes
+//! the spectrum in floating-point. These passes convert whole buffers between the formats, scaling the
+//! integers to the -1 to 1 range of the fixed-point types, and saturating the values out of range.
+//! For example:
+//! ```
+//! use embfft::convert::{convert_complex, convert_to_complex};
+//! use embfft::fixed_point::Q15;
+//!
+//! let adc: [i16; 8] = [0, 4096, 8192, 4096, 0, -4096, -8192, -4096];
+//! let mut data: [(Q15, Q15); 8] = [(Q15::from_bits(0), Q15::from_bits(0)); 8];
+//! convert_to_complex(&adc, &mut data).unwrap();
+//! embfft::EmbFft::new(&mut data).fft();
+//!
+//! let mut spectrum = [(0.0f32, 0.0); 8];
+//! convert_complex(&data, &mut spectrum).unwrap();
+//! assert!((spectrum[1].1 + 0.854).abs() < 1e-3);
+//! ```
+
+/******************************************************************************/
+
+use crate::error::Error;
+use crate::fixed_point::{Q15, Q31};
+
+/******************************************************************************/
+
+/// Conversion from another sample format
+///
+/// The integers are scaled to the -1 to 1 range, i16 being equivalent to [`Q15`] and i32 to [`Q31`]. The
+/// values out of range saturate to the nearest representable one.
+pub trait ConvertFrom<S>: Sized {
+    /// Converts a sample
+    fn convert_from(x: S) -> Self;
+}
+
+/// Same values, for the generic pipelines
+impl ConvertFrom<f32> for f32 {
+    fn convert_from(x: f32) -> Self {
+        x
+    }
+}
+
+/// Same values, for the generic pipelines
+impl ConvertFrom<f64> for f64 {
+    fn convert_from(x: f64) -> Self {
+        x
+    }
+}
+
+impl ConvertFrom<f32> for f64 {
+    fn convert_from(x: f32) -> Self {
+        x as f64
+    }
+}
+
+/// Rounds to the nearest value, the values out of range saturate to the largest finite one
+impl ConvertFrom<f64> for f32 {
+    fn convert_from(x: f64) -> Self {
+        if x.is_nan() { f32::NAN } else { x.clamp(f32::MIN as f64, f32::MAX as f64) as f32 }
+    }
+}
+
+#[cfg(feature = "half")]
+impl ConvertFrom<half::f16> for f32 {
+    fn convert_from(x: half::f16) -> Self {
+        x.to_f32()
+    }
+}
+
+/// Rounds to the nearest value, the values out of range overflow to infinity
+#[cfg(feature = "half")]
+impl ConvertFrom<f32> for half::f16 {
+    fn convert_from(x: f32) -> Self {
+        half::f16::from_f32(x)
+    }
+}
+
+/// Same values, in the layout of the `num-complex` crate
+#[cfg(feature = "num-complex")]
+impl<T> ConvertFrom<(T, T)> for num_complex::Complex<T> {
+    fn convert_from(x: (T, T)) -> Self {
+        Self::new(x.0, x.1)
+    }
+}
+
+/// Same values, in the layout of the transforms
+#[cfg(feature = "num-complex")]
+impl<T> ConvertFrom<num_complex::Complex<T>> for (T, T) {
+    fn convert_from(x: num_complex::Complex<T>) -> Self {
+        (x.re, x.im)
+    }
+}
+
+macro_rules! gen_convert_impl {
+    ($Q: ident, $I: ty) => {
+        impl<O, R> ConvertFrom<$I> for $Q<O, R> {
+            fn convert_from(x: $I) -> Self {
+                Self::from_bits(x)
+            }
+        }
+
+        impl<O, R> ConvertFrom<$Q<O, R>> for $I {
+            fn convert_from(x: $Q<O, R>) -> Self {
+                x.to_bits()
+            }
+        }
+
+        impl<O, R> ConvertFrom<f32> for $Q<O, R> {
+            fn convert_from(x: f32) -> Self {
+                Self::from_f32(x)
+            }
+        }
+
+        impl<O, R> ConvertFrom<$Q<O, R>> for f32 {
+            fn convert_from(x: $Q<O, R>) -> Self {
+                x.to_f32()
+            }
+        }
+
+        impl<O, R> ConvertFrom<f64> for $Q<O, R> {
+            fn convert_from(x: f64) -> Self {
+                Self::from_f64(x)
+            }
+        }
+
+        impl<O, R> ConvertFrom<$Q<O, R>> for f64 {
+            fn convert_from(x: $Q<O, R>) -> Self {
+                x.to_f64()
+            }
+        }
+
+        impl ConvertFrom<$I> for f32 {
+            fn convert_from(x: $I) -> Self {
+                <$Q>::from_bits(x).to_f32()
+            }
+        }
+
+        impl ConvertFrom<$I> for f64 {
+            fn convert_from(x: $I) -> Self {
+                <$Q>::from_bits(x).to_f64()
+            }
+        }
+    };
+}
+
+gen_convert_impl!(Q15, i16);
+gen_convert_impl!(Q31, i32);
+
+/******************************************************************************/
+
+/// Converts a buffer of samples to another format
+///
+/// Fails with [`Error::InvalidLength`] if `input` and `output` have different lengths.
+pub fn convert<S: Copy, D: ConvertFrom<S>>(input: &[S], output: &mut [D]) -> Result<(), Error> {
+    if input.len() != output.len() {
+        return Err(Error::InvalidLength);
+    }
+    for (x, y) in input.iter().zip(output.iter_mut()) {
+        *y = D::convert_from(*x);
+    }
+    Ok(())
+}
+
+/// Converts a buffer of complex samples, such as a spectrum, to another format
+///
+/// Fails with [`Error::InvalidLength`] if `input` and `output` have different lengths.
+pub fn convert_complex<S: Copy, D: ConvertFrom<S>>(input: &[(S, S)], output: &mut [(D, D)]) -> Result<(), Error> {
+    if input.len() != output.len() {
+        return Err(Error::InvalidLength);
+    }
+    for (x, y) in input.iter().zip(output.iter_mut()) {
+        *y = (D::convert_from(x.0), D::convert_from(x.1));
+    }
+    Ok(())
+}
+
+/// Converts a buffer of real samples into the real parts of a complex buffer, clearing the imaginary parts
+///
+/// Fails with [`Error::InvalidLength`] if `input` and `output` have different lengths.
+pub fn convert_to_complex<S: Copy, D: ConvertFrom<S> + Default>(input: &[S], output: &mut [(D, D)]) -> Result<(), Error> {
+    if input.len() != output.len() {
+        return Err(Error::InvalidLength);
+    }
+    for (x, y) in input.iter().zip(output.iter_mut()) {
+        *y = (D::convert_from(*x), D::default());
+    }
+    Ok(())
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::Saturating;
+
+    #[test]
+    fn test_convert() {
+        let mut q15 = [Q15::<Saturating>::from_bits(0); 4];
+        assert_eq!(convert(&[0i16; 3], &mut q15), Err(Error::InvalidLength));
+
+        // i16 and Q15 share the same representation
+        convert(&[i16::MIN, -1, 1, i16::MAX], &mut q15).unwrap();
+        let mut float = [0.0f32; 4];
+        convert(&q15, &mut float).unwrap();
+        assert_eq!(float, [-1.0, -1.0 / 32768.0, 1.0 / 32768.0, 32767.0 / 32768.0]);
+
+        // Saturated back to the fixed-point range
+        convert(&[-2.0f32, -0.5, 0.25, 1.0], &mut q15).unwrap();
+        let mut int = [0i16; 4];
+        convert(&q15, &mut int).unwrap();
+        assert_eq!(int, [i16::MIN, -16384, 8192, i16::MAX]);
+
+        let mut q31 = [Q31::<Saturating>::from_bits(0); 2];
+        convert(&[0.5f64, -1.0], &mut q31).unwrap();
+        let mut int = [0i32; 2];
+        convert(&q31, &mut int).unwrap();
+        assert_eq!(int, [1 << 30, i32::MIN]);
+
+        // The integers are scaled like the fixed-point types
+        let mut float = [0.0f64; 2];
+        convert(&[i16::MIN, 8192], &mut float).unwrap();
+        assert_eq!(float, [-1.0, 0.25]);
+        convert(&[1i32 << 30, -1], &mut float).unwrap();
+        assert_eq!(float, [0.5, -1.0 / 2147483648.0]);
+    }
+
+    #[test]
+    fn test_float() {
+        let mut single = [0.0f32; 3];
+        convert(&[0.1f64, 1e300, -1e300], &mut single).unwrap();
+        assert_eq!(single, [0.1, f32::MAX, f32::MIN]);
+        let mut double = [0.0f64; 3];
+        convert(&single, &mut double).unwrap();
+        assert_eq!(double[0], 0.1f32 as f64);
+
+        let mut complex = [(0.0f64, 0.0); 2];
+        assert_eq!(convert_to_complex(&single, &mut complex), Err(Error::InvalidLength));
+        convert_to_complex(&single[..2], &mut complex).unwrap();
+        assert_eq!(complex[1], (f32::MAX as f64, 0.0));
+        assert_eq!(convert_complex(&complex, &mut [(0.0f32, 0.0); 3]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    #[cfg(feature = "half")]
+    fn test_half() {
+        let mut half = [half::f16::ZERO; 3];
+        convert(&[0.5f32, 1e5, -1.0 / 3.0], &mut half).unwrap();
+        let mut single = [0.0f32; 3];
+        convert(&half, &mut single).unwrap();
+        assert_eq!(single[..2], [0.5, f32::INFINITY]);
+        assert!((single[2] + 1.0 / 3.0).abs() < 1e-3);
+    }
+
+    #[test]
+    #[cfg(feature = "num-complex")]
+    fn test_complex() {
+        use num_complex::Complex;
+
+        let mut complex = [Complex::new(0.0f32, 0.0); 2];
+        convert(&[(1.0f32, 2.0), (3.0, -4.0)], &mut complex).unwrap();
+        assert_eq!(complex, [Complex::new(1.0, 2.0), Complex::new(3.0, -4.0)]);
+        let mut tuples = [(0.0f32, 0.0); 2];
+        convert(&complex, &mut tuples).unwrap();
+        assert_eq!(tuples, [(1.0, 2.0), (3.0, -4.0)]);
+    }
+}
@@ -0,0 +1,480 @@
+/* embfft | convolve.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Fast convolution
+//!
+//! The convolution is circular: to compute a linear convolution, the data must be zero-padded so that
+//! the sum of the signal and kernel lengths, minus one, does not exceed N.
+//! Long kernels, such as room impulse responses, can be split into partitions of N / 2 samples with a
+//! [`PartitionedConvolver`], which streams blocks of N / 2 samples with a latency of a single block.
+//! A [`MatchedFilterBank`] correlates each frame with several templates at once, sharing the FFT of the
+//! frame between them.
+
+/******************************************************************************/
+
+use crate::common::{Base, Float};
+use crate::{EmbFft, EmbIfft, Error, Status};
+
+/******************************************************************************/
+
+/// Kernel spectrum, computed once and cached for successive convolutions
+///
+/// The spectrum is stored in bit-reversed order, so that no reordering pass is required. The 1 / N scaling
+/// of the inverse transform is also folded into it.
+pub struct FftKernel<T, const N: usize> {
+    spectrum: [(T, T); N]
+}
+
+impl<T: Float<N>, const N: usize> FftKernel<T, N> {
+    /// Computes the spectrum of a time-domain kernel
+    ///
+    /// This is a blocking function, which performs an FFT.
+    pub fn new(kernel: &[(T, T); N]) -> Self {
+        let mut spectrum = *kernel;
+        EmbFft::new_unordered(&mut spectrum).fft();
+        for x in spectrum.iter_mut() {
+            *x = (x.0 * T::N_INV, x.1 * T::N_INV);
+        }
+        Self { spectrum }
+    }
+
+    /// Uses a frequency response, sorted by frequency, as kernel spectrum
+    pub fn from_spectrum(spectrum: &[(T, T); N]) -> Self {
+        assert!(Base::<N>::IS_N_POW2);
+        Self {
+            spectrum: core::array::from_fn(|k| {
+                let x = spectrum[Base::<N>::reverse_bits(k)];
+                (x.0 * T::N_INV, x.1 * T::N_INV)
+            })
+        }
+    }
+}
+
+/// Processing stage
+enum Stage<'a, T, const N: usize> {
+    Forward(EmbFft<'a, T, N>),
+    Multiply(&'a mut [(T, T); N], usize),
+    Inverse(EmbIfft<'a, T, N>),
+    Empty
+}
+
+/// Fast convolution of the data with a cached kernel spectrum
+///
+/// This structure chains a forward transform, the per-bin multiplication by the kernel spectrum and an
+/// inverse transform into a single non-blocking state machine, without any reordering pass.
+/// For example:
+/// ```
+/// use embfft::convolve::{FftConvolver, FftKernel};
+///
+/// let mut kernel = [(0.0f32, 0.0); 8];
+/// kernel[1] = (1.0, 0.0);
+/// let kernel = FftKernel::new(&kernel);
+///
+/// let mut data = [
+///     (1.0f32, 1.0), (2.0, 2.0),
+///     (3.0f32, 3.0), (4.0, 4.0),
+///     (5.0f32, 5.0), (6.0, 6.0),
+///     (7.0f32, 7.0), (8.0, 8.0)
+/// ];
+///
+/// let mut conv = FftConvolver::new(&kernel, &mut data);
+/// while !conv.is_done() {
+///     conv.convolve_iterate();
+///     // Other actions can be performed here between two iterations
+/// }
+/// ```
+pub struct FftConvolver<'a, T, const N: usize> {
+    kernel: &'a FftKernel<T, N>,
+    stage: Stage<'a, T, N>,
+    status: Status
+}
+
+impl<'a, T: Float<N>, const N: usize> FftConvolver<'a, T, N> {
+    /// Initializes a new convolution
+    ///
+    /// Use this function whenever a new convolution is required.
+    pub fn new(kernel: &'a FftKernel<T, N>, data: &'a mut [(T, T); N]) -> Self {
+        Self {
+            kernel,
+            stage: Stage::Forward(EmbFft::new_unordered(data)),
+            status: Status::OK
+        }
+    }
+
+    /// Non-blocking convolution
+    ///
+    /// Use this together with the [`FftConvolver::is_done()`] function.
+    pub fn convolve_iterate(&mut self) {
+        self.stage = match core::mem::replace(&mut self.stage, Stage::Empty) {
+            Stage::Forward(mut fft) => {
+                fft.fft_iterate();
+                if fft.is_done() {
+                    self.status |= fft.status();
+                    Stage::Multiply(fft.into_data(), 0)
+                } else {
+                    Stage::Forward(fft)
+                }
+            },
+            Stage::Multiply(data, k) => {
+                let x = data[k];
+                let h = self.kernel.spectrum[k];
+                data[k] = (x.0 * h.0 - x.1 * h.1, x.0 * h.1 + x.1 * h.0);
+                self.status |= data[k].0.status() | data[k].1.status();
+                if k < N - 1 {
+                    Stage::Multiply(data, k + 1)
+                } else {
+                    Stage::Inverse(EmbIfft::new_unordered(data).unscaled())
+                }
+            },
+            Stage::Inverse(mut ifft) => {
+                ifft.ifft_iterate();
+                Stage::Inverse(ifft)
+            },
+            Stage::Empty => Stage::Empty
+        };
+    }
+
+    /// Blocking convolution
+    pub fn convolve(&mut self) {
+        while !self.is_done() {
+            self.convolve_iterate();
+        }
+    }
+
+    /// Checks if the convolution is complete
+    ///
+    /// Use this together with the [`FftConvolver::convolve_iterate()`] function.
+    pub fn is_done(&self) -> bool {
+        match &self.stage {
+            Stage::Inverse(ifft) => ifft.is_done(),
+            _ => false
+        }
+    }
+
+    /// Returns the status flags accumulated so far by all the processing stages
+    pub fn status(&self) -> Status {
+        match &self.stage {
+            Stage::Forward(fft) => self.status | fft.status(),
+            Stage::Inverse(ifft) => self.status | ifft.status(),
+            _ => self.status
+        }
+    }
+
+    /// Consumes the convolution and hands back the data buffer
+    ///
+    /// If the convolution is not complete, the buffer contains partially processed data.
+    pub fn into_data(self) -> &'a mut [(T, T); N] {
+        match self.stage {
+            Stage::Forward(fft) => fft.into_data(),
+            Stage::Multiply(data, _) => data,
+            Stage::Inverse(ifft) => ifft.into_data(),
+            Stage::Empty => unreachable!()
+        }
+    }
+}
+
+/******************************************************************************/
+
+/// Peak of the correlation of a frame with a template
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CorrelationPeak<T> {
+    /// Delay of the template in the frame, in samples
+    pub lag: usize,
+    /// Complex correlation at that delay
+    pub value: (T, T),
+    /// Squared magnitude of the correlation at that delay
+    pub power: T
+}
+
+/// Bank of K matched filters, with the spectra of the templates cached
+///
+/// Each frame is transformed once, then correlated with all the templates, e.g. the chirps of an
+/// ultrasonic ranging system, at the cost of a spectrum product and an IFFT per template. As with any
+/// fast convolution, the correlation is circular: a template delayed past the end of the frame wraps
+/// around to its start.
+/// For example:
+/// ```
+/// use embfft::convolve::{CorrelationPeak, MatchedFilterBank};
+///
+/// let up = [(1.0f32, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)];
+/// let down = [(1.0f32, 0.0), (0.0, -1.0), (-1.0, 0.0), (0.0, 1.0)];
+/// let bank = MatchedFilterBank::<f32, 16, 2>::new([&up, &down]).unwrap();
+///
+/// // Echo of the down chirp, delayed by 5 samples
+/// let mut frame = [(0.0f32, 0.0); 16];
+/// frame[5..9].copy_from_slice(&down);
+/// let mut peaks = [CorrelationPeak { lag: 0, value: (0.0, 0.0), power: 0.0 }; 2];
+/// bank.correlate(&frame, &mut peaks);
+/// assert_eq!(peaks[1].lag, 5);
+/// assert!(peaks[1].power > 4.0 * peaks[0].power);
+/// ```
+pub struct MatchedFilterBank<T, const N: usize, const K: usize> {
+    kernels: [FftKernel<T, N>; K]
+}
+
+impl<T: Float<N>, const N: usize, const K: usize> MatchedFilterBank<T, N, K> {
+    /// Computes the spectra of the matched filters of the templates, of up to N samples each
+    ///
+    /// This is a blocking function, which performs K FFTs.
+    /// Fails with [`Error::InvalidLength`] if a template is longer than N.
+    pub fn new(templates: [&[(T, T)]; K]) -> Result<Self, Error> {
+        if templates.iter().any(|template| template.len() > N) {
+            return Err(Error::InvalidLength);
+        }
+        Ok(Self {
+            kernels: core::array::from_fn(|i| {
+                // The matched filter is the time-reversed conjugate of the template
+                let mut kernel = [(T::ZERO, T::ZERO); N];
+                for (n, x) in templates[i].iter().enumerate() {
+                    kernel[(N - n) % N] = (x.0, T::ZERO - x.1);
+                }
+                FftKernel::new(&kernel)
+            })
+        })
+    }
+
+    /// Correlates a frame with all the templates, and finds the peak of each correlation
+    ///
+    /// This is a blocking function, which performs an FFT and K IFFTs, and returns the status flags of the
+    /// processing.
+    pub fn correlate(&self, frame: &[(T, T); N], peaks: &mut [CorrelationPeak<T>; K]) -> Status {
+        let mut spectrum = *frame;
+        let mut fft = EmbFft::new_unordered(&mut spectrum);
+        fft.fft();
+        let mut status = fft.status();
+
+        for (peak, kernel) in peaks.iter_mut().zip(self.kernels.iter()) {
+            let mut correlation = [(T::ZERO, T::ZERO); N];
+            for ((y, x), h) in correlation.iter_mut().zip(spectrum.iter()).zip(kernel.spectrum.iter()) {
+                *y = (x.0 * h.0 - x.1 * h.1, x.0 * h.1 + x.1 * h.0);
+            }
+            let mut ifft = EmbIfft::new_unordered(&mut correlation).unscaled();
+            ifft.ifft();
+            status |= ifft.status();
+
+            *peak = CorrelationPeak { lag: 0, value: correlation[0], power: T::ZERO - T::ONE };
+            for (lag, &value) in correlation.iter().enumerate() {
+                let power = value.0 * value.0 + value.1 * value.1;
+                if power > peak.power {
+                    *peak = CorrelationPeak { lag, value, power };
+                }
+            }
+        }
+        status
+    }
+}
+
+/******************************************************************************/
+
+/// Kernel split into P partitions of N / 2 samples, with the spectrum of each partition cached
+pub struct PartitionedKernel<T, const N: usize, const P: usize> {
+    partitions: [FftKernel<T, N>; P]
+}
+
+impl<T: Float<N>, const N: usize, const P: usize> PartitionedKernel<T, N, P> {
+    /// Splits a time-domain kernel of up to P × N / 2 samples, and computes the spectra of the partitions
+    ///
+    /// This is a blocking function, which performs P FFTs.
+    /// Fails with [`Error::InvalidLength`] if the kernel is too long.
+    pub fn new(kernel: &[(T, T)]) -> Result<Self, Error> {
+        let block = N / 2;
+        if kernel.len() > P * block {
+            return Err(Error::InvalidLength);
+        }
+        Ok(Self {
+            partitions: core::array::from_fn(|p| {
+                let mut partition = [(T::ZERO, T::ZERO); N];
+                let start = (p * block).min(kernel.len());
+                let end = ((p + 1) * block).min(kernel.len());
+                partition[..end - start].copy_from_slice(&kernel[start..end]);
+                FftKernel::new(&partition)
+            })
+        })
+    }
+}
+
+/// Uniformly partitioned convolution, for long kernels with a low latency
+///
+/// Each block of N / 2 samples is transformed together with the previous block (overlap-save), and its
+/// spectrum is stored in a frequency-domain delay line of P spectra. The output block is the sum of the
+/// products of these spectra with the kernel partitions, so that the latency is a single block whatever
+/// the kernel length.
+/// For example:
+/// ```
+/// use embfft::convolve::{PartitionedConvolver, PartitionedKernel};
+///
+/// // 20-sample echo, convolved by blocks of 4 samples
+/// let mut kernel = [(0.0f32, 0.0); 21];
+/// kernel[20] = (1.0, 0.0);
+/// let kernel = PartitionedKernel::<f32, 8, 6>::new(&kernel).unwrap();
+/// let mut conv = PartitionedConvolver::new(&kernel);
+///
+/// let mut block = [(1.0f32, 0.0); 4];
+/// conv.process(&mut block).unwrap();
+/// ```
+pub struct PartitionedConvolver<'a, T, const N: usize, const P: usize> {
+    kernel: &'a PartitionedKernel<T, N, P>,
+    frame: [(T, T); N],
+    history: [[(T, T); N]; P],
+    head: usize
+}
+
+impl<'a, T: Float<N>, const N: usize, const P: usize> PartitionedConvolver<'a, T, N, P> {
+    /// Initializes a new convolution, with a silent history
+    pub fn new(kernel: &'a PartitionedKernel<T, N, P>) -> Self {
+        const { assert!(P > 0, "P must be greater than 0") };
+        Self {
+            kernel,
+            frame: [(T::ZERO, T::ZERO); N],
+            history: [[(T::ZERO, T::ZERO); N]; P],
+            head: 0
+        }
+    }
+
+    /// Clears the history
+    pub fn reset(&mut self) {
+        self.frame = [(T::ZERO, T::ZERO); N];
+        self.history = [[(T::ZERO, T::ZERO); N]; P];
+        self.head = 0;
+    }
+
+    /// Convolves the next block of N / 2 samples, in place
+    ///
+    /// This is a blocking function, which performs an FFT, P spectrum products and an IFFT, and returns
+    /// the status flags of the processing.
+    /// Fails with [`Error::InvalidLength`] if the block length is not N / 2.
+    pub fn process(&mut self, block: &mut [(T, T)]) -> Result<Status, Error> {
+        let half = N / 2;
+        if block.len() != half {
+            return Err(Error::InvalidLength);
+        }
+
+        // Overlap-save: the previous block followed by the new one
+        self.frame.copy_within(half.., 0);
+        self.frame[half..].copy_from_slice(block);
+        let spectrum = &mut self.history[self.head];
+        *spectrum = self.frame;
+        let mut fft = EmbFft::new_unordered(spectrum);
+        fft.fft();
+        let mut status = fft.status();
+
+        // Frequency-domain delay line: partition p is applied to the spectrum of p blocks ago
+        let mut sum = [(T::ZERO, T::ZERO); N];
+        for (p, partition) in self.kernel.partitions.iter().enumerate() {
+            let x = &self.history[(self.head + P - p) % P];
+            for ((y, x), h) in sum.iter_mut().zip(x.iter()).zip(partition.spectrum.iter()) {
+                *y = (y.0 + x.0 * h.0 - x.1 * h.1, y.1 + x.0 * h.1 + x.1 * h.0);
+            }
+        }
+        for y in sum.iter() {
+            status |= y.0.status() | y.1.status();
+        }
+        self.head = (self.head + 1) % P;
+
+        let mut ifft = EmbIfft::new_unordered(&mut sum).unscaled();
+        ifft.ifft();
+        status |= ifft.status();
+        block.copy_from_slice(&sum[half..]);
+        Ok(status)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_convolve() {
+        // Circular convolution with a delayed and scaled impulse
+        let mut kernel = [(0.0f64, 0.0); 16];
+        kernel[2] = (0.5, 0.0);
+        kernel[3] = (0.0, 1.0);
+        let kernel = FftKernel::new(&kernel);
+
+        let mut data: [(f64, f64); 16] = core::array::from_fn(|i| (i as f64, 1.0));
+        let input = data;
+        let mut conv = FftConvolver::new(&kernel, &mut data);
+        assert!(!conv.is_done());
+        conv.convolve();
+        assert!(conv.is_done());
+
+        let data = conv.into_data();
+        for (i, y) in data.iter().enumerate() {
+            let a = input[(i + 14) % 16];
+            let b = input[(i + 13) % 16];
+            assert_abs_diff_eq!(y.0, 0.5 * a.0 - b.1, epsilon = 1e-12);
+            assert_abs_diff_eq!(y.1, 0.5 * a.1 + b.0, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_partitioned() {
+        let kernel: [(f64, f64); 37] = core::array::from_fn(|i| (((i * 5) % 9) as f64 - 4.0, (i % 2) as f64));
+        let input: [(f64, f64); 80] = core::array::from_fn(|i| (((i * 7) % 11) as f64 - 5.0, 0.0));
+        assert_eq!(PartitionedKernel::<f64, 16, 4>::new(&kernel).err(), Some(Error::InvalidLength));
+        let partitions = PartitionedKernel::<f64, 16, 5>::new(&kernel).unwrap();
+        let mut conv = PartitionedConvolver::new(&partitions);
+        assert_eq!(conv.process(&mut [(0.0, 0.0); 16]), Err(Error::InvalidLength));
+
+        let mut output = input;
+        for block in output.chunks_mut(8) {
+            assert!(!conv.process(block).unwrap().is_corrupted());
+        }
+        for (n, y) in output.iter().enumerate() {
+            let mut expected = (0.0, 0.0);
+            for (i, h) in kernel.iter().enumerate().take(n + 1) {
+                let x = input[n - i];
+                expected = (expected.0 + x.0 * h.0 - x.1 * h.1, expected.1 + x.0 * h.1 + x.1 * h.0);
+            }
+            assert_abs_diff_eq!(y.0, expected.0, epsilon = 1e-9);
+            assert_abs_diff_eq!(y.1, expected.1, epsilon = 1e-9);
+        }
+
+        conv.reset();
+        let mut block = [(1.0, 0.0); 8];
+        conv.process(&mut block).unwrap();
+        assert_abs_diff_eq!(block[0].0, kernel[0].0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_matched_bank() {
+        let templates: [[(f64, f64); 5]; 3] =
+            core::array::from_fn(|t| core::array::from_fn(|i| (((i * (t + 2)) % 5) as f64 - 2.0, ((i + t) % 3) as f64 - 1.0)));
+        assert_eq!(MatchedFilterBank::<f64, 4, 1>::new([&templates[0]]).err(), Some(Error::InvalidLength));
+        let bank = MatchedFilterBank::<f64, 32, 3>::new([&templates[0], &templates[1], &templates[2]]).unwrap();
+
+        // Second template wrapped around the end of the frame, scaled by 1 - j
+        let mut frame = [(0.0, 0.0); 32];
+        for (i, x) in templates[1].iter().enumerate() {
+            frame[(30 + i) % 32] = (x.0 + x.1, x.1 - x.0);
+        }
+        let mut peaks = [CorrelationPeak { lag: 0, value: (0.0, 0.0), power: 0.0 }; 3];
+        assert!(!bank.correlate(&frame, &mut peaks).is_corrupted());
+
+        let energy: f64 = templates[1].iter().map(|x| x.0 * x.0 + x.1 * x.1).sum();
+        assert_eq!(peaks[1].lag, 30);
+        assert_abs_diff_eq!(peaks[1].value.0, energy, epsilon = 1e-9);
+        assert_abs_diff_eq!(peaks[1].value.1, -energy, epsilon = 1e-9);
+        assert_abs_diff_eq!(peaks[1].power, 2.0 * energy * energy, epsilon = 1e-9);
+        assert!(peaks[0].power < peaks[1].power && peaks[2].power < peaks[1].power);
+    }
+
+    #[test]
+    fn test_from_spectrum() {
+        // A flat spectrum is an identity filter
+        let kernel = FftKernel::from_spectrum(&[(1.0f32, 0.0); 8]);
+        let mut data: [(f32, f32); 8] = core::array::from_fn(|i| (i as f32, -(i as f32)));
+        let input = data;
+        FftConvolver::new(&kernel, &mut data).convolve();
+        for (x, y) in core::iter::zip(data, input) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-5);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-5);
+        }
+    }
+}
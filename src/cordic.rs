@@ -5,38 +5,432 @@
 
 //! CORDIC functions
 //!
-//! Useful for precomputing trigonometry tables
+//! Useful for precomputing trigonometry tables, at compile time or at runtime, and for converting between
+//! rectangular and polar coordinates, e.g. to extract the magnitude and phase of the bins and resynthesize
+//! them in a phase vocoder
 
 /******************************************************************************/
 
-use core::f64::consts::PI;
+include!(concat!(env!("OUT_DIR"), "/cordic_tables.rs"));
 
 /******************************************************************************/
 
-include!(concat!(env!("OUT_DIR"), "/cordic_tables.rs"));
+/// Default number of iterations for the f64 functions
+pub const ITERATIONS: usize = 63;
 
-/******************************************************************************/
+/// Default number of iterations for the f32 functions
+pub const ITERATIONS_F32: usize = 26;
+
+/// First 1152 fractional bits of 1 / 2π, for the reduction of the large angles
+const INV_TWO_PI: [u64; 18] = [
+    0x28BE_60DB_9391_054A, 0x7F09_D5F4_7D4D_3770, 0x36D8_A566_4F10_E410,
+    0x7F94_58EA_F7AE_F158, 0x6DC9_1B8E_9093_74B8, 0x0192_4BBA_8274_6487,
+    0x3F87_7AC7_2C4A_69CF, 0xBA20_8D7D_4BAE_D121, 0x3A67_1C09_AD17_DF90,
+    0x4E64_758E_60D4_CE7D, 0x2721_17E2_EF7E_4A0E, 0xC7FE_25FF_F781_6603,
+    0xFBCB_C462_D682_9B47, 0xDB4D_9FB3_C9F2_C26D, 0xD3D1_8FD9_A797_FA8B,
+    0x5D49_EEB1_FAF9_7C5E, 0xCF41_CE7D_E294_A4BA, 0x9AFE_D7EC_47E3_5742
+];
+
+/// Return 64 bits of 1 / 2π, starting after the given number of fractional bits
+const fn inv_two_pi_bits(start: usize) -> u64 {
+    let (i, shift) = (start / 64, start % 64);
+    if shift == 0 {
+        INV_TWO_PI[i]
+    } else {
+        INV_TWO_PI[i] << shift | INV_TWO_PI[i + 1] >> (64 - shift)
+    }
+}
 
-/// Compute the sine of an angle
+/// Reduce a finite angle to [-π, π]
 ///
-/// The angle in radians must be comprised between -π/2 and +π/2
-pub const fn sin(alpha: f64) -> f64 {
-    const N: usize = 63;
-    let mut theta = 0.0;
-    let mut x = 1.0;
-    let mut y = 0.0;
-    let mut p2i = 1.0;
+/// The angle is a multiple m × 2^e of an integer mantissa m. Only the fractional part of m × 2^e / 2π
+/// matters, which is computed modulo 1 from the 128 bits of 1 / 2π that follow its first e fractional
+/// bits (Payne-Hanek reduction). The reduced angle is thus accurate whatever the magnitude of the input.
+const fn reduce_angle(alpha: f64) -> f64 {
+    const PI: f64 = core::f64::consts::PI;
+
+    if alpha >= -PI && alpha <= PI {
+        return alpha;
+    }
+
+    // Normal number, larger than π, i.e. e >= -51
+    let bits = alpha.to_bits();
+    let m = (bits & ((1 << 52) - 1)) | 1 << 52;
+    let e = ((bits >> 52) & 0x7FF) as i32 - 1075;
 
-    assert!(alpha > -PI / 2.0 && alpha < PI / 2.0);
+    // 2^(e + 128) / 2π modulo 2^128, whose product by m modulo 2^128 is the fraction of a turn
+    let window = if e < 0 {
+        ((INV_TWO_PI[0] as u128) << 64 | INV_TWO_PI[1] as u128) >> -e
+    } else {
+        (inv_two_pi_bits(e as usize) as u128) << 64 | inv_two_pi_bits(e as usize + 64) as u128
+    };
+    let turns = window.wrapping_mul(m as u128) as i128;
+    let angle = turns as f64 * (PI / (1u128 << 127) as f64);
+    if alpha < 0.0 { -angle } else { angle }
+}
+
+macro_rules! gen_circular_impl {
+    ($T: ident, $PI: expr, $rotate: ident, $sin_cos_n: ident, $sin_cos: ident, $sin: ident, $cos: ident, $iter: expr) => {
+        /// Rotate the unit vector by an angle, returning its cosine and sine
+        ///
+        /// The angle in radians must be comprised between -π/2 and +π/2
+        const fn $rotate(alpha: $T, iterations: usize) -> ($T, $T) {
+            let mut theta = 0.0;
+            let mut x = 1.0;
+            let mut y = 0.0;
+            let mut p2i = 1.0;
+
+            assert!(iterations > 0 && iterations <= THETA_TABLE.len());
+
+            let mut i = 0;
+            while i < iterations {
+                let sigma = if theta < alpha { 1.0 } else { -1.0 };
+                theta += sigma * THETA_TABLE[i] as $T;
+                (x, y) = (x - sigma * y * p2i, y + sigma * x * p2i);
+                p2i /= 2.0;
+                i += 1;
+            }
+
+            let k = K_TABLE[iterations - 1] as $T;
+            (x * k, y * k)
+        }
+
+        /// Compute the sine and cosine of an angle, with the given number of iterations
+        ///
+        /// The angle in radians is reduced to the range supported by the CORDIC, any finite value is
+        /// accepted, and NaN is returned for the others.
+        /// Each iteration adds about one bit of accuracy, up to 64 iterations.
+        pub const fn $sin_cos_n(alpha: $T, iterations: usize) -> ($T, $T) {
+            const PI: $T = $PI;
+
+            if alpha.is_nan() || alpha.is_infinite() {
+                return ($T::NAN, $T::NAN);
+            }
+
+            // Reduce to [-π, π]
+            let alpha = reduce_angle(alpha as f64) as $T;
+
+            // Reduce to [-π/2, π/2], the other half turn has the opposite sign
+            if alpha == 0.0 {
+                // Exact result, for the first point of the tables
+                (alpha, 1.0)
+            } else if alpha > PI / 2.0 {
+                let (c, s) = $rotate(alpha - PI, iterations);
+                (-s, -c)
+            } else if alpha < -PI / 2.0 {
+                let (c, s) = $rotate(alpha + PI, iterations);
+                (-s, -c)
+            } else {
+                let (c, s) = $rotate(alpha, iterations);
+                (s, c)
+            }
+        }
+
+        /// Compute the sine and cosine of an angle, in radians
+        pub const fn $sin_cos(alpha: $T) -> ($T, $T) {
+            $sin_cos_n(alpha, $iter)
+        }
+
+        /// Compute the sine of an angle, in radians
+        pub const fn $sin(alpha: $T) -> $T {
+            $sin_cos(alpha).0
+        }
+
+        /// Compute the cosine of an angle, in radians
+        pub const fn $cos(alpha: $T) -> $T {
+            $sin_cos(alpha).1
+        }
+    };
+}
+
+gen_circular_impl!(f64, core::f64::consts::PI, rotate, sin_cos_n, sin_cos, sin, cos, ITERATIONS);
+gen_circular_impl!(f32, core::f32::consts::PI, rotate_f32, sin_cos_f32_n, sin_cos_f32, sin_f32, cos_f32, ITERATIONS_F32);
+
+/// Rotate the vector (x, y) onto the positive x axis, returning its magnitude and angle
+///
+/// The angle in radians is comprised between -π and +π.
+const fn vectoring(x: f64, y: f64) -> (f64, f64) {
+    const PI: f64 = core::f64::consts::PI;
+
+    if x == 0.0 && y == 0.0 {
+        return (0.0, 0.0);
+    } else if x.abs() >= pow2(1000) || y.abs() >= pow2(1000) {
+        // Scale the large vectors down, or the gain of the CORDIC would overflow
+        let (magnitude, angle) = vectoring(x * pow2(-64), y * pow2(-64));
+        return (magnitude * pow2(64), angle);
+    }
+
+    // Fold into the right half-plane, supported by the CORDIC
+    let (mut x, mut y, mut z) = if x < 0.0 {
+        (-x, -y, if y < 0.0 { -PI } else { PI })
+    } else {
+        (x, y, 0.0)
+    };
+    let mut p2i = 1.0;
 
     let mut i = 0;
-    while i < N {
-        let sigma = if theta < alpha { 1.0 } else { -1.0 };
-        theta += sigma * THETA_TABLE[i];
+    while i < ITERATIONS {
+        let sigma = if y < 0.0 { 1.0 } else { -1.0 };
+        z -= sigma * THETA_TABLE[i];
         (x, y) = (x - sigma * y * p2i, y + sigma * x * p2i);
         p2i /= 2.0;
         i += 1;
     }
 
-    y * K_TABLE[N - 1]
+    (x * K_TABLE[ITERATIONS - 1], z)
+}
+
+/// Compute the angle of the vector (x, y), in radians between -π and +π
+pub const fn atan2(y: f64, x: f64) -> f64 {
+    vectoring(x, y).1
+}
+
+/// Convert a complex number to polar coordinates, returning its magnitude and its angle in radians between
+/// -π and +π
+///
+/// For example:
+/// ```
+/// let (magnitude, angle) = embfft::cordic::to_polar(-1.0, 1.0);
+/// assert!((magnitude - core::f64::consts::SQRT_2).abs() < 1e-15);
+/// assert!((angle - 3.0 * core::f64::consts::FRAC_PI_4).abs() < 1e-15);
+/// ```
+pub const fn to_polar(re: f64, im: f64) -> (f64, f64) {
+    vectoring(re, im)
+}
+
+/// Convert polar coordinates, a magnitude and an angle in radians, to a complex number
+///
+/// This is the inverse of [`to_polar()`].
+pub const fn from_polar(magnitude: f64, angle: f64) -> (f64, f64) {
+    let (s, c) = sin_cos(angle);
+    (magnitude * c, magnitude * s)
+}
+
+/******************************************************************************/
+
+/// Number of hyperbolic iterations, not counting the repeated ones
+const HYPERBOLIC_ITERATIONS: usize = 60;
+
+/// Perform the hyperbolic CORDIC iterations, in rotation mode if `rotation` is set, in vectoring mode otherwise
+///
+/// Iterations 4, 13, 40, ... are repeated to ensure convergence. The angle must be comprised between about
+/// -1.118 and +1.118.
+const fn hyperbolic(mut x: f64, mut y: f64, mut z: f64, rotation: bool, iterations: usize) -> (f64, f64, f64) {
+    let mut p2i = 0.5;
+    let mut repeat = 4;
+
+    assert!(iterations > 0 && iterations <= ATANH_TABLE.len());
+
+    let mut i = 1;
+    while i <= iterations {
+        let sigma = if rotation { if z >= 0.0 { 1.0 } else { -1.0 } } else if y < 0.0 { 1.0 } else { -1.0 };
+        z -= sigma * ATANH_TABLE[i - 1];
+        (x, y) = (x + sigma * y * p2i, y + sigma * x * p2i);
+        if i == repeat {
+            repeat = 3 * repeat + 1;
+        } else {
+            p2i /= 2.0;
+            i += 1;
+        }
+    }
+
+    (x, y, z)
+}
+
+/// Compute 2^k, for any integer k
+const fn pow2(k: i64) -> f64 {
+    if k > 1023 {
+        f64::INFINITY
+    } else if k < -1074 {
+        0.0
+    } else if k < -1022 {
+        // Subnormal result
+        pow2(k + 52) * pow2(-52)
+    } else {
+        f64::from_bits(((k + 1023) as u64) << 52)
+    }
+}
+
+/// Compute the exponential of a number
+pub const fn exp(x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    }
+
+    // x = k * ln(2) + r, with |r| <= ln(2) / 2
+    let k = x / core::f64::consts::LN_2;
+    let k = if k >= 0.0 { k + 0.5 } else { k - 0.5 } as i64;
+    let r = x - k as f64 * core::f64::consts::LN_2;
+
+    let (c, s, _) = hyperbolic(KH_TABLE[HYPERBOLIC_ITERATIONS - 1], 0.0, r, true, HYPERBOLIC_ITERATIONS);
+    if k < -1022 {
+        // Avoid an intermediate underflow
+        (c + s) * pow2(k + 52) * pow2(-52)
+    } else if k > 1023 {
+        // Avoid an intermediate overflow, c + s may be below 1
+        (c + s) * pow2(k - 1) * 2.0
+    } else {
+        (c + s) * pow2(k)
+    }
+}
+
+/// Compute the natural logarithm of a number
+///
+/// Returns NaN for negative numbers and -∞ for zero.
+pub const fn ln(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    } else if x == 0.0 {
+        return f64::NEG_INFINITY;
+    } else if x == 1.0 {
+        return 0.0;
+    } else if x.is_infinite() {
+        return x;
+    }
+
+    // x = m * 2^e, with 1 <= m < 2
+    let (x, e) = if x.is_subnormal() { (x * pow2(52), -52) } else { (x, 0) };
+    let bits = x.to_bits();
+    let e = e + ((bits >> 52) as i64 - 1023);
+    let m = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+
+    // ln(m) = 2 * atanh((m - 1) / (m + 1))
+    let (_, _, z) = hyperbolic(m + 1.0, m - 1.0, 0.0, false, HYPERBOLIC_ITERATIONS);
+    2.0 * z + e as f64 * core::f64::consts::LN_2
+}
+
+/// Compute the hyperbolic sine of a number
+pub const fn sinh(x: f64) -> f64 {
+    if x > -1.0 && x < 1.0 {
+        // Direct computation, to keep the accuracy close to zero
+        let (_, s, _) = hyperbolic(KH_TABLE[HYPERBOLIC_ITERATIONS - 1], 0.0, x, true, HYPERBOLIC_ITERATIONS);
+        s
+    } else {
+        let e = exp(x);
+        (e - 1.0 / e) / 2.0
+    }
+}
+
+/// Compute the hyperbolic cosine of a number
+pub const fn cosh(x: f64) -> f64 {
+    let e = exp(x);
+    (e + 1.0 / e) / 2.0
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+    use core::f64::consts::{FRAC_1_SQRT_2, PI};
+
+    #[test]
+    fn test_sin_cos() {
+        let expected = [
+            (0.0, 1.0), (FRAC_1_SQRT_2, FRAC_1_SQRT_2), (1.0, 0.0), (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+            (0.0, -1.0), (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2), (-1.0, 0.0), (-FRAC_1_SQRT_2, FRAC_1_SQRT_2)
+        ];
+        for k in -16i32..16 {
+            let (s, c) = sin_cos(k as f64 * PI / 4.0);
+            let (x, y) = expected[k.rem_euclid(8) as usize];
+            assert_abs_diff_eq!(s, x, epsilon = 1e-15);
+            assert_abs_diff_eq!(c, y, epsilon = 1e-15);
+        }
+        assert_abs_diff_eq!(sin(-PI / 6.0), -0.5, epsilon = 1e-15);
+        assert_abs_diff_eq!(cos(100.0 * PI / 3.0), -0.5, epsilon = 1e-13);
+    }
+
+    #[test]
+    fn test_range_reduction() {
+        for x in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(sin(x).is_nan() && cos(x).is_nan());
+            assert!(sin_f32(x as f32).is_nan());
+        }
+
+        // Far beyond the range of the integers, the reduction must keep all the bits of 1 / 2π
+        let expected = [
+            (12345.678, -0.7040813137533816, 0.7101193587160628),
+            (-1e15, -0.8582727931702359, -0.5131937377869702),
+            (1e20, -0.6452512852657809, 0.7639704044417283),
+            (1e22, -0.8522008497671888, 0.523214785395139),
+            (1e300, -0.8178819121159087, -0.575386111957549),
+            (f64::MAX, 0.004961954789184062, -0.9999876894265599)
+        ];
+        for (x, s, c) in expected {
+            let (sin, cos) = sin_cos(x);
+            assert_abs_diff_eq!(sin, s, epsilon = 1e-15);
+            assert_abs_diff_eq!(cos, c, epsilon = 1e-15);
+        }
+        assert_abs_diff_eq!(sin_f32(1e20), 0.6565767, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_atan2() {
+        assert_eq!(atan2(0.0, 0.0), 0.0);
+        assert_abs_diff_eq!(atan2(1.0, 1.0), PI / 4.0, epsilon = 1e-15);
+        assert_abs_diff_eq!(atan2(1.0, -1.0), 3.0 * PI / 4.0, epsilon = 1e-15);
+        assert_abs_diff_eq!(atan2(-2.0, -2.0), -3.0 * PI / 4.0, epsilon = 1e-15);
+        assert_abs_diff_eq!(atan2(-1.0, 0.0), -PI / 2.0, epsilon = 1e-15);
+        assert_abs_diff_eq!(atan2(0.5, 0.8660254037844386), PI / 6.0, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn test_polar() {
+        assert_eq!(to_polar(0.0, 0.0), (0.0, 0.0));
+        for (re, im) in [(3.0, 4.0), (-2.0, 0.5), (0.0, -1e-3), (-1e6, -1e6), (1e-100, 0.0)] {
+            let (m, a) = to_polar(re, im);
+            let m2: f64 = re * re + im * im;
+            assert_abs_diff_eq!(m, m2.sqrt(), epsilon = 1e-15 * m2.sqrt());
+            assert_abs_diff_eq!(a, atan2(im, re), epsilon = 1e-15);
+
+            let (x, y) = from_polar(m, a);
+            assert_abs_diff_eq!(x, re, epsilon = 1e-14 * m);
+            assert_abs_diff_eq!(y, im, epsilon = 1e-14 * m);
+        }
+        assert_abs_diff_eq!(to_polar(-1.0, 0.0).1, PI, epsilon = 1e-15);
+
+        // Extreme magnitudes, which the gain of the CORDIC must not overflow
+        let (m, a) = to_polar(1e308, 1e308);
+        assert_relative_eq!(m, 1e308 * core::f64::consts::SQRT_2, max_relative = 1e-15);
+        assert_abs_diff_eq!(a, PI / 4.0, epsilon = 1e-15);
+        let (m, a) = to_polar(-f64::MAX, 0.0);
+        assert_relative_eq!(m, f64::MAX, max_relative = 1e-15);
+        assert_abs_diff_eq!(a, PI, epsilon = 1e-15);
+        assert_relative_eq!(to_polar(3e300, -4e300).0, 5e300, max_relative = 1e-15);
+        let (x, y) = from_polar(2.0, -PI / 2.0);
+        assert_abs_diff_eq!(x, 0.0, epsilon = 1e-15);
+        assert_abs_diff_eq!(y, -2.0, epsilon = 1e-15);
+
+        // Usable at compile time
+        const POLAR: (f64, f64) = to_polar(0.0, 2.0);
+        assert_abs_diff_eq!(POLAR.0, 2.0, epsilon = 1e-15);
+        assert_abs_diff_eq!(POLAR.1, PI / 2.0, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn test_iterations() {
+        assert_abs_diff_eq!(sin_cos_n(PI / 3.0, 12).1, 0.5, epsilon = 1e-3);
+        assert_abs_diff_eq!(sin_f32(core::f32::consts::PI / 6.0), 0.5, epsilon = 1e-6);
+        assert_abs_diff_eq!(cos_f32(-4.0 * core::f32::consts::PI / 3.0), -0.5, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_hyperbolic() {
+        assert_abs_diff_eq!(exp(1.0), core::f64::consts::E, epsilon = 1e-14);
+        assert_abs_diff_eq!(exp(-2.0), 0.1353352832366127, epsilon = 1e-16);
+        assert_abs_diff_eq!(exp(20.0), 485165195.4097903, epsilon = 1e-6);
+        assert_eq!(exp(-1000.0), 0.0);
+        assert_eq!(exp(1000.0), f64::INFINITY);
+        assert_relative_eq!(exp(709.7), 1.6549840276802644e308, max_relative = 1e-13);
+        assert_relative_eq!(exp(709.78), 1.7928227943945155e308, max_relative = 1e-13);
+        assert_eq!(exp(709.8), f64::INFINITY);
+        assert_abs_diff_eq!(ln(10.0), core::f64::consts::LN_10, epsilon = 1e-15);
+        assert_abs_diff_eq!(ln(0.001), -6.907755278982137, epsilon = 1e-14);
+        assert_abs_diff_eq!(ln(1.0), 0.0, epsilon = 1e-15);
+        assert!(ln(-1.0).is_nan());
+        assert_abs_diff_eq!(sinh(0.5), 0.5210953054937474, epsilon = 1e-15);
+        assert_abs_diff_eq!(sinh(-3.0), -10.017874927409903, epsilon = 1e-14);
+        assert_abs_diff_eq!(cosh(2.0), 3.7621956910836314, epsilon = 1e-15);
+    }
 }
@@ -0,0 +1,167 @@
+/* embfft | cross_spectrum.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Auto and cross power spectra of two real channels
+
+/******************************************************************************/
+
+use crate::common::{count_inv, Float};
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::window::{Rectangular, Window};
+
+/******************************************************************************/
+
+/// Separates bin k of the spectrum of A + jB, where A and B are real, into twice the bins k of A and B
+pub(crate) fn separate<T: Float<N>, const N: usize>(data: &[(T, T); N], k: usize) -> ((T, T), (T, T)) {
+    // Z = A + jB, with A and B conjugate-symmetric
+    let z = data[k];
+    let w = data[(N - k) % N];
+    ((z.0 + w.0, z.1 - w.1), (z.1 + w.1, w.0 - z.0))
+}
+
+/// Averaged auto and cross power spectra of two real channels
+///
+/// Both channels are transformed at once, by packing them in the real and imaginary parts of a single
+/// complex FFT, and separating their spectra afterwards. This halves the cost of coherence and transfer
+/// function measurements.
+///
+/// Since the inputs are real, only the bins up to N / 2 are meaningful, the others are their mirror image.
+///
+/// For example:
+/// ```
+/// let mut cross = embfft::cross_spectrum::CrossSpectrum::<f32, 8>::new();
+/// let a = [1.0, 0.0, -1.0, 0.0, 1.0, 0.0, -1.0, 0.0];
+/// let b = [0.0, 2.0, 0.0, -2.0, 0.0, 2.0, 0.0, -2.0];
+/// cross.add(&a, &b);
+///
+/// let (mut paa, mut pbb, mut pab) = ([0.0; 8], [0.0; 8], [(0.0, 0.0); 8]);
+/// cross.average(&mut paa, &mut pbb, &mut pab).unwrap();
+/// assert_eq!(paa[2], 16.0);
+/// assert_eq!(pbb[2], 64.0);
+/// assert_eq!(pab[2], (0.0, 32.0));
+/// ```
+pub struct CrossSpectrum<T, const N: usize, W = Rectangular> {
+    paa: [T; N],
+    pbb: [T; N],
+    pab: [(T, T); N],
+    count: usize,
+    window: core::marker::PhantomData<W>
+}
+
+impl<T: Float<N>, const N: usize> CrossSpectrum<T, N> {
+    /// Initializes an empty accumulator
+    pub fn new() -> Self {
+        Self::windowed()
+    }
+}
+
+impl<T: Float<N>, const N: usize, W: Window<T, N>> CrossSpectrum<T, N, W> {
+    /// Initializes an empty accumulator, the frames being weighted by the window `W`
+    pub fn windowed() -> Self {
+        Self {
+            paa: [T::ZERO; N],
+            pbb: [T::ZERO; N],
+            pab: [(T::ZERO, T::ZERO); N],
+            count: 0,
+            window: core::marker::PhantomData
+        }
+    }
+
+    /// Discards all the accumulated frames
+    pub fn reset(&mut self) {
+        *self = Self::windowed();
+    }
+
+    /// Transforms a frame of both channels, and accumulates their spectra
+    pub fn add(&mut self, a: &[T; N], b: &[T; N]) {
+        let mut data = [(T::ZERO, T::ZERO); N];
+        for (z, (&a, &b)) in data.iter_mut().zip(core::iter::zip(a, b)) {
+            *z = (a, b);
+        }
+        EmbFft::<T, N, W>::windowed(&mut data).fft();
+
+        for k in 0..N {
+            let (a, b) = separate(&data, k);
+
+            // These are twice the actual spectra, the factor 4 is removed when averaging
+            self.paa[k] = self.paa[k] + a.0 * a.0 + a.1 * a.1;
+            self.pbb[k] = self.pbb[k] + b.0 * b.0 + b.1 * b.1;
+            self.pab[k].0 = self.pab[k].0 + a.0 * b.0 + a.1 * b.1;
+            self.pab[k].1 = self.pab[k].1 + a.1 * b.0 - a.0 * b.1;
+        }
+        self.count += 1;
+    }
+
+    /// Returns the number of accumulated frames
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Computes the averaged auto spectra of both channels, and their cross spectrum A·B*
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet.
+    pub fn average(&self, paa: &mut [T; N], pbb: &mut [T; N], pab: &mut [(T, T); N]) -> Result<(), Error> {
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        let count_inv = count_inv(4 * self.count);
+        for k in 0..N {
+            paa[k] = self.paa[k] * count_inv;
+            pbb[k] = self.pbb[k] * count_inv;
+            pab[k] = (self.pab[k].0 * count_inv, self.pab[k].1 * count_inv);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float<N>, const N: usize, W: Window<T, N>> Default for CrossSpectrum<T, N, W> {
+    fn default() -> Self {
+        Self::windowed()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_cross_spectrum() {
+        let mut cross = CrossSpectrum::<f64, 8>::new();
+        let (mut paa, mut pbb, mut pab) = ([0.0; 8], [0.0; 8], [(0.0, 0.0); 8]);
+        assert_eq!(cross.average(&mut paa, &mut pbb, &mut pab), Err(Error::NotDone));
+
+        // b is a delayed and scaled copy of a, with a DC offset
+        let a = [1.0, 2.0, 0.0, -1.0, 0.5, 0.0, -2.0, 1.0];
+        let mut b = [0.0; 8];
+        for i in 0..8 {
+            b[i] = 0.5 * a[(i + 7) % 8] + 1.0;
+        }
+        cross.add(&a, &b);
+        cross.add(&a, &b);
+        assert_eq!(cross.count(), 2);
+        cross.average(&mut paa, &mut pbb, &mut pab).unwrap();
+
+        // Reference values, computed with separate transforms
+        let mut fa = [(0.0, 0.0); 8];
+        let mut fb = [(0.0, 0.0); 8];
+        for i in 0..8 {
+            fa[i] = (a[i], 0.0);
+            fb[i] = (b[i], 0.0);
+        }
+        EmbFft::new(&mut fa).fft();
+        EmbFft::new(&mut fb).fft();
+        for k in 0..8 {
+            let (a, b) = (fa[k], fb[k]);
+            assert_abs_diff_eq!(paa[k], a.0 * a.0 + a.1 * a.1, epsilon = 1e-12);
+            assert_abs_diff_eq!(pbb[k], b.0 * b.0 + b.1 * b.1, epsilon = 1e-12);
+            assert_abs_diff_eq!(pab[k].0, a.0 * b.0 + a.1 * b.1, epsilon = 1e-12);
+            assert_abs_diff_eq!(pab[k].1, a.1 * b.0 - a.0 * b.1, epsilon = 1e-12);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+/* embfft | cyclostationary.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Cyclostationary feature detection
+//!
+//! Modulated signals are cyclostationary: their statistics repeat at the symbol rate, the chip rate or
+//! twice the carrier frequency. Their spectral components spaced by these cyclic frequencies are then
+//! correlated, while those of stationary noise are not, however strong the noise. The spectral correlation
+//! at a few candidate cyclic frequencies thus detects the presence of a signal at a signal-to-noise ratio
+//! where an energy detector fails, and without knowing the noise level.
+
+/******************************************************************************/
+
+use crate::common::{count_inv, Float};
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::window::{Rectangular, Window};
+
+/******************************************************************************/
+
+/// Averaged spectral correlation of complex frames, at A candidate cyclic frequencies
+///
+/// The cyclic frequencies are given in bins, i.e. in multiples of the sample rate / N. For each of them,
+/// the products X(k) · X*(k - a) of the spectra of successive frames are averaged, and compared to the
+/// power of the bins they combine. The frames must be contiguous, or spaced by a multiple of N samples, so
+/// that the cyclic components keep the same phase from one frame to the next.
+///
+/// The resulting feature is close to 1 for a signal that is fully cyclostationary at the cyclic frequency,
+/// and decreases as 1 / count for stationary noise, so that the detection threshold can be set to a few
+/// times 1 / [`count()`](CyclicDetector::count).
+/// For example:
+/// ```
+/// use embfft::cyclostationary::CyclicDetector;
+///
+/// // Pseudo-random BPSK symbols of 4 samples, i.e. a cyclic frequency of 16 / 4 = 4 bins
+/// let mut detector = CyclicDetector::<f32, 16, 2>::new([4, 3]).unwrap();
+/// for i in 0..32u32 {
+///     let frame: [(f32, f32); 16] = core::array::from_fn(|j| {
+///         let bit = (4 * i + j as u32 / 4).wrapping_mul(2654435761) >> 31;
+///         (if bit == 1 { 1.0 } else { -1.0 }, 0.0)
+///     });
+///     detector.add(&frame);
+/// }
+/// let features = detector.features().unwrap();
+/// assert!(features[0] > 0.9);
+/// assert!(features[1] < 0.2);
+/// ```
+pub struct CyclicDetector<T, const N: usize, const A: usize, W = Rectangular> {
+    cyclic_bins: [usize; A],
+    correlation: [[(T, T); N]; A],
+    power: [T; N],
+    count: usize,
+    window: core::marker::PhantomData<W>
+}
+
+impl<T: Float<N>, const N: usize, const A: usize> CyclicDetector<T, N, A> {
+    /// Initializes an empty accumulator for the given cyclic frequencies, in bins
+    ///
+    /// Fails with [`Error::InvalidParameter`] if a cyclic frequency is 0 or not lower than N.
+    pub fn new(cyclic_bins: [usize; A]) -> Result<Self, Error> {
+        Self::windowed(cyclic_bins)
+    }
+}
+
+impl<T: Float<N>, const N: usize, const A: usize, W: Window<T, N>> CyclicDetector<T, N, A, W> {
+    /// Initializes an empty accumulator for the given cyclic frequencies, in bins, the frames being weighted
+    /// by the window `W`
+    ///
+    /// Fails with [`Error::InvalidParameter`] if a cyclic frequency is 0 or not lower than N.
+    pub fn windowed(cyclic_bins: [usize; A]) -> Result<Self, Error> {
+        if cyclic_bins.iter().any(|&a| a == 0 || a >= N) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            cyclic_bins,
+            correlation: [[(T::ZERO, T::ZERO); N]; A],
+            power: [T::ZERO; N],
+            count: 0,
+            window: core::marker::PhantomData
+        })
+    }
+
+    /// Discards all the accumulated frames
+    pub fn reset(&mut self) {
+        self.correlation = [[(T::ZERO, T::ZERO); N]; A];
+        self.power = [T::ZERO; N];
+        self.count = 0;
+    }
+
+    /// Returns the candidate cyclic frequencies, in bins
+    pub fn cyclic_bins(&self) -> &[usize; A] {
+        &self.cyclic_bins
+    }
+
+    /// Transforms a frame, and accumulates its spectral correlation
+    ///
+    /// This is a blocking function, which performs an FFT.
+    pub fn add(&mut self, frame: &[(T, T); N]) {
+        let mut data = *frame;
+        EmbFft::<T, N, W>::windowed(&mut data).fft();
+
+        for (p, x) in self.power.iter_mut().zip(data.iter()) {
+            *p = *p + x.0 * x.0 + x.1 * x.1;
+        }
+        for (correlation, &a) in self.correlation.iter_mut().zip(self.cyclic_bins.iter()) {
+            for (k, c) in correlation.iter_mut().enumerate() {
+                // X(k) · X*(k - a)
+                let (x, y) = (data[k], data[(k + N - a) % N]);
+                *c = (c.0 + x.0 * y.0 + x.1 * y.1, c.1 + x.1 * y.0 - x.0 * y.1);
+            }
+        }
+        self.count += 1;
+    }
+
+    /// Returns the number of accumulated frames
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the spectral correlation at the cyclic frequency i, for each bin k, as
+    /// mean(X(k) · X*(k - a)) with a the cyclic frequency in bins
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet, and with [`Error::OutOfRange`] if
+    /// there is no cyclic frequency i.
+    pub fn correlation(&self, i: usize, output: &mut [(T, T); N]) -> Result<(), Error> {
+        let correlation = self.correlation.get(i).ok_or(Error::OutOfRange)?;
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        let count_inv = count_inv(self.count);
+        for (y, c) in output.iter_mut().zip(correlation.iter()) {
+            *y = (c.0 * count_inv, c.1 * count_inv);
+        }
+        Ok(())
+    }
+
+    /// Returns the cyclostationary feature at the cyclic frequency i, between 0 and 1
+    ///
+    /// This is the squared spectral coherence Σ|S(k)|² / Σ P(k) · P(k - a), with S the averaged spectral
+    /// correlation and P the averaged power of each bin.
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet, and with [`Error::OutOfRange`] if
+    /// there is no cyclic frequency i.
+    pub fn feature(&self, i: usize) -> Result<f64, Error> {
+        let correlation = self.correlation.get(i).ok_or(Error::OutOfRange)?;
+        if self.count == 0 {
+            return Err(Error::NotDone);
+        }
+        let a = self.cyclic_bins[i];
+        let (mut num, mut den) = (0.0, 0.0);
+        for (k, c) in correlation.iter().enumerate() {
+            let c = (c.0.to_f64(), c.1.to_f64());
+            num += c.0 * c.0 + c.1 * c.1;
+            den += self.power[k].to_f64() * self.power[(k + N - a) % N].to_f64();
+        }
+        Ok(if den > 0.0 { num / den } else { 0.0 })
+    }
+
+    /// Returns the cyclostationary features at all the cyclic frequencies, see
+    /// [`CyclicDetector::feature()`]
+    ///
+    /// Fails with [`Error::NotDone`] if no frame was accumulated yet.
+    pub fn features(&self) -> Result<[f64; A], Error> {
+        let mut features = [0.0; A];
+        for (i, f) in features.iter_mut().enumerate() {
+            *f = self.feature(i)?;
+        }
+        Ok(features)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth::Lfsr;
+    use crate::window::Hann;
+
+    #[test]
+    fn test_errors() {
+        assert!(CyclicDetector::<f32, 16, 1>::new([0]).is_err());
+        assert!(CyclicDetector::<f32, 16, 2>::new([4, 16]).is_err());
+        let mut detector = CyclicDetector::<f32, 16, 1>::new([4]).unwrap();
+        assert_eq!(detector.cyclic_bins(), &[4]);
+        assert_eq!(detector.feature(0), Err(Error::NotDone));
+        detector.add(&[(1.0, 0.0); 16]);
+        assert_eq!(detector.feature(1), Err(Error::OutOfRange));
+        assert_eq!(detector.correlation(1, &mut [(0.0, 0.0); 16]), Err(Error::OutOfRange));
+        detector.reset();
+        assert_eq!(detector.count(), 0);
+        assert_eq!(detector.features(), Err(Error::NotDone));
+    }
+
+    #[test]
+    fn test_detection() {
+        // BPSK at 8 samples per symbol, i.e. 8 bins, buried in noise of twice the amplitude
+        let mut signal = CyclicDetector::<f64, 64, 2, Hann>::windowed([8, 5]).unwrap();
+        let mut stationary = CyclicDetector::<f64, 64, 2, Hann>::windowed([8, 5]).unwrap();
+        let mut lfsr = Lfsr::new(7);
+        for _ in 0..200 {
+            let mut symbols = [0.0; 8];
+            symbols.iter_mut().for_each(|s| *s = if lfsr.uniform() > 0.0 { 1.0 } else { -1.0 });
+            let mut frame = [(0.0, 0.0); 64];
+            let mut reference = [(0.0, 0.0); 64];
+            for (i, (x, r)) in frame.iter_mut().zip(reference.iter_mut()).enumerate() {
+                let n = (2.0 * lfsr.uniform(), 2.0 * lfsr.uniform());
+                *x = (symbols[i / 8] + n.0, n.1);
+                *r = (2.0 * lfsr.uniform(), 2.0 * lfsr.uniform());
+            }
+            signal.add(&frame);
+            stationary.add(&reference);
+        }
+        assert_eq!(signal.count(), 200);
+
+        let features = signal.features().unwrap();
+        let floor = stationary.features().unwrap();
+        assert!(features[0] > 10.0 / 200.0, "feature {}", features[0]);
+        assert!(features[1] < 3.0 / 200.0, "feature {}", features[1]);
+        assert!(floor.iter().all(|&f| f < 3.0 / 200.0), "floor {floor:?}");
+
+        // The spectral correlation itself is stronger at the symbol rate
+        let magnitude = |i: usize| {
+            let mut correlation = [(0.0, 0.0); 64];
+            signal.correlation(i, &mut correlation).unwrap();
+            correlation.iter().map(|c| (c.0 * c.0 + c.1 * c.1).sqrt()).sum::<f64>()
+        };
+        assert!(magnitude(0) > 2.0 * magnitude(1));
+    }
+}
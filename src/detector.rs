@@ -0,0 +1,185 @@
+/* embfft | detector.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Signal presence detection
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::mask::ExclusionMask;
+
+/******************************************************************************/
+
+/// Carrier or energy detector, with hysteresis
+///
+/// The power of a band of bins is compared to its noise floor, tracked while no signal is present.
+/// The signal is reported as present once the power exceeds `on` times the noise floor for `attack`
+/// successive spectra, and absent once it falls below `off` times the noise floor for `release` successive
+/// spectra.
+///
+/// For example:
+/// ```
+/// let mut detector = embfft::detector::CarrierDetector::<f32, 8>::new(1, 2, 4.0, 2.0, 0.1).unwrap();
+/// let mut data = [(0.1f32, 0.0); 8];
+/// assert!(!detector.update(&data));
+///
+/// data[1] = (1.0, 0.0);
+/// assert!(detector.update(&data));
+/// ```
+pub struct CarrierDetector<T, const N: usize> {
+    first_bin: usize,
+    last_bin: usize,
+    on: T,
+    off: T,
+    alpha: T,
+    attack: usize,
+    release: usize,
+    mask: ExclusionMask<N>,
+    noise: Option<T>,
+    present: bool,
+    count: usize
+}
+
+impl<T: Float<N>, const N: usize> CarrierDetector<T, N> {
+    /// Initializes a detector for the bins `first_bin` to `last_bin`, inclusive
+    ///
+    /// `on` and `off` are the power ratios to the noise floor, and `alpha` is the weight of each new
+    /// spectrum in the noise floor estimate. Fails with [`Error::InvalidParameter`] if the bins are out of
+    /// range, if `off` is greater than `on`, or if `alpha` is not between 0 and 1.
+    pub fn new(first_bin: usize, last_bin: usize, on: T, off: T, alpha: T) -> Result<Self, Error> {
+        if first_bin > last_bin || last_bin >= N || off > on || alpha <= T::ZERO || alpha > T::ONE {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            first_bin,
+            last_bin,
+            on,
+            off,
+            alpha,
+            attack: 1,
+            release: 1,
+            mask: ExclusionMask::new(),
+            noise: None,
+            present: false,
+            count: 0
+        })
+    }
+
+    /// Sets the number of successive spectra required to change the detection state
+    ///
+    /// Values of 0 are treated as 1.
+    pub fn with_hold(mut self, attack: usize, release: usize) -> Self {
+        self.attack = attack.max(1);
+        self.release = release.max(1);
+        self
+    }
+
+    /// Ignores the excluded bins, in both the signal power and the noise floor
+    pub fn with_mask(mut self, mask: &ExclusionMask<N>) -> Self {
+        self.mask = *mask;
+        self
+    }
+
+    /// Forgets the noise floor and the detection state
+    pub fn reset(&mut self) {
+        self.noise = None;
+        self.present = false;
+        self.count = 0;
+    }
+
+    /// Processes a new spectrum, and returns whether a signal is present
+    pub fn update(&mut self, data: &[(T, T); N]) -> bool {
+        let power = (self.first_bin..=self.last_bin)
+            .filter(|&k| !self.mask.is_excluded(k))
+            .fold(T::ZERO, |acc, k| acc + data[k].0 * data[k].0 + data[k].1 * data[k].1);
+
+        let noise = match self.noise {
+            Some(noise) => noise,
+            None => {
+                // The first spectrum is assumed to only contain noise
+                self.noise = Some(power);
+                return false;
+            }
+        };
+
+        // Count the successive spectra that would change the state
+        let (threshold, hold) = if self.present { (self.off, self.release) } else { (self.on, self.attack) };
+        let change = if self.present { power < threshold * noise } else { power > threshold * noise };
+        self.count = if change { self.count + 1 } else { 0 };
+        if self.count >= hold {
+            self.present = !self.present;
+            self.count = 0;
+        }
+
+        if !self.present && self.count == 0 {
+            self.noise = Some(noise + self.alpha * (power - noise));
+        }
+        self.present
+    }
+
+    /// Returns whether a signal is present
+    pub fn is_present(&self) -> bool {
+        self.present
+    }
+
+    /// Returns the tracked noise floor, in the same unit as the band power
+    pub fn noise_floor(&self) -> Option<T> {
+        self.noise
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameters() {
+        assert!(CarrierDetector::<f32, 4>::new(2, 4, 2.0, 1.0, 0.5).is_err());
+        assert!(CarrierDetector::<f32, 4>::new(2, 1, 2.0, 1.0, 0.5).is_err());
+        assert!(CarrierDetector::<f32, 4>::new(1, 2, 1.0, 2.0, 0.5).is_err());
+        assert!(CarrierDetector::<f32, 4>::new(1, 2, 2.0, 1.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut detector = CarrierDetector::<f64, 4>::new(1, 1, 4.0, 2.0, 0.5).unwrap().with_hold(2, 1);
+        let noise = [(1.0, 0.0); 4];
+        let tone = [(1.0, 0.0), (3.0, 0.0), (1.0, 0.0), (1.0, 0.0)];
+        let weak = [(1.0, 0.0), (1.5, 0.0), (1.0, 0.0), (1.0, 0.0)];
+        assert!(!detector.update(&noise));
+        assert_eq!(detector.noise_floor(), Some(1.0));
+
+        // The attack requires two spectra, during which the noise floor is frozen
+        assert!(!detector.update(&tone));
+        assert!(detector.update(&tone));
+        assert_eq!(detector.noise_floor(), Some(1.0));
+
+        // Between the thresholds, the state does not change
+        assert!(detector.update(&weak));
+        assert!(!detector.update(&noise));
+        assert!(!detector.is_present());
+        assert!(!detector.update(&weak));
+        assert_eq!(detector.noise_floor(), Some(1.625));
+    }
+
+    #[test]
+    fn test_mask() {
+        let mut mask = ExclusionMask::new();
+        mask.exclude(2).unwrap();
+        let mut detector = CarrierDetector::<f64, 4>::new(1, 2, 4.0, 2.0, 0.5).unwrap().with_mask(&mask);
+        let mut data = [(1.0, 0.0); 4];
+        assert!(!detector.update(&data));
+        assert_eq!(detector.noise_floor(), Some(1.0));
+
+        // A spur in the excluded bin is ignored
+        data[2] = (10.0, 0.0);
+        assert!(!detector.update(&data));
+        data[1] = (3.0, 0.0);
+        assert!(detector.update(&data));
+    }
+}
@@ -0,0 +1,254 @@
+/* embfft | discriminator.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Phase and frequency discrimination, for carrier tracking loops
+//!
+//! Coherent demodulation needs a PLL, or a Costas loop for suppressed-carrier modulations, that keeps a
+//! local oscillator aligned with the carrier. A [`PhaseDiscriminator`] follows the carrier bin of
+//! successive frames, from an FFT or a [`Goertzel`] filter, and returns its phase and frequency errors in
+//! radians, ready to drive the loop filter. The modulation is removed first, so that the data symbols do
+//! not disturb the loop.
+//! For example:
+//! ```
+//! use embfft::discriminator::{Modulation, PhaseDiscriminator};
+//!
+//! let mut discriminator = PhaseDiscriminator::new(Modulation::Bpsk);
+//! let mut nco_phase = 0.0;
+//! for symbol in [1.0, -1.0, -1.0, 1.0, -1.0] {
+//!     // Carrier 0.3 rad ahead of the NCO, BPSK modulated
+//!     let (sin, cos) = embfft::cordic::sin_cos(0.3 - nco_phase);
+//!     let error = discriminator.update((symbol * cos, symbol * sin));
+//!     // Proportional loop filter
+//!     nco_phase += 0.5 * error.phase;
+//! }
+//! assert!((nco_phase - 0.3f64).abs() < 0.02);
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::goertzel::Goertzel;
+use crate::math;
+use crate::units::{Hertz, SampleRate};
+
+/******************************************************************************/
+
+/// Modulation of the carrier, removed before the discrimination
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Modulation {
+    /// Unmodulated carrier, as for a PLL, with errors between -π and +π
+    Carrier,
+    /// Binary phase shift keying, as for a Costas loop, with errors between -π / 2 and +π / 2
+    Bpsk,
+    /// Quadrature phase shift keying, with errors between -π / 4 and +π / 4
+    Qpsk
+}
+
+impl Modulation {
+    /// Returns the phase ambiguity of the modulation, in radians
+    pub fn ambiguity(self) -> f64 {
+        match self {
+            Modulation::Carrier => 2.0 * PI,
+            Modulation::Bpsk => PI,
+            Modulation::Qpsk => PI / 2.0
+        }
+    }
+}
+
+/// Phase and frequency errors of a frame
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PhaseError {
+    /// Phase error, in radians, within ± half the ambiguity of the modulation
+    pub phase: f64,
+    /// Frequency error, in radians per frame, i.e. the phase change since the previous frame
+    pub frequency: f64,
+    /// Magnitude of the bin, e.g. for a lock detector or an automatic gain control
+    pub magnitude: f64
+}
+
+impl PhaseError {
+    /// Converts the frequency error to Hertz, for frames spaced by `hop` samples
+    pub fn to_hertz(&self, hop: usize, sample_rate: SampleRate) -> Hertz {
+        Hertz(self.frequency / (2.0 * PI) * sample_rate.hertz().0 / hop as f64)
+    }
+}
+
+/// Wraps an angle between -period / 2 (excluded) and +period / 2 (included)
+fn wrap(x: f64, period: f64) -> f64 {
+    let x = x % period;
+    if x > period / 2.0 {
+        x - period
+    } else if x <= -period / 2.0 {
+        x + period
+    } else {
+        x
+    }
+}
+
+/// Discriminator of the phase and frequency errors of a carrier bin, frame after frame
+pub struct PhaseDiscriminator {
+    modulation: Modulation,
+    advance: f64,
+    squelch: f64,
+    previous: Option<f64>
+}
+
+impl PhaseDiscriminator {
+    /// Initializes a discriminator for the given modulation
+    pub fn new(modulation: Modulation) -> Self {
+        Self { modulation, advance: 0.0, squelch: 0.0, previous: None }
+    }
+
+    /// Sets the nominal phase advance of the carrier between two frames, removed from the frequency error
+    ///
+    /// With frames of N samples spaced by `hop` samples, a carrier at `bin` advances by 2π × bin × hop / N
+    /// radians from one frame to the next. This is a multiple of 2π, which can be ignored, when `hop` is N
+    /// and `bin` is an integer.
+    pub fn with_advance(mut self, bin: f64, n: usize, hop: usize) -> Self {
+        self.advance = wrap(2.0 * PI * bin * hop as f64 / n as f64, 2.0 * PI);
+        self
+    }
+
+    /// Sets the magnitude below which the carrier is considered lost
+    ///
+    /// Without carrier, the errors are reported as 0, so that the loop holds its state.
+    pub fn with_squelch(mut self, magnitude: f64) -> Self {
+        self.squelch = magnitude;
+        self
+    }
+
+    /// Forgets the previous frame
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// Discriminates the value of the carrier bin in a new frame
+    ///
+    /// The frequency error of the first frame, or of the first frame after the carrier was lost, is 0.
+    pub fn update(&mut self, bin: (f64, f64)) -> PhaseError {
+        let magnitude = math::sqrt(bin.0 * bin.0 + bin.1 * bin.1);
+        if magnitude == 0.0 || magnitude < self.squelch {
+            self.previous = None;
+            return PhaseError { phase: 0.0, frequency: 0.0, magnitude };
+        }
+
+        let ambiguity = self.modulation.ambiguity();
+        let phase = wrap(math::atan2(bin.1, bin.0), ambiguity);
+        let frequency = match self.previous {
+            Some(previous) => wrap(phase - previous - self.advance, ambiguity),
+            None => 0.0
+        };
+        self.previous = Some(phase);
+        PhaseError { phase, frequency, magnitude }
+    }
+
+    /// Discriminates the bin k of a new spectrum
+    ///
+    /// Fails with [`Error::OutOfRange`] if k is not lower than N.
+    pub fn update_spectrum<T: Float<N>, const N: usize>(&mut self, spectrum: &[(T, T); N], k: usize) -> Result<PhaseError, Error> {
+        let bin = spectrum.get(k).ok_or(Error::OutOfRange)?;
+        Ok(self.update((bin.0.to_f64(), bin.1.to_f64())))
+    }
+
+    /// Discriminates the result of a Goertzel filter
+    ///
+    /// Fails with [`Error::NotDone`] if the filter did not process N samples.
+    pub fn update_goertzel<T: Float<N>, const N: usize>(&mut self, goertzel: &Goertzel<T, N>) -> Result<PhaseError, Error> {
+        let bin = goertzel.result()?;
+        Ok(self.update((bin.0.to_f64(), bin.1.to_f64())))
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+
+    fn phasor(magnitude: f64, phase: f64) -> (f64, f64) {
+        let (sin, cos) = cordic::sin_cos(phase);
+        (magnitude * cos, magnitude * sin)
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_abs_diff_eq!(wrap(3.0 * PI / 2.0, 2.0 * PI), -PI / 2.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(wrap(-PI, 2.0 * PI), PI, epsilon = 1e-12);
+        assert_abs_diff_eq!(wrap(0.9 * PI, PI), -0.1 * PI, epsilon = 1e-12);
+        assert_abs_diff_eq!(wrap(-0.3, PI / 2.0), -0.3, epsilon = 1e-12);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_modulations() {
+        // Carrier drifting by 0.1 rad per frame, QPSK modulated
+        let mut carrier = PhaseDiscriminator::new(Modulation::Carrier);
+        let mut qpsk = PhaseDiscriminator::new(Modulation::Qpsk);
+        for (i, symbol) in [0, 3, 1, 1, 2, 0, 3].into_iter().enumerate() {
+            let phase = 0.2 + 0.1 * i as f64;
+            let error = carrier.update(phasor(2.0, phase));
+            assert_abs_diff_eq!(error.phase, phase, epsilon = 1e-9);
+            assert_abs_diff_eq!(error.frequency, if i == 0 { 0.0 } else { 0.1 }, epsilon = 1e-9);
+            assert_abs_diff_eq!(error.magnitude, 2.0, epsilon = 1e-9);
+
+            let error = qpsk.update(phasor(2.0, phase + symbol as f64 * PI / 2.0));
+            assert_abs_diff_eq!(error.phase, wrap(phase, PI / 2.0), epsilon = 1e-9);
+            assert_abs_diff_eq!(error.frequency, if i == 0 { 0.0 } else { 0.1 }, epsilon = 1e-9);
+        }
+
+        // The BPSK symbols are removed, but not the quadrature ones
+        let mut bpsk = PhaseDiscriminator::new(Modulation::Bpsk);
+        assert_abs_diff_eq!(bpsk.update(phasor(1.0, 0.1 + PI)).phase, 0.1, epsilon = 1e-9);
+        assert_abs_diff_eq!(bpsk.update(phasor(1.0, 0.1 + PI / 2.0)).phase, 0.1 - PI / 2.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_squelch() {
+        let mut discriminator = PhaseDiscriminator::new(Modulation::Carrier).with_squelch(0.5);
+        discriminator.update(phasor(1.0, 0.0));
+        let error = discriminator.update(phasor(0.1, 1.0));
+        assert_eq!((error.phase, error.frequency), (0.0, 0.0));
+        assert_abs_diff_eq!(error.magnitude, 0.1, epsilon = 1e-9);
+        assert_eq!(discriminator.update(phasor(1.0, 1.0)).frequency, 0.0);
+        assert_abs_diff_eq!(discriminator.update(phasor(1.0, 1.5)).frequency, 0.5, epsilon = 1e-9);
+        discriminator.reset();
+        assert_eq!(discriminator.update(phasor(1.0, 2.0)).frequency, 0.0);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_goertzel() {
+        // Tone 2 Hz above the bin 8 of frames of 64 samples at 1 kHz, overlapping by half
+        let fs = SampleRate::new(1000.0).unwrap();
+        let frequency = fs.bin_frequency::<64>(8.0).0 + 2.0;
+        let mut goertzel = Goertzel::<f64, 64>::new(8.0);
+        let mut discriminator = PhaseDiscriminator::new(Modulation::Carrier).with_advance(8.0, 64, 32);
+        assert_eq!(discriminator.update_goertzel(&goertzel), Err(Error::NotDone));
+        for frame in 0..4 {
+            goertzel.reset();
+            for i in 0..64 {
+                let t = (frame * 32 + i) as f64 / 1000.0;
+                goertzel.push(cordic::cos(2.0 * PI * frequency * t));
+            }
+            let error = discriminator.update_goertzel(&goertzel).unwrap();
+            if frame > 0 {
+                assert_abs_diff_eq!(error.to_hertz(32, fs).0, 2.0, epsilon = 0.1);
+            }
+        }
+
+        let mut spectrum = [(0.0f32, 0.0); 8];
+        spectrum[3] = (0.0, -1.0);
+        assert_eq!(discriminator.update_spectrum(&spectrum, 8), Err(Error::OutOfRange));
+        assert_abs_diff_eq!(discriminator.update_spectrum(&spectrum, 3).unwrap().phase, -PI / 2.0, epsilon = 1e-9);
+    }
+}
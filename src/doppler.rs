@@ -0,0 +1,241 @@
+/* embfft | doppler.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Doppler velocity estimation
+//!
+//! Radar and sonar speed sensors transmit a continuous tone, and measure the frequency shift of its echo,
+//! which is proportional to the radial velocity of the reflector. A [`DopplerEstimator`] finds the echo
+//! in a spectrum, refines its frequency between the bins, and converts the shift to a velocity.
+//!
+//! The spectrum is either that of the I/Q baseband, where the echo of a stationary reflector lies at 0 Hz
+//! and the sign of the shift gives the direction, or that of the real passband samples, where it lies at
+//! the carrier frequency, which is then set with [`DopplerEstimator::with_center()`].
+//! For example:
+//! ```
+//! use embfft::doppler::DopplerEstimator;
+//! use embfft::units::{Hertz, SampleRate};
+//!
+//! // 24 GHz radar, I/Q baseband sampled at 10 kHz
+//! let fs = SampleRate::new(10000.0).unwrap();
+//! let radar = DopplerEstimator::new(fs, Hertz::mhz(24000.0), 299792458.0).unwrap();
+//!
+//! // Echo of a car moving away at 10 m/s, i.e. a shift of -1601 Hz
+//! let shift = -2.0 * 10.0 * 24e9 / 299792458.0;
+//! let mut data: [(f64, f64); 256] = core::array::from_fn(|i| {
+//!     let phase = 2.0 * core::f64::consts::PI * shift * i as f64 / 10000.0;
+//!     (phase.cos(), phase.sin())
+//! });
+//! embfft::EmbFft::new(&mut data).fft();
+//!
+//! let echo = radar.estimate(&data).unwrap();
+//! assert!((echo.shift.0 - shift).abs() < 0.5);
+//! assert!((echo.velocity + 10.0).abs() < 0.01);
+//! ```
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::interpolate::{refine_peak, Interpolation};
+use crate::units::{Hertz, SampleRate};
+
+/******************************************************************************/
+
+/// Doppler shift found by an estimator
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Doppler {
+    /// Bin of the echo, interpolated between the bins, negative for the negative frequencies
+    pub bin: f64,
+    /// Frequency shift of the echo from the center frequency
+    pub shift: Hertz,
+    /// Radial velocity of the reflector, in the unit of the propagation speed, positive when approaching
+    pub velocity: f64,
+    /// Magnitude of the peak bin
+    pub magnitude: f64
+}
+
+/// Estimator of the Doppler shift of a continuous tone
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DopplerEstimator {
+    sample_rate: SampleRate,
+    carrier: Hertz,
+    speed: f64,
+    center: Hertz,
+    min_shift: Hertz,
+    max_shift: Hertz,
+    round_trip: bool,
+    threshold: f64
+}
+
+impl DopplerEstimator {
+    /// Initializes an estimator for a transmitted `carrier`, propagating at `speed`, e.g. in meters per
+    /// second
+    ///
+    /// The echo is searched in the whole spectrum, around 0 Hz, and the wave is assumed to travel to the
+    /// reflector and back.
+    /// Fails with [`Error::InvalidParameter`] if the carrier or the speed is not positive and finite.
+    pub fn new(sample_rate: SampleRate, carrier: Hertz, speed: f64) -> Result<Self, Error> {
+        if !(carrier.0 > 0.0 && carrier.0.is_finite() && speed > 0.0 && speed.is_finite()) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            sample_rate,
+            carrier,
+            speed,
+            center: Hertz(0.0),
+            min_shift: Hertz(0.0),
+            max_shift: sample_rate.nyquist(),
+            round_trip: true,
+            threshold: 0.0
+        })
+    }
+
+    /// Sets the frequency at which the echo of a stationary reflector appears in the spectrum, e.g. the
+    /// carrier for passband samples
+    pub fn with_center(mut self, center: Hertz) -> Self {
+        self.center = center;
+        self
+    }
+
+    /// Ignores the shifts smaller than `min`, e.g. the clutter of the stationary reflectors, or larger than
+    /// `max`
+    pub fn with_shift_range(mut self, min: Hertz, max: Hertz) -> Self {
+        self.min_shift = min;
+        self.max_shift = max;
+        self
+    }
+
+    /// Assumes that the wave only travels one way, e.g. from a moving transmitter to a fixed receiver,
+    /// which halves the shift for a given velocity
+    pub fn with_one_way(mut self) -> Self {
+        self.round_trip = false;
+        self
+    }
+
+    /// Ignores the echoes whose peak magnitude is not above a threshold
+    pub fn with_threshold(mut self, magnitude: f64) -> Self {
+        self.threshold = magnitude;
+        self
+    }
+
+    /// Converts a frequency shift to a radial velocity
+    ///
+    /// This is the first-order approximation, valid as long as the velocity is small compared to the
+    /// propagation speed.
+    pub fn velocity(&self, shift: Hertz) -> f64 {
+        let velocity = shift / self.carrier * self.speed;
+        if self.round_trip { velocity / 2.0 } else { velocity }
+    }
+
+    /// Finds the strongest echo within the shift range of a spectrum
+    ///
+    /// The peak is refined with [`Interpolation::Jacobsen`], which is best suited to unwindowed frames.
+    /// Returns `None` if no bin of the range rises above the threshold.
+    pub fn estimate<T: Float<N>, const N: usize>(&self, spectrum: &[(T, T); N]) -> Option<Doppler> {
+        let signed = |bin: f64| if 2.0 * bin > N as f64 { bin - N as f64 } else { bin };
+
+        let mut peak = None;
+        for (k, x) in spectrum.iter().enumerate() {
+            let shift = self.sample_rate.bin_frequency::<N>(signed(k as f64)) - self.center;
+            if shift.0.abs() < self.min_shift.0 || shift.0.abs() > self.max_shift.0 {
+                continue;
+            }
+            let (re, im) = (x.0.to_f64(), x.1.to_f64());
+            let power = re * re + im * im;
+            if power > self.threshold * self.threshold && peak.is_none_or(|(_, p)| power > p) {
+                peak = Some((k, power));
+            }
+        }
+        let (k, power) = peak?;
+
+        let bin = signed(refine_peak(spectrum, k, Interpolation::Jacobsen).ok()?);
+        let shift = self.sample_rate.bin_frequency::<N>(bin) - self.center;
+        Some(Doppler {
+            bin,
+            shift,
+            velocity: self.velocity(shift),
+            magnitude: crate::math::sqrt(power)
+        })
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+    use core::f64::consts::PI;
+
+    /// Transformed complex tone at `f` cycles per sample, of amplitude `a`
+    fn tone(f: f64, a: f64) -> [(f64, f64); 128] {
+        let mut data = core::array::from_fn(|i| {
+            let (s, c) = cordic::sin_cos(2.0 * PI * f * i as f64);
+            (a * c, a * s)
+        });
+        crate::EmbFft::new(&mut data).fft();
+        data
+    }
+
+    #[test]
+    fn test_errors() {
+        let fs = SampleRate::new(1000.0).unwrap();
+        assert_eq!(DopplerEstimator::new(fs, Hertz(0.0), 1.0), Err(Error::InvalidParameter));
+        assert_eq!(DopplerEstimator::new(fs, Hertz(f64::NAN), 1.0), Err(Error::InvalidParameter));
+        assert_eq!(DopplerEstimator::new(fs, Hertz(1e3), -1.0), Err(Error::InvalidParameter));
+        assert_eq!(DopplerEstimator::new(fs, Hertz(1e3), f64::INFINITY), Err(Error::InvalidParameter));
+
+        let doppler = DopplerEstimator::new(fs, Hertz(1e3), 1.0).unwrap();
+        assert_eq!(doppler.estimate(&[(0.0, 0.0); 128]), None);
+        assert_eq!(doppler.velocity(Hertz(100.0)), 0.05);
+        assert_eq!(doppler.with_one_way().velocity(Hertz(-100.0)), -0.1);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_baseband() {
+        let fs = SampleRate::new(1.0).unwrap();
+        let doppler = DopplerEstimator::new(fs, Hertz(10.0), 20.0).unwrap();
+        for f in [0.1, 0.2537, -0.0811, -0.4] {
+            let echo = doppler.estimate(&tone(f, 0.5)).unwrap();
+            assert_abs_diff_eq!(echo.bin, f * 128.0, epsilon = 1e-3);
+            assert_abs_diff_eq!(echo.shift.0, f, epsilon = 1e-5);
+            assert_abs_diff_eq!(echo.velocity, f, epsilon = 1e-5);
+        }
+
+        // Strong clutter at 0 Hz, and a weak echo
+        let mut data = tone(0.0, 10.0);
+        for (x, e) in data.iter_mut().zip(tone(-0.1234, 0.1).iter()) {
+            *x = (x.0 + e.0, x.1 + e.1);
+        }
+        let echo = doppler.estimate(&data).unwrap();
+        assert_abs_diff_eq!(echo.shift.0, 0.0, epsilon = 1e-6);
+        let echo = doppler.with_shift_range(Hertz(0.05), Hertz(0.5)).estimate(&data).unwrap();
+        assert_abs_diff_eq!(echo.shift.0, -0.1234, epsilon = 2e-3);
+        assert_abs_diff_eq!(echo.magnitude, 12.8, epsilon = 2.0);
+        assert_eq!(doppler.with_shift_range(Hertz(0.05), Hertz(0.5)).with_threshold(20.0).estimate(&data), None);
+    }
+
+    #[test]
+    fn test_passband() {
+        // 40 kHz sonar, real samples at 200 kHz, reflector approaching at 1 m/s
+        let fs = SampleRate::new(200000.0).unwrap();
+        let shift = 2.0 * 40000.0 / 343.0;
+        let f = (40000.0 + shift) / 200000.0;
+        let mut data: [(f64, f64); 128] = core::array::from_fn(|i| (cordic::cos(2.0 * PI * f * i as f64), 0.0));
+        crate::EmbFft::new(&mut data).fft();
+
+        let sonar = DopplerEstimator::new(fs, Hertz::khz(40.0), 343.0)
+            .unwrap()
+            .with_center(Hertz::khz(40.0))
+            .with_shift_range(Hertz(0.0), Hertz::khz(10.0));
+        let echo = sonar.estimate(&data).unwrap();
+        assert!(echo.bin > 0.0);
+        assert_abs_diff_eq!(echo.shift.0, shift, epsilon = 20.0);
+        assert_abs_diff_eq!(echo.velocity, 1.0, epsilon = 0.1);
+    }
+}
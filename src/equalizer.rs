@@ -0,0 +1,228 @@
+/* embfft | equalizer.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Per-channel frequency-domain equalization
+//!
+//! A channelizer or an SDR receiver already holds the spectrum of each channel, so a filter can be
+//! applied there as a complex gain per bin, without going back and forth through the time domain. Each
+//! channel of a [`ChannelEqualizer`] has its own profile of gains, which can be a stored profile, the
+//! response of a FIR filter, a matched filter, or the regularized inverse of a measured channel response.
+//! As with any frequency-domain filtering, the result is a circular convolution in the time domain.
+//! For example:
+//! ```
+//! use embfft::equalizer::ChannelEqualizer;
+//!
+//! // The second channel halves the level of every bin
+//! let mut eq = ChannelEqualizer::<f32, 8, 2>::new();
+//! eq.set_flattening(1, &[(0.5, 0.0); 8], 0.0).unwrap();
+//!
+//! let mut data = [(1.0f32, -0.5); 8];
+//! eq.apply(1, &mut data).unwrap();
+//! assert_eq!(data[3], (2.0, -1.0));
+//! ```
+
+/******************************************************************************/
+
+use crate::common::{Base, Float};
+use crate::error::Error;
+use crate::EmbFft;
+
+/******************************************************************************/
+
+/// Complex gains of each bin, for C channels of N bins
+pub struct ChannelEqualizer<T, const N: usize, const C: usize> {
+    profiles: [[(T, T); N]; C]
+}
+
+impl<T: Float<N>, const N: usize, const C: usize> ChannelEqualizer<T, N, C> {
+    /// Initializes an equalizer that leaves all the channels unchanged
+    pub fn new() -> Self {
+        Self { profiles: [[(T::ONE, T::ZERO); N]; C] }
+    }
+
+    /// Returns the profile of a channel, in natural order
+    ///
+    /// Fails with [`Error::OutOfRange`] if the channel does not exist.
+    pub fn profile(&self, channel: usize) -> Result<&[(T, T); N], Error> {
+        self.profiles.get(channel).ok_or(Error::OutOfRange)
+    }
+
+    /// Sets the profile of a channel from stored complex gains, in natural order
+    ///
+    /// Fails with [`Error::OutOfRange`] if the channel does not exist.
+    pub fn set_profile(&mut self, channel: usize, profile: &[(T, T); N]) -> Result<(), Error> {
+        *self.profiles.get_mut(channel).ok_or(Error::OutOfRange)? = *profile;
+        Ok(())
+    }
+
+    /// Sets the profile of a channel to the frequency response of a FIR filter
+    ///
+    /// This is a blocking function, which performs an FFT.
+    /// Fails with [`Error::OutOfRange`] if the channel does not exist, and with [`Error::InvalidLength`]
+    /// if there are more than N taps.
+    pub fn set_fir(&mut self, channel: usize, taps: &[(T, T)]) -> Result<(), Error> {
+        let profile = self.profiles.get_mut(channel).ok_or(Error::OutOfRange)?;
+        if taps.len() > N {
+            return Err(Error::InvalidLength);
+        }
+        *profile = [(T::ZERO, T::ZERO); N];
+        profile[..taps.len()].copy_from_slice(taps);
+        EmbFft::new(profile).fft();
+        Ok(())
+    }
+
+    /// Sets the profile of a channel to the filter matched to a template, i.e. the conjugate of its spectrum
+    ///
+    /// The output peaks at the delay where the template is found, with the template energy at delay 0.
+    /// This is a blocking function, which performs an FFT.
+    /// Fails with [`Error::OutOfRange`] if the channel does not exist, and with [`Error::InvalidLength`]
+    /// if the template is longer than N.
+    pub fn set_matched(&mut self, channel: usize, template: &[(T, T)]) -> Result<(), Error> {
+        self.set_fir(channel, template)?;
+        for x in self.profiles[channel].iter_mut() {
+            *x = (x.0, T::ZERO - x.1);
+        }
+        Ok(())
+    }
+
+    /// Sets the profile of a channel to flatten a measured channel response, in natural order
+    ///
+    /// Each gain is `conj(H) / (|H|² + floor)`, which is the inverse of the response H, regularized by
+    /// `floor` so that the deep notches of the channel are not amplified without bound.
+    /// Fails with [`Error::OutOfRange`] if the channel does not exist, and with
+    /// [`Error::InvalidParameter`] if a bin of the response is null while `floor` is not positive.
+    pub fn set_flattening(&mut self, channel: usize, response: &[(T, T); N], floor: f64) -> Result<(), Error> {
+        let profile = self.profiles.get_mut(channel).ok_or(Error::OutOfRange)?;
+        let mut gains = [(T::ZERO, T::ZERO); N];
+        for (g, h) in gains.iter_mut().zip(response.iter()) {
+            let h = (h.0.to_f64(), h.1.to_f64());
+            let d = h.0 * h.0 + h.1 * h.1 + floor;
+            if d.is_nan() || d <= 0.0 {
+                return Err(Error::InvalidParameter);
+            }
+            *g = (T::from_f64(h.0 / d), T::from_f64(-h.1 / d));
+        }
+        *profile = gains;
+        Ok(())
+    }
+
+    /// Equalizes the spectrum of a channel in natural order, in place
+    ///
+    /// Fails with [`Error::OutOfRange`] if the channel does not exist.
+    pub fn apply(&self, channel: usize, data: &mut [(T, T); N]) -> Result<(), Error> {
+        let profile = self.profile(channel)?;
+        for (x, g) in data.iter_mut().zip(profile.iter()) {
+            *x = (x.0 * g.0 - x.1 * g.1, x.0 * g.1 + x.1 * g.0);
+        }
+        Ok(())
+    }
+
+    /// Equalizes the spectrum of a channel in bit-reversed order, as output by
+    /// [`EmbFft::new_unordered()`], in place
+    ///
+    /// The spectrum can then be transformed back by [`EmbIfft::new_unordered()`](crate::EmbIfft::new_unordered),
+    /// without any reordering pass.
+    /// Fails with [`Error::OutOfRange`] if the channel does not exist.
+    pub fn apply_unordered(&self, channel: usize, data: &mut [(T, T); N]) -> Result<(), Error> {
+        assert!(Base::<N>::IS_N_POW2);
+        let profile = self.profile(channel)?;
+        for (k, x) in data.iter_mut().enumerate() {
+            let g = profile[Base::<N>::reverse_bits(k)];
+            *x = (x.0 * g.0 - x.1 * g.1, x.0 * g.1 + x.1 * g.0);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float<N>, const N: usize, const C: usize> Default for ChannelEqualizer<T, N, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmbIfft;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_errors() {
+        let mut eq = ChannelEqualizer::<f32, 8, 2>::new();
+        assert_eq!(eq.profile(2).err(), Some(Error::OutOfRange));
+        assert_eq!(eq.set_profile(2, &[(1.0, 0.0); 8]), Err(Error::OutOfRange));
+        assert_eq!(eq.set_fir(0, &[(1.0, 0.0); 9]), Err(Error::InvalidLength));
+        assert_eq!(eq.apply(3, &mut [(1.0, 0.0); 8]), Err(Error::OutOfRange));
+        let mut response = [(1.0, 0.0); 8];
+        response[2] = (0.0, 0.0);
+        assert_eq!(eq.set_flattening(0, &response, 0.0), Err(Error::InvalidParameter));
+        assert!(eq.set_flattening(0, &response, 0.01).is_ok());
+        assert_eq!(eq.profile(0).unwrap()[2], (0.0, 0.0));
+        assert_eq!(eq.profile(1).unwrap(), &[(1.0, 0.0); 8]);
+    }
+
+    #[test]
+    fn test_fir() {
+        // 2-tap filter on channel 0, channel 1 left unchanged
+        let signal: [(f64, f64); 16] = core::array::from_fn(|i| ((i % 5) as f64, 1.0 - (i % 3) as f64));
+        let taps = [(0.5, 0.0), (0.0, -0.25)];
+        let mut eq = ChannelEqualizer::<f64, 16, 2>::default();
+        eq.set_fir(0, &taps).unwrap();
+
+        // Natural and bit-reversed spectra give the same circular convolution
+        let mut natural = signal;
+        EmbFft::new(&mut natural).fft();
+        eq.apply(0, &mut natural).unwrap();
+        EmbIfft::new(&mut natural).ifft();
+        let mut unordered = signal;
+        EmbFft::new_unordered(&mut unordered).fft();
+        eq.apply_unordered(0, &mut unordered).unwrap();
+        EmbIfft::new_unordered(&mut unordered).ifft();
+        for i in 0..16 {
+            let (x, y) = (signal[i], signal[(i + 15) % 16]);
+            let expected = (0.5 * x.0 + 0.25 * y.1, 0.5 * x.1 - 0.25 * y.0);
+            assert_abs_diff_eq!(natural[i].0, expected.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(natural[i].1, expected.1, epsilon = 1e-12);
+            assert_abs_diff_eq!(unordered[i].0, expected.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(unordered[i].1, expected.1, epsilon = 1e-12);
+        }
+
+        let mut data = signal;
+        eq.apply(1, &mut data).unwrap();
+        assert_eq!(data, signal);
+    }
+
+    #[test]
+    fn test_matched() {
+        // Template hidden at delay 5
+        let template = [(1.0, 0.0), (-1.0, 0.0), (1.0, 0.0), (1.0, 0.0)];
+        let mut data = [(0.0f64, 0.0); 16];
+        data[5..9].copy_from_slice(&template);
+        let mut eq = ChannelEqualizer::<f64, 16, 1>::new();
+        eq.set_matched(0, &template).unwrap();
+
+        EmbFft::new(&mut data).fft();
+        eq.apply(0, &mut data).unwrap();
+        EmbIfft::new(&mut data).ifft();
+        let peak = (0..16).max_by(|&a, &b| data[a].0.total_cmp(&data[b].0)).unwrap();
+        assert_eq!(peak, 5);
+        assert_abs_diff_eq!(data[5].0, 4.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_flattening() {
+        let response: [(f64, f64); 8] = core::array::from_fn(|k| (1.0 + k as f64, 0.5 * k as f64));
+        let mut eq = ChannelEqualizer::<f64, 8, 1>::new();
+        eq.set_flattening(0, &response, 0.0).unwrap();
+        let mut data = response;
+        eq.apply(0, &mut data).unwrap();
+        for x in data {
+            assert_abs_diff_eq!(x.0, 1.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, 0.0, epsilon = 1e-12);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+/* embfft | error.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+/******************************************************************************/
+
+use core::fmt;
+
+/******************************************************************************/
+
+/// Errors returned by the fallible functions of this crate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The buffer length does not match the transform size
+    InvalidLength,
+    /// A parameter is outside of its valid range
+    InvalidParameter,
+    /// The operation is not supported by the current configuration
+    InvalidConfiguration,
+    /// The requested index is out of range
+    OutOfRange,
+    /// The result is not available until the conversion is complete
+    NotDone,
+    /// Samples were lost because they were not consumed in time
+    Overrun
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::InvalidLength => "invalid buffer length",
+            Error::InvalidParameter => "invalid parameter",
+            Error::InvalidConfiguration => "operation not supported by the current configuration",
+            Error::OutOfRange => "index out of range",
+            Error::NotDone => "conversion not complete",
+            Error::Overrun => "sample overrun"
+        })
+    }
+}
+
+impl core::error::Error for Error {}
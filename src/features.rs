@@ -0,0 +1,80 @@
+/* embfft | features.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Per-frame spectral features
+//!
+//! The spectra are those of real signals, in natural order: only the bins from DC to the Nyquist
+//! frequency are considered.
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+
+/******************************************************************************/
+
+/// Computes the normalized spectral entropy, between 0 and 1
+///
+/// The power spectrum is normalized to a probability distribution, whose Shannon entropy is divided by
+/// its maximum, ln(N / 2 + 1). A pure tone gives 0, white noise gives close to 1: voice and machine
+/// sounds fall in between, which makes this a cheap feature for voice activity detection and anomaly
+/// scoring.
+/// Fails with [`Error::InvalidParameter`] if the spectrum is null.
+///
+/// For example:
+/// ```
+/// let mut data = [(0.0f32, 0.0); 64];
+/// data[0] = (1.0, 0.0);
+/// embfft::EmbFft::new(&mut data).fft();
+/// let entropy = embfft::features::spectral_entropy(&data).unwrap();
+/// # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+/// assert!((entropy - 1.0).abs() < 1e-6);
+/// ```
+pub fn spectral_entropy<T: Float<N>, const N: usize>(spectrum: &[(T, T); N]) -> Result<f64, Error> {
+    let power = |k: usize| {
+        let (re, im) = (spectrum[k].0.to_f64(), spectrum[k].1.to_f64());
+        re * re + im * im
+    };
+    let total: f64 = (0..=N / 2).map(power).sum();
+    if total.is_nan() || total <= 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    let mut entropy = 0.0;
+    for k in 0..=N / 2 {
+        let p = power(k) / total;
+        if p > 0.0 {
+            entropy -= p * math::ln(p);
+        }
+    }
+    Ok(entropy / math::ln((N / 2 + 1) as f64))
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_spectral_entropy() {
+        assert_eq!(spectral_entropy(&[(0.0f64, 0.0); 16]), Err(Error::InvalidParameter));
+
+        let mut data = [(0.0f64, 0.0); 16];
+        data[3] = (0.0, 2.0);
+        data[13] = (0.0, -2.0);
+        assert_eq!(spectral_entropy(&data), Ok(0.0));
+
+        // Two bins of equal power out of 9
+        data[5] = (2.0, 0.0);
+        assert_abs_diff_eq!(spectral_entropy(&data).unwrap(), 2.0f64.ln() / 9.0f64.ln(), epsilon = 1e-9);
+
+        let data = [(1.0f64, -1.0); 16];
+        assert_abs_diff_eq!(spectral_entropy(&data).unwrap(), 1.0, epsilon = 1e-9);
+    }
+}
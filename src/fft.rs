@@ -5,7 +5,17 @@
 
 /******************************************************************************/
 
+use core::ops::Index;
+
+#[cfg(feature = "num-complex")]
+use num_complex::Complex;
+
 use crate::common::{Base, Float};
+use crate::error::Error;
+use crate::kernel::Kernel;
+use crate::metadata::{Direction, Scaling};
+use crate::status::Status;
+use crate::window::{Rectangular, Window};
 
 /******************************************************************************/
 
@@ -13,27 +23,14 @@ use crate::common::{Base, Float};
 ///
 /// This structure contains a reference to the input / output data, as well as information related to the
 /// internal state.
-pub struct EmbFft<'a, T, const N: usize> {
-    data: &'a mut [(T, T); N],
-    state: State,
-    length: usize,
-    step: usize,
-    step_size: usize,
-    top_idx: usize,
-    bottom_idx: usize
-}
-
-/// Conversion state
-#[derive(PartialEq)]
-enum State {
-    Step1,
-    Step2,
-    Step3,
-    Step4,
-    Step5,
-    Step6,
-    Reorder,
-    Done
+/// The window function `W` is applied to the data before the transform, see the [`window`](crate::window)
+/// module.
+///
+/// The data is an array of complex numbers stored as tuples, a flat array of interleaved real and imaginary
+/// parts, see [`EmbFft::from_interleaved()`], or an array of `num_complex::Complex` with the `num-complex`
+/// feature, see `EmbFft::from_complex()`.
+pub struct EmbFft<'a, T, const N: usize, W = Rectangular, B = [(T, T); N]> {
+    kernel: Kernel<'a, T, N, false, W, B>
 }
 
 impl<'a, T: Float<N>, const N: usize> EmbFft<'a, T, N> {
@@ -41,140 +38,85 @@ impl<'a, T: Float<N>, const N: usize> EmbFft<'a, T, N> {
     ///
     /// Use this function whenever a new conversion is required.
     pub fn new(data: &'a mut [(T, T); N]) -> Self {
-        assert!(Base::<N>::IS_N_POW2);
-        Self {
-            data,
-            state: State::Step1,
-            length: N / 4,
-            step: 0,
-            step_size: 1,
-            top_idx: 0,
-            bottom_idx: 0
-        }
+        Self::windowed(data)
     }
 
-    fn step1(&mut self) {
-        // Twiddle = 1
-        self.bottom_idx = self.top_idx + (self.length << 1);
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        self.data[self.top_idx].0 = bottom.0 + top.0;
-        self.data[self.top_idx].1 = bottom.1 + top.1;
-        self.data[self.bottom_idx].0 = top.0 - bottom.0;
-        self.data[self.bottom_idx].1 = top.1 - bottom.1;
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        self.step = self.step_size;
-        if self.step_size < N / 4 {
-            self.state = State::Step2;
-        } else {
-            self.state = State::Step3;
-        }
-    }
-
-    fn step2(&mut self) {
-        // Twiddle = e^(-j * theta)
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        let temp = (top.0 - bottom.0, top.1 - bottom.1);
-        self.data[self.top_idx].0 = bottom.0 + top.0;
-        self.data[self.top_idx].1 = bottom.1 + top.1;
-        self.data[self.bottom_idx].0 = temp.0 * T::SINE_TABLE[N / 4 - self.step] + temp.1 * T::SINE_TABLE[self.step];
-        self.data[self.bottom_idx].1 = temp.1 * T::SINE_TABLE[N / 4 - self.step] - temp.0 * T::SINE_TABLE[self.step];
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        if self.step < N / 4 - self.step_size {
-            self.step += self.step_size;
-        } else {
-            self.state = State::Step3;
-        }
+    /// Initializes a new FFT conversion from a slice
+    ///
+    /// Fails with [`Error::InvalidLength`] if the slice length is not N.
+    pub fn try_from_slice(data: &'a mut [(T, T)]) -> Result<Self, Error> {
+        data.try_into().map(Self::new).map_err(|_| Error::InvalidLength)
     }
 
-    fn step3(&mut self) {
-        // Twiddle = -j
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        self.data[self.top_idx].0 = bottom.0 + top.0;
-        self.data[self.top_idx].1 = bottom.1 + top.1;
-        self.data[self.bottom_idx].0 = top.1 - bottom.1;
-        self.data[self.bottom_idx].1 = bottom.0 - top.0;
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        self.step = self.step_size;
-        if self.step_size < N / 4 {
-            self.state = State::Step4;
-        } else {
-            self.state = State::Step5;
-        }
+    /// Initializes a new FFT conversion without the final reordering pass
+    ///
+    /// The output is left in bit-reversed order, which saves N iterations. Use [`EmbFft::get_bin()`] or
+    /// indexing to access the bins by frequency regardless of the order.
+    pub fn new_unordered(data: &'a mut [(T, T); N]) -> Self {
+        Self::windowed_unordered(data)
     }
 
-    fn step4(&mut self) {
-        // Twiddle = -j * e^(-j * theta)
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        let temp = (top.1 - bottom.1, bottom.0 - top.0);
-        self.data[self.top_idx].0 = bottom.0 + top.0;
-        self.data[self.top_idx].1 = bottom.1 + top.1;
-        self.data[self.bottom_idx].0 = temp.0 * T::SINE_TABLE[N / 4 - self.step] + temp.1 * T::SINE_TABLE[self.step];
-        self.data[self.bottom_idx].1 = temp.1 * T::SINE_TABLE[N / 4 - self.step] - temp.0 * T::SINE_TABLE[self.step];
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        if self.step < N / 4 - self.step_size {
-            self.step += self.step_size;
-        } else {
-            self.state = State::Step5;
-        }
+    /// Initializes a new FFT conversion, applying a window given as a table of N coefficients
+    ///
+    /// This is meant for the windows computed at run time, the type-level windows of the
+    /// [`window`](crate::window) module being used with [`EmbFft::windowed()`] instead. In both cases, the
+    /// window is applied by the first stage of the transform, without a separate pass over the data.
+    /// For example:
+    /// ```
+    /// // Triangular window, computed at run time
+    /// let window: [f32; 8] = core::array::from_fn(|i| 1.0 - (i as f32 - 4.0).abs() / 4.0);
+    /// let mut data = [(1.0f32, 0.0); 8];
+    /// embfft::EmbFft::new_windowed(&mut data, &window).fft();
+    /// assert_eq!(data[0], (4.0, 0.0));
+    /// ```
+    pub fn new_windowed(data: &'a mut [(T, T); N], window: &'a [T; N]) -> Self {
+        let mut fft = Self::new(data);
+        fft.kernel.set_window(window);
+        fft
     }
+}
 
-    fn step5(&mut self) {
-        // Check if we need to loop
-        if self.bottom_idx < N {
-            self.top_idx = self.bottom_idx;
-            self.state = State::Step1;
-        } else if self.length > 1 {
-            self.length >>= 1;
-            self.step_size <<= 1;
-            self.top_idx = 0;
-            self.state = State::Step1;
-        } else {
-            self.top_idx = 0;
-            self.bottom_idx = 1;
-            self.state = State::Step6;
+impl<'a, T: Float<N>, const N: usize, W: Window<T, N>> EmbFft<'a, T, N, W> {
+    /// Initializes a new FFT conversion, applying the window `W` to the data first
+    ///
+    /// The window is applied by the first stage of the transform, without a separate pass over the data.
+    /// For example:
+    /// ```
+    /// let mut data = [(1.0f32, 0.0); 8];
+    /// embfft::EmbFft::<f32, 8, embfft::window::Hann>::windowed(&mut data).fft();
+    /// ```
+    pub fn windowed(data: &'a mut [(T, T); N]) -> Self {
+        Self {
+            kernel: Kernel::new(data, true, false)
         }
     }
 
-    fn step6(&mut self) {
-        // Twiddle = 1
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        self.data[self.top_idx].0 = bottom.0 + top.0;
-        self.data[self.top_idx].1 = bottom.1 + top.1;
-        self.data[self.bottom_idx].0 = top.0 - bottom.0;
-        self.data[self.bottom_idx].1 = top.1 - bottom.1;
-        if self.bottom_idx < N - 2 {
-            self.top_idx += 2;
-            self.bottom_idx += 2;
-        } else {
-            self.top_idx = 0;
-            self.bottom_idx = 0;
-            self.state = State::Reorder;
+    /// Initializes a new FFT conversion, applying the window `W` to the data first, without the final
+    /// reordering pass
+    ///
+    /// See [`EmbFft::new_unordered()`].
+    pub fn windowed_unordered(data: &'a mut [(T, T); N]) -> Self {
+        Self {
+            kernel: Kernel::new(data, false, false)
         }
     }
 
-    fn reorder(&mut self) {
-        // Ensure the output order is the same as the input
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        if self.bottom_idx > self.top_idx {
-            self.data[self.top_idx] = bottom;
-            self.data[self.bottom_idx] = top;
-        }
-        if self.top_idx < N - 1 {
-            self.bottom_idx = Base::<N>::reverse_bits(self.top_idx + 1);
-            self.top_idx += 1;
-        } else {
-            self.state = State::Done;
-        }
+    /// References the phases to the center of the frame
+    ///
+    /// The two halves of the input are swapped before the transform, which multiplies bin k by (-1)^k.
+    /// The phase of a component centered in the frame, such as a windowed impulse, is then independent of
+    /// its frequency, which is what transfer function measurements and phase vocoders expect. Call this
+    /// before the first iteration.
+    /// For example:
+    /// ```
+    /// let mut data = [(0.0f32, 0.0); 8];
+    /// data[4] = (1.0, 0.0);
+    /// embfft::EmbFft::new(&mut data).centered().fft();
+    /// assert!(data.iter().all(|x| *x == (1.0, 0.0)));
+    /// ```
+    pub fn centered(mut self) -> Self {
+        self.kernel.set_centered();
+        self
     }
 
     /// Non-blocking FFT computation
@@ -196,16 +138,7 @@ impl<'a, T: Float<N>, const N: usize> EmbFft<'a, T, N> {
     /// }
     /// ```
     pub fn fft_iterate(&mut self) {
-        match self.state {
-            State::Step1 => { self.step1(); },
-            State::Step2 => { self.step2(); },
-            State::Step3 => { self.step3(); },
-            State::Step4 => { self.step4(); },
-            State::Step5 => { self.step5(); },
-            State::Step6 => { self.step6(); },
-            State::Reorder => { self.reorder(); },
-            State::Done => {}
-        }
+        self.kernel.iterate();
     }
 
     /// Blocking FFT computation
@@ -221,25 +154,244 @@ impl<'a, T: Float<N>, const N: usize> EmbFft<'a, T, N> {
     /// embfft::EmbFft::new(&mut data).fft();
     /// ```
     pub fn fft(&mut self) {
-        while self.state != State::Done {
-            self.fft_iterate();
-        }
+        self.kernel.run();
     }
 
     /// Checks if the conversion is complete
     ///
     /// Use this together with the [`EmbFft::fft_iterate()`] function.
     pub fn is_done(&self) -> bool {
-        self.state == State::Done
+        self.kernel.is_done()
+    }
+
+    /// Returns the number of samples of the transform
+    pub const fn len() -> usize {
+        N
+    }
+
+    /// Returns the base 2 logarithm of the number of samples
+    pub const fn log2_len() -> usize {
+        Base::<N>::LOG2_N
+    }
+
+    /// Returns the direction of the transform
+    pub const fn direction() -> Direction {
+        Direction::Forward
+    }
+
+    /// Returns the scaling of the output
+    pub fn scaling(&self) -> Scaling {
+        if self.kernel.is_scaled() { Scaling::InverseN } else { Scaling::Unscaled }
+    }
+
+    /// Checks if the output is sorted by frequency
+    ///
+    /// This is `false` for conversions created with [`EmbFft::new_unordered()`].
+    pub fn is_natural_order(&self) -> bool {
+        self.kernel.is_natural_order()
+    }
+
+    /// Returns the bin at index `k`, sorted by frequency
+    ///
+    /// The bit-reversal is taken into account if the final reordering pass was skipped.
+    /// Fails with [`Error::NotDone`] until the conversion is complete, or with [`Error::OutOfRange`]
+    /// if `k` is not lower than N.
+    pub fn get_bin(&self, k: usize) -> Result<&(T, T), Error> {
+        let data = self.output()?;
+        if k >= N {
+            Err(Error::OutOfRange)
+        } else if self.is_natural_order() {
+            Ok(&data[k])
+        } else {
+            Ok(&data[Base::<N>::reverse_bits(k)])
+        }
+    }
+
+    /// Returns the status flags accumulated so far
+    ///
+    /// Use this to detect overflows or invalid values, and discard the corrupted results.
+    pub fn status(&self) -> Status {
+        self.kernel.status()
+    }
+
+    /// Returns a view of the transformed data
+    ///
+    /// The data is in bit-reversed order if the final reordering pass was skipped.
+    /// Fails with [`Error::NotDone`] until the conversion is complete.
+    pub fn output(&self) -> Result<&[(T, T); N], Error> {
+        if self.is_done() {
+            Ok(self.kernel.data())
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Returns a view of the first half of the transformed data
+    ///
+    /// For real input signals, the second half only contains the complex conjugates of the first half.
+    /// Fails with [`Error::NotDone`] until the conversion is complete, or with
+    /// [`Error::InvalidConfiguration`] if the output is not sorted by frequency.
+    pub fn output_half(&self) -> Result<&[(T, T)], Error> {
+        let data = self.output()?;
+        if self.is_natural_order() {
+            Ok(&data[..N / 2])
+        } else {
+            Err(Error::InvalidConfiguration)
+        }
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    ///
+    /// If the conversion is not complete, the buffer contains partially transformed data.
+    pub fn into_data(self) -> &'a mut [(T, T); N] {
+        self.kernel.into_data()
+    }
+}
+
+/// Transforms of flat buffers of R = 2N interleaved real and imaginary parts, as filled by DMA peripherals,
+/// computed in place without any copy
+impl<'a, T: Float<N>, const N: usize, W: Window<T, N>, const R: usize> EmbFft<'a, T, N, W, [T; R]> {
+    /// Initializes a new FFT conversion of interleaved samples, applying the window `W` to the data first
+    ///
+    /// The value i is `(data[2 * i], data[2 * i + 1])`, and the bins are stored the same way. As N cannot be
+    /// inferred from R, the type parameters must be given explicitly.
+    /// For example:
+    /// ```
+    /// use embfft::window::Rectangular;
+    ///
+    /// // Interleaved I/Q samples, as written by an ADC through DMA
+    /// let mut dma = [1.0f32, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+    /// embfft::EmbFft::<_, 4, Rectangular, _>::from_interleaved(&mut dma).fft();
+    /// assert_eq!(dma, [4.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    /// ```
+    pub fn from_interleaved(data: &'a mut [T; R]) -> Self {
+        const { assert!(R == 2 * N, "R must be equal to 2 * N") };
+        Self {
+            kernel: Kernel::new(data, true, false)
+        }
+    }
+
+    /// Initializes a new FFT conversion of interleaved samples from a slice
+    ///
+    /// Fails with [`Error::InvalidLength`] if the slice length is not 2N.
+    pub fn try_from_interleaved(data: &'a mut [T]) -> Result<Self, Error> {
+        data.try_into().map(Self::from_interleaved).map_err(|_| Error::InvalidLength)
+    }
+
+    /// Non-blocking FFT computation, see [`EmbFft::fft_iterate()`]
+    pub fn fft_iterate(&mut self) {
+        self.kernel.iterate();
+    }
+
+    /// Blocking FFT computation
+    pub fn fft(&mut self) {
+        self.kernel.run();
+    }
+
+    /// Checks if the conversion is complete
+    pub fn is_done(&self) -> bool {
+        self.kernel.is_done()
+    }
+
+    /// Returns the status flags accumulated so far
+    pub fn status(&self) -> Status {
+        self.kernel.status()
+    }
+
+    /// Returns a view of the transformed data, with interleaved real and imaginary parts
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete.
+    pub fn output(&self) -> Result<&[T; R], Error> {
+        if self.is_done() {
+            Ok(self.kernel.data())
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    pub fn into_data(self) -> &'a mut [T; R] {
+        self.kernel.into_data()
+    }
+}
+
+/// Transforms of buffers of the `num-complex` crate, computed in place without any copy
+#[cfg(feature = "num-complex")]
+impl<'a, T: Float<N>, const N: usize> EmbFft<'a, T, N, Rectangular, [Complex<T>; N]> {
+    /// Initializes a new FFT conversion of complex numbers
+    ///
+    /// For example:
+    /// ```
+    /// use num_complex::Complex;
+    ///
+    /// let mut data = [Complex::new(1.0f32, 0.0); 8];
+    /// embfft::EmbFft::from_complex(&mut data).fft();
+    /// assert_eq!(data[0], Complex::new(8.0, 0.0));
+    /// ```
+    pub fn from_complex(data: &'a mut [Complex<T>; N]) -> Self {
+        Self {
+            kernel: Kernel::new(data, true, false)
+        }
+    }
+
+    /// Non-blocking FFT computation, see [`EmbFft::fft_iterate()`]
+    pub fn fft_iterate(&mut self) {
+        self.kernel.iterate();
+    }
+
+    /// Blocking FFT computation
+    pub fn fft(&mut self) {
+        self.kernel.run();
+    }
+
+    /// Checks if the conversion is complete
+    pub fn is_done(&self) -> bool {
+        self.kernel.is_done()
+    }
+
+    /// Returns the status flags accumulated so far
+    pub fn status(&self) -> Status {
+        self.kernel.status()
+    }
+
+    /// Returns a view of the transformed data
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete.
+    pub fn output(&self) -> Result<&[Complex<T>; N], Error> {
+        if self.is_done() {
+            Ok(self.kernel.data())
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    pub fn into_data(self) -> &'a mut [Complex<T>; N] {
+        self.kernel.into_data()
+    }
+}
+
+impl<T: Float<N>, const N: usize, W: Window<T, N>> Index<usize> for EmbFft<'_, T, N, W> {
+    type Output = (T, T);
+
+    /// Returns the bin at index `k`, sorted by frequency
+    ///
+    /// Panics if the conversion is not complete, or if `k` is out of range.
+    fn index(&self, k: usize) -> &Self::Output {
+        match self.get_bin(k) {
+            Ok(bin) => bin,
+            Err(e) => panic!("The FFT bin {k} is not available: {e}")
+        }
     }
 }
 
 /******************************************************************************/
 
 #[cfg(test)]
+#[allow(clippy::excessive_precision)]
 mod tests {
     use super::*;
-    use approx::assert_ulps_eq;
+    use approx::{assert_abs_diff_eq, assert_ulps_eq};
 
     #[test]
     fn test_fft_f32() {
@@ -352,4 +504,170 @@ mod tests {
             assert_ulps_eq!(x.1, y.1);
         }
     }
+
+    #[test]
+    fn test_fft_output() {
+        let mut data = [(1.0f32, 0.0), (2.0, 0.0), (3.0, 0.0), (4.0, 0.0)];
+
+        let mut fft = EmbFft::new(&mut data);
+        assert_eq!(fft.output(), Err(Error::NotDone));
+        assert_eq!(fft.output_half(), Err(Error::NotDone));
+        fft.fft();
+        assert_eq!(fft.output().unwrap()[0], (10.0, 0.0));
+        assert_eq!(fft.output_half().unwrap(), &[(10.0, 0.0), (-2.0, 2.0)]);
+
+        assert_eq!(fft[1], (-2.0, 2.0));
+
+        let data = fft.into_data();
+        data[0].0 = 0.0;
+        assert_eq!(data[1], (-2.0, 2.0));
+    }
+
+    #[test]
+    fn test_fft_from_slice() {
+        let mut data = [(1.0f32, 0.0); 8];
+        assert!(EmbFft::<_, 8>::try_from_slice(&mut data[..4]).is_err());
+        assert!(EmbFft::<_, 8>::try_from_slice(&mut data).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "num-complex")]
+    fn test_fft_complex() {
+        let mut data: [(f64, f64); 16] = core::array::from_fn(|i| (i as f64, 1.0 - i as f64 / 4.0));
+        let mut complex: [Complex<f64>; 16] = core::array::from_fn(|i| Complex::new(data[i].0, data[i].1));
+        EmbFft::new(&mut data).fft();
+
+        let mut fft = EmbFft::from_complex(&mut complex);
+        assert_eq!(fft.output(), Err(Error::NotDone));
+        fft.fft();
+        assert_eq!(fft.status(), Status::OK);
+        for (x, y) in core::iter::zip(fft.into_data().iter(), data) {
+            assert_eq!((x.re, x.im), y);
+        }
+    }
+
+    #[test]
+    fn test_fft_interleaved() {
+        use crate::window::Hann;
+
+        let mut data: [(f32, f32); 16] = core::array::from_fn(|i| (i as f32, 2.0 - i as f32 / 3.0));
+        let mut flat: [f32; 32] = core::array::from_fn(|i| if i % 2 == 0 { data[i / 2].0 } else { data[i / 2].1 });
+        EmbFft::<_, 16, Hann>::windowed(&mut data).fft();
+
+        assert!(EmbFft::<f32, 16, Hann, [f32; 32]>::try_from_interleaved(&mut flat[..30]).is_err());
+        let mut fft = EmbFft::<_, 16, Hann, [f32; 32]>::try_from_interleaved(&mut flat).unwrap();
+        assert_eq!(fft.output(), Err(Error::NotDone));
+        fft.fft();
+        assert_eq!(fft.status(), Status::OK);
+        let flat = fft.into_data();
+        for (i, x) in data.iter().enumerate() {
+            assert_eq!((flat[2 * i], flat[2 * i + 1]), *x);
+        }
+    }
+
+    #[test]
+    fn test_fft_windowed() {
+        use crate::window::Hann;
+
+        let mut data = [(1.0f64, 1.0); 8];
+        EmbFft::<_, 8, Hann>::windowed(&mut data).fft();
+
+        let expected_data = [(4.0, 4.0), (-2.0, -2.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (0.0, 0.0), (-2.0, -2.0)];
+        for (x, y) in core::iter::zip(data, expected_data) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_fft_window_table() {
+        use crate::window::{Hann, Window};
+
+        let mut data: [(f32, f32); 32] = core::array::from_fn(|i| (crate::cordic::cos(0.7 * i as f64) as f32, i as f32 / 8.0));
+        let mut expected_data = data;
+        EmbFft::<_, 32, Hann>::windowed(&mut expected_data).centered().fft();
+
+        // The same window, given as a table
+        let table = <Hann as Window<f32, 32>>::TABLE;
+        EmbFft::new_windowed(&mut data, &table).centered().fft();
+        assert_eq!(data, expected_data);
+
+        // Each value is multiplied by its coefficient before the transform
+        let mut data = [(2.0f64, -1.0); 8];
+        let mut table = [0.0; 8];
+        table[3] = 0.5;
+        let mut fft = EmbFft::new_windowed(&mut data, &table);
+        fft.fft();
+        assert_eq!(fft.status(), Status::OK);
+        for (k, x) in data.iter().enumerate() {
+            let (sin, cos) = crate::cordic::sin_cos(-2.0 * core::f64::consts::PI * 3.0 * k as f64 / 8.0);
+            assert_abs_diff_eq!(x.0, cos + 0.5 * sin, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, sin - 0.5 * cos, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_fft_status() {
+        let mut data = [(1.0f32, 0.0); 8];
+        let mut fft = EmbFft::new(&mut data);
+        fft.fft();
+        assert_eq!(fft.status(), Status::OK);
+
+        data[3].1 = f32::NAN;
+        data[5].0 = 1e-40;
+        let mut fft = EmbFft::new(&mut data);
+        fft.fft();
+        assert!(fft.status().contains(Status::INVALID | Status::DENORMAL));
+        assert!(fft.status().is_corrupted());
+
+        let mut data = [(f32::MAX, 0.0); 8];
+        let mut fft = EmbFft::new(&mut data);
+        fft.fft();
+        assert!(fft.status().contains(Status::OVERFLOW));
+    }
+
+    #[test]
+    fn test_fft_unordered() {
+        let mut data: [(f64, f64); 16] = core::array::from_fn(|i| (i as f64, (i * i) as f64));
+        let mut expected_data = data;
+        EmbFft::new(&mut expected_data).fft();
+
+        let mut fft = EmbFft::new_unordered(&mut data);
+        assert_eq!(fft.get_bin(0), Err(Error::NotDone));
+        fft.fft();
+        assert!(!fft.is_natural_order());
+        assert_eq!(fft.output_half(), Err(Error::InvalidConfiguration));
+        assert_eq!(fft.get_bin(16), Err(Error::OutOfRange));
+        for (k, y) in expected_data.iter().enumerate() {
+            assert_eq!(fft.get_bin(k), Ok(y));
+            assert_eq!(&fft[k], y);
+        }
+        assert_eq!(fft.output().unwrap()[1], expected_data[8]);
+    }
+
+    #[test]
+    fn test_fft_centered() {
+        let data: [(f64, f64); 32] = core::array::from_fn(|i| ((i % 7) as f64, (i % 3) as f64));
+        let mut expected_data = data;
+        EmbFft::<f64, 32, crate::window::Hann>::windowed(&mut expected_data).fft();
+
+        let mut centered = data;
+        EmbFft::<f64, 32, crate::window::Hann>::windowed(&mut centered).centered().fft();
+        for (k, (x, y)) in centered.iter().zip(expected_data.iter()).enumerate() {
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            assert_abs_diff_eq!(x.0, sign * y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, sign * y.1, epsilon = 1e-12);
+        }
+
+        let mut expected_data = data;
+        EmbFft::new(&mut expected_data).fft();
+        let mut unordered = data;
+        let mut fft = EmbFft::new_unordered(&mut unordered).centered();
+        fft.fft();
+        for (k, y) in expected_data.iter().enumerate() {
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            assert_abs_diff_eq!(fft[k].0, sign * y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(fft[k].1, sign * y.1, epsilon = 1e-12);
+        }
+    }
 }
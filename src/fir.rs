@@ -0,0 +1,514 @@
+/* embfft | fir.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! FIR filter design, with the window method
+//!
+//! The coefficients are computed by const functions, so that they can be generated at compile time.
+//! Fractional delay filters, as windowed sincs or Thiran all-pass filters, align the channels sampled by
+//! different converters to a fraction of a sample.
+//! The frequencies are normalized to the sample rate, between 0 and 0.5.
+//! For example:
+//! ```
+//! use embfft::fir::{self, FirWindow};
+//!
+//! const TAPS: [f64; 31] = fir::lowpass(0.1, FirWindow::Hamming);
+//! let kernel = fir::kernel::<f32, 31, 64>(&TAPS).unwrap();
+//!
+//! let mut data = [(0.0f32, 0.0); 64];
+//! data[0] = (1.0, 0.0);
+//! embfft::convolve::FftConvolver::new(&kernel, &mut data).convolve();
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::convolve::FftKernel;
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::ifft::EmbIfft;
+use crate::math;
+use crate::window::{blackman, hamming, hann, Window};
+
+/******************************************************************************/
+
+/// Window applied to the ideal impulse response
+///
+/// Unlike the windows of the transforms, these are symmetric, to preserve the linear phase of the filter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirWindow {
+    /// Rectangular window, i.e. truncation of the ideal response
+    Rectangular,
+    /// Hann window
+    Hann,
+    /// Hamming window
+    Hamming,
+    /// Blackman window
+    Blackman
+}
+
+impl FirWindow {
+    /// Computes coefficient i of the symmetric window of length m
+    const fn coefficient(self, i: usize, m: usize) -> f64 {
+        if m == 1 {
+            return 1.0;
+        }
+        match self {
+            FirWindow::Rectangular => 1.0,
+            FirWindow::Hann => hann(i, m - 1),
+            FirWindow::Hamming => hamming(i, m - 1),
+            FirWindow::Blackman => blackman(i, m - 1)
+        }
+    }
+}
+
+/// Computes the ideal low-pass response, offset by the center of the filter
+const fn ideal_lowpass(cutoff: f64, i: usize, m: usize) -> f64 {
+    let t = i as f64 - (m - 1) as f64 / 2.0;
+    if t == 0.0 {
+        2.0 * cutoff
+    } else {
+        crate::cordic::sin(2.0 * PI * cutoff * t) / (PI * t)
+    }
+}
+
+/// Designs a low-pass filter with M taps
+///
+/// The gain is normalized to 1 at DC.
+pub const fn lowpass<const M: usize>(cutoff: f64, window: FirWindow) -> [f64; M] {
+    let mut taps = [0.0; M];
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < M {
+        taps[i] = ideal_lowpass(cutoff, i, M) * window.coefficient(i, M);
+        sum += taps[i];
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < M {
+        taps[i] /= sum;
+        i += 1;
+    }
+    taps
+}
+
+/// Designs a high-pass filter with M taps, by spectral inversion of a low-pass filter
+///
+/// M must be odd. The gain is normalized to 1 at the Nyquist frequency.
+pub const fn highpass<const M: usize>(cutoff: f64, window: FirWindow) -> [f64; M] {
+    assert!(M % 2 == 1);
+    let mut taps = lowpass::<M>(cutoff, window);
+    let mut i = 0;
+    while i < M {
+        taps[i] = -taps[i];
+        i += 1;
+    }
+    taps[M / 2] += 1.0;
+    taps
+}
+
+/// Designs a band-pass filter with M taps, as the difference of two low-pass filters
+///
+/// The gain is normalized to 1 at the center of the band.
+pub const fn bandpass<const M: usize>(low: f64, high: f64, window: FirWindow) -> [f64; M] {
+    let mut taps = [0.0; M];
+    let center = (low + high) / 2.0;
+    let mut re = 0.0;
+    let mut im = 0.0;
+    let mut i = 0;
+    while i < M {
+        taps[i] = (ideal_lowpass(high, i, M) - ideal_lowpass(low, i, M)) * window.coefficient(i, M);
+        let (s, c) = crate::cordic::sin_cos(2.0 * PI * center * i as f64);
+        re += taps[i] * c;
+        im += taps[i] * s;
+        i += 1;
+    }
+
+    // |H(center)|, the square root is computed with a few Newton steps
+    let m2 = re * re + im * im;
+    let mut gain = if m2 > 1.0 { m2 } else { 1.0 };
+    let mut j = 0;
+    while j < 64 {
+        gain = (gain + m2 / gain) / 2.0;
+        j += 1;
+    }
+
+    let mut i = 0;
+    while i < M {
+        taps[i] /= gain;
+        i += 1;
+    }
+    taps
+}
+
+/// Designs a fractional delay filter with M taps, as a windowed sinc delayed by `delay` samples
+///
+/// The window stays centered on the taps, so the delay should be within a sample of (M - 1) / 2 for the
+/// best accuracy, the integer part of a larger delay being better applied by shifting the samples. The
+/// gain is normalized to 1 at DC.
+/// For example:
+/// ```
+/// use embfft::fir::{self, FirWindow};
+///
+/// // Aligns a channel sampled 0.3 sample earlier than the other ones, which are delayed by 15 samples
+/// const TAPS: [f64; 31] = fir::fractional_delay(15.3, FirWindow::Blackman);
+/// let kernel = fir::kernel::<f32, 31, 64>(&TAPS).unwrap();
+/// ```
+pub const fn fractional_delay<const M: usize>(delay: f64, window: FirWindow) -> [f64; M] {
+    let mut taps = [0.0; M];
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < M {
+        let t = i as f64 - delay;
+        let sinc = if t == 0.0 { 1.0 } else { crate::cordic::sin(PI * t) / (PI * t) };
+        taps[i] = sinc * window.coefficient(i, M);
+        sum += taps[i];
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < M {
+        taps[i] /= sum;
+        i += 1;
+    }
+    taps
+}
+
+/// Designs a Thiran all-pass fractional delay filter of order M - 1, with a maximally flat group delay of
+/// `delay` samples at DC
+///
+/// Returns the M coefficients a0 = 1, a1, ... of the denominator, the numerator having the same ones in
+/// reverse order. The magnitude response is exactly flat, unlike the one of [`fractional_delay()`], and the
+/// filter is stable for a delay greater than M - 2. It is best used with a delay between M - 1.5 and
+/// M - 0.5.
+pub const fn thiran<const M: usize>(delay: f64) -> [f64; M] {
+    assert!(M > 0);
+    let order = M - 1;
+    let mut a = [0.0; M];
+    a[0] = 1.0;
+    let mut binomial = 1.0;
+    let mut k = 1;
+    while k < M {
+        binomial = binomial * (order - k + 1) as f64 / k as f64;
+        let mut product = 1.0;
+        let mut n = 0;
+        while n <= order {
+            let base = delay - order as f64 + n as f64;
+            product *= base / (base + k as f64);
+            n += 1;
+        }
+        a[k] = if k % 2 == 0 { binomial } else { -binomial } * product;
+        k += 1;
+    }
+    a
+}
+
+/// Converts the coefficients of an all-pass filter, such as those of [`thiran()`], to a kernel for the
+/// [`FftConvolver`](crate::convolve::FftConvolver)
+///
+/// The kernel is the frequency response of the filter, sampled at the bins, so that it applies the
+/// steady-state response to periodic frames, without the transient of a recursive implementation.
+/// N must be a power of 2.
+/// For example:
+/// ```
+/// use embfft::fir;
+///
+/// const A: [f64; 4] = fir::thiran(3.25);
+/// let kernel = fir::allpass_kernel::<f64, 4, 64>(&A);
+///
+/// let mut data: [(f64, f64); 64] = core::array::from_fn(|i| (embfft::cordic::cos(0.2 * i as f64), 0.0));
+/// embfft::convolve::FftConvolver::new(&kernel, &mut data).convolve();
+/// ```
+pub fn allpass_kernel<T: Float<N>, const M: usize, const N: usize>(coefficients: &[f64; M]) -> FftKernel<T, N> {
+    let mut spectrum = [(T::ZERO, T::ZERO); N];
+    for k in 0..=N / 2 {
+        let (mut num, mut den) = ((0.0, 0.0), (0.0, 0.0));
+        for (i, &a) in coefficients.iter().enumerate() {
+            let (s, c) = math::sin_cos(-2.0 * PI * ((k * i) % N) as f64 / N as f64);
+            let b = coefficients[M - 1 - i];
+            num = (num.0 + b * c, num.1 + b * s);
+            den = (den.0 + a * c, den.1 + a * s);
+        }
+        let m2 = den.0 * den.0 + den.1 * den.1;
+        let h = ((num.0 * den.0 + num.1 * den.1) / m2, (num.1 * den.0 - num.0 * den.1) / m2);
+
+        // The response of a real filter is conjugate-symmetric, and real at DC and at the Nyquist frequency
+        let h = if k == 0 || 2 * k == N { (h.0, 0.0) } else { h };
+        spectrum[k] = (T::from_f64(h.0), T::from_f64(h.1));
+        spectrum[(N - k) % N] = (T::from_f64(h.0), T::from_f64(-h.1));
+    }
+    FftKernel::from_spectrum(&spectrum)
+}
+
+/// Converts filter taps to a kernel for the [`FftConvolver`](crate::convolve::FftConvolver)
+///
+/// The taps are zero-padded to N. Fails with [`Error::InvalidLength`] if M is greater than N.
+pub fn kernel<T: Float<N>, const M: usize, const N: usize>(taps: &[f64; M]) -> Result<FftKernel<T, N>, Error> {
+    if M > N {
+        return Err(Error::InvalidLength);
+    }
+    let mut kernel = [(T::ZERO, T::ZERO); N];
+    for (k, &x) in kernel.iter_mut().zip(taps) {
+        *k = (T::from_f64(x), T::ZERO);
+    }
+    Ok(FftKernel::new(&kernel))
+}
+
+/// Designs a filter with N taps, from its magnitude response sampled at the FFT bins
+///
+/// `magnitude` contains the N / 2 + 1 gains from DC to the Nyquist frequency. The zero-phase response is
+/// transformed with an IFFT, centered on tap N / 2, and weighted by the window `W` to reduce the ripple
+/// between the bins. This is a blocking function, which performs an IFFT.
+/// Fails with [`Error::InvalidLength`] if the length of `magnitude` is not N / 2 + 1.
+///
+/// For example:
+/// ```
+/// use embfft::window::Hann;
+///
+/// // Equalization of a sensor that loses 6 dB at high frequencies
+/// let gains: [f32; 9] = core::array::from_fn(|k| 1.0 + k as f32 / 8.0);
+/// let taps = embfft::fir::frequency_sampling::<f32, Hann, 16>(&gains).unwrap();
+/// ```
+pub fn frequency_sampling<T: Float<N>, W: Window<T, N>, const N: usize>(magnitude: &[T]) -> Result<[T; N], Error> {
+    if magnitude.len() != N / 2 + 1 {
+        return Err(Error::InvalidLength);
+    }
+    let mut spectrum = [(T::ZERO, T::ZERO); N];
+    for (k, &a) in magnitude.iter().enumerate() {
+        spectrum[k] = (a, T::ZERO);
+        spectrum[(N - k) % N] = (a, T::ZERO);
+    }
+    EmbIfft::new(&mut spectrum).ifft();
+
+    let mut taps = [T::ZERO; N];
+    for (i, t) in taps.iter_mut().enumerate() {
+        let x = spectrum[(i + N / 2) % N].0;
+        *t = if W::IS_RECTANGULAR { x } else { x * W::TABLE[i] };
+    }
+    Ok(taps)
+}
+
+/// Designs a minimum-phase filter with N taps, from its magnitude response sampled at the FFT bins
+///
+/// `magnitude` contains the N / 2 + 1 gains from DC to the Nyquist frequency, for example from a
+/// magnitude-only calibration. The phase is obtained from the real cepstrum of the log-magnitude: its
+/// anti-causal part is folded onto the causal one, which concentrates the energy at the start of the
+/// impulse response, for the lowest latency. The gains are clamped to 1e-5 times the largest one, i.e.
+/// -100 dB, and N must be large enough for the cepstrum to decay, or the response is aliased.
+/// This is a blocking function, which performs two IFFTs and an FFT, and needs floating-point types.
+/// Fails with [`Error::InvalidLength`] if the length of `magnitude` is not N / 2 + 1, and with
+/// [`Error::InvalidParameter`] if all the gains are zero.
+///
+/// For example:
+/// ```
+/// // Taps [1, 0.5], from their magnitude response
+/// let gains: [f64; 33] = core::array::from_fn(|k| {
+///     let (s, c) = embfft::cordic::sin_cos(core::f64::consts::PI * k as f64 / 32.0);
+///     ((1.0 + 0.5 * c) * (1.0 + 0.5 * c) + 0.25 * s * s).sqrt()
+/// });
+/// let taps = embfft::fir::minimum_phase::<f64, 64>(&gains).unwrap();
+/// # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+/// assert!((taps[0] - 1.0).abs() < 1e-6 && (taps[1] - 0.5).abs() < 1e-6);
+/// ```
+pub fn minimum_phase<T: Float<N>, const N: usize>(magnitude: &[T]) -> Result<[T; N], Error> {
+    if magnitude.len() != N / 2 + 1 {
+        return Err(Error::InvalidLength);
+    }
+    let max = magnitude.iter().fold(0.0, |m: f64, a| m.max(a.to_f64().abs()));
+    if max.is_nan() || max <= 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    // Real cepstrum of the log-magnitude
+    let mut data = [(T::ZERO, T::ZERO); N];
+    for (k, a) in magnitude.iter().enumerate() {
+        let x = T::from_f64(math::ln(a.to_f64().abs().max(1e-5 * max)));
+        data[k] = (x, T::ZERO);
+        data[(N - k) % N] = (x, T::ZERO);
+    }
+    EmbIfft::new(&mut data).ifft();
+
+    // Folding, which keeps the log-magnitude and gives the minimum phase
+    let two = T::from_f64(2.0);
+    for (n, x) in data.iter_mut().enumerate() {
+        *x = match n {
+            0 => (x.0, T::ZERO),
+            _ if n < N / 2 => (x.0 * two, T::ZERO),
+            _ if n == N / 2 => (x.0, T::ZERO),
+            _ => (T::ZERO, T::ZERO)
+        };
+    }
+    EmbFft::new(&mut data).fft();
+
+    // Complex exponential of the log-spectrum
+    for x in data.iter_mut() {
+        let gain = math::exp(x.0.to_f64());
+        let (s, c) = math::sin_cos(x.1.to_f64());
+        *x = (T::from_f64(gain * c), T::from_f64(gain * s));
+    }
+    EmbIfft::new(&mut data).ifft();
+    Ok(data.map(|x| x.0))
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    /// Computes the magnitude of the frequency response
+    fn response<const M: usize>(taps: &[f64; M], f: f64) -> f64 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, x) in taps.iter().enumerate() {
+            let (s, c) = crate::cordic::sin_cos(2.0 * PI * f * i as f64);
+            re += x * c;
+            im += x * s;
+        }
+        let m2: f64 = re * re + im * im;
+        m2.sqrt()
+    }
+
+    #[test]
+    fn test_lowpass() {
+        const TAPS: [f64; 41] = lowpass(0.125, FirWindow::Hamming);
+        for i in 0..20 {
+            assert_abs_diff_eq!(TAPS[i], TAPS[40 - i], epsilon = 1e-15);
+        }
+        assert_abs_diff_eq!(response(&TAPS, 0.0), 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(response(&TAPS, 0.05), 1.0, epsilon = 1e-2);
+        assert!(response(&TAPS, 0.25) < 1e-2);
+    }
+
+    #[test]
+    fn test_highpass_bandpass() {
+        const HIGH: [f64; 41] = highpass(0.2, FirWindow::Blackman);
+        assert!(response(&HIGH, 0.0) < 1e-3);
+        assert_abs_diff_eq!(response(&HIGH, 0.5), 1.0, epsilon = 1e-3);
+
+        const BAND: [f64; 64] = bandpass(0.1, 0.2, FirWindow::Hann);
+        assert_abs_diff_eq!(response(&BAND, 0.15), 1.0, epsilon = 1e-12);
+        assert!(response(&BAND, 0.0) < 1e-2);
+        assert!(response(&BAND, 0.35) < 1e-2);
+    }
+
+    #[test]
+    fn test_frequency_sampling() {
+        use crate::window::{Hann, Rectangular};
+        assert_eq!(frequency_sampling::<f64, Rectangular, 8>(&[1.0; 4]).err(), Some(Error::InvalidLength));
+
+        // A flat response is a delay
+        let taps = frequency_sampling::<f64, Hann, 8>(&[1.0; 5]).unwrap();
+        for (i, x) in taps.iter().enumerate() {
+            assert_abs_diff_eq!(*x, if i == 4 { 1.0 } else { 0.0 }, epsilon = 1e-12);
+        }
+
+        // Without window, the response is exact at the bins
+        let gains = [1.0, 0.9, 0.5, 0.1, 0.0];
+        let taps = frequency_sampling::<f64, Rectangular, 8>(&gains).unwrap();
+        let mut data = [(0.0, 0.0); 8];
+        for (d, &t) in data.iter_mut().zip(&taps) {
+            *d = (t, 0.0);
+        }
+        crate::EmbFft::new(&mut data).fft();
+        for (k, &g) in gains.iter().enumerate() {
+            let m2: f64 = data[k].0 * data[k].0 + data[k].1 * data[k].1;
+            assert_abs_diff_eq!(m2.sqrt(), g, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_minimum_phase() {
+        assert_eq!(minimum_phase::<f64, 8>(&[1.0; 4]).err(), Some(Error::InvalidLength));
+        assert_eq!(minimum_phase::<f64, 8>(&[0.0; 5]).err(), Some(Error::InvalidParameter));
+
+        // The magnitude response is kept, and the energy is concentrated at the start
+        let gains: [f64; 33] = core::array::from_fn(|k| if k < 8 { 1.0 } else if k < 12 { 0.5 } else { 0.1 });
+        let taps = minimum_phase::<f64, 64>(&gains).unwrap();
+        let linear = frequency_sampling::<f64, crate::window::Rectangular, 64>(&gains).unwrap();
+        let mut data = [(0.0, 0.0); 64];
+        for (d, &t) in data.iter_mut().zip(&taps) {
+            *d = (t, 0.0);
+        }
+        EmbFft::new(&mut data).fft();
+        for (k, &g) in gains.iter().enumerate() {
+            let m2: f64 = data[k].0 * data[k].0 + data[k].1 * data[k].1;
+            assert_abs_diff_eq!(m2.sqrt(), g, epsilon = 0.05);
+        }
+        let energy = |taps: &[f64; 64]| taps[..8].iter().map(|x| x * x).sum::<f64>() / taps.iter().map(|x| x * x).sum::<f64>();
+        assert!(energy(&taps) > 0.9);
+        assert!(energy(&linear) < 0.1);
+    }
+
+    /// Computes the difference between the phase delay of a frequency response and a delay, in samples
+    fn delay_error(h: (f64, f64), f: f64, delay: f64) -> f64 {
+        let (s, c) = crate::cordic::sin_cos(2.0 * PI * f * delay);
+        let (re, im) = (h.0 * c - h.1 * s, h.0 * s + h.1 * c);
+        -im.atan2(re) / (2.0 * PI * f)
+    }
+
+    #[test]
+    fn test_fractional_delay() {
+        const TAPS: [f64; 32] = fractional_delay(15.3, FirWindow::Blackman);
+        assert_abs_diff_eq!(TAPS.iter().sum::<f64>(), 1.0, epsilon = 1e-12);
+        let kernel = kernel::<f64, 32, 64>(&TAPS).unwrap();
+        let mut data = [(0.0, 0.0); 64];
+        data[0] = (1.0, 0.0);
+        crate::convolve::FftConvolver::new(&kernel, &mut data).convolve();
+        EmbFft::new(&mut data).fft();
+        for (k, x) in data.iter().enumerate().take(10).skip(1) {
+            let m2: f64 = x.0 * x.0 + x.1 * x.1;
+            assert_abs_diff_eq!(m2.sqrt(), 1.0, epsilon = 1e-3);
+            assert_abs_diff_eq!(delay_error(*x, k as f64 / 64.0, 15.3), 0.0, epsilon = 1e-3);
+        }
+
+        // An integer delay is a pure shift
+        const SHIFT: [f64; 9] = fractional_delay(4.0, FirWindow::Hann);
+        for (i, &x) in SHIFT.iter().enumerate() {
+            assert_abs_diff_eq!(x, if i == 4 { 1.0 } else { 0.0 }, epsilon = 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_thiran() {
+        // First order: (1 - D) / (1 + D)
+        let a = thiran::<2>(0.5);
+        assert_eq!(a[0], 1.0);
+        assert_abs_diff_eq!(a[1], 1.0 / 3.0, epsilon = 1e-15);
+        assert_eq!(thiran::<1>(0.0), [1.0]);
+
+        const A: [f64; 4] = thiran(3.3);
+        let kernel = allpass_kernel::<f64, 4, 64>(&A);
+        let mut data = [(0.0, 0.0); 64];
+        data[0] = (1.0, 0.0);
+        crate::convolve::FftConvolver::new(&kernel, &mut data).convolve();
+        assert!(data.iter().all(|x| x.1.abs() < 1e-12));
+        EmbFft::new(&mut data).fft();
+        let (gain, delay) = if cfg!(all(feature = "micromath", not(feature = "libm"))) { (1e-2, 0.05) } else { (1e-9, 1e-3) };
+        for (k, x) in data.iter().enumerate() {
+            assert_abs_diff_eq!(x.0 * x.0 + x.1 * x.1, 1.0, epsilon = gain);
+            if k > 0 && k < 4 {
+                assert_abs_diff_eq!(delay_error(*x, k as f64 / 64.0, 3.3), 0.0, epsilon = delay);
+            }
+        }
+    }
+
+    #[test]
+    fn test_kernel() {
+        assert_eq!(kernel::<f32, 8, 4>(&[0.0; 8]).err(), Some(Error::InvalidLength));
+
+        // The impulse response of the convolution is the filter itself
+        let taps: [f64; 5] = lowpass(0.2, FirWindow::Rectangular);
+        let kernel = kernel::<f64, 5, 8>(&taps).unwrap();
+        let mut data = [(0.0, 0.0); 8];
+        data[0] = (1.0, 0.0);
+        crate::convolve::FftConvolver::new(&kernel, &mut data).convolve();
+        for (i, x) in data.iter().enumerate() {
+            assert_abs_diff_eq!(x.0, if i < 5 { taps[i] } else { 0.0 }, epsilon = 1e-12);
+        }
+    }
+}
@@ -0,0 +1,579 @@
+/* embfft | fixed_point.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Fixed-point number types
+//!
+//! The [`Q15`] and [`Q31`] types represent numbers in the [-1, 1) range, and can be used with all the
+//! transforms. To avoid overflows, the input data must be scaled down by N; otherwise, the [`Saturating`]
+//! overflow mode ensures the results degrade gracefully instead of wrapping around. The [`Dithered`]
+//! rounding mode decorrelates the rounding errors from the signal, which turns the spurs they cause into
+//! noise and improves the spurious-free dynamic range.
+//! With the `fixed` feature, the signed types of the `fixed` crate, such as `FixedI32<U16>`, can be used
+//! with the transforms as well.
+//! For example:
+//! ```
+//! use embfft::fixed_point::{Q15, Saturating};
+//!
+//! let mut data = [(Q15::<Saturating>::from_f32(0.1), Q15::from_f32(0.0)); 8];
+//! embfft::EmbFft::new(&mut data).fft();
+//! ```
+
+/******************************************************************************/
+
+use core::marker::PhantomData;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::common::Float;
+use crate::status::Status;
+
+/******************************************************************************/
+
+// Rounded quarter-wave sine tables, generated by the build script
+include!(concat!(env!("OUT_DIR"), "/fixed_twiddles.rs"));
+
+/******************************************************************************/
+
+/// Behavior of the fixed-point arithmetic when the result is out of range
+pub trait Overflow {
+    /// Whether reaching the limits of the range is reported as an overflow
+    const REPORTS: bool;
+
+    /// Narrows an intermediate 32-bit result down to 16 bits
+    fn narrow16(x: i32) -> i16;
+    /// Narrows an intermediate 64-bit result down to 32 bits
+    fn narrow32(x: i64) -> i32;
+}
+
+/// Out of range results wrap around, like the native integer types in release builds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Wrapping;
+
+/// Out of range results are clamped to the nearest representable value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Saturating;
+
+impl Overflow for Wrapping {
+    const REPORTS: bool = false;
+
+    fn narrow16(x: i32) -> i16 {
+        x as i16
+    }
+
+    fn narrow32(x: i64) -> i32 {
+        x as i32
+    }
+}
+
+impl Overflow for Saturating {
+    const REPORTS: bool = true;
+
+    fn narrow16(x: i32) -> i16 {
+        x.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+    }
+
+    fn narrow32(x: i64) -> i32 {
+        x.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+}
+
+/******************************************************************************/
+
+/// Rounding of the products and of the twiddle factors
+pub trait Rounding {
+    /// Whether the rounding is dithered
+    const DITHERED: bool;
+}
+
+/// Products and twiddle factors are rounded to the nearest value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Nearest;
+
+/// Products and twiddle factors are rounded up or down at random, with a probability depending on
+/// their distance to the two nearest values
+///
+/// The rounding is unbiased on average, and the pseudo-random sequence is derived from the values
+/// themselves, so that the results remain reproducible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dithered;
+
+impl Rounding for Nearest {
+    const DITHERED: bool = false;
+}
+
+impl Rounding for Dithered {
+    const DITHERED: bool = true;
+}
+
+/// Hashes a value into a uniformly distributed pseudo-random number (SplitMix64 finalizer)
+const fn dither(x: u64) -> u64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/******************************************************************************/
+
+macro_rules! gen_fixed_impl {
+    ($Q: ident, $I: ty, $W: ty, $narrow: ident, $sine: ident, $sine_dithered: ident, $doc: literal) => {
+        #[doc = $doc]
+        ///
+        /// The overflow behavior is selected by the `O` type parameter, and the rounding by the `R` one.
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+        pub struct $Q<O = Wrapping, R = Nearest> {
+            bits: $I,
+            overflow: PhantomData<(O, R)>
+        }
+
+        impl<O, R> $Q<O, R> {
+            const FRAC_BITS: u32 = <$I>::BITS - 1;
+
+            /// Largest representable value, just below 1
+            pub const MAX: Self = Self::from_bits(<$I>::MAX);
+            /// Smallest representable value, -1
+            pub const MIN: Self = Self::from_bits(<$I>::MIN);
+
+            /// Creates a number from its raw representation
+            pub const fn from_bits(bits: $I) -> Self {
+                Self { bits, overflow: PhantomData }
+            }
+
+            /// Returns the raw representation of the number
+            pub const fn to_bits(self) -> $I {
+                self.bits
+            }
+
+            /// Converts a floating-point number, rounding to the nearest value and saturating
+            pub const fn from_f64(x: f64) -> Self {
+                let x = x * (1u64 << Self::FRAC_BITS) as f64;
+                let x = if x >= 0.0 { x + 0.5 } else { x - 0.5 };
+                Self::from_bits(x as $I)
+            }
+
+            /// Converts a floating-point number, rounding to the nearest value and saturating
+            pub fn from_f32(x: f32) -> Self {
+                Self::from_f64(x as f64)
+            }
+
+            /// Converts to a floating-point number
+            pub fn to_f64(self) -> f64 {
+                self.bits as f64 / (1u64 << Self::FRAC_BITS) as f64
+            }
+
+            /// Converts to a floating-point number
+            pub fn to_f32(self) -> f32 {
+                self.to_f64() as f32
+            }
+
+            /// Computes the base 2 logarithm, in Q16.16 format
+            ///
+            /// The integer part is given by the position of the leading one, and the fractional part by a
+            /// polynomial, accurate to about 2e-4. Returns `i32::MIN` for zero and negative numbers.
+            pub fn log2(self) -> i32 {
+                if self.bits <= 0 {
+                    return i32::MIN;
+                }
+
+                // bits = 2^msb * (1 + f)
+                let msb = <$I>::BITS - 1 - self.bits.leading_zeros();
+                let f = ((((self.bits as u64) << 16) >> msb) & 0xFFFF) as i64;
+
+                // log2(1 + f) ~= f + f * (1 - f) * (a + b * f + c * f^2)
+                let p = ((5263 * f) >> 16) - 15512;
+                let p = ((p * f) >> 16) + 28710;
+                let frac = f + ((((f * (65536 - f)) >> 16) * p) >> 16);
+
+                ((msb as i32 - Self::FRAC_BITS as i32) << 16) + frac as i32
+            }
+
+            /// Computes the amplitude level in decibels, 20 * log10(x), in Q16.16 format
+            ///
+            /// Returns `i32::MIN` for zero and negative numbers.
+            pub fn to_db(self) -> i32 {
+                // 20 * log10(2) in Q16.16
+                Self::scale_log2(self.log2(), 394566)
+            }
+
+            /// Computes the power level in decibels, 10 * log10(x), in Q16.16 format
+            ///
+            /// Returns `i32::MIN` for zero and negative numbers.
+            pub fn power_to_db(self) -> i32 {
+                // 10 * log10(2) in Q16.16
+                Self::scale_log2(self.log2(), 197283)
+            }
+
+            fn scale_log2(x: i32, k: i64) -> i32 {
+                if x == i32::MIN { x } else { ((x as i64 * k) >> 16) as i32 }
+            }
+        }
+
+        impl<O: Overflow, R: Rounding> Add for $Q<O, R> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self::from_bits(O::$narrow(self.bits as $W + rhs.bits as $W))
+            }
+        }
+
+        impl<O: Overflow, R: Rounding> Sub for $Q<O, R> {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self::from_bits(O::$narrow(self.bits as $W - rhs.bits as $W))
+            }
+        }
+
+        impl<O: Overflow, R: Rounding> Mul for $Q<O, R> {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                let x = self.bits as $W * rhs.bits as $W;
+                let x = if R::DITHERED {
+                    // Add a random fraction, uniform in [0, 1)
+                    x + (dither(x as u64) >> (64 - Self::FRAC_BITS)) as $W
+                } else {
+                    // Round to the nearest value
+                    x + (1 << (Self::FRAC_BITS - 1))
+                };
+                Self::from_bits(O::$narrow(x >> Self::FRAC_BITS))
+            }
+        }
+
+        impl<O: Overflow, R: Rounding> Div for $Q<O, R> {
+            type Output = Self;
+
+            /// Divides two numbers
+            ///
+            /// Panics if the divisor is zero, like the native integer types.
+            fn div(self, rhs: Self) -> Self {
+                Self::from_bits(O::$narrow(((self.bits as $W) << Self::FRAC_BITS) / rhs.bits as $W))
+            }
+        }
+
+        impl<O: Overflow, R: Rounding> Neg for $Q<O, R> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self::from_bits(O::$narrow(-(self.bits as $W)))
+            }
+        }
+
+        impl<O: Overflow + Copy + PartialOrd, R: Rounding + Copy + PartialOrd, const N: usize> Float<N> for $Q<O, R> {
+            const ZERO: Self = Self::from_bits(0);
+            /// 1 cannot be represented, the largest value is used instead
+            const ONE: Self = Self::MAX;
+            const N_INV: Self = Self::from_bits(((1 << Self::FRAC_BITS) / N as $W) as $I);
+            /// Taken from the generated tables when possible, computed with the CORDIC otherwise
+            ///
+            /// With the dithered rounding, each factor is rounded at random.
+            const SINE_TABLE: [Self; N] = {
+                let mut table = [Self::from_bits(0); N];
+                let mut i = 1;
+                while i < N / 4 {
+                    table[i] = if N <= FIXED_TABLE_N && FIXED_TABLE_N % N == 0 {
+                        let j = i * (FIXED_TABLE_N / N);
+                        Self::from_bits(if R::DITHERED { $sine_dithered[j] } else { $sine[j] })
+                    } else if R::DITHERED {
+                        let x = crate::cordic::sin(2.0 * core::f64::consts::PI * i as f64 / N as f64);
+                        let x = x * (1u64 << Self::FRAC_BITS) as f64;
+                        let u = (dither((N << 32 | i) as u64) >> 11) as f64 / (1u64 << 53) as f64;
+                        let bits = if x - (x as $I as f64) > u { x as $W + 1 } else { x as $W };
+                        Self::from_bits(if bits > <$I>::MAX as $W { <$I>::MAX } else { bits as $I })
+                    } else {
+                        Self::from_f64(crate::cordic::sin(2.0 * core::f64::consts::PI * i as f64 / N as f64))
+                    };
+                    i += 1;
+                }
+                table
+            };
+
+            /// Integers cannot be represented, any non-zero value saturates to the largest value
+            fn from_usize(x: usize) -> Self {
+                if x == 0 { Self::from_bits(0) } else { Self::MAX }
+            }
+
+            fn from_f64(x: f64) -> Self {
+                Self::from_f64(x)
+            }
+
+            fn to_f64(self) -> f64 {
+                self.to_f64()
+            }
+
+            /// The resolution is constant
+            fn ulp(self) -> f64 {
+                1.0 / (1u64 << Self::FRAC_BITS) as f64
+            }
+
+            /// Wrapped values cannot be detected, only the saturating mode reports overflows
+            fn status(self) -> Status {
+                if O::REPORTS && (self.bits == <$I>::MAX || self.bits == <$I>::MIN) {
+                    Status::OVERFLOW
+                } else {
+                    Status::OK
+                }
+            }
+        }
+    };
+}
+
+gen_fixed_impl!(Q15, i16, i32, narrow16, SINE_Q15, SINE_DITHERED_Q15, "Signed fixed-point number with 15 fractional bits");
+gen_fixed_impl!(Q31, i32, i64, narrow32, SINE_Q31, SINE_DITHERED_Q31, "Signed fixed-point number with 31 fractional bits");
+
+/******************************************************************************/
+
+/// Converts a value to the bits of a number with `frac` fractional bits in a signed integer of `bits` bits,
+/// rounding to the nearest and saturating
+#[cfg(feature = "fixed")]
+pub(crate) const fn fixed_bits(x: f64, frac: u32, bits: u32) -> i128 {
+    let max = (1i128 << (bits - 1)) - 1;
+    let min = -(1i128 << (bits - 1));
+    let x = x * (1u128 << frac) as f64;
+    let x = if x < 0.0 { x - 0.5 } else { x + 0.5 };
+    if x >= max as f64 {
+        max
+    } else if x <= min as f64 {
+        min
+    } else {
+        x as i128
+    }
+}
+
+#[cfg(feature = "fixed")]
+macro_rules! gen_fixed_crate_impl {
+    ($F: ident, $I: ty, $LeEqU: ident) => {
+        /// Wrapped values cannot be detected: the operations of the `fixed` crate panic on overflow in debug
+        /// builds, and wrap otherwise
+        impl<Frac: fixed::types::extra::$LeEqU, const N: usize> Float<N> for fixed::$F<Frac> {
+            const ZERO: Self = Self::from_bits(0);
+            /// Without integer bits, 1 cannot be represented, the largest value is used instead
+            const ONE: Self = if Self::FRAC_NBITS < <$I>::BITS - 1 { Self::from_bits(1 << Self::FRAC_NBITS) } else { Self::MAX };
+            const N_INV: Self = Self::from_bits(fixed_bits(1.0 / N as f64, Self::FRAC_NBITS, <$I>::BITS) as $I);
+            const SINE_TABLE: [Self; N] = {
+                let mut table = [Self::from_bits(0); N];
+                let mut i = 1;
+                while i < N / 4 {
+                    let x = crate::cordic::sin(2.0 * core::f64::consts::PI * i as f64 / N as f64);
+                    table[i] = Self::from_bits(fixed_bits(x, Self::FRAC_NBITS, <$I>::BITS) as $I);
+                    i += 1;
+                }
+                table
+            };
+
+            /// The values out of range saturate
+            fn from_usize(x: usize) -> Self {
+                Self::saturating_from_num(x)
+            }
+
+            /// The values out of range saturate, NaN is converted to 0
+            fn from_f64(x: f64) -> Self {
+                if x.is_nan() { Self::from_bits(0) } else { Self::saturating_from_num(x) }
+            }
+
+            fn to_f64(self) -> f64 {
+                self.to_num()
+            }
+
+            /// The resolution is constant
+            fn ulp(self) -> f64 {
+                Self::DELTA.to_num()
+            }
+
+            fn status(self) -> Status {
+                Status::OK
+            }
+        }
+    };
+}
+
+#[cfg(feature = "fixed")]
+gen_fixed_crate_impl!(FixedI16, i16, LeEqU16);
+#[cfg(feature = "fixed")]
+gen_fixed_crate_impl!(FixedI32, i32, LeEqU32);
+#[cfg(feature = "fixed")]
+gen_fixed_crate_impl!(FixedI64, i64, LeEqU64);
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::count_inv;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(Q15::<Wrapping>::MAX + Q15::from_bits(1), Q15::MIN);
+        assert_eq!(Q15::<Saturating>::MAX + Q15::from_bits(1), Q15::MAX);
+        assert_eq!(Q15::<Saturating>::MIN - Q15::from_bits(1), Q15::MIN);
+        assert_eq!(-Q15::<Saturating>::MIN, Q15::MAX);
+        assert_eq!(Q31::<Saturating>::MIN * Q31::MIN, Q31::MAX);
+        assert_eq!(Q31::<Wrapping>::MIN * Q31::MIN, Q31::MIN);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Q15::<Wrapping>::from_f32(0.5);
+        let b = Q15::from_f32(-0.25);
+        assert_eq!((a * b).to_f32(), -0.125);
+        assert_eq!((b / a).to_f32(), -0.5);
+        assert_eq!((a + b).to_f32(), 0.25);
+        assert_eq!(Q31::<Wrapping>::from_f64(-1.5), Q31::MIN);
+        assert_eq!(Q31::<Saturating>::from_f64(0.75).to_f64(), 0.75);
+
+        // The counts saturate, but their reciprocals are in range
+        assert_eq!(count_inv::<Q15<Saturating>, 8>(4).to_f32(), 0.25);
+        assert_eq!(count_inv::<Q31<Saturating>, 8>(1), Q31::MAX);
+    }
+
+    #[test]
+    fn test_dithered_rounding() {
+        // Halving odd numbers always ties: rounding to the nearest is biased, the dithered rounding is not
+        let half = Q15::<Wrapping, Dithered>::from_f64(0.5);
+        let (mut nearest, mut dithered) = (0, 0);
+        for bits in (-32767..32767).step_by(2) {
+            let x = Q15::<Wrapping, Dithered>::from_bits(bits);
+            let error = 2 * (x * half).to_bits() as i32 - bits as i32;
+            assert!(error.abs() <= 1);
+            dithered += error;
+            nearest += 2 * (Q15::<Wrapping>::from_bits(bits) * Q15::from_f64(0.5)).to_bits() as i32 - bits as i32;
+        }
+        assert_eq!(nearest, 32767);
+        assert!(dithered.abs() < 1000);
+    }
+
+    #[test]
+    fn test_dithered_sine_table() {
+        // Each factor is one of the two nearest values, and the errors average out
+        let table = <Q15<Wrapping, Dithered> as Float<4096>>::SINE_TABLE;
+        let mut sum = 0.0;
+        for (i, x) in table.into_iter().enumerate().take(1024) {
+            let exact = crate::cordic::sin(2.0 * core::f64::consts::PI * i as f64 / 4096.0) * 32768.0;
+            let error = x.to_bits() as f64 - exact;
+            assert!(error.abs() < 1.0);
+            sum += error;
+        }
+        assert!(sum.abs() < 0.05 * 1024.0);
+        assert_ne!(table, <Q15 as Float<4096>>::SINE_TABLE.map(|x| Q15::from_bits(x.to_bits())));
+
+        // The smaller tables are taken from the generated one, the others are computed
+        let small = <Q15<Wrapping, Dithered> as Float<1024>>::SINE_TABLE;
+        assert!((0..256).all(|i| small[i] == table[4 * i]));
+        let other = <Q15<Wrapping, Dithered> as Float<96>>::SINE_TABLE;
+        assert!((0..24).all(|i| (other[i].to_bits() as f64 - 32768.0 * crate::cordic::sin(2.0 * core::f64::consts::PI * i as f64 / 96.0)).abs() < 1.0));
+    }
+
+    #[test]
+    fn test_log2() {
+        assert_eq!(Q15::<Wrapping>::from_f64(0.5).log2(), -65536);
+        assert_eq!(Q15::<Wrapping>::from_bits(1).log2(), -15 * 65536);
+        assert_eq!(Q31::<Wrapping>::from_bits(0).log2(), i32::MIN);
+        assert_eq!(Q31::<Wrapping>::MIN.to_db(), i32::MIN);
+        assert_abs_diff_eq!(Q31::<Wrapping>::from_f64(0.1).log2(), -217705, epsilon = 20);
+        assert_abs_diff_eq!(Q15::<Wrapping>::from_f64(0.1).to_db(), -20 * 65536, epsilon = 100);
+        assert_abs_diff_eq!(Q31::<Wrapping>::from_f64(0.001).power_to_db(), -30 * 65536, epsilon = 100);
+    }
+
+    #[test]
+    fn test_sine_table() {
+        // The generated table and the CORDIC fallback must agree
+        let table = <Q31 as Float<64>>::SINE_TABLE;
+        for (i, x) in table.into_iter().enumerate().take(16) {
+            let y = Q31::<Wrapping>::from_f64(crate::cordic::sin(2.0 * core::f64::consts::PI * i as f64 / 64.0));
+            assert_abs_diff_eq!(x.to_bits(), y.to_bits(), epsilon = 1);
+        }
+        assert_eq!(<Q15 as Float<8>>::SINE_TABLE[1], Q15::from_bits(23170));
+    }
+
+    #[test]
+    fn test_fft_q15() {
+        let mut data: [(Q15<Saturating>, Q15<Saturating>); 8] = core::array::from_fn(|i| {
+            (Q15::from_f32(if i < 4 { 0.1 } else { -0.1 }), Q15::from_bits(0))
+        });
+        crate::EmbFft::new(&mut data).fft();
+
+        let expected_data = [
+            (0.0, 0.0), (0.2, -0.482842712), (0.0, 0.0), (0.2, -0.082842712),
+            (0.0, 0.0), (0.2, 0.082842712), (0.0, 0.0), (0.2, 0.482842712)
+        ];
+        for (x, y) in core::iter::zip(data, expected_data) {
+            assert_abs_diff_eq!(x.0.to_f64(), y.0, epsilon = 1e-3);
+            assert_abs_diff_eq!(x.1.to_f64(), y.1, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_fft_q15_dithered() {
+        let mut data: [(Q15<Saturating, Dithered>, Q15<Saturating, Dithered>); 8] = core::array::from_fn(|i| {
+            (Q15::from_f32(if i < 4 { 0.1 } else { -0.1 }), Q15::from_bits(0))
+        });
+        crate::EmbFft::new(&mut data).fft();
+
+        let expected_data = [
+            (0.0, 0.0), (0.2, -0.482842712), (0.0, 0.0), (0.2, -0.082842712),
+            (0.0, 0.0), (0.2, 0.082842712), (0.0, 0.0), (0.2, 0.482842712)
+        ];
+        for (x, y) in core::iter::zip(data, expected_data) {
+            assert_abs_diff_eq!(x.0.to_f64(), y.0, epsilon = 1e-3);
+            assert_abs_diff_eq!(x.1.to_f64(), y.1, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_q31() {
+        let mut data: [(Q31, Q31); 16] = core::array::from_fn(|i| (Q31::from_f64(i as f64 / 256.0), Q31::from_f64(-0.01)));
+        let expected_data = data;
+        crate::EmbFft::new(&mut data).fft();
+        crate::EmbIfft::new(&mut data).ifft();
+        for (x, y) in core::iter::zip(data, expected_data) {
+            assert_abs_diff_eq!(x.0.to_f64(), y.0.to_f64(), epsilon = 1e-8);
+            assert_abs_diff_eq!(x.1.to_f64(), y.1.to_f64(), epsilon = 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_fft_q15_saturation() {
+        // The DC bin cannot be represented, it saturates instead of wrapping around
+        let mut data = [(Q15::<Saturating>::from_f32(0.5), Q15::from_bits(0)); 8];
+        let mut fft = crate::EmbFft::new(&mut data);
+        fft.fft();
+        assert!(fft.status().contains(crate::Status::OVERFLOW));
+        assert_eq!(data[0].0, Q15::MAX);
+
+        let mut data = [(Q15::<Wrapping>::from_f32(0.5), Q15::from_bits(0)); 8];
+        crate::EmbFft::new(&mut data).fft();
+        assert_eq!(data[0].0, Q15::from_bits(0));
+    }
+
+    #[test]
+    #[cfg(feature = "fixed")]
+    fn test_fixed_crate() {
+        use fixed::types::extra::{U14, U16, U32};
+        use fixed::{FixedI16, FixedI32};
+        type F = FixedI32<U16>;
+        assert_eq!(<F as Float<8>>::ONE, F::ONE);
+        assert_eq!(<F as Float<8>>::N_INV, F::from_num(0.125));
+        assert_eq!(<FixedI32<U32> as Float<8>>::ONE, FixedI32::<U32>::MAX);
+        assert_eq!(<F as Float<8>>::from_f64(f64::NAN), F::ZERO);
+        assert_eq!(<F as Float<8>>::from_usize(1 << 20), F::MAX);
+        assert_eq!(count_inv::<F, 8>(1 << 20), F::from_num(1.0 / (1 << 20) as f64));
+
+        // With integer bits, the input does not need to be scaled down
+        let mut data: [(F, F); 8] = core::array::from_fn(|i| (F::from_num(if i < 4 { 1 } else { -1 }), F::ZERO));
+        crate::EmbFft::new(&mut data).fft();
+        let expected_data = [
+            (0.0, 0.0), (2.0, -4.828427125), (0.0, 0.0), (2.0, -0.828427125),
+            (0.0, 0.0), (2.0, 0.828427125), (0.0, 0.0), (2.0, 4.828427125)
+        ];
+        for (x, y) in core::iter::zip(data, expected_data) {
+            assert_abs_diff_eq!(x.0.to_num::<f64>(), y.0, epsilon = 1e-4);
+            assert_abs_diff_eq!(x.1.to_num::<f64>(), y.1, epsilon = 1e-4);
+        }
+
+        // Windowed transform, the Hann window halves the DC bin
+        let mut data = [(FixedI16::<U14>::from_num(0.125), FixedI16::ZERO); 8];
+        crate::EmbFft::<_, 8, crate::window::Hann>::windowed(&mut data).fft();
+        assert_abs_diff_eq!(data[0].0.to_num::<f64>(), 0.5, epsilon = 1e-3);
+    }
+}
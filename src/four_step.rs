@@ -0,0 +1,464 @@
+/* embfft | four_step.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Four-step FFT of large buffers
+//!
+//! An N-point transform, with N = N1 × N2, is decomposed into N2 transforms of N1 points and N1 transforms
+//! of N2 points, which operate on small tiles copied to the stack. Each sample of the large buffer is
+//! only read and written twice, which suits buffers in slow external RAM. The [`PagedFft`] even allows
+//! the buffer to live outside of the address space, behind the [`BlockAccess`] trait.
+//! For example:
+//! ```
+//! use embfft::four_step::FourStepFft;
+//!
+//! let mut data = [(1.0f32, 0.0); 64];
+//! let mut fft = FourStepFft::<f32, 8, 8>::new(&mut data).unwrap();
+//! fft.fft();
+//! assert_eq!(fft.get_bin(0).unwrap().0, 64.0);
+//! ```
+
+/******************************************************************************/
+
+use crate::common::{Base, Float};
+use crate::math;
+use crate::error::Error;
+use crate::metadata::{Direction, Scaling};
+use crate::status::Status;
+use crate::EmbFft;
+
+/******************************************************************************/
+
+/// Number of columns transformed together, so that the storage is accessed by blocks of several samples
+const COLUMNS: usize = 4;
+
+/// Storage of samples that can be accessed by blocks
+///
+/// Implement this for buffers outside of the address space, such as an SPI PSRAM or a file, to run a
+/// [`PagedFft`] on them. The offsets and lengths are expressed in samples.
+pub trait BlockAccess<T> {
+    /// Error reported by the storage
+    type Error;
+
+    /// Returns the number of samples in the storage
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the storage is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads `buffer.len()` samples, starting at `offset`
+    fn read(&mut self, offset: usize, buffer: &mut [(T, T)]) -> Result<(), Self::Error>;
+
+    /// Writes `buffer.len()` samples, starting at `offset`
+    fn write(&mut self, offset: usize, buffer: &[(T, T)]) -> Result<(), Self::Error>;
+}
+
+impl<T: Copy> BlockAccess<T> for [(T, T)] {
+    type Error = Error;
+
+    fn len(&self) -> usize {
+        <[(T, T)]>::len(self)
+    }
+
+    fn read(&mut self, offset: usize, buffer: &mut [(T, T)]) -> Result<(), Error> {
+        let source = self.get(offset..offset + buffer.len()).ok_or(Error::OutOfRange)?;
+        buffer.copy_from_slice(source);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, buffer: &[(T, T)]) -> Result<(), Error> {
+        let target = self.get_mut(offset..offset + buffer.len()).ok_or(Error::OutOfRange)?;
+        target.copy_from_slice(buffer);
+        Ok(())
+    }
+}
+
+/// Errors returned by the [`PagedFft`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PagedError<E> {
+    /// The storage reported an error
+    Access(E),
+    /// The conversion reported an error, see [`PagedFft`]
+    Fft(Error)
+}
+
+impl<E> From<Error> for PagedError<E> {
+    fn from(error: Error) -> Self {
+        PagedError::Fft(error)
+    }
+}
+
+/// Four-step fast Fourier transform of N1 × N2 samples behind a [`BlockAccess`] storage
+///
+/// The data is seen as a matrix of N1 rows and N2 columns, stored row by row. The first step reads the
+/// columns four at a time, with a block of four samples per row, transforms them and multiplies them by
+/// the twiddle factors, the second step reads the rows as single blocks and transforms them. Only the
+/// tiles of 4 × N1 and N2 samples are on the stack, so N can be much larger than the internal RAM.
+/// The final transposition is skipped, so the output is in transposed order: use
+/// [`PagedFft::read_bin()`] to access the bins by frequency. N1 and N2 must be powers of two.
+pub struct PagedFft<'a, T, const N1: usize, const N2: usize, B: ?Sized> {
+    storage: &'a mut B,
+    step: usize,
+    status: Status,
+    _marker: core::marker::PhantomData<T>
+}
+
+impl<'a, T: Float<N1> + Float<N2>, const N1: usize, const N2: usize, B: BlockAccess<T> + ?Sized> PagedFft<'a, T, N1, N2, B> {
+    /// Number of samples of the transform
+    pub const N: usize = N1 * N2;
+
+    /// Initializes a new FFT conversion
+    ///
+    /// Fails with [`Error::InvalidLength`] if the storage length is not N1 × N2.
+    pub fn new(storage: &'a mut B) -> Result<Self, Error> {
+        if storage.len() != Self::N {
+            return Err(Error::InvalidLength);
+        }
+        Ok(Self { storage, step: 0, status: Status::default(), _marker: core::marker::PhantomData })
+    }
+
+    /// Non-blocking FFT computation
+    ///
+    /// Each call transforms four columns or one row. Use this together with the [`PagedFft::is_done()`]
+    /// function. If the storage reports an error, the data may be partially transformed, and the conversion
+    /// should be discarded.
+    pub fn fft_iterate(&mut self) -> Result<(), B::Error> {
+        if self.step < N2 / COLUMNS {
+            self.columns(COLUMNS * self.step)?;
+        } else if self.step < N2 / COLUMNS + N1 {
+            self.row(self.step - N2 / COLUMNS)?;
+        } else {
+            return Ok(());
+        }
+        self.step += 1;
+        Ok(())
+    }
+
+    /// Blocking FFT computation
+    pub fn fft(&mut self) -> Result<(), B::Error> {
+        while !self.is_done() {
+            self.fft_iterate()?;
+        }
+        Ok(())
+    }
+
+    /// Checks if the conversion is complete
+    pub fn is_done(&self) -> bool {
+        self.step >= N2 / COLUMNS + N1
+    }
+
+    /// Reads the bin at index `k`, sorted by frequency
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete, or with [`Error::OutOfRange`]
+    /// if `k` is not lower than N.
+    pub fn read_bin(&mut self, k: usize) -> Result<(T, T), PagedError<B::Error>> {
+        if !self.is_done() {
+            return Err(Error::NotDone.into());
+        } else if k >= Self::N {
+            return Err(Error::OutOfRange.into());
+        }
+        let mut bin = [(<T as Float<N1>>::ZERO, <T as Float<N1>>::ZERO)];
+        self.storage.read(Self::position(k), &mut bin).map_err(PagedError::Access)?;
+        Ok(bin[0])
+    }
+
+    /// Returns the position of bin `k` in the storage
+    ///
+    /// Bin k1 + N1 × k2 is stored at position N2 × k1 + k2.
+    pub const fn position(k: usize) -> usize {
+        N2 * (k % N1) + k / N1
+    }
+
+    /// Returns the number of samples of the transform
+    pub const fn len() -> usize {
+        N1 * N2
+    }
+
+    /// Returns the base 2 logarithm of the number of samples
+    pub const fn log2_len() -> usize {
+        Base::<N1>::LOG2_N + Base::<N2>::LOG2_N
+    }
+
+    /// Returns the direction of the transform
+    pub const fn direction() -> Direction {
+        Direction::Forward
+    }
+
+    /// Returns the scaling of the output
+    pub fn scaling(&self) -> Scaling {
+        Scaling::Unscaled
+    }
+
+    /// Checks if the output is sorted by frequency
+    ///
+    /// This is always `false`, as the final transposition is skipped.
+    pub fn is_natural_order(&self) -> bool {
+        false
+    }
+
+    /// Returns the status flags accumulated so far
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Returns a reference to the storage
+    pub fn storage(&self) -> &B {
+        self.storage
+    }
+
+    /// Transforms the columns n2 to n2 + COLUMNS - 1, and multiplies them by the twiddle factors
+    fn columns(&mut self, n2: usize) -> Result<(), B::Error> {
+        let zero = <T as Float<N1>>::ZERO;
+        let mut tiles = [[(zero, zero); N1]; COLUMNS];
+        let mut block = [(zero, zero); COLUMNS];
+        for n1 in 0..N1 {
+            self.storage.read(N2 * n1 + n2, &mut block)?;
+            for (tile, &x) in tiles.iter_mut().zip(&block) {
+                tile[n1] = x;
+            }
+        }
+
+        let mut status = Status::default();
+        for (i, tile) in tiles.iter_mut().enumerate() {
+            let mut fft = EmbFft::<T, N1>::new(tile);
+            fft.fft();
+            status |= fft.status();
+
+            // The twiddle factor of bin k1 is e^(-j2π n2 k1 / N)
+            let step = -2.0 * core::f64::consts::PI * (n2 + i) as f64 / Self::N as f64;
+            for (k1, x) in tile.iter_mut().enumerate() {
+                let (s, c) = math::sin_cos(step * k1 as f64);
+                let (s, c) = (<T as Float<N1>>::from_f64(s), <T as Float<N1>>::from_f64(c));
+                *x = (x.0 * c - x.1 * s, x.0 * s + x.1 * c);
+                status |= <T as Float<N1>>::status(x.0) | <T as Float<N1>>::status(x.1);
+            }
+        }
+
+        for k1 in 0..N1 {
+            for (x, tile) in block.iter_mut().zip(&tiles) {
+                *x = tile[k1];
+            }
+            self.storage.write(N2 * k1 + n2, &block)?;
+        }
+        self.status |= status;
+        Ok(())
+    }
+
+    /// Transforms row k1, which is contiguous
+    fn row(&mut self, k1: usize) -> Result<(), B::Error> {
+        let mut tile = [(<T as Float<N2>>::ZERO, <T as Float<N2>>::ZERO); N2];
+        self.storage.read(N2 * k1, &mut tile)?;
+        let mut fft = EmbFft::<T, N2>::new(&mut tile);
+        fft.fft();
+        let status = fft.status();
+        self.storage.write(N2 * k1, &tile)?;
+        self.status |= status;
+        Ok(())
+    }
+}
+
+/// Four-step fast Fourier transform of N1 × N2 samples in memory
+///
+/// This is a [`PagedFft`] over a slice, which can be located in a slow external RAM mapped in the address
+/// space. The output is in transposed order: use [`FourStepFft::get_bin()`] to access the bins by
+/// frequency.
+pub struct FourStepFft<'a, T, const N1: usize, const N2: usize> {
+    paged: PagedFft<'a, T, N1, N2, [(T, T)]>
+}
+
+impl<'a, T: Float<N1> + Float<N2>, const N1: usize, const N2: usize> FourStepFft<'a, T, N1, N2> {
+    /// Number of samples of the transform
+    pub const N: usize = N1 * N2;
+
+    /// Initializes a new FFT conversion
+    ///
+    /// Fails with [`Error::InvalidLength`] if the slice length is not N1 × N2.
+    pub fn new(data: &'a mut [(T, T)]) -> Result<Self, Error> {
+        PagedFft::new(data).map(|paged| Self { paged })
+    }
+
+    /// Non-blocking FFT computation
+    ///
+    /// Each call transforms four columns or one row. Use this together with the
+    /// [`FourStepFft::is_done()`] function.
+    pub fn fft_iterate(&mut self) {
+        // The length was checked, so the accesses cannot fail
+        let _ = self.paged.fft_iterate();
+    }
+
+    /// Blocking FFT computation
+    pub fn fft(&mut self) {
+        while !self.is_done() {
+            self.fft_iterate();
+        }
+    }
+
+    /// Checks if the conversion is complete
+    pub fn is_done(&self) -> bool {
+        self.paged.is_done()
+    }
+
+    /// Returns the bin at index `k`, sorted by frequency
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete, or with [`Error::OutOfRange`]
+    /// if `k` is not lower than N.
+    pub fn get_bin(&self, k: usize) -> Result<&(T, T), Error> {
+        if !self.is_done() {
+            Err(Error::NotDone)
+        } else if k >= Self::N {
+            Err(Error::OutOfRange)
+        } else {
+            Ok(&self.paged.storage()[Self::position(k)])
+        }
+    }
+
+    /// Returns the position of bin `k` in the buffer
+    ///
+    /// Bin k1 + N1 × k2 is stored at position N2 × k1 + k2.
+    pub const fn position(k: usize) -> usize {
+        PagedFft::<T, N1, N2, [(T, T)]>::position(k)
+    }
+
+    /// Returns the number of samples of the transform
+    pub const fn len() -> usize {
+        N1 * N2
+    }
+
+    /// Returns the base 2 logarithm of the number of samples
+    pub const fn log2_len() -> usize {
+        Base::<N1>::LOG2_N + Base::<N2>::LOG2_N
+    }
+
+    /// Returns the direction of the transform
+    pub const fn direction() -> Direction {
+        Direction::Forward
+    }
+
+    /// Returns the scaling of the output
+    pub fn scaling(&self) -> Scaling {
+        Scaling::Unscaled
+    }
+
+    /// Checks if the output is sorted by frequency
+    ///
+    /// This is always `false`, as the final transposition is skipped.
+    pub fn is_natural_order(&self) -> bool {
+        false
+    }
+
+    /// Returns the status flags accumulated so far
+    pub fn status(&self) -> Status {
+        self.paged.status()
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    ///
+    /// If the conversion is not complete, the buffer contains partially transformed data.
+    pub fn into_data(self) -> &'a mut [(T, T)] {
+        self.paged.storage
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+
+    fn check<const N1: usize, const N2: usize, const N: usize>() {
+        let mut data = [(0.0f64, 0.0); N];
+        for (n, x) in data.iter_mut().enumerate() {
+            *x = (((n * 7) % 13) as f64 - 6.0, ((n * 5) % 11) as f64 - 5.0);
+        }
+        let mut reference = data;
+        EmbFft::new(&mut reference).fft();
+
+        let mut fft = FourStepFft::<f64, N1, N2>::new(&mut data).unwrap();
+        assert_eq!(fft.get_bin(0), Err(Error::NotDone));
+        fft.fft();
+        assert!(!fft.status().is_corrupted());
+        assert_eq!(fft.get_bin(N), Err(Error::OutOfRange));
+        for (k, y) in reference.iter().enumerate() {
+            let x = fft.get_bin(k).unwrap();
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-9);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_four_step() {
+        check::<8, 8, 64>();
+        check::<4, 32, 128>();
+        check::<32, 4, 128>();
+        check::<16, 64, 1024>();
+
+        let mut data = [(0.0f32, 0.0); 60];
+        assert!(FourStepFft::<f32, 8, 8>::new(&mut data).is_err());
+        assert_eq!(FourStepFft::<f32, 16, 64>::len(), 1024);
+        assert_eq!(FourStepFft::<f32, 16, 64>::log2_len(), 10);
+    }
+
+    /// Storage that counts the accesses, and fails after a given number of them
+    struct Psram {
+        data: [(f32, f32); 256],
+        accesses: usize,
+        limit: usize
+    }
+
+    impl Psram {
+        fn access(&mut self) -> Result<(), ()> {
+            self.accesses += 1;
+            if self.accesses > self.limit { Err(()) } else { Ok(()) }
+        }
+    }
+
+    impl BlockAccess<f32> for Psram {
+        type Error = ();
+
+        fn len(&self) -> usize {
+            self.data.len()
+        }
+
+        fn read(&mut self, offset: usize, buffer: &mut [(f32, f32)]) -> Result<(), ()> {
+            self.access()?;
+            buffer.copy_from_slice(&self.data[offset..offset + buffer.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: usize, buffer: &[(f32, f32)]) -> Result<(), ()> {
+            self.access()?;
+            self.data[offset..offset + buffer.len()].copy_from_slice(buffer);
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_paged() {
+        let mut psram = Psram { data: [(0.0, 0.0); 256], accesses: 0, limit: usize::MAX };
+        psram.data[3] = (1.0, 0.0);
+        let mut fft = PagedFft::<f32, 16, 16, Psram>::new(&mut psram).unwrap();
+        assert_eq!(fft.read_bin(0), Err(PagedError::Fft(Error::NotDone)));
+        fft.fft().unwrap();
+        assert_eq!(fft.read_bin(256), Err(PagedError::Fft(Error::OutOfRange)));
+        for k in [0, 1, 64, 200] {
+            let (s, c) = cordic::sin_cos(-2.0 * core::f64::consts::PI * 3.0 * k as f64 / 256.0);
+            let bin = fft.read_bin(k).unwrap();
+            assert_abs_diff_eq!(bin.0, c as f32, epsilon = 1e-5);
+            assert_abs_diff_eq!(bin.1, s as f32, epsilon = 1e-5);
+        }
+        // Each row of a tile of 4 columns is read and written as a block, each row as a single block
+        assert_eq!(psram.accesses, 4 * 2 * 16 + 16 * 2 + 4);
+
+        let mut psram = Psram { data: [(0.0, 0.0); 256], accesses: 0, limit: 40 };
+        let mut fft = PagedFft::<f32, 16, 16, Psram>::new(&mut psram).unwrap();
+        assert_eq!(fft.fft(), Err(()));
+        assert!(!fft.is_done());
+    }
+}
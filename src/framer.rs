@@ -0,0 +1,297 @@
+/* embfft | framer.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Overlapping frames
+
+/******************************************************************************/
+
+use core::marker::PhantomData;
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::status::Status;
+use crate::window::{Rectangular, Window};
+
+/******************************************************************************/
+
+/// Substitute for the dropped samples
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Concealment {
+    /// The dropped samples are replaced by zeros
+    Zero,
+    /// The last received sample is repeated
+    Hold
+}
+
+/******************************************************************************/
+
+/// Overlapping frame manager
+///
+/// The samples are kept in a ring buffer, and a frame of the last N samples is released every `hop`
+/// samples, weighted by the window `W`. A hop of N / 2 gives a 50% overlap, and a hop of N / 4 a 75%
+/// overlap. This decouples the size of the acquired blocks from the size of the FFT.
+///
+/// For example:
+/// ```
+/// let mut framer = embfft::framer::Framer::<f32, 8>::new(4).unwrap();
+/// let block = [1.0; 6];
+/// let mut data = [(0.0, 0.0); 8];
+///
+/// let mut remaining = &block[..];
+/// let mut frames = 0;
+/// for _ in 0..3 {
+///     remaining = &remaining[framer.extend(remaining)..];
+///     if framer.read(&mut data).is_ok() {
+///         embfft::EmbFft::new(&mut data).fft();
+///         frames += 1;
+///     }
+///     remaining = if remaining.is_empty() { &block } else { remaining };
+/// }
+/// assert_eq!(frames, 2);
+/// ```
+pub struct Framer<T, const N: usize, W = Rectangular> {
+    buffer: [T; N],
+    hop: usize,
+    idx: usize,
+    count: usize,
+    clean: usize,
+    overrun: bool,
+    ticks: Option<u64>,
+    frame_ticks: Option<u64>,
+    window: PhantomData<W>
+}
+
+impl<T: Float<N>, const N: usize> Framer<T, N> {
+    /// Initializes a new frame manager, without window
+    ///
+    /// Fails with [`Error::InvalidParameter`] if `hop` is 0 or greater than N.
+    pub fn new(hop: usize) -> Result<Self, Error> {
+        Self::windowed(hop)
+    }
+}
+
+impl<T: Float<N>, const N: usize, W: Window<T, N>> Framer<T, N, W> {
+    /// Initializes a new frame manager, the frames being weighted by the window `W`
+    ///
+    /// Fails with [`Error::InvalidParameter`] if `hop` is 0 or greater than N.
+    pub fn windowed(hop: usize) -> Result<Self, Error> {
+        if hop == 0 || hop > N {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            buffer: [T::ZERO; N],
+            hop,
+            idx: 0,
+            count: N,
+            clean: N,
+            overrun: false,
+            ticks: None,
+            frame_ticks: None,
+            window: PhantomData
+        })
+    }
+
+    /// Discards the acquired samples
+    pub fn reset(&mut self) {
+        self.idx = 0;
+        self.count = N;
+        self.clean = N;
+        self.overrun = false;
+        self.ticks = None;
+        self.frame_ticks = None;
+    }
+
+    /// Returns the number of samples between two frames
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Feeds a new sample
+    ///
+    /// Returns `true` once a frame is available. Further samples are discarded until the frame is read.
+    pub fn push(&mut self, x: T) -> bool {
+        if self.count == 0 {
+            self.overrun = true;
+        } else {
+            self.buffer[self.idx] = x;
+            self.idx = (self.idx + 1) % N;
+            self.count -= 1;
+            self.clean = usize::min(self.clean + 1, N);
+            if self.count == 0 {
+                self.frame_ticks = self.ticks;
+            }
+        }
+        self.is_ready()
+    }
+
+    /// Records a timestamp, in user ticks, typically the acquisition time of the next block of samples
+    ///
+    /// Each frame carries the last timestamp recorded before it was complete, see [`Framer::timestamp()`].
+    pub fn stamp(&mut self, ticks: u64) {
+        self.ticks = Some(ticks);
+    }
+
+    /// Returns the timestamp of the available frame, or of the last read one
+    ///
+    /// Returns `None` if no timestamp was recorded before the frame was complete.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.frame_ticks
+    }
+
+    /// Replaces dropped samples, until a frame is available
+    ///
+    /// Use this when the acquisition reports lost samples, e.g. a DMA overrun or a missing packet, to keep
+    /// the frames aligned in time. The frames containing a substitute are then flagged as degraded, see
+    /// [`Framer::read_with_status()`].
+    /// Returns the number of replaced samples, the remaining ones should be replaced after reading the
+    /// frame.
+    pub fn conceal(&mut self, count: usize, concealment: Concealment) -> usize {
+        let n = usize::min(self.count, count);
+        let x = match concealment {
+            Concealment::Zero => T::ZERO,
+            Concealment::Hold => self.buffer[(self.idx + N - 1) % N]
+        };
+        for _ in 0..n {
+            self.push(x);
+            self.clean = 0;
+        }
+        n
+    }
+
+    /// Feeds a block of samples, until a frame is available
+    ///
+    /// Returns the number of consumed samples, the remaining ones should be fed after reading the frame.
+    pub fn extend(&mut self, samples: &[T]) -> usize {
+        let n = usize::min(self.count, samples.len());
+        for &x in &samples[..n] {
+            self.push(x);
+        }
+        n
+    }
+
+    /// Checks if a frame is available
+    pub fn is_ready(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Copies the windowed frame into an FFT buffer, and waits for the next one
+    ///
+    /// The oldest sample is placed first, and the imaginary parts are set to zero.
+    /// Fails with [`Error::NotDone`] if no frame is available. If samples were discarded because the
+    /// frame was not read in time, the frame is still copied, but fails with [`Error::Overrun`].
+    pub fn read(&mut self, data: &mut [(T, T); N]) -> Result<(), Error> {
+        self.read_with_status(data).map(|_| ())
+    }
+
+    /// Copies the windowed frame into an FFT buffer, and waits for the next one
+    ///
+    /// Same as [`Framer::read()`], but also returns [`Status::DEGRADED`] if the frame contains substitutes
+    /// for dropped samples, so that it can be combined with the status of the transform.
+    pub fn read_with_status(&mut self, data: &mut [(T, T); N]) -> Result<Status, Error> {
+        if !self.is_ready() {
+            return Err(Error::NotDone);
+        }
+        // The write index points to the oldest sample of the frame
+        for (i, y) in data.iter_mut().enumerate() {
+            let x = self.buffer[(self.idx + i) % N];
+            *y = (if W::IS_RECTANGULAR { x } else { x * W::TABLE[i] }, T::ZERO);
+        }
+        self.count = self.hop;
+        if core::mem::take(&mut self.overrun) {
+            return Err(Error::Overrun);
+        }
+        Ok(if self.clean < N { Status::DEGRADED } else { Status::OK })
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::Hann;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_overlap() {
+        assert_eq!(Framer::<f32, 4>::new(0).err(), Some(Error::InvalidParameter));
+        assert_eq!(Framer::<f32, 4>::new(5).err(), Some(Error::InvalidParameter));
+
+        // 75% overlap
+        let mut framer = Framer::<f32, 4>::new(1).unwrap();
+        let mut data = [(0.0, 0.0); 4];
+        assert_eq!(framer.extend(&[1.0, 2.0, 3.0, 4.0, 5.0]), 4);
+        framer.read(&mut data).unwrap();
+        assert_eq!(data, [(1.0, 0.0), (2.0, 0.0), (3.0, 0.0), (4.0, 0.0)]);
+        assert_eq!(framer.read(&mut data), Err(Error::NotDone));
+        assert!(framer.push(5.0));
+        framer.read(&mut data).unwrap();
+        assert_eq!(data, [(2.0, 0.0), (3.0, 0.0), (4.0, 0.0), (5.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_windowed() {
+        let mut framer = Framer::<f64, 4, Hann>::windowed(2).unwrap();
+        let mut data = [(0.0, 0.0); 4];
+        assert_eq!(framer.extend(&[1.0; 8]), 4);
+
+        // The frame is not read before more samples arrive
+        assert!(framer.push(2.0));
+        assert_eq!(framer.read(&mut data), Err(Error::Overrun));
+        for (x, y) in core::iter::zip(data, [0.0, 0.5, 1.0, 0.5]) {
+            assert_abs_diff_eq!(x.0, y, epsilon = 1e-15);
+        }
+        assert_eq!(framer.extend(&[2.0; 8]), 2);
+        framer.read(&mut data).unwrap();
+        for (x, y) in core::iter::zip(data, [0.0, 0.5, 2.0, 1.0]) {
+            assert_abs_diff_eq!(x.0, y, epsilon = 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_conceal() {
+        let mut framer = Framer::<f32, 4>::new(2).unwrap();
+        let mut data = [(0.0, 0.0); 4];
+        framer.extend(&[1.0, 2.0]);
+        assert_eq!(framer.conceal(3, Concealment::Hold), 2);
+        assert_eq!(framer.read_with_status(&mut data), Ok(Status::DEGRADED));
+        assert_eq!(data.map(|x| x.0), [1.0, 2.0, 2.0, 2.0]);
+
+        // The substitutes remain in the overlapping frame
+        assert_eq!(framer.conceal(1, Concealment::Zero), 1);
+        framer.push(5.0);
+        assert_eq!(framer.read_with_status(&mut data), Ok(Status::DEGRADED));
+        assert_eq!(data.map(|x| x.0), [2.0, 2.0, 0.0, 5.0]);
+
+        // Until they are pushed out of the frame
+        framer.extend(&[6.0, 7.0]);
+        assert_eq!(framer.read_with_status(&mut data), Ok(Status::DEGRADED));
+        framer.extend(&[8.0, 9.0]);
+        assert_eq!(framer.read_with_status(&mut data), Ok(Status::OK));
+        assert_eq!(data.map(|x| x.0), [6.0, 7.0, 8.0, 9.0]);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let mut framer = Framer::<f32, 4>::new(2).unwrap();
+        let mut data = [(0.0, 0.0); 4];
+        framer.extend(&[1.0; 4]);
+        assert_eq!(framer.timestamp(), None);
+        framer.read(&mut data).unwrap();
+
+        // Blocks of 3 samples, stamped on arrival
+        framer.stamp(1000);
+        assert_eq!(framer.extend(&[2.0; 3]), 2);
+        framer.stamp(1003);
+        assert_eq!(framer.timestamp(), Some(1000));
+        framer.read(&mut data).unwrap();
+        assert_eq!(framer.extend(&[2.0]), 1);
+        assert_eq!(framer.extend(&[3.0; 3]), 1);
+        assert_eq!(framer.timestamp(), Some(1003));
+        framer.reset();
+        assert_eq!(framer.timestamp(), None);
+    }
+}
@@ -0,0 +1,178 @@
+/* embfft | gating.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Time-gating of impulse responses
+//!
+//! In an ordinary room, the impulse response of a loudspeaker or an antenna is followed by the reflections
+//! on the walls. Gating the response before the first reflection, and transforming it again, gives a
+//! quasi-anechoic frequency response, valid above roughly the inverse of the gate duration.
+//! For example:
+//! ```
+//! use embfft::gating::TimeGate;
+//!
+//! // Direct sound at sample 10, reflection at sample 40
+//! let mut impulse = [(0.0f32, 0.0); 64];
+//! impulse[10] = (1.0, 0.0);
+//! impulse[40] = (0.5, 0.0);
+//!
+//! let gate = TimeGate::around_peak(&impulse, 5, 25).unwrap().with_tapers(4, 8);
+//! gate.frequency_response(&mut impulse).unwrap();
+//! assert!(impulse.iter().all(|x| (x.0 * x.0 + x.1 * x.1 - 1.0).abs() < 1e-5));
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::math;
+use crate::status::Status;
+
+/******************************************************************************/
+
+/// Gate passing the samples `start` to `end`, excluded, of an impulse response
+///
+/// The edges of the gate can be tapered with half Hann windows, inside the gate, to limit the ripple of
+/// the frequency response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeGate {
+    start: usize,
+    end: usize,
+    fade_in: usize,
+    fade_out: usize
+}
+
+impl TimeGate {
+    /// Initializes a rectangular gate
+    ///
+    /// Fails with [`Error::InvalidParameter`] if `start` is not lower than `end`.
+    pub fn new(start: usize, end: usize) -> Result<Self, Error> {
+        if start >= end {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { start, end, fade_in: 0, fade_out: 0 })
+    }
+
+    /// Initializes a rectangular gate of `length` samples, opened `pre` samples before the largest one
+    ///
+    /// The largest sample is usually the direct sound.
+    /// Fails with [`Error::InvalidParameter`] if `length` is 0, and with [`Error::OutOfRange`] if the gate
+    /// ends after the response.
+    pub fn around_peak<T: Float<N>, const N: usize>(impulse: &[(T, T); N], pre: usize, length: usize) -> Result<Self, Error> {
+        let magnitude = |k: usize| impulse[k].0 * impulse[k].0 + impulse[k].1 * impulse[k].1;
+        let peak = (0..N).fold(0, |p, k| if magnitude(k) > magnitude(p) { k } else { p });
+        let start = peak.saturating_sub(pre);
+        if start + length > N {
+            return Err(Error::OutOfRange);
+        }
+        Self::new(start, start + length)
+    }
+
+    /// Sets the lengths of the tapers at the opening and at the closing of the gate
+    ///
+    /// The tapers are shortened if needed to fit in the gate.
+    pub fn with_tapers(mut self, fade_in: usize, fade_out: usize) -> Self {
+        let length = self.end - self.start;
+        self.fade_in = fade_in.min(length);
+        self.fade_out = fade_out.min(length - self.fade_in);
+        self
+    }
+
+    /// Returns the first sample of the gate
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the sample following the gate
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Applies the gate to an impulse response, in place
+    ///
+    /// Fails with [`Error::OutOfRange`] if the gate ends after the response.
+    pub fn apply<T: Float<N>, const N: usize>(&self, impulse: &mut [(T, T); N]) -> Result<(), Error> {
+        if self.end > N {
+            return Err(Error::OutOfRange);
+        }
+        let taper = |i: usize, length: usize| T::from_f64(0.5 - 0.5 * math::sin_cos(PI * (i as f64 + 0.5) / length as f64).1);
+        for (i, x) in impulse.iter_mut().enumerate() {
+            let w = if i < self.start || i >= self.end {
+                T::ZERO
+            } else if i < self.start + self.fade_in {
+                taper(i - self.start, self.fade_in)
+            } else if i >= self.end - self.fade_out {
+                taper(self.end - 1 - i, self.fade_out)
+            } else {
+                continue;
+            };
+            *x = (x.0 * w, x.1 * w);
+        }
+        Ok(())
+    }
+
+    /// Applies the gate to an impulse response, and transforms it in place into a frequency response
+    ///
+    /// This is a blocking function, which returns the status flags of the conversion.
+    /// Fails with [`Error::OutOfRange`] if the gate ends after the response.
+    pub fn frequency_response<T: Float<N>, const N: usize>(&self, impulse: &mut [(T, T); N]) -> Result<Status, Error> {
+        self.apply(impulse)?;
+        let mut fft = EmbFft::new(impulse);
+        fft.fft();
+        Ok(fft.status())
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_gate() {
+        assert_eq!(TimeGate::new(4, 4), Err(Error::InvalidParameter));
+        let mut impulse = [(1.0f64, 0.0); 16];
+        assert_eq!(TimeGate::new(8, 17).unwrap().apply(&mut impulse), Err(Error::OutOfRange));
+        assert_eq!(TimeGate::around_peak(&impulse, 0, 17), Err(Error::OutOfRange));
+
+        // Symmetric tapers, shortened to fit in the gate
+        let gate = TimeGate::new(2, 12).unwrap().with_tapers(4, 8);
+        gate.apply(&mut impulse).unwrap();
+        for (i, x) in impulse.iter().enumerate() {
+            let expected = match i {
+                2..=5 => 0.5 - 0.5 * cordic::cos(PI * (i as f64 - 1.5) / 4.0),
+                6..=11 => 0.5 - 0.5 * cordic::cos(PI * (11.5 - i as f64) / 6.0),
+                _ => 0.0
+            };
+            assert_abs_diff_eq!(x.0, expected, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_reflection() {
+        let mut impulse = [(0.0f64, 0.0); 128];
+        impulse[20] = (1.0, 0.0);
+        impulse[21] = (-0.5, 0.0);
+        impulse[70] = (0.3, 0.0);
+        let gate = TimeGate::around_peak(&impulse, 8, 40).unwrap().with_tapers(4, 16);
+        assert_eq!((gate.start(), gate.end()), (12, 52));
+        let status = gate.frequency_response(&mut impulse).unwrap();
+        assert!(!status.is_corrupted());
+
+        // Only the direct sound remains: 1 - 0.5 e^(-jω)
+        for (k, x) in impulse.iter().enumerate() {
+            let w = 2.0 * PI * k as f64 / 128.0;
+            let expected = 1.25 - cordic::cos(w);
+            assert_abs_diff_eq!(x.0 * x.0 + x.1 * x.1, expected, epsilon = 1e-9);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+/* embfft | goertzel.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Single frequency detection, with the Goertzel algorithm
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::math;
+use crate::error::Error;
+use crate::units::{Hertz, SampleRate};
+
+/******************************************************************************/
+
+/// Goertzel filter, computing a single DFT bin over blocks of N samples
+///
+/// Much cheaper than a full FFT when only a few frequencies are of interest. The frequency does not need
+/// to be an integer bin: the generalized algorithm corrects the final phase, so that tones like 1 kHz at a
+/// 44.1 kHz sample rate are measured without leakage.
+///
+/// For example:
+/// ```
+/// use embfft::goertzel::Goertzel;
+/// use embfft::units::{Hertz, SampleRate};
+///
+/// let fs = SampleRate::new(44100.0).unwrap();
+/// let mut goertzel = Goertzel::<f32, 441>::from_frequency(Hertz::khz(1.0), fs);
+/// for i in 0..441 {
+///     let phase = 2.0 * core::f32::consts::PI * 1000.0 * i as f32 / 44100.0;
+///     goertzel.push(phase.cos());
+/// }
+/// # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+/// assert!((goertzel.power().unwrap() - 220.5 * 220.5).abs() < 1.0);
+/// ```
+pub struct Goertzel<T, const N: usize> {
+    coeff: T,
+    // e^(jω) and e^(-jω(N-1))
+    rotation: (T, T),
+    correction: (T, T),
+    s1: T,
+    s2: T,
+    count: usize
+}
+
+impl<T: Float<N>, const N: usize> Goertzel<T, N> {
+    /// Initializes a filter for the given bin, which may be fractional
+    pub fn new(bin: f64) -> Self {
+        let omega = 2.0 * PI * bin / N as f64;
+        let (sin, cos) = math::sin_cos(omega);
+        let (sin_n, cos_n) = math::sin_cos(-omega * (N - 1) as f64);
+        Self {
+            coeff: T::from_f64(2.0 * cos),
+            rotation: (T::from_f64(cos), T::from_f64(sin)),
+            correction: (T::from_f64(cos_n), T::from_f64(sin_n)),
+            s1: T::ZERO,
+            s2: T::ZERO,
+            count: 0
+        }
+    }
+
+    /// Initializes a filter for the given frequency
+    pub fn from_frequency(frequency: Hertz, sample_rate: SampleRate) -> Self {
+        Self::new(sample_rate.to_bin::<N>(frequency))
+    }
+
+    /// Discards the processed samples
+    pub fn reset(&mut self) {
+        self.s1 = T::ZERO;
+        self.s2 = T::ZERO;
+        self.count = 0;
+    }
+
+    /// Feeds a new sample
+    ///
+    /// Returns `true` once N samples were processed. Further samples are ignored until the filter is reset.
+    pub fn push(&mut self, x: T) -> bool {
+        if self.count < N {
+            let s = x + self.coeff * self.s1 - self.s2;
+            self.s2 = self.s1;
+            self.s1 = s;
+            self.count += 1;
+        }
+        self.is_done()
+    }
+
+    /// Processes a complete block, and returns the DFT bin
+    ///
+    /// This resets the filter first.
+    pub fn process(&mut self, samples: &[T; N]) -> (T, T) {
+        self.reset();
+        for &x in samples {
+            self.push(x);
+        }
+        self.compute()
+    }
+
+    /// Checks if N samples were processed
+    pub fn is_done(&self) -> bool {
+        self.count == N
+    }
+
+    fn compute(&self) -> (T, T) {
+        // y = s1 - e^(-jω) * s2
+        let y = (self.s1 - self.rotation.0 * self.s2, self.rotation.1 * self.s2);
+        let c = self.correction;
+        (y.0 * c.0 - y.1 * c.1, y.0 * c.1 + y.1 * c.0)
+    }
+
+    /// Returns the DFT bin, with the same phase as an FFT would give
+    ///
+    /// Fails with [`Error::NotDone`] if less than N samples were processed.
+    pub fn result(&self) -> Result<(T, T), Error> {
+        if !self.is_done() {
+            return Err(Error::NotDone);
+        }
+        Ok(self.compute())
+    }
+
+    /// Returns the squared magnitude of the DFT bin, which does not require the phase correction
+    ///
+    /// Fails with [`Error::NotDone`] if less than N samples were processed.
+    pub fn power(&self) -> Result<T, Error> {
+        if !self.is_done() {
+            return Err(Error::NotDone);
+        }
+        Ok(self.s1 * self.s1 + self.s2 * self.s2 - self.coeff * self.s1 * self.s2)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_integer_bin() {
+        let samples = [1.0, 2.0, 0.0, -1.0, 0.5, 0.0, -2.0, 1.0];
+        let mut data = [(0.0, 0.0); 8];
+        for (d, &x) in data.iter_mut().zip(&samples) {
+            *d = (x, 0.0);
+        }
+        crate::EmbFft::new(&mut data).fft();
+
+        for (k, y) in data.iter().enumerate() {
+            let mut goertzel = Goertzel::<f64, 8>::new(k as f64);
+            assert_eq!(goertzel.result(), Err(Error::NotDone));
+            let x = goertzel.process(&samples);
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-12);
+            assert_abs_diff_eq!(goertzel.power().unwrap(), x.0 * x.0 + x.1 * x.1, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_fractional_bin() {
+        // Tone at 2.3 bins, with a phase of π/3
+        let samples: [f64; 16] = core::array::from_fn(|i| cordic::cos(2.0 * PI * 2.3 * i as f64 / 16.0 + PI / 3.0));
+        let mut goertzel = Goertzel::<f64, 16>::new(2.3);
+        for &x in &samples {
+            goertzel.push(x);
+        }
+        assert!(goertzel.push(0.0));
+
+        // Reference value, computed with the DTFT at the same frequency
+        let mut expected = (0.0, 0.0);
+        for (i, x) in samples.iter().enumerate() {
+            let (s, c) = cordic::sin_cos(-2.0 * PI * 2.3 * i as f64 / 16.0);
+            expected = (expected.0 + x * c, expected.1 + x * s);
+        }
+        let x = goertzel.result().unwrap();
+        assert_abs_diff_eq!(x.0, expected.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(x.1, expected.1, epsilon = 1e-12);
+    }
+}
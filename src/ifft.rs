@@ -6,6 +6,14 @@
 /******************************************************************************/
 
 use crate::common::{Base, Float};
+use crate::error::Error;
+use crate::kernel::Kernel;
+use crate::metadata::{Direction, Scaling};
+use crate::status::Status;
+use crate::window::Rectangular;
+
+#[cfg(feature = "num-complex")]
+use num_complex::Complex;
 
 /******************************************************************************/
 
@@ -13,27 +21,13 @@ use crate::common::{Base, Float};
 ///
 /// This structure contains a reference to the input / output data, as well as information related to the
 /// internal state.
-pub struct EmbIfft<'a, T, const N: usize> {
-    data: &'a mut [(T, T); N],
-    state: State,
-    length: usize,
-    step: usize,
-    step_size: usize,
-    top_idx: usize,
-    bottom_idx: usize
-}
-
-/// Conversion state
-#[derive(PartialEq)]
-enum State {
-    Reorder,
-    Step1,
-    Step2,
-    Step3,
-    Step4,
-    Step5,
-    Step6,
-    Done
+/// A decimation in frequency variant is used for bit-reversed input, see [`EmbIfft::new_unordered()`].
+///
+/// The data is an array of complex numbers stored as tuples, a flat array of interleaved real and imaginary
+/// parts, see [`EmbIfft::from_interleaved()`], or an array of `num_complex::Complex` with the `num-complex`
+/// feature, see `EmbIfft::from_complex()`.
+pub struct EmbIfft<'a, T, const N: usize, B = [(T, T); N]> {
+    kernel: Kernel<'a, T, N, true, Rectangular, B>
 }
 
 impl<'a, T: Float<N>, const N: usize> EmbIfft<'a, T, N> {
@@ -41,145 +35,44 @@ impl<'a, T: Float<N>, const N: usize> EmbIfft<'a, T, N> {
     ///
     /// Use this function whenever a new conversion is required.
     pub fn new(data: &'a mut [(T, T); N]) -> Self {
-        assert!(Base::<N>::IS_N_POW2);
         Self {
-            data,
-            state: State::Reorder,
-            length: 1,
-            step: 0,
-            step_size: N / 4,
-            top_idx: 0,
-            bottom_idx: 0
-        }
-    }
-
-    fn reorder(&mut self) {
-        // Ensure the input order is reversed
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        if self.bottom_idx > self.top_idx {
-            self.data[self.top_idx] = bottom;
-            self.data[self.bottom_idx] = top;
-        }
-        if self.top_idx < N - 1 {
-            self.bottom_idx = Base::<N>::reverse_bits(self.top_idx + 1);
-            self.top_idx += 1;
-        } else {
-            self.top_idx = 0;
-            self.bottom_idx = 1;
-            self.state = State::Step1;
-        }
-    }
-
-    fn step1(&mut self) {
-        // Twiddle = 1 / N
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        self.data[self.top_idx].0 = (bottom.0 + top.0) * T::N_INV;
-        self.data[self.top_idx].1 = (bottom.1 + top.1) * T::N_INV;
-        self.data[self.bottom_idx].0 = (-bottom.0 + top.0) * T::N_INV;
-        self.data[self.bottom_idx].1 = (-bottom.1 + top.1) * T::N_INV;
-        if self.bottom_idx < N - 2 {
-            self.top_idx += 2;
-            self.bottom_idx += 2;
-        } else {
-            self.top_idx = 0;
-            self.state = State::Step2;
-        }
-    }
-
-    fn step2(&mut self) {
-        // Twiddle = 1
-        self.bottom_idx = self.top_idx + (self.length << 1);
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        self.data[self.top_idx].0 = bottom.0 + top.0;
-        self.data[self.top_idx].1 = bottom.1 + top.1;
-        self.data[self.bottom_idx].0 = top.0 - bottom.0;
-        self.data[self.bottom_idx].1 = top.1 - bottom.1;
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        self.step = self.step_size;
-        if self.step_size < N / 4 {
-            self.state = State::Step3;
-        } else {
-            self.state = State::Step4;
-        }
-    }
-
-    fn step3(&mut self) {
-        // Twiddle = e^(+j * theta)
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        let temp = (
-            bottom.0 * T::SINE_TABLE[N / 4 - self.step] - bottom.1 * T::SINE_TABLE[self.step],
-            bottom.1 * T::SINE_TABLE[N / 4 - self.step] + bottom.0 * T::SINE_TABLE[self.step]
-        );
-        self.data[self.top_idx].0 = top.0 + temp.0;
-        self.data[self.top_idx].1 = top.1 + temp.1;
-        self.data[self.bottom_idx].0 = top.0 - temp.0;
-        self.data[self.bottom_idx].1 = top.1 - temp.1;
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        if self.step < N / 4 - self.step_size {
-            self.step += self.step_size;
-        } else {
-            self.state = State::Step4;
+            kernel: Kernel::new_dit(data, true)
         }
     }
 
-    fn step4(&mut self) {
-        // Twiddle = +j
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        self.data[self.top_idx].0 = top.0 - bottom.1;
-        self.data[self.top_idx].1 = top.1 + bottom.0;
-        self.data[self.bottom_idx].0 = top.0 + bottom.1;
-        self.data[self.bottom_idx].1 = top.1 - bottom.0;
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        self.step = self.step_size;
-        if self.step_size < N / 4 {
-            self.state = State::Step5;
-        } else {
-            self.state = State::Step6;
-        }
+    /// Initializes a new IFFT conversion from a slice
+    ///
+    /// Fails with [`Error::InvalidLength`] if the slice length is not N.
+    pub fn try_from_slice(data: &'a mut [(T, T)]) -> Result<Self, Error> {
+        data.try_into().map(Self::new).map_err(|_| Error::InvalidLength)
     }
 
-    fn step5(&mut self) {
-        // Twiddle = +j * e^(+j * theta)
-        let top = self.data[self.top_idx];
-        let bottom = self.data[self.bottom_idx];
-        let temp = (
-            -bottom.1 * T::SINE_TABLE[N / 4 - self.step] - bottom.0 * T::SINE_TABLE[self.step],
-            bottom.0 * T::SINE_TABLE[N / 4 - self.step] - bottom.1 * T::SINE_TABLE[self.step]
-        );
-        self.data[self.top_idx].0 = top.0 + temp.0;
-        self.data[self.top_idx].1 = top.1 + temp.1;
-        self.data[self.bottom_idx].0 = top.0 - temp.0;
-        self.data[self.bottom_idx].1 = top.1 - temp.1;
-        self.top_idx += 1;
-        self.bottom_idx += 1;
-        if self.step < N / 4 - self.step_size {
-            self.step += self.step_size;
-        } else {
-            self.state = State::Step6;
+    /// Initializes a new IFFT conversion from bit-reversed input
+    ///
+    /// The input must be in bit-reversed order, as produced by [`EmbFft::new_unordered()`], while the
+    /// output is in natural order. This allows chaining an FFT, some per-bin processing and an IFFT
+    /// without any reordering pass.
+    ///
+    /// [`EmbFft::new_unordered()`]: crate::EmbFft::new_unordered()
+    pub fn new_unordered(data: &'a mut [(T, T); N]) -> Self {
+        Self {
+            kernel: Kernel::new_bit_reversed(data, true)
         }
     }
 
-    fn step6(&mut self) {
-        // Check if we need to loop
-        if self.bottom_idx < N {
-            self.top_idx = self.bottom_idx;
-            self.state = State::Step2;
-        } else if self.step_size > 1 {
-            self.length <<= 1;
-            self.step_size >>= 1;
-            self.top_idx = 0;
-            self.state = State::Step2;
-        } else {
-            self.state = State::Done;
-        }
+    /// Disables the multiplication by 1 / N
+    ///
+    /// This is useful when the scaling is applied elsewhere, for instance combined with other factors in
+    /// a convolution kernel. Call this before the first iteration.
+    /// For example:
+    /// ```
+    /// let mut data = [(1.0f32, 0.0); 8];
+    /// embfft::EmbIfft::new(&mut data).unscaled().ifft();
+    /// assert_eq!(data[0], (8.0, 0.0));
+    /// ```
+    pub fn unscaled(mut self) -> Self {
+        self.kernel.set_scaled(false);
+        self
     }
 
     /// Non-blocking IFFT computation
@@ -201,16 +94,7 @@ impl<'a, T: Float<N>, const N: usize> EmbIfft<'a, T, N> {
     /// }
     /// ```
     pub fn ifft_iterate(&mut self) {
-        match self.state {
-            State::Reorder => { self.reorder(); },
-            State::Step1 => { self.step1(); },
-            State::Step2 => { self.step2(); },
-            State::Step3 => { self.step3(); },
-            State::Step4 => { self.step4(); },
-            State::Step5 => { self.step5(); },
-            State::Step6 => { self.step6(); },
-            State::Done => {}
-        }
+        self.kernel.iterate();
     }
 
     /// Blocking IFFT computation
@@ -226,25 +110,208 @@ impl<'a, T: Float<N>, const N: usize> EmbIfft<'a, T, N> {
     /// embfft::EmbIfft::new(&mut data).ifft();
     /// ```
     pub fn ifft(&mut self) {
-        while self.state != State::Done {
-            self.ifft_iterate();
-        }
+        self.kernel.run();
     }
 
     /// Checks if the conversion is complete
     ///
     /// Use this together with the [`EmbIfft::ifft_iterate()`] function.
     pub fn is_done(&self) -> bool {
-        self.state == State::Done
+        self.kernel.is_done()
+    }
+
+    /// Returns the number of samples of the transform
+    pub const fn len() -> usize {
+        N
+    }
+
+    /// Returns the base 2 logarithm of the number of samples
+    pub const fn log2_len() -> usize {
+        Base::<N>::LOG2_N
+    }
+
+    /// Returns the direction of the transform
+    pub const fn direction() -> Direction {
+        Direction::Inverse
+    }
+
+    /// Returns the scaling of the output
+    pub fn scaling(&self) -> Scaling {
+        if self.kernel.is_scaled() { Scaling::InverseN } else { Scaling::Unscaled }
+    }
+
+    /// Checks if the output is sorted by time
+    ///
+    /// This is always `true`, as the inverse transforms produce their output in natural order.
+    pub fn is_natural_order(&self) -> bool {
+        self.kernel.is_natural_order()
+    }
+
+    /// Returns the status flags accumulated so far
+    ///
+    /// Use this to detect overflows or invalid values, and discard the corrupted results.
+    pub fn status(&self) -> Status {
+        self.kernel.status()
+    }
+
+    /// Returns a view of the transformed data
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete.
+    pub fn output(&self) -> Result<&[(T, T); N], Error> {
+        if self.is_done() {
+            Ok(self.kernel.data())
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    ///
+    /// If the conversion is not complete, the buffer contains partially transformed data.
+    pub fn into_data(self) -> &'a mut [(T, T); N] {
+        self.kernel.into_data()
+    }
+}
+
+/// Transforms of flat buffers of R = 2N interleaved real and imaginary parts, as filled by DMA peripherals,
+/// computed in place without any copy
+impl<'a, T: Float<N>, const N: usize, const R: usize> EmbIfft<'a, T, N, [T; R]> {
+    /// Initializes a new IFFT conversion of interleaved samples
+    ///
+    /// The value i is `(data[2 * i], data[2 * i + 1])`, and the samples are stored the same way. As N cannot
+    /// be inferred from R, the type parameters must be given explicitly.
+    /// For example:
+    /// ```
+    /// let mut dma = [4.0f32, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+    /// embfft::EmbIfft::<_, 4, _>::from_interleaved(&mut dma).ifft();
+    /// assert_eq!(dma, [1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+    /// ```
+    pub fn from_interleaved(data: &'a mut [T; R]) -> Self {
+        const { assert!(R == 2 * N, "R must be equal to 2 * N") };
+        Self {
+            kernel: Kernel::new_dit(data, true)
+        }
+    }
+
+    /// Initializes a new IFFT conversion of interleaved samples from a slice
+    ///
+    /// Fails with [`Error::InvalidLength`] if the slice length is not 2N.
+    pub fn try_from_interleaved(data: &'a mut [T]) -> Result<Self, Error> {
+        data.try_into().map(Self::from_interleaved).map_err(|_| Error::InvalidLength)
+    }
+
+    /// Disables the multiplication by 1 / N, see [`EmbIfft::unscaled()`]
+    pub fn unscaled(mut self) -> Self {
+        self.kernel.set_scaled(false);
+        self
+    }
+
+    /// Non-blocking IFFT computation, see [`EmbIfft::ifft_iterate()`]
+    pub fn ifft_iterate(&mut self) {
+        self.kernel.iterate();
+    }
+
+    /// Blocking IFFT computation
+    pub fn ifft(&mut self) {
+        self.kernel.run();
+    }
+
+    /// Checks if the conversion is complete
+    pub fn is_done(&self) -> bool {
+        self.kernel.is_done()
+    }
+
+    /// Returns the status flags accumulated so far
+    pub fn status(&self) -> Status {
+        self.kernel.status()
+    }
+
+    /// Returns a view of the transformed data, with interleaved real and imaginary parts
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete.
+    pub fn output(&self) -> Result<&[T; R], Error> {
+        if self.is_done() {
+            Ok(self.kernel.data())
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    pub fn into_data(self) -> &'a mut [T; R] {
+        self.kernel.into_data()
+    }
+}
+
+/// Transforms of buffers of the `num-complex` crate, computed in place without any copy
+#[cfg(feature = "num-complex")]
+impl<'a, T: Float<N>, const N: usize> EmbIfft<'a, T, N, [Complex<T>; N]> {
+    /// Initializes a new IFFT conversion of complex numbers
+    ///
+    /// For example:
+    /// ```
+    /// use num_complex::Complex;
+    ///
+    /// let mut data = [Complex::new(1.0f32, 0.0); 8];
+    /// embfft::EmbIfft::from_complex(&mut data).ifft();
+    /// assert_eq!(data[0], Complex::new(1.0, 0.0));
+    /// ```
+    pub fn from_complex(data: &'a mut [Complex<T>; N]) -> Self {
+        Self {
+            kernel: Kernel::new_dit(data, true)
+        }
+    }
+
+    /// Disables the multiplication by 1 / N, see [`EmbIfft::unscaled()`]
+    pub fn unscaled(mut self) -> Self {
+        self.kernel.set_scaled(false);
+        self
+    }
+
+    /// Non-blocking IFFT computation, see [`EmbIfft::ifft_iterate()`]
+    pub fn ifft_iterate(&mut self) {
+        self.kernel.iterate();
+    }
+
+    /// Blocking IFFT computation
+    pub fn ifft(&mut self) {
+        self.kernel.run();
+    }
+
+    /// Checks if the conversion is complete
+    pub fn is_done(&self) -> bool {
+        self.kernel.is_done()
+    }
+
+    /// Returns the status flags accumulated so far
+    pub fn status(&self) -> Status {
+        self.kernel.status()
+    }
+
+    /// Returns a view of the transformed data
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete.
+    pub fn output(&self) -> Result<&[Complex<T>; N], Error> {
+        if self.is_done() {
+            Ok(self.kernel.data())
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    pub fn into_data(self) -> &'a mut [Complex<T>; N] {
+        self.kernel.into_data()
     }
 }
 
 /******************************************************************************/
 
 #[cfg(test)]
+#[allow(clippy::excessive_precision)]
 mod tests {
     use super::*;
-    use approx::assert_ulps_eq;
+    use approx::{assert_abs_diff_eq, assert_ulps_eq};
 
     #[test]
     fn test_ifft_f32() {
@@ -356,4 +423,86 @@ mod tests {
             assert_ulps_eq!(x.1, y.1, max_ulps = 75);
         }
     }
+
+    #[test]
+    fn test_ifft_unordered() {
+        let mut data: [(f64, f64); 64] = core::array::from_fn(|i| (i as f64, (64 - i) as f64 / 2.0));
+        let expected_data = data;
+
+        crate::EmbFft::new_unordered(&mut data).fft();
+        EmbIfft::new_unordered(&mut data).ifft();
+
+        for (x, y) in core::iter::zip(data, expected_data) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_ifft_unscaled() {
+        let mut data: [(f64, f64); 16] = core::array::from_fn(|i| (i as f64, 1.0));
+        let mut expected_data = data;
+        let mut ifft = EmbIfft::new(&mut expected_data);
+        ifft.ifft();
+        assert_eq!(ifft.status(), Status::SCALED);
+
+        let mut ifft = EmbIfft::new(&mut data).unscaled();
+        ifft.ifft();
+        assert_eq!(ifft.status(), Status::OK);
+        for (x, y) in core::iter::zip(data, expected_data) {
+            assert_ulps_eq!(x.0, y.0 * 16.0);
+            assert_ulps_eq!(x.1, y.1 * 16.0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "num-complex")]
+    fn test_ifft_complex() {
+        let mut data: [Complex<f64>; 16] = core::array::from_fn(|i| Complex::new(i as f64, 1.0));
+        let expected_data = data;
+        crate::EmbFft::from_complex(&mut data).fft();
+
+        let mut ifft = EmbIfft::from_complex(&mut data);
+        assert_eq!(ifft.output(), Err(Error::NotDone));
+        ifft.ifft();
+        assert_eq!(ifft.status(), Status::SCALED);
+        for (x, y) in core::iter::zip(ifft.into_data().iter(), expected_data) {
+            assert_abs_diff_eq!(x.re, y.re, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.im, y.im, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_ifft_interleaved() {
+        let mut data: [(f64, f64); 16] = core::array::from_fn(|i| (i as f64, 1.0));
+        let mut flat: [f64; 32] = core::array::from_fn(|i| if i % 2 == 0 { data[i / 2].0 } else { data[i / 2].1 });
+        EmbIfft::new(&mut data).unscaled().ifft();
+
+        assert!(EmbIfft::<f64, 16, [f64; 32]>::try_from_interleaved(&mut flat[..31]).is_err());
+        let mut ifft = EmbIfft::<_, 16, _>::from_interleaved(&mut flat).unscaled();
+        assert_eq!(ifft.output(), Err(Error::NotDone));
+        ifft.ifft();
+        assert_eq!(ifft.status(), Status::OK);
+        let flat = ifft.into_data();
+        for (i, x) in data.iter().enumerate() {
+            assert_eq!((flat[2 * i], flat[2 * i + 1]), *x);
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut data = [(0.0f32, 0.0); 256];
+        assert_eq!(EmbIfft::<f32, 256>::len(), 256);
+        assert_eq!(EmbIfft::<f32, 256>::log2_len(), 8);
+        assert_eq!(EmbIfft::<f32, 256>::direction(), Direction::Inverse);
+        let ifft = EmbIfft::new(&mut data);
+        assert!(ifft.is_natural_order());
+        assert_eq!(ifft.scaling(), Scaling::InverseN);
+        assert_eq!(ifft.unscaled().scaling(), Scaling::Unscaled);
+
+        assert_eq!(crate::EmbFft::<f32, 256>::direction(), Direction::Forward);
+        let fft = crate::EmbFft::new_unordered(&mut data);
+        assert!(!fft.is_natural_order());
+        assert_eq!(fft.scaling(), Scaling::Unscaled);
+    }
 }
@@ -0,0 +1,214 @@
+/* embfft | interpolate.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Sub-bin frequency estimation
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Sub-bin frequency estimator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Interpolation {
+    /// Parabola through the magnitudes of the peak and its neighbours
+    ///
+    /// Simple and robust, but biased, especially for windowed data.
+    Quadratic,
+    /// Jacobsen's estimator on the complex bins, with Candan's bias correction
+    ///
+    /// Nearly unbiased for rectangular windows.
+    Jacobsen,
+    /// Quinn's second estimator on the complex bins
+    ///
+    /// Best accuracy in noise for rectangular windows.
+    Quinn
+}
+
+fn div(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let m2 = b.0 * b.0 + b.1 * b.1;
+    ((a.0 * b.0 + a.1 * b.1) / m2, (a.1 * b.0 - a.0 * b.1) / m2)
+}
+
+fn tau(x: f64) -> f64 {
+    const SQRT_2_3: f64 = 0.816496580927726;
+    const SQRT_6_24: f64 = 0.10206207261596575;
+    0.25 * math::ln(3.0 * x * x + 6.0 * x + 1.0) - SQRT_6_24 * math::ln((x + 1.0 - SQRT_2_3) / (x + 1.0 + SQRT_2_3))
+}
+
+/// Refines the position of the peak at bin k, using its neighbours
+///
+/// Returns the fractional bin, between k - 0.5 and k + 0.5. The spectrum is considered circular, the first
+/// and last bins are neighbours.
+/// Fails with [`Error::OutOfRange`] if k is not lower than N.
+///
+/// For example:
+/// ```
+/// use embfft::interpolate::{refine_peak, Interpolation};
+///
+/// let mut data: [(f64, f64); 32] = core::array::from_fn(|i| {
+///     let phase = 2.0 * core::f64::consts::PI * 5.3 * i as f64 / 32.0;
+///     (phase.cos(), phase.sin())
+/// });
+/// embfft::EmbFft::new(&mut data).fft();
+/// let bin = refine_peak(&data, 5, Interpolation::Jacobsen).unwrap();
+/// # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+/// assert!((bin - 5.3).abs() < 1e-3);
+/// ```
+pub fn refine_peak<T: Float<N>, const N: usize>(spectrum: &[(T, T); N], k: usize, method: Interpolation) -> Result<f64, Error> {
+    if k >= N {
+        return Err(Error::OutOfRange);
+    }
+    let get = |i: usize| {
+        let x = spectrum[i % N];
+        (x.0.to_f64(), x.1.to_f64())
+    };
+    let (xm, x0, xp) = (get(k + N - 1), get(k), get(k + 1));
+    if x0 == (0.0, 0.0) {
+        return Ok(k as f64);
+    }
+
+    let delta = match method {
+        Interpolation::Quadratic => {
+            let magnitude = |x: (f64, f64)| math::sqrt(x.0 * x.0 + x.1 * x.1);
+            let (a, b, c) = (magnitude(xm), magnitude(x0), magnitude(xp));
+            let d = 2.0 * (2.0 * b - a - c);
+            if d == 0.0 { 0.0 } else { (c - a) / d }
+        },
+        Interpolation::Jacobsen => {
+            let num = (xm.0 - xp.0, xm.1 - xp.1);
+            let den = (2.0 * x0.0 - xm.0 - xp.0, 2.0 * x0.1 - xm.1 - xp.1);
+            let (sin, cos) = math::sin_cos(PI / N as f64);
+            let correction = sin / cos / (PI / N as f64);
+            if den == (0.0, 0.0) { 0.0 } else { correction * div(num, den).0 }
+        },
+        Interpolation::Quinn => {
+            let ap = div(xp, x0).0;
+            let am = div(xm, x0).0;
+            let dp = -ap / (1.0 - ap);
+            let dm = am / (1.0 - am);
+            (dp + dm) / 2.0 + tau(dp * dp) - tau(dm * dm)
+        }
+    };
+    Ok(k as f64 + delta.clamp(-0.5, 0.5))
+}
+
+/// Returns the magnitude response of the window `W` at `delta` bins from its center, relative to its peak
+///
+/// This is the scalloping curve of the window: a tone between two bins is attenuated by this factor.
+pub fn scalloping_gain<T: Float<N>, W: Window<T, N>, const N: usize>(delta: f64) -> f64 {
+    let (mut re, mut im, mut sum) = (0.0, 0.0, 0.0);
+    for (i, w) in W::TABLE.iter().enumerate() {
+        let w = w.to_f64();
+        // Keep the argument small, to preserve the accuracy
+        let cycles = delta * i as f64 / N as f64;
+        let (sin, cos) = math::sin_cos(-2.0 * PI * (cycles - cycles as i64 as f64));
+        re += w * cos;
+        im += w * sin;
+        sum += w;
+    }
+    math::sqrt(re * re + im * im) / sum
+}
+
+/// Estimates the amplitude of a tone, from a windowed spectrum and its refined frequency
+///
+/// `bin` is the fractional bin returned by [`refine_peak()`]. The magnitude of the nearest bin is
+/// divided by the response of the window `W` at the offset between both, which compensates the
+/// scalloping loss. The result is the amplitude of a complex exponential, i.e. half the amplitude of a
+/// real sine wave.
+///
+/// For example:
+/// ```
+/// use embfft::interpolate::{peak_amplitude, refine_peak, Interpolation};
+/// use embfft::window::Rectangular;
+///
+/// let mut data: [(f64, f64); 32] = core::array::from_fn(|i| {
+///     let phase = 2.0 * core::f64::consts::PI * 5.5 * i as f64 / 32.0;
+///     (0.8 * phase.cos(), 0.8 * phase.sin())
+/// });
+/// embfft::EmbFft::new(&mut data).fft();
+/// let bin = refine_peak(&data, 5, Interpolation::Jacobsen).unwrap();
+/// let amplitude = peak_amplitude::<f64, Rectangular, 32>(&data, bin);
+/// # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+/// assert!((amplitude - 0.8).abs() < 1e-3);
+/// ```
+pub fn peak_amplitude<T: Float<N>, W: Window<T, N>, const N: usize>(spectrum: &[(T, T); N], bin: f64) -> f64 {
+    let shifted = bin + N as f64 + 0.5;
+    let k = shifted as usize;
+    let delta = shifted - k as f64 - 0.5;
+    let x = spectrum[k % N];
+    let (re, im) = (x.0.to_f64(), x.1.to_f64());
+
+    let mut sum = 0.0;
+    for w in W::TABLE.iter() {
+        sum += w.to_f64();
+    }
+    math::sqrt(re * re + im * im) / (sum * scalloping_gain::<T, W, N>(delta))
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+
+    fn tone(f: f64) -> [(f64, f64); 64] {
+        let mut data = core::array::from_fn(|i| {
+            let (s, c) = cordic::sin_cos(2.0 * PI * f * i as f64 / 64.0 + 0.4);
+            (c, s)
+        });
+        crate::EmbFft::new(&mut data).fft();
+        data
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_estimators() {
+        assert_eq!(refine_peak(&tone(3.0), 64, Interpolation::Quinn), Err(Error::OutOfRange));
+        for f in [10.0, 10.1, 10.25, 10.45, 9.7] {
+            let data = tone(f);
+            let k = (f + 0.5) as usize;
+            assert_abs_diff_eq!(refine_peak(&data, k, Interpolation::Jacobsen).unwrap(), f, epsilon = 1e-4);
+            assert_abs_diff_eq!(refine_peak(&data, k, Interpolation::Quinn).unwrap(), f, epsilon = 1e-3);
+        }
+
+        // The quadratic estimator is only exact on the bins, but moves in the right direction
+        assert_abs_diff_eq!(refine_peak(&tone(10.0), 10, Interpolation::Quadratic).unwrap(), 10.0, epsilon = 1e-9);
+        let bin = refine_peak(&tone(10.3), 10, Interpolation::Quadratic).unwrap();
+        assert!(bin > 10.0 && bin < 10.3);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_scalloping() {
+        use crate::window::{Hann, Rectangular};
+
+        assert_abs_diff_eq!(scalloping_gain::<f64, Rectangular, 64>(0.0), 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(scalloping_gain::<f64, Rectangular, 64>(0.5), core::f64::consts::FRAC_2_PI, epsilon = 1e-3);
+        assert_abs_diff_eq!(scalloping_gain::<f64, Hann, 64>(0.5), 0.8488, epsilon = 1e-3);
+
+        for f in [10.0, 10.2, 10.5, 12.8] {
+            // Unit tone, windowed before the transform
+            let mut data: [(f64, f64); 64] = core::array::from_fn(|i| {
+                let (s, c) = cordic::sin_cos(2.0 * PI * f * i as f64 / 64.0);
+                (c, s)
+            });
+            crate::EmbFft::<f64, 64, Hann>::windowed(&mut data).fft();
+            assert_abs_diff_eq!(peak_amplitude::<f64, Hann, 64>(&data, f), 1.0, epsilon = 1e-9);
+
+            let bin = refine_peak(&tone(f), (f + 0.5) as usize, Interpolation::Jacobsen).unwrap();
+            assert_abs_diff_eq!(peak_amplitude::<f64, Rectangular, 64>(&tone(f), bin), 1.0, epsilon = 1e-3);
+        }
+    }
+}
@@ -0,0 +1,733 @@
+/* embfft | kernel.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+/******************************************************************************/
+
+use core::marker::PhantomData;
+
+use crate::common::{Base, Float};
+use crate::status::Status;
+use crate::window::{Rectangular, Window};
+
+/******************************************************************************/
+
+/// Storage of the N complex values of a transform
+pub(crate) trait Buffer<T, const N: usize> {
+    /// Reads the value i
+    fn load(&self, i: usize) -> (T, T);
+    /// Writes the value i
+    fn save(&mut self, i: usize, x: (T, T));
+}
+
+impl<T: Copy, const N: usize> Buffer<T, N> for [(T, T); N] {
+    fn load(&self, i: usize) -> (T, T) {
+        self[i]
+    }
+
+    fn save(&mut self, i: usize, x: (T, T)) {
+        self[i] = x;
+    }
+}
+
+/// Real samples, packed by pairs into complex values: z[i] = x[2i] + jx[2i + 1], with R = 2N
+impl<T: Copy, const N: usize, const R: usize> Buffer<T, N> for [T; R] {
+    fn load(&self, i: usize) -> (T, T) {
+        (self[2 * i], self[2 * i + 1])
+    }
+
+    fn save(&mut self, i: usize, x: (T, T)) {
+        self[2 * i] = x.0;
+        self[2 * i + 1] = x.1;
+    }
+}
+
+/// Complex values of the `num-complex` crate
+#[cfg(feature = "num-complex")]
+impl<T: Copy, const N: usize> Buffer<T, N> for [num_complex::Complex<T>; N] {
+    fn load(&self, i: usize) -> (T, T) {
+        (self[i].re, self[i].im)
+    }
+
+    fn save(&mut self, i: usize, x: (T, T)) {
+        self[i] = num_complex::Complex::new(x.0, x.1);
+    }
+}
+
+/******************************************************************************/
+
+/// Transform kernel, shared by the forward and inverse transforms
+///
+/// The direction only changes the sign of the twiddle factor angles, so that both transforms are
+/// guaranteed to remain numerically consistent.
+/// Three data flows are supported:
+/// * decimation in frequency, natural order input, bit-reversed output, optionally followed by a
+///   reordering pass
+/// * decimation in time, reordering pass followed by bit-reversed input, natural order output
+/// * decimation in frequency, bit-reversed input, natural order output
+///
+/// The values are stored in a [`Buffer`], an array of complex values by default.
+/// For natural order input, the window and the centering are folded into the first stage of butterflies,
+/// which reads each input value exactly once, so that they do not require a separate pass.
+pub(crate) struct Kernel<'a, T, const N: usize, const INVERSE: bool, W = Rectangular, B = [(T, T); N]> {
+    data: &'a mut B,
+    values: PhantomData<T>,
+    window: PhantomData<W>,
+    table: Option<&'a [T; N]>,
+    state: State,
+    after_reorder: State,
+    ordered: bool,
+    scaled: bool,
+    natural_input: bool,
+    centered: bool,
+    status: Status,
+    stage: usize,
+    length: usize,
+    step: usize,
+    step_size: usize,
+    top_idx: usize,
+    bottom_idx: usize
+}
+
+/// Conversion state
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Step1,
+    Step2,
+    Step3,
+    Step4,
+    Step5,
+    Step6,
+    Reorder,
+    DitStep1,
+    DitStep2,
+    DitStep3,
+    DitStep4,
+    DitStep5,
+    DitStep6,
+    RnStep1,
+    RnStep2,
+    RnStep3,
+    RnStep4,
+    RnStep5,
+    RnStep6,
+    Done
+}
+
+impl<'a, T: Float<N>, const N: usize, const INVERSE: bool, W: Window<T, N>, B: Buffer<T, N>> Kernel<'a, T, N, INVERSE, W, B> {
+    /// Initializes a conversion from natural order input
+    ///
+    /// The output is reordered if `ordered` is set, and multiplied by 1 / N if `scaled` is set.
+    pub fn new(data: &'a mut B, ordered: bool, scaled: bool) -> Self {
+        assert!(Base::<N>::IS_N_POW2);
+        Self {
+            data,
+            values: PhantomData,
+            window: PhantomData,
+            table: None,
+            state: State::Step1,
+            after_reorder: State::Done,
+            ordered,
+            scaled,
+            natural_input: true,
+            centered: false,
+            status: Status::OK,
+            stage: 0,
+            length: N / 4,
+            step: 0,
+            step_size: 1,
+            top_idx: 0,
+            bottom_idx: 0
+        }
+    }
+
+    /// Initializes a decimation in time conversion from natural order input
+    ///
+    /// The output is in natural order, and multiplied by 1 / N if `scaled` is set. The window is ignored.
+    pub fn new_dit(data: &'a mut B, scaled: bool) -> Self {
+        Self {
+            state: State::Reorder,
+            after_reorder: State::DitStep1,
+            natural_input: false,
+            length: 1,
+            step_size: N / 4,
+            ..Self::new(data, true, scaled)
+        }
+    }
+
+    /// Initializes a conversion from bit-reversed input
+    ///
+    /// The output is in natural order, and multiplied by 1 / N if `scaled` is set. The window is ignored.
+    pub fn new_bit_reversed(data: &'a mut B, scaled: bool) -> Self {
+        Self {
+            state: State::RnStep1,
+            natural_input: false,
+            length: 1,
+            ..Self::new(data, true, scaled)
+        }
+    }
+
+    /// Enables or disables the multiplication by 1 / N
+    pub fn set_scaled(&mut self, scaled: bool) {
+        self.scaled = scaled;
+    }
+
+    /// Swaps the two halves of the input in the first stage, for natural order input
+    ///
+    /// The phases are then referenced to the center of the frame. Must be called before the first
+    /// iteration.
+    pub fn set_centered(&mut self) {
+        self.centered = true;
+    }
+
+    /// Uses a window table instead of the window `W`, for natural order input
+    ///
+    /// Must be called before the first iteration.
+    pub fn set_window(&mut self, table: &'a [T; N]) {
+        self.table = Some(table);
+    }
+
+    /// Multiply by the twiddle factor e^(∓j * 2π * step / N), with 0 < step < N / 4
+    fn rotate(x: (T, T), step: usize) -> (T, T) {
+        let (cos, sin) = (T::SINE_TABLE[N / 4 - step], T::SINE_TABLE[step]);
+        if INVERSE {
+            (T::difference_of_products(x.0, cos, x.1, sin), T::sum_of_products(x.1, cos, x.0, sin))
+        } else {
+            (T::sum_of_products(x.0, cos, x.1, sin), T::difference_of_products(x.1, cos, x.0, sin))
+        }
+    }
+
+    /// Multiply by the twiddle factor ∓j
+    fn rotate_quarter(x: (T, T)) -> (T, T) {
+        if INVERSE {
+            (-x.1, x.0)
+        } else {
+            (x.1, -x.0)
+        }
+    }
+
+    /// Stores a computed value, keeping track of abnormal values
+    fn store(&mut self, idx: usize, x: (T, T)) {
+        self.status |= x.0.status() | x.1.status();
+        self.data.save(idx, x);
+    }
+
+    /// Stores a value computed by a butterfly, after the scaling hook of the current stage
+    fn store_stage(&mut self, idx: usize, x: (T, T)) {
+        debug_assert!(self.stage < Base::<N>::LOG2_N);
+        self.store(idx, (x.0.scale_stage(self.stage), x.1.scale_stage(self.stage)));
+    }
+
+    /// Loads an input value, multiplied by its window coefficient
+    fn load_windowed(&mut self, idx: usize) -> (T, T) {
+        let x = self.data.load(idx);
+        let w = match self.table {
+            Some(table) => table[idx],
+            None if W::IS_RECTANGULAR => return x,
+            None => W::coefficient(idx)
+        };
+        let x = (x.0 * w, x.1 * w);
+        self.status |= x.0.status() | x.1.status();
+        x
+    }
+
+    fn butterfly(&mut self) -> (T, T) {
+        let (top, bottom) = if self.natural_input && self.stage == 0 {
+            // First stage: the bottom value is the top one shifted by N / 2, which is where the window
+            // is applied and the halves are swapped
+            let top = self.load_windowed(self.top_idx);
+            let bottom = self.load_windowed(self.bottom_idx);
+            if self.centered { (bottom, top) } else { (top, bottom) }
+        } else {
+            (self.data.load(self.top_idx), self.data.load(self.bottom_idx))
+        };
+        self.store_stage(self.top_idx, (top.0 + bottom.0, top.1 + bottom.1));
+        (top.0 - bottom.0, top.1 - bottom.1)
+    }
+
+    fn scaled_butterfly(&mut self) {
+        let top = self.data.load(self.top_idx);
+        let bottom = self.data.load(self.bottom_idx);
+        if self.scaled {
+            self.status |= Status::SCALED;
+            self.store_stage(self.top_idx, ((top.0 + bottom.0) * T::N_INV, (top.1 + bottom.1) * T::N_INV));
+            self.store_stage(self.bottom_idx, ((top.0 - bottom.0) * T::N_INV, (top.1 - bottom.1) * T::N_INV));
+        } else {
+            self.store_stage(self.top_idx, (top.0 + bottom.0, top.1 + bottom.1));
+            self.store_stage(self.bottom_idx, (top.0 - bottom.0, top.1 - bottom.1));
+        }
+    }
+
+    fn step1(&mut self) {
+        // Twiddle = 1
+        self.bottom_idx = self.top_idx + (self.length << 1);
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, temp);
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        self.step = self.step_size;
+        if self.step_size < N / 4 {
+            self.state = State::Step2;
+        } else {
+            self.state = State::Step3;
+        }
+    }
+
+    fn step2(&mut self) {
+        // Twiddle = e^(∓j * theta)
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, Self::rotate(temp, self.step));
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        if self.step < N / 4 - self.step_size {
+            self.step += self.step_size;
+        } else {
+            self.state = State::Step3;
+        }
+    }
+
+    fn step3(&mut self) {
+        // Twiddle = ∓j
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, Self::rotate_quarter(temp));
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        self.step = self.step_size;
+        if self.step_size < N / 4 {
+            self.state = State::Step4;
+        } else {
+            self.state = State::Step5;
+        }
+    }
+
+    fn step4(&mut self) {
+        // Twiddle = ∓j * e^(∓j * theta)
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, Self::rotate(Self::rotate_quarter(temp), self.step));
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        if self.step < N / 4 - self.step_size {
+            self.step += self.step_size;
+        } else {
+            self.state = State::Step5;
+        }
+    }
+
+    fn step5(&mut self) {
+        // Check if we need to loop
+        if self.bottom_idx < N {
+            self.top_idx = self.bottom_idx;
+            self.state = State::Step1;
+        } else if self.length > 1 {
+            self.length >>= 1;
+            self.step_size <<= 1;
+            self.stage += 1;
+            self.top_idx = 0;
+            self.state = State::Step1;
+        } else {
+            self.stage += 1;
+            self.top_idx = 0;
+            self.bottom_idx = 1;
+            self.state = State::Step6;
+        }
+    }
+
+    fn step6(&mut self) {
+        // Twiddle = 1, optionally scaled by 1 / N
+        self.scaled_butterfly();
+        if self.bottom_idx < N - 2 {
+            self.top_idx += 2;
+            self.bottom_idx += 2;
+        } else {
+            self.top_idx = 0;
+            self.bottom_idx = 0;
+            self.state = if self.ordered { State::Reorder } else { State::Done };
+        }
+    }
+
+    fn reorder(&mut self) {
+        // Ensure the output order is the same as the input
+        let top = self.data.load(self.top_idx);
+        let bottom = self.data.load(self.bottom_idx);
+        if self.bottom_idx > self.top_idx {
+            self.data.save(self.top_idx, bottom);
+            self.data.save(self.bottom_idx, top);
+        }
+        if self.top_idx < N - 1 {
+            self.bottom_idx = Base::<N>::reverse_bits(self.top_idx + 1);
+            self.top_idx += 1;
+        } else {
+            self.top_idx = 0;
+            self.bottom_idx = 1;
+            self.state = self.after_reorder;
+        }
+    }
+
+    fn dit_butterfly(&mut self, temp: (T, T)) {
+        let top = self.data.load(self.top_idx);
+        self.store_stage(self.top_idx, (top.0 + temp.0, top.1 + temp.1));
+        self.store_stage(self.bottom_idx, (top.0 - temp.0, top.1 - temp.1));
+    }
+
+    fn dit_step1(&mut self) {
+        // Twiddle = 1, optionally scaled by 1 / N
+        self.scaled_butterfly();
+        if self.bottom_idx < N - 2 {
+            self.top_idx += 2;
+            self.bottom_idx += 2;
+        } else {
+            self.stage += 1;
+            self.top_idx = 0;
+            self.state = State::DitStep2;
+        }
+    }
+
+    fn dit_step2(&mut self) {
+        // Twiddle = 1
+        self.bottom_idx = self.top_idx + (self.length << 1);
+        self.dit_butterfly(self.data.load(self.bottom_idx));
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        self.step = self.step_size;
+        if self.step_size < N / 4 {
+            self.state = State::DitStep3;
+        } else {
+            self.state = State::DitStep4;
+        }
+    }
+
+    fn dit_step3(&mut self) {
+        // Twiddle = e^(∓j * theta)
+        self.dit_butterfly(Self::rotate(self.data.load(self.bottom_idx), self.step));
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        if self.step < N / 4 - self.step_size {
+            self.step += self.step_size;
+        } else {
+            self.state = State::DitStep4;
+        }
+    }
+
+    fn dit_step4(&mut self) {
+        // Twiddle = ∓j
+        self.dit_butterfly(Self::rotate_quarter(self.data.load(self.bottom_idx)));
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        self.step = self.step_size;
+        if self.step_size < N / 4 {
+            self.state = State::DitStep5;
+        } else {
+            self.state = State::DitStep6;
+        }
+    }
+
+    fn dit_step5(&mut self) {
+        // Twiddle = ∓j * e^(∓j * theta)
+        self.dit_butterfly(Self::rotate(Self::rotate_quarter(self.data.load(self.bottom_idx)), self.step));
+        self.top_idx += 1;
+        self.bottom_idx += 1;
+        if self.step < N / 4 - self.step_size {
+            self.step += self.step_size;
+        } else {
+            self.state = State::DitStep6;
+        }
+    }
+
+    fn dit_step6(&mut self) {
+        // Check if we need to loop
+        if self.bottom_idx < N {
+            self.top_idx = self.bottom_idx;
+            self.state = State::DitStep2;
+        } else if self.step_size > 1 {
+            self.length <<= 1;
+            self.step_size >>= 1;
+            self.stage += 1;
+            self.top_idx = 0;
+            self.state = State::DitStep2;
+        } else {
+            self.state = State::Done;
+        }
+    }
+
+    fn rn_step1(&mut self) {
+        // Select the twiddle factor of the current block, or check if we need to loop
+        if self.top_idx >= N {
+            self.length <<= 1;
+            self.stage += 1;
+            self.top_idx = 0;
+        }
+        if self.length == N / 2 {
+            self.state = State::RnStep6;
+            return;
+        }
+        // The twiddle factor index is the bit-reversed block index
+        let step = Base::<N>::reverse_bits(self.top_idx / (self.length << 1)) >> 1;
+        if step == 0 {
+            self.state = State::RnStep2;
+        } else if step < N / 4 {
+            self.step = step;
+            self.state = State::RnStep3;
+        } else if step == N / 4 {
+            self.state = State::RnStep4;
+        } else {
+            self.step = step - N / 4;
+            self.state = State::RnStep5;
+        }
+    }
+
+    fn rn_next(&mut self) {
+        // Move to the next block once its top half is processed
+        self.top_idx += 1;
+        if self.top_idx % (self.length << 1) == self.length {
+            self.top_idx += self.length;
+            self.state = State::RnStep1;
+        }
+    }
+
+    fn rn_step2(&mut self) {
+        // Twiddle = 1
+        self.bottom_idx = self.top_idx + self.length;
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, temp);
+        self.rn_next();
+    }
+
+    fn rn_step3(&mut self) {
+        // Twiddle = e^(∓j * theta)
+        self.bottom_idx = self.top_idx + self.length;
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, Self::rotate(temp, self.step));
+        self.rn_next();
+    }
+
+    fn rn_step4(&mut self) {
+        // Twiddle = ∓j
+        self.bottom_idx = self.top_idx + self.length;
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, Self::rotate_quarter(temp));
+        self.rn_next();
+    }
+
+    fn rn_step5(&mut self) {
+        // Twiddle = ∓j * e^(∓j * theta)
+        self.bottom_idx = self.top_idx + self.length;
+        let temp = self.butterfly();
+        self.store_stage(self.bottom_idx, Self::rotate(Self::rotate_quarter(temp), self.step));
+        self.rn_next();
+    }
+
+    fn rn_step6(&mut self) {
+        // Twiddle = 1, optionally scaled by 1 / N
+        self.bottom_idx = self.top_idx + N / 2;
+        self.scaled_butterfly();
+        if self.top_idx < N / 2 - 1 {
+            self.top_idx += 1;
+        } else {
+            self.state = State::Done;
+        }
+    }
+
+    /// Performs a single iteration
+    pub fn iterate(&mut self) {
+        match self.state {
+            State::Step1 => { self.step1(); },
+            State::Step2 => { self.step2(); },
+            State::Step3 => { self.step3(); },
+            State::Step4 => { self.step4(); },
+            State::Step5 => { self.step5(); },
+            State::Step6 => { self.step6(); },
+            State::Reorder => { self.reorder(); },
+            State::DitStep1 => { self.dit_step1(); },
+            State::DitStep2 => { self.dit_step2(); },
+            State::DitStep3 => { self.dit_step3(); },
+            State::DitStep4 => { self.dit_step4(); },
+            State::DitStep5 => { self.dit_step5(); },
+            State::DitStep6 => { self.dit_step6(); },
+            State::RnStep1 => { self.rn_step1(); },
+            State::RnStep2 => { self.rn_step2(); },
+            State::RnStep3 => { self.rn_step3(); },
+            State::RnStep4 => { self.rn_step4(); },
+            State::RnStep5 => { self.rn_step5(); },
+            State::RnStep6 => { self.rn_step6(); },
+            State::Done => {}
+        }
+    }
+
+    /// Performs all the remaining iterations
+    pub fn run(&mut self) {
+        while self.state != State::Done {
+            self.iterate();
+        }
+    }
+
+    /// Checks if the conversion is complete
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// Checks if the output is in natural order
+    pub fn is_natural_order(&self) -> bool {
+        self.ordered
+    }
+
+    /// Returns the accumulated status flags
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Checks if the output is multiplied by 1 / N
+    pub fn is_scaled(&self) -> bool {
+        self.scaled
+    }
+
+    /// Returns the data buffer
+    pub fn data(&self) -> &B {
+        self.data
+    }
+
+    /// Returns the data buffer, for the passes performed after the transform
+    pub fn data_mut(&mut self) -> &mut B {
+        self.data
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    pub fn into_data(self) -> &'a mut B {
+        self.data
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn check_flows<const INVERSE: bool>() {
+        let input: [(f64, f64); 32] = core::array::from_fn(|i| (i as f64, (i % 5) as f64));
+
+        let mut dif = input;
+        Kernel::<_, 32, INVERSE>::new(&mut dif, true, INVERSE).run();
+
+        let mut dit = input;
+        Kernel::<_, 32, INVERSE>::new_dit(&mut dit, INVERSE).run();
+
+        let mut bit_reversed: [(f64, f64); 32] = core::array::from_fn(|i| input[Base::<32>::reverse_bits(i)]);
+        Kernel::<_, 32, INVERSE>::new_bit_reversed(&mut bit_reversed, INVERSE).run();
+
+        for ((x, y), z) in core::iter::zip(core::iter::zip(dif, dit), bit_reversed) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.0, z.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, z.1, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_flows() {
+        check_flows::<false>();
+        check_flows::<true>();
+    }
+
+    /// Block floating-point style number, halved after each stage
+    #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+    struct Halved(f64);
+
+    impl core::ops::Add for Halved {
+        type Output = Self;
+        fn add(self, rhs: Self) -> Self {
+            Self(self.0 + rhs.0)
+        }
+    }
+
+    impl core::ops::Sub for Halved {
+        type Output = Self;
+        fn sub(self, rhs: Self) -> Self {
+            Self(self.0 - rhs.0)
+        }
+    }
+
+    impl core::ops::Mul for Halved {
+        type Output = Self;
+        fn mul(self, rhs: Self) -> Self {
+            Self(self.0 * rhs.0)
+        }
+    }
+
+    impl core::ops::Div for Halved {
+        type Output = Self;
+        fn div(self, rhs: Self) -> Self {
+            Self(self.0 / rhs.0)
+        }
+    }
+
+    impl core::ops::Neg for Halved {
+        type Output = Self;
+        fn neg(self) -> Self {
+            Self(-self.0)
+        }
+    }
+
+    impl<const N: usize> Float<N> for Halved {
+        const ZERO: Self = Self(0.0);
+        const ONE: Self = Self(1.0);
+        const N_INV: Self = Self(1.0 / N as f64);
+        const SINE_TABLE: [Self; N] = {
+            let sine = <f64 as Float<N>>::SINE_TABLE;
+            let mut table = [Self(0.0); N];
+            let mut i = 0;
+            while i < N {
+                table[i] = Self(sine[i]);
+                i += 1;
+            }
+            table
+        };
+
+        fn from_usize(x: usize) -> Self {
+            Self(x as f64)
+        }
+
+        fn from_f64(x: f64) -> Self {
+            Self(x)
+        }
+
+        fn to_f64(self) -> f64 {
+            self.0
+        }
+
+        fn ulp(self) -> f64 {
+            Float::<N>::ulp(self.0)
+        }
+
+        fn status(self) -> Status {
+            Float::<N>::status(self.0)
+        }
+
+        fn scale_stage(self, _stage: usize) -> Self {
+            Self(self.0 * 0.5)
+        }
+    }
+
+    #[test]
+    fn test_scale_stage() {
+        // Halving the values after each of the log2(N) stages scales the output by 1 / N, in all the flows
+        let input: [(f64, f64); 32] = core::array::from_fn(|i| (i as f64, (i % 5) as f64));
+        let mut expected = input;
+        Kernel::<_, 32, false>::new(&mut expected, true, true).run();
+
+        let mut dif = input.map(|x| (Halved(x.0), Halved(x.1)));
+        Kernel::<_, 32, false>::new(&mut dif, true, false).run();
+        let mut dit = input.map(|x| (Halved(x.0), Halved(x.1)));
+        Kernel::<_, 32, false>::new_dit(&mut dit, false).run();
+        let mut bit_reversed = core::array::from_fn(|i| (Halved(input[Base::<32>::reverse_bits(i)].0), Halved(input[Base::<32>::reverse_bits(i)].1)));
+        Kernel::<_, 32, false>::new_bit_reversed(&mut bit_reversed, false).run();
+
+        for output in [dif, dit, bit_reversed] {
+            for (x, y) in expected.iter().zip(output) {
+                assert_abs_diff_eq!(x.0, y.0.0, epsilon = 1e-12);
+                assert_abs_diff_eq!(x.1, y.1.0, epsilon = 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+/* embfft | kurtosis.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Spectral kurtosis
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::framer::Framer;
+use crate::window::Window;
+use crate::EmbFft;
+
+/******************************************************************************/
+
+/// Per-bin spectral kurtosis across frames
+///
+/// Detects the transient or impulsive content of each frequency band, such as bearing impacts or arcing,
+/// which averaged power spectra hide. The unbiased estimator is 0 for Gaussian noise, -1 for a stationary
+/// tone, and positive for impulsive signals. The frames are typically overlapping and windowed, as
+/// produced by a [`Framer`].
+///
+/// For example:
+/// ```
+/// use embfft::framer::Framer;
+/// use embfft::kurtosis::SpectralKurtosis;
+/// use embfft::window::Hann;
+///
+/// let mut framer = Framer::<f32, 16, Hann>::windowed(8).unwrap();
+/// let mut sk = SpectralKurtosis::new();
+/// for i in 0..256 {
+///     let phase = 2.0 * core::f32::consts::PI * 4.0 * i as f32 / 16.0;
+///     framer.push(phase.cos());
+///     sk.feed(&mut framer).unwrap();
+/// }
+///
+/// let mut kurtosis = [0.0; 16];
+/// sk.kurtosis(&mut kurtosis).unwrap();
+/// assert!((kurtosis[4] + 1.0).abs() < 1e-3);
+/// ```
+pub struct SpectralKurtosis<T, const N: usize> {
+    s2: [T; N],
+    s4: [T; N],
+    count: usize
+}
+
+impl<T: Float<N>, const N: usize> SpectralKurtosis<T, N> {
+    /// Initializes an empty accumulator
+    pub fn new() -> Self {
+        Self { s2: [T::ZERO; N], s4: [T::ZERO; N], count: 0 }
+    }
+
+    /// Discards all the accumulated frames
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Accumulates the spectrum of a new frame
+    pub fn add(&mut self, data: &[(T, T); N]) {
+        for ((s2, s4), x) in self.s2.iter_mut().zip(self.s4.iter_mut()).zip(data) {
+            let power = x.0 * x.0 + x.1 * x.1;
+            *s2 = *s2 + power;
+            *s4 = *s4 + power * power;
+        }
+        self.count += 1;
+    }
+
+    /// Reads the next frame of the framer if it is available, and accumulates its spectrum
+    ///
+    /// Returns `true` if a frame was accumulated. This is a blocking function, which performs an FFT.
+    /// Fails with [`Error::Overrun`] if samples were lost before the frame, which is still accumulated.
+    pub fn feed<W: Window<T, N>>(&mut self, framer: &mut Framer<T, N, W>) -> Result<bool, Error> {
+        let mut data = [(T::ZERO, T::ZERO); N];
+        let result = framer.read(&mut data);
+        if result == Err(Error::NotDone) {
+            return Ok(false);
+        }
+        EmbFft::new(&mut data).fft();
+        self.add(&data);
+        result.map(|_| true)
+    }
+
+    /// Returns the number of accumulated frames
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Computes the spectral kurtosis of each bin
+    ///
+    /// The bins without any power are set to 0.
+    /// Fails with [`Error::NotDone`] if less than two frames were accumulated.
+    pub fn kurtosis(&self, output: &mut [T; N]) -> Result<(), Error> {
+        if self.count < 2 {
+            return Err(Error::NotDone);
+        }
+        // M / (M - 1) * ((M + 1) * S4 / S2² - 2), computed in double precision since the factors exceed 1
+        let m = self.count as f64;
+        for (y, (&s2, &s4)) in output.iter_mut().zip(core::iter::zip(&self.s2, &self.s4)) {
+            let (s2, s4) = (s2.to_f64(), s4.to_f64());
+            *y = if s2 > 0.0 {
+                T::from_f64(m / (m - 1.0) * ((m + 1.0) * s4 / (s2 * s2) - 2.0))
+            } else {
+                T::ZERO
+            };
+        }
+        Ok(())
+    }
+}
+
+impl<T: Float<N>, const N: usize> Default for SpectralKurtosis<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_kurtosis() {
+        let mut sk = SpectralKurtosis::<f64, 4>::default();
+        let mut kurtosis = [0.0; 4];
+        sk.add(&[(1.0, 0.0), (0.0, 2.0), (0.0, 0.0), (0.0, 0.0)]);
+        assert_eq!(sk.kurtosis(&mut kurtosis), Err(Error::NotDone));
+
+        // Stationary tones in bins 0 and 1, single impulse in bin 2, nothing in bin 3
+        for i in 1..8 {
+            sk.add(&[(1.0, 0.0), (0.0, 2.0), (if i == 5 { 3.0 } else { 0.0 }, 0.0), (0.0, 0.0)]);
+        }
+        assert_eq!(sk.count(), 8);
+        sk.kurtosis(&mut kurtosis).unwrap();
+        assert_abs_diff_eq!(kurtosis[0], -1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(kurtosis[1], -1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(kurtosis[2], 8.0, epsilon = 1e-12);
+        assert_eq!(kurtosis[3], 0.0);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        use crate::fixed_point::Q15;
+
+        // The frame count and the factors of the estimator cannot be represented
+        let mut sk = SpectralKurtosis::<Q15, 4>::new();
+        let (x, zero) = (Q15::from_f64(0.25), Q15::from_f64(0.0));
+        for _ in 0..4 {
+            sk.add(&[(x, zero), (zero, -x), (zero, zero), (zero, zero)]);
+        }
+        let mut kurtosis = [x; 4];
+        sk.kurtosis(&mut kurtosis).unwrap();
+        assert_eq!(kurtosis[0], Q15::from_f64(-1.0));
+        assert_eq!(kurtosis[1], Q15::from_f64(-1.0));
+        assert_eq!(kurtosis[2], zero);
+    }
+
+    #[test]
+    fn test_feed() {
+        let mut framer = Framer::<f64, 8>::new(8).unwrap();
+        let mut sk = SpectralKurtosis::new();
+        assert_eq!(sk.feed(&mut framer), Ok(false));
+        for _ in 0..17 {
+            framer.push(1.0);
+        }
+        assert_eq!(sk.feed(&mut framer), Err(Error::Overrun));
+        assert_eq!(sk.count(), 1);
+    }
+}
@@ -0,0 +1,192 @@
+/* embfft | latency.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Acquisition-to-spectrum latency accounting
+//!
+//! Closed-loop uses, such as active noise control, need to know how long a spectrum takes from the
+//! acquisition of its samples to the end of its post-processing. A [`LatencyMeter`] reads a [`Clock`],
+//! typically a cycle counter, at the milestones of each frame, and reports the components of the latency
+//! to an [`Instrumentation`] sink.
+//! For example:
+//! ```
+//! use embfft::framer::Framer;
+//! use embfft::latency::{Latency, LatencyMeter};
+//!
+//! // Simulated cycle counter
+//! let mut cycles = 0;
+//! let mut meter = LatencyMeter::new(move || { cycles += 100; cycles });
+//! let mut framer = Framer::<f32, 8>::new(8).unwrap();
+//! let mut data = [(0.0, 0.0); 8];
+//!
+//! meter.frame_started();
+//! framer.extend(&[1.0; 8]);
+//! meter.frame_ready();
+//! framer.read(&mut data).unwrap();
+//! meter.fft(&mut embfft::EmbFft::new(&mut data));
+//! // Post-processing...
+//! let latency = meter.finish(&mut |latency: &Latency| assert!(latency.iterations > 0)).unwrap();
+//! assert_eq!(latency.total(), 300);
+//! ```
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Source of time, in user ticks, such as a cycle counter or a free-running timer
+pub trait Clock {
+    /// Returns the current time
+    fn now(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> Clock for F {
+    fn now(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// Components of the latency of a frame, in clock ticks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Latency {
+    /// Time taken to acquire the samples of the frame
+    pub fill: u64,
+    /// Time from the end of the acquisition to the end of the transform, waiting included
+    pub transform: u64,
+    /// Number of transform iterations consumed
+    pub iterations: usize,
+    /// Time taken by the post-processing of the spectrum
+    pub post_processing: u64
+}
+
+impl Latency {
+    /// Returns the end-to-end latency, from the start of the acquisition to the end of the post-processing
+    pub fn total(&self) -> u64 {
+        self.fill + self.transform + self.post_processing
+    }
+}
+
+/// Receiver of the latency measurements, e.g. a logger or a budget checker
+pub trait Instrumentation {
+    /// Called once per frame, when its post-processing is complete
+    fn latency(&mut self, latency: &Latency);
+}
+
+impl<F: FnMut(&Latency)> Instrumentation for F {
+    fn latency(&mut self, latency: &Latency) {
+        self(latency)
+    }
+}
+
+/// Latency meter, marking the milestones of each frame
+pub struct LatencyMeter<C> {
+    clock: C,
+    started: Option<u64>,
+    ready: Option<u64>,
+    transformed: Option<u64>,
+    iterations: usize
+}
+
+impl<C: Clock> LatencyMeter<C> {
+    /// Initializes a meter reading the given clock
+    pub fn new(clock: C) -> Self {
+        Self { clock, started: None, ready: None, transformed: None, iterations: 0 }
+    }
+
+    /// Marks the arrival of the first sample of a frame
+    ///
+    /// With overlapping frames, this is the first sample after the previous frame was read.
+    pub fn frame_started(&mut self) {
+        self.started = Some(self.clock.now());
+        self.ready = None;
+        self.transformed = None;
+        self.iterations = 0;
+    }
+
+    /// Marks the arrival of the last sample of the frame
+    pub fn frame_ready(&mut self) {
+        self.ready = Some(self.clock.now());
+    }
+
+    /// Counts a transform iteration, for the non-blocking transforms
+    pub fn iteration(&mut self) {
+        self.iterations += 1;
+    }
+
+    /// Marks the end of the transform
+    pub fn transform_done(&mut self) {
+        self.transformed = Some(self.clock.now());
+    }
+
+    /// Runs a complete FFT, counting its iterations, and marks its end
+    pub fn fft<T: Float<N>, const N: usize, W: Window<T, N>>(&mut self, fft: &mut EmbFft<T, N, W>) {
+        while !fft.is_done() {
+            fft.fft_iterate();
+            self.iteration();
+        }
+        self.transform_done();
+    }
+
+    /// Marks the end of the post-processing, and reports the latency of the frame
+    ///
+    /// Fails with [`Error::NotDone`] if a milestone of the frame was not marked.
+    pub fn finish<I: Instrumentation + ?Sized>(&mut self, sink: &mut I) -> Result<Latency, Error> {
+        let (Some(started), Some(ready), Some(transformed)) = (self.started, self.ready, self.transformed) else {
+            return Err(Error::NotDone);
+        };
+        let now = self.clock.now();
+        let latency = Latency {
+            fill: ready.wrapping_sub(started),
+            transform: transformed.wrapping_sub(ready),
+            iterations: self.iterations,
+            post_processing: now.wrapping_sub(transformed)
+        };
+        self.started = None;
+        sink.latency(&latency);
+        Ok(latency)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter() {
+        let mut time = 0;
+        let mut meter = LatencyMeter::new(move || {
+            time += 7;
+            time
+        });
+        let mut reported = None;
+        assert_eq!(meter.finish(&mut |latency: &Latency| reported = Some(*latency)), Err(Error::NotDone));
+        assert_eq!(reported, None);
+
+        let mut data = [(1.0f32, 0.0); 16];
+        let mut reference = EmbFft::new(&mut data);
+        let mut iterations = 0;
+        while !reference.is_done() {
+            reference.fft_iterate();
+            iterations += 1;
+        }
+
+        meter.frame_started();
+        meter.frame_ready();
+        meter.fft(&mut EmbFft::new(&mut data));
+        let latency = meter.finish(&mut |latency: &Latency| reported = Some(*latency)).unwrap();
+        assert_eq!(latency, Latency { fill: 7, transform: 7, iterations, post_processing: 7 });
+        assert_eq!(latency.total(), 21);
+        assert_eq!(reported, Some(latency));
+
+        // Each frame must be started again
+        assert_eq!(meter.finish(&mut |_: &Latency| {}), Err(Error::NotDone));
+    }
+}
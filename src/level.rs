@@ -0,0 +1,486 @@
+/* embfft | level.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Sound and vibration level logging
+//!
+//! Environmental monitoring reports the equivalent continuous level (Leq) of a signal over long periods,
+//! in total and in bands such as octaves, usually with a frequency weighting. A [`LevelLogger`] accumulates
+//! the power of successive windowed spectra, as in Welch's method, and converts it to decibels.
+//! Consumer audio normalizes the loudness instead, which a [`LoudnessMeter`] estimates with the K-weighting
+//! and the gating of ITU-R BS.1770.
+//! For example:
+//! ```
+//! use embfft::level::{Averaging, LevelLogger, Weighting};
+//! use embfft::units::{Hertz, SampleRate};
+//! use embfft::window::Hann;
+//!
+//! let fs = SampleRate::new(8000.0).unwrap();
+//! let octaves = [(Hertz(707.0), Hertz(1414.0)), (Hertz(1414.0), Hertz(2828.0))];
+//! let mut logger = LevelLogger::new(octaves, fs, Weighting::A, Averaging::Linear).unwrap().with_reference(1.0);
+//! logger.start();
+//!
+//! // 1 kHz tone of RMS amplitude 1, i.e. 0 dB, slightly spread by the window over weighted neighbours
+//! let mut data: [(f32, f32); 64] = core::array::from_fn(|i| {
+//!     let phase = 2.0 * core::f32::consts::PI * 1000.0 * i as f32 / 8000.0;
+//!     (core::f32::consts::SQRT_2 * phase.cos(), 0.0)
+//! });
+//! embfft::EmbFft::<f32, 64, Hann>::windowed(&mut data).fft();
+//! logger.add::<f32, Hann, 64>(&data);
+//! assert!(logger.level().unwrap().abs() < 0.1);
+//! assert!(logger.band_level(0).unwrap().abs() < 0.1);
+//! ```
+
+/******************************************************************************/
+
+use crate::band::{bin_power, bin_range, power_scale};
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+use crate::units::{Hertz, SampleRate};
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Frequency weighting of the levels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Weighting {
+    /// No weighting
+    Z,
+    /// A-weighting of IEC 61672-1, which follows the sensitivity of the ear at low levels
+    A,
+    /// K-weighting of ITU-R BS.1770, the high-shelf and high-pass filters of the loudness measurements
+    K
+}
+
+/// Returns the squared magnitude of a biquad, whose coefficients are given as [b0, b1, b2, a1, a2]
+fn biquad_gain(coefficients: [f64; 5], sin_cos: (f64, f64)) -> f64 {
+    let [b0, b1, b2, a1, a2] = coefficients;
+    let (s, c) = sin_cos;
+    // e^-jω = c - js, e^-2jω = (c² - s²) - 2jcs
+    let (c2, s2) = (c * c - s * s, 2.0 * c * s);
+    let numerator = (b0 + b1 * c + b2 * c2, -b1 * s - b2 * s2);
+    let denominator = (1.0 + a1 * c + a2 * c2, -a1 * s - a2 * s2);
+    (numerator.0 * numerator.0 + numerator.1 * numerator.1) / (denominator.0 * denominator.0 + denominator.1 * denominator.1)
+}
+
+impl Weighting {
+    /// Returns the power gain of the weighting at a frequency
+    pub fn gain(self, frequency: Hertz) -> f64 {
+        match self {
+            Weighting::Z => 1.0,
+            Weighting::A => {
+                let f2 = frequency.0 * frequency.0;
+                let (p1, p2, p3, p4) = (20.598997 * 20.598997, 107.65265 * 107.65265, 737.86223 * 737.86223, 12194.217 * 12194.217);
+                // |R_A(f)|², normalized to 0 dB at 1 kHz
+                let r = p4 * p4 * f2 * f2 * f2 * f2 / ((f2 + p1) * (f2 + p1) * (f2 + p2) * (f2 + p3) * (f2 + p4) * (f2 + p4));
+                1.5848931924611136 * r
+            },
+            Weighting::K => {
+                // Filters specified at 48 kHz, the response above 24 kHz is that of the 24 kHz bin
+                const SHELF: [f64; 5] = [1.53512485958697, -2.69169618940638, 1.19839281085285, -1.69065929318241, 0.73248077421585];
+                const HIGH_PASS: [f64; 5] = [1.0, -2.0, 1.0, -1.99004745483398, 0.99007225036621];
+                let f = if frequency.0 < 24000.0 { frequency.0 } else { 24000.0 };
+                let sin_cos = math::sin_cos(2.0 * core::f64::consts::PI * f / 48000.0);
+                biquad_gain(SHELF, sin_cos) * biquad_gain(HIGH_PASS, sin_cos)
+            }
+        }
+    }
+}
+
+/// Averaging of the successive spectra
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Averaging {
+    /// Mean power since the start of the measurement, i.e. the equivalent continuous level
+    Linear,
+    /// Exponential averaging, with the given weight of each new spectrum, between 0 and 1
+    Exponential(f64)
+}
+
+impl Averaging {
+    /// Exponential averaging with a time constant, e.g. 0.125 s for the Fast or 1 s for the Slow levels
+    ///
+    /// `hop` is the time between two successive spectra.
+    /// Fails with [`Error::InvalidParameter`] if either duration is not positive.
+    pub fn time_constant(time_constant: f64, hop: f64) -> Result<Self, Error> {
+        if time_constant.is_nan() || time_constant <= 0.0 || hop.is_nan() || hop <= 0.0 {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Averaging::Exponential(1.0 - math::exp(-hop / time_constant)))
+    }
+}
+
+/// Logger of the total level and of the levels in B bands
+///
+/// The spectra are those of real signals, in natural order, computed with the window `W`, like those of
+/// the [`band`](crate::band) functions. The levels are in decibels relative to a reference power, 1 by
+/// default, e.g. (20 µPa)² for sound pressure levels.
+pub struct LevelLogger<const B: usize> {
+    bands: [(Hertz, Hertz); B],
+    sample_rate: SampleRate,
+    weighting: Weighting,
+    averaging: Averaging,
+    reference: f64,
+    running: bool,
+    frames: usize,
+    power: f64,
+    band_power: [f64; B],
+    max: f64
+}
+
+impl<const B: usize> LevelLogger<B> {
+    /// Initializes a stopped logger
+    ///
+    /// Fails with [`Error::InvalidParameter`] if a band is not between 0 and the Nyquist frequency, or if
+    /// the exponential averaging weight is not between 0 and 1.
+    pub fn new(bands: [(Hertz, Hertz); B], sample_rate: SampleRate, weighting: Weighting, averaging: Averaging) -> Result<Self, Error> {
+        if bands.iter().any(|(lo, hi)| lo.0.is_nan() || *lo < Hertz(0.0) || lo > hi || *hi > sample_rate.nyquist()) {
+            return Err(Error::InvalidParameter);
+        }
+        if let Averaging::Exponential(alpha) = averaging {
+            if alpha.is_nan() || alpha <= 0.0 || alpha > 1.0 {
+                return Err(Error::InvalidParameter);
+            }
+        }
+        Ok(Self {
+            bands,
+            sample_rate,
+            weighting,
+            averaging,
+            reference: 1.0,
+            running: false,
+            frames: 0,
+            power: 0.0,
+            band_power: [0.0; B],
+            max: 0.0
+        })
+    }
+
+    /// Sets the reference power of the levels
+    pub fn with_reference(mut self, reference: f64) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    /// Starts or resumes the measurement
+    pub fn start(&mut self) {
+        self.running = true;
+    }
+
+    /// Pauses the measurement, the spectra added until the next start are ignored
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    /// Checks if the measurement is running
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Clears the measurement, without stopping it
+    pub fn reset(&mut self) {
+        self.frames = 0;
+        self.power = 0.0;
+        self.band_power = [0.0; B];
+        self.max = 0.0;
+    }
+
+    /// Returns the number of spectra accumulated since the last reset
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Accumulates a spectrum, if the measurement is running
+    pub fn add<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N]) {
+        if !self.running {
+            return;
+        }
+        let scale = power_scale::<T, W, N>();
+        let mut power = 0.0;
+        let mut band_power = [0.0; B];
+        for k in 0..=N / 2 {
+            let (a, b) = bin_range::<N>(k);
+            let p = bin_power(spectrum, k, scale) * self.weighting.gain(self.sample_rate.bin_frequency::<N>(k as f64));
+            power += p;
+            for (sum, (lo, hi)) in band_power.iter_mut().zip(self.bands.iter()) {
+                let (lo, hi) = (self.sample_rate.to_bin::<N>(*lo), self.sample_rate.to_bin::<N>(*hi));
+                let overlap = (if b < hi { b } else { hi }) - (if a > lo { a } else { lo });
+                if overlap > 0.0 {
+                    *sum += p * overlap / (b - a);
+                }
+            }
+        }
+
+        self.frames += 1;
+        let alpha = match self.averaging {
+            Averaging::Linear => 1.0 / self.frames as f64,
+            Averaging::Exponential(alpha) => if self.frames == 1 { 1.0 } else { alpha }
+        };
+        self.power += alpha * (power - self.power);
+        for (average, p) in self.band_power.iter_mut().zip(band_power.iter()) {
+            *average += alpha * (p - *average);
+        }
+        if self.power > self.max {
+            self.max = self.power;
+        }
+    }
+
+    /// Returns the averaged total level, in decibels
+    ///
+    /// Fails with [`Error::NotDone`] if no spectrum was accumulated.
+    pub fn level(&self) -> Result<f64, Error> {
+        self.to_level(self.power)
+    }
+
+    /// Returns the highest averaged total level since the last reset, in decibels
+    ///
+    /// Fails with [`Error::NotDone`] if no spectrum was accumulated.
+    pub fn max_level(&self) -> Result<f64, Error> {
+        self.to_level(self.max)
+    }
+
+    /// Returns the averaged level of band b, in decibels
+    ///
+    /// Fails with [`Error::OutOfRange`] if b is not a valid band, and with [`Error::NotDone`] if no
+    /// spectrum was accumulated.
+    pub fn band_level(&self, b: usize) -> Result<f64, Error> {
+        let power = *self.band_power.get(b).ok_or(Error::OutOfRange)?;
+        self.to_level(power)
+    }
+
+    /// Returns the averaged levels of all the bands, in decibels
+    ///
+    /// Fails with [`Error::NotDone`] if no spectrum was accumulated.
+    pub fn band_levels(&self, output: &mut [f64; B]) -> Result<(), Error> {
+        for (level, power) in output.iter_mut().zip(self.band_power.iter()) {
+            *level = self.to_level(*power)?;
+        }
+        Ok(())
+    }
+
+    fn to_level(&self, power: f64) -> Result<f64, Error> {
+        if self.frames == 0 {
+            return Err(Error::NotDone);
+        }
+        Ok(math::power_to_db(power / self.reference))
+    }
+}
+
+/******************************************************************************/
+
+/// Lowest block loudness kept by the absolute gate, in LUFS
+const ABSOLUTE_GATE: f64 = -70.0;
+/// Highest block loudness of the histogram, in LUFS
+const HISTOGRAM_TOP: f64 = 5.0;
+
+/// Gated loudness estimation, in LUFS
+///
+/// Each spectrum is a block of the measurement, which should last 400 ms, with successive blocks
+/// overlapping by 75 %. The integrated loudness follows BS.1770: the blocks quieter than -70 LUFS are
+/// discarded, then those 10 LU quieter than the loudness of the remaining ones. To avoid storing the
+/// blocks, they are sorted into a histogram of H bins between -70 and +5 LUFS, so that the relative gate
+/// is accurate to 75 / H LU.
+/// For example:
+/// ```
+/// use embfft::level::LoudnessMeter;
+/// use embfft::units::SampleRate;
+///
+/// let mut meter = LoudnessMeter::<150>::new(SampleRate::new(48000.0).unwrap()).unwrap();
+/// let mut data: [(f32, f32); 64] = core::array::from_fn(|i| {
+///     let phase = 2.0 * core::f32::consts::PI * 3000.0 * i as f32 / 48000.0;
+///     (0.1 * phase.cos(), 0.0)
+/// });
+/// embfft::EmbFft::new(&mut data).fft();
+/// meter.add::<f32, embfft::window::Rectangular, 64>(&data);
+/// // -23 dB tone, raised by the K-weighting at 3 kHz
+/// # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+/// assert!((meter.integrated().unwrap() + 19.9).abs() < 0.1);
+/// ```
+pub struct LoudnessMeter<const H: usize> {
+    sample_rate: SampleRate,
+    histogram: [(u32, f64); H],
+    momentary: Option<f64>
+}
+
+impl<const H: usize> LoudnessMeter<H> {
+    /// Initializes an empty meter
+    ///
+    /// Fails with [`Error::InvalidLength`] if the histogram has no bins.
+    pub fn new(sample_rate: SampleRate) -> Result<Self, Error> {
+        if H == 0 {
+            return Err(Error::InvalidLength);
+        }
+        Ok(Self { sample_rate, histogram: [(0, 0.0); H], momentary: None })
+    }
+
+    /// Clears the measurement
+    pub fn reset(&mut self) {
+        self.histogram = [(0, 0.0); H];
+        self.momentary = None;
+    }
+
+    /// Adds a block, given as the spectrum of a real signal computed with the window `W`
+    pub fn add<T: Float<N>, W: Window<T, N>, const N: usize>(&mut self, spectrum: &[(T, T); N]) {
+        let scale = power_scale::<T, W, N>();
+        let mut power = 0.0;
+        for k in 0..=N / 2 {
+            power += bin_power(spectrum, k, scale) * Weighting::K.gain(self.sample_rate.bin_frequency::<N>(k as f64));
+        }
+        let loudness = Self::loudness(power);
+        self.momentary = Some(loudness);
+        if loudness > ABSOLUTE_GATE {
+            let bin = ((loudness - ABSOLUTE_GATE) / (HISTOGRAM_TOP - ABSOLUTE_GATE) * H as f64) as usize;
+            let bin = &mut self.histogram[if bin < H { bin } else { H - 1 }];
+            bin.0 += 1;
+            bin.1 += power;
+        }
+    }
+
+    /// Returns the loudness of the last block
+    ///
+    /// Fails with [`Error::NotDone`] if no block was added.
+    pub fn momentary(&self) -> Result<f64, Error> {
+        self.momentary.ok_or(Error::NotDone)
+    }
+
+    /// Returns the gated loudness of all the blocks
+    ///
+    /// Fails with [`Error::NotDone`] if no block is louder than the absolute gate.
+    pub fn integrated(&self) -> Result<f64, Error> {
+        let ungated = Self::loudness(self.mean_power(0).ok_or(Error::NotDone)?);
+        let threshold = ungated - 10.0;
+        // The bin that contains the threshold is kept if its center is above it
+        let first = (threshold - ABSOLUTE_GATE) / (HISTOGRAM_TOP - ABSOLUTE_GATE) * H as f64 + 0.5;
+        let first = if first > 0.0 { first as usize } else { 0 };
+        Ok(Self::loudness(self.mean_power(first).ok_or(Error::NotDone)?))
+    }
+
+    fn mean_power(&self, first: usize) -> Option<f64> {
+        let (count, sum) = self.histogram.iter().skip(first).fold((0, 0.0), |(n, s), (count, sum)| (n + count, s + sum));
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    fn loudness(power: f64) -> f64 {
+        -0.691 + math::power_to_db(power)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmbFft;
+    use crate::cordic;
+    use crate::window::{Hann, Rectangular};
+    use approx::assert_abs_diff_eq;
+
+    fn tone(frequency: f64, amplitude: f64) -> [(f64, f64); 64] {
+        let mut data = core::array::from_fn(|i| (amplitude * cordic::cos(2.0 * core::f64::consts::PI * frequency * i as f64 / 8000.0), 0.0));
+        EmbFft::<f64, 64, Hann>::windowed(&mut data).fft();
+        data
+    }
+
+    #[test]
+    fn test_weighting() {
+        let db = |f: f64| 10.0 * Weighting::A.gain(Hertz(f)).log10();
+        assert_abs_diff_eq!(db(1000.0), 0.0, epsilon = 0.01);
+        assert_abs_diff_eq!(db(125.0), -16.1, epsilon = 0.1);
+        assert_abs_diff_eq!(db(10000.0), -2.5, epsilon = 0.05);
+        assert_eq!(Weighting::A.gain(Hertz(0.0)), 0.0);
+        assert_eq!(Weighting::Z.gain(Hertz(125.0)), 1.0);
+
+        // The K-weighting compensates the -0.691 dB offset of the loudness at 997 Hz
+        let tolerance = if cfg!(all(feature = "micromath", not(feature = "libm"))) { 0.05 } else { 0.01 };
+        let db = |f: f64| 10.0 * Weighting::K.gain(Hertz(f)).log10();
+        assert_abs_diff_eq!(db(997.0), 0.691, epsilon = tolerance);
+        assert_abs_diff_eq!(db(10000.0), 4.0, epsilon = 0.1);
+        assert!(db(20.0) < -10.0);
+        assert_eq!(Weighting::K.gain(Hertz(30000.0)), Weighting::K.gain(Hertz(24000.0)));
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_loudness() {
+        let block = |amplitude: f64| {
+            let mut data: [(f64, f64); 64] = core::array::from_fn(|i| (amplitude * cordic::cos(2.0 * core::f64::consts::PI * 3000.0 * i as f64 / 48000.0), 0.0));
+            EmbFft::new(&mut data).fft();
+            data
+        };
+        assert!(LoudnessMeter::<0>::new(SampleRate::new(48000.0).unwrap()).is_err());
+        let mut meter = LoudnessMeter::<750>::new(SampleRate::new(48000.0).unwrap()).unwrap();
+        assert_eq!(meter.momentary(), Err(Error::NotDone));
+
+        // Silence is below the absolute gate
+        meter.add::<f64, Rectangular, 64>(&[(0.0, 0.0); 64]);
+        assert_eq!(meter.momentary(), Ok(f64::NEG_INFINITY));
+        assert_eq!(meter.integrated(), Err(Error::NotDone));
+
+        // The quiet blocks are below the relative gate
+        let loud = -0.691 + 10.0 * (0.5 * Weighting::K.gain(Hertz(3000.0))).log10();
+        for _ in 0..10 {
+            meter.add::<f64, Rectangular, 64>(&block(1.0));
+            meter.add::<f64, Rectangular, 64>(&block(0.01));
+        }
+        assert_abs_diff_eq!(meter.momentary().unwrap(), loud - 40.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(meter.integrated().unwrap(), loud, epsilon = 1e-9);
+
+        // Blocks 6 dB quieter still count
+        for _ in 0..10 {
+            meter.add::<f64, Rectangular, 64>(&block(0.5));
+        }
+        assert_abs_diff_eq!(meter.integrated().unwrap(), loud + 10.0 * (1.25f64 / 2.0).log10(), epsilon = 1e-9);
+
+        meter.reset();
+        assert_eq!(meter.integrated(), Err(Error::NotDone));
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_logger() {
+        let fs = SampleRate::new(8000.0).unwrap();
+        let bands = [(Hertz(0.0), Hertz(1500.0)), (Hertz(1500.0), Hertz(4000.0))];
+        assert!(LevelLogger::new([(Hertz(0.0), Hertz(5000.0))], fs, Weighting::Z, Averaging::Linear).is_err());
+        assert!(LevelLogger::new(bands, fs, Weighting::Z, Averaging::Exponential(0.0)).is_err());
+        assert!(Averaging::time_constant(0.0, 0.008).is_err());
+
+        let mut logger = LevelLogger::new(bands, fs, Weighting::Z, Averaging::Linear).unwrap();
+        assert_eq!(logger.level(), Err(Error::NotDone));
+        logger.add::<f64, Hann, 64>(&tone(1000.0, 1.0));
+        assert_eq!(logger.frames(), 0);
+
+        // Tones of powers 1 / 2 and 2, i.e. a mean power of 5 / 4 in the first band
+        logger.start();
+        logger.add::<f64, Hann, 64>(&tone(1000.0, 1.0));
+        logger.add::<f64, Hann, 64>(&tone(1000.0, 2.0));
+        logger.stop();
+        logger.add::<f64, Hann, 64>(&tone(3000.0, 8.0));
+        assert_eq!(logger.frames(), 2);
+        assert_abs_diff_eq!(logger.level().unwrap(), 10.0 * 1.25f64.log10(), epsilon = 1e-6);
+        assert_abs_diff_eq!(logger.max_level().unwrap(), 10.0 * 1.25f64.log10(), epsilon = 1e-6);
+        let mut levels = [0.0; 2];
+        logger.band_levels(&mut levels).unwrap();
+        assert_abs_diff_eq!(levels[0], 10.0 * 1.25f64.log10(), epsilon = 1e-6);
+        assert!(levels[1] < -100.0);
+        assert_eq!(logger.band_level(2), Err(Error::OutOfRange));
+
+        // The exponential average forgets the first spectrum
+        let averaging = Averaging::time_constant(0.008, 0.008).unwrap();
+        let mut logger = LevelLogger::new(bands, fs, Weighting::Z, averaging).unwrap().with_reference(0.5);
+        logger.start();
+        logger.add::<f64, Hann, 64>(&tone(3000.0, 2.0));
+        for _ in 0..50 {
+            logger.add::<f64, Hann, 64>(&tone(3000.0, 1.0));
+        }
+        assert_abs_diff_eq!(logger.level().unwrap(), 0.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(logger.max_level().unwrap(), 10.0 * 4.0f64.log10(), epsilon = 1e-6);
+        assert_abs_diff_eq!(logger.band_level(1).unwrap(), 0.0, epsilon = 1e-6);
+
+        logger.reset();
+        assert!(logger.is_running());
+        assert_eq!(logger.max_level(), Err(Error::NotDone));
+    }
+}
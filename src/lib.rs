@@ -9,15 +9,87 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 
+#[cfg(all(feature = "bit-exact", target_arch = "x86", not(target_feature = "sse2")))]
+compile_error!("the `bit-exact` feature requires SSE2, the x87 unit does not round to single precision");
+
 /******************************************************************************/
 
+pub mod adaptive;
+pub mod alarm;
+pub mod average;
+pub mod band;
+pub mod baseline;
+pub mod calibration;
 mod common;
-mod cordic;
+#[cfg(feature = "heapless")]
+pub mod containers;
+pub mod convert;
+pub mod convolve;
+pub mod cordic;
+pub mod cross_spectrum;
+pub mod cyclostationary;
+pub mod detector;
+pub mod discriminator;
+pub mod doppler;
+pub mod equalizer;
+mod error;
+pub mod features;
 mod fft;
+pub mod fir;
+pub mod fixed_point;
+pub mod four_step;
+pub mod framer;
+pub mod gating;
+pub mod goertzel;
 mod ifft;
+pub mod interpolate;
+mod kernel;
+pub mod kurtosis;
+pub mod latency;
+pub mod level;
+pub mod limits;
+pub mod lomb_scargle;
+pub mod mask;
+pub mod math;
+mod metadata;
+pub mod metrics;
+pub mod notch;
+pub mod offset;
+pub mod queue;
+pub mod real;
+#[cfg(feature = "bit-exact")]
+pub mod reproducible;
+pub mod resample;
+mod rfft;
+pub mod selftest;
+pub mod shared;
+pub mod shift;
+#[cfg(feature = "heapless")]
+pub mod sidebands;
+pub mod sine_fit;
+pub mod spectrum;
+mod status;
+pub mod stereo;
+#[cfg(feature = "embedded-storage")]
+pub mod storage;
+pub mod synth;
+pub mod telemetry;
+pub mod tof;
+pub mod trigger;
+pub mod units;
+pub mod view;
+pub mod wake;
+pub mod whitening;
+pub mod wiener;
+pub mod window;
 
+pub use crate::common::Float;
+pub use crate::error::Error;
 pub use crate::fft::EmbFft;
 pub use crate::ifft::EmbIfft;
+pub use crate::metadata::{Direction, Scaling};
+pub use crate::rfft::{EmbIrfft, EmbRfft};
+pub use crate::status::Status;
 
 /******************************************************************************/
 
@@ -38,7 +110,7 @@ mod tests {
             (57.0, 1.0), (58.0, 1.0), (59.0, 1.0), (60.0, 1.0), (61.0, 1.0), (62.0, 1.0), (63.0, 1.0), (64.0, 1.0)
         ];
 
-        let expected_data = data.clone();
+        let expected_data = data;
 
         crate::EmbFft::new(&mut data).fft();
         crate::EmbIfft::new(&mut data).ifft();
@@ -62,7 +134,7 @@ mod tests {
             (57.0, 1.0), (58.0, 1.0), (59.0, 1.0), (60.0, 1.0), (61.0, 1.0), (62.0, 1.0), (63.0, 1.0), (64.0, 1.0)
         ];
 
-        let expected_data = data.clone();
+        let expected_data = data;
 
         crate::EmbFft::new(&mut data).fft();
         crate::EmbIfft::new(&mut data).ifft();
@@ -72,4 +144,30 @@ mod tests {
             assert_ulps_eq!(x.1, y.1, max_ulps = 500);
         }
     }
+
+    #[test]
+    #[cfg(feature = "half")]
+    fn test_f16() {
+        use half::f16;
+        let mut data: [(f16, f16); 64] = core::array::from_fn(|i| (f16::from_f32(i as f32 / 64.0), f16::from_f32(0.5)));
+        let mut reference: [(f64, f64); 64] = core::array::from_fn(|i| (data[i].0.to_f64(), data[i].1.to_f64()));
+        let expected_data = data;
+
+        crate::EmbFft::new(&mut data).fft();
+        crate::EmbFft::new(&mut reference).fft();
+        for (x, y) in core::iter::zip(data, reference) {
+            approx::assert_abs_diff_eq!(x.0.to_f64(), y.0, epsilon = 0.05);
+            approx::assert_abs_diff_eq!(x.1.to_f64(), y.1, epsilon = 0.05);
+        }
+
+        crate::EmbIfft::new(&mut data).ifft();
+        for (x, y) in core::iter::zip(data, expected_data) {
+            approx::assert_abs_diff_eq!(x.0.to_f64(), y.0.to_f64(), epsilon = 2e-3);
+            approx::assert_abs_diff_eq!(x.1.to_f64(), y.1.to_f64(), epsilon = 2e-3);
+        }
+
+        let mut data = [(f16::from_f32(0.25), f16::ZERO); 8];
+        crate::EmbFft::<_, 8, crate::window::Hann>::windowed(&mut data).fft();
+        assert_eq!(data[0].0, f16::ONE);
+    }
 }
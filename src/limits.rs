@@ -0,0 +1,280 @@
+/* embfft | limits.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Compliance of spectra with limit lines
+//!
+//! EMC and acoustic standards specify the allowed levels as piecewise-linear lines, often on a
+//! logarithmic frequency axis. A spectrum in decibels is checked point by point against such a line, and
+//! the margin is reported for each frequency: positive when compliant, negative when violating the limit.
+//! For example:
+//! ```
+//! use embfft::limits::{LimitKind, LimitLine, LimitPoint};
+//! use embfft::units::Hertz;
+//!
+//! // Quasi-peak limit of CISPR 32 class B, conducted emissions, in dBµV
+//! const CLASS_B: [LimitPoint; 4] = [
+//!     LimitPoint { frequency: Hertz(150e3), level: 66.0 },
+//!     LimitPoint { frequency: Hertz(500e3), level: 56.0 },
+//!     LimitPoint { frequency: Hertz(500e3), level: 56.0 },
+//!     LimitPoint { frequency: Hertz(30e6), level: 60.0 }
+//! ];
+//! let line = LimitLine::logarithmic(&CLASS_B, LimitKind::Upper).unwrap();
+//!
+//! let frequencies = [Hertz(200e3), Hertz(1e6), Hertz(10e6)];
+//! let levels = [50.0, 57.0, 40.0];
+//! let compliance = line.check(&frequencies, &levels).unwrap();
+//! assert!(!compliance.passed());
+//! assert_eq!(compliance.worst_frequency, Hertz(1e6));
+//! ```
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+use crate::units::Hertz;
+use crate::view::SpectrumView;
+
+/******************************************************************************/
+
+/// Corner of a limit line
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LimitPoint {
+    /// Frequency of the corner
+    pub frequency: Hertz,
+    /// Limit at that frequency, usually in decibels
+    pub level: f64
+}
+
+/// Side of the limit line on which the levels must stay
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LimitKind {
+    /// The levels must not exceed the line
+    Upper,
+    /// The levels must not fall below the line
+    Lower
+}
+
+/// Level of a spectrum that violates a limit line
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Violation {
+    /// Frequency of the level
+    pub frequency: Hertz,
+    /// Measured level
+    pub level: f64,
+    /// Limit at that frequency
+    pub limit: f64,
+    /// Margin to the limit, negative
+    pub margin: f64
+}
+
+/// Summary of the comparison of a spectrum with a limit line
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Compliance {
+    /// Number of levels within the frequency range of the line
+    pub checked: usize,
+    /// Number of levels that violate the line
+    pub violations: usize,
+    /// Smallest margin, +∞ if no level was checked
+    pub worst_margin: f64,
+    /// Frequency of the smallest margin
+    pub worst_frequency: Hertz
+}
+
+impl Compliance {
+    /// Checks that no level violates the line
+    pub fn passed(&self) -> bool {
+        self.violations == 0
+    }
+}
+
+/// Piecewise-linear limit line
+///
+/// Two successive corners at the same frequency make a step, where the strictest limit applies. The
+/// levels outside the frequency range of the line are not checked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LimitLine<'a> {
+    points: &'a [LimitPoint],
+    kind: LimitKind,
+    logarithmic: bool
+}
+
+impl<'a> LimitLine<'a> {
+    /// Initializes a line interpolated linearly in frequency
+    ///
+    /// Fails with [`Error::InvalidLength`] if there are no corners, and with [`Error::InvalidParameter`] if
+    /// their frequencies are decreasing.
+    pub fn new(points: &'a [LimitPoint], kind: LimitKind) -> Result<Self, Error> {
+        if points.is_empty() {
+            return Err(Error::InvalidLength);
+        }
+        if points.iter().any(|p| p.frequency.0.is_nan()) || points.windows(2).any(|w| w[0].frequency > w[1].frequency) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { points, kind, logarithmic: false })
+    }
+
+    /// Initializes a line interpolated linearly in the logarithm of the frequency, as drawn on a log scale
+    ///
+    /// Fails with [`Error::InvalidLength`] if there are no corners, and with [`Error::InvalidParameter`] if
+    /// their frequencies are decreasing or not positive.
+    pub fn logarithmic(points: &'a [LimitPoint], kind: LimitKind) -> Result<Self, Error> {
+        if points.iter().any(|p| p.frequency.0.is_nan() || p.frequency.0 <= 0.0) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { logarithmic: true, ..Self::new(points, kind)? })
+    }
+
+    /// Returns the limit at a frequency, or `None` outside the range of the line
+    pub fn limit(&self, frequency: Hertz) -> Option<f64> {
+        let axis = |f: Hertz| if self.logarithmic { math::ln(f.0) } else { f.0 };
+        let stricter = |a: f64, b: f64| match self.kind {
+            LimitKind::Upper => a.min(b),
+            LimitKind::Lower => a.max(b)
+        };
+        let mut limit = None;
+        for (i, p) in self.points.iter().enumerate() {
+            let q = self.points.get(i + 1).unwrap_or(p);
+            if frequency < p.frequency || frequency > q.frequency {
+                continue;
+            }
+            let level = if p.frequency == q.frequency {
+                stricter(p.level, q.level)
+            } else {
+                let t = (axis(frequency) - axis(p.frequency)) / (axis(q.frequency) - axis(p.frequency));
+                p.level + t * (q.level - p.level)
+            };
+            limit = Some(limit.map_or(level, |l| stricter(l, level)));
+        }
+        limit
+    }
+
+    /// Returns the margin of a level to the line, positive when compliant, or `None` outside its range
+    pub fn margin(&self, frequency: Hertz, level: f64) -> Option<f64> {
+        self.limit(frequency).map(|limit| match self.kind {
+            LimitKind::Upper => limit - level,
+            LimitKind::Lower => level - limit
+        })
+    }
+
+    /// Lists the levels that violate the line, without any allocation
+    pub fn violations<'b>(&'b self, frequencies: &'b [Hertz], levels: &'b [f64]) -> impl Iterator<Item = Violation> + 'b {
+        frequencies.iter().zip(levels).filter_map(|(&frequency, &level)| {
+            let limit = self.limit(frequency)?;
+            let margin = self.margin(frequency, level)?;
+            (margin < 0.0).then_some(Violation { frequency, level, limit, margin })
+        })
+    }
+
+    /// Compares levels to the line
+    ///
+    /// Fails with [`Error::InvalidLength`] if `frequencies` and `levels` have different lengths.
+    pub fn check(&self, frequencies: &[Hertz], levels: &[f64]) -> Result<Compliance, Error> {
+        if frequencies.len() != levels.len() {
+            return Err(Error::InvalidLength);
+        }
+        Ok(self.summarize(frequencies.iter().copied().zip(levels.iter().copied())))
+    }
+
+    /// Compares the bins of a spectrum, from DC to the Nyquist frequency, to the line
+    ///
+    /// The levels are those of the view, which should be in decibels with the same reference as the line.
+    pub fn check_spectrum<T: Float<N>, const N: usize>(&self, view: &SpectrumView<T, N>) -> Compliance {
+        self.summarize((0..=N / 2).filter_map(|k| view.bin(k).ok()).map(|bin| (bin.frequency, bin.level)))
+    }
+
+    fn summarize(&self, levels: impl Iterator<Item = (Hertz, f64)>) -> Compliance {
+        let mut compliance = Compliance { checked: 0, violations: 0, worst_margin: f64::INFINITY, worst_frequency: Hertz(0.0) };
+        for (frequency, level) in levels {
+            if let Some(margin) = self.margin(frequency, level) {
+                compliance.checked += 1;
+                if margin < 0.0 {
+                    compliance.violations += 1;
+                }
+                if margin < compliance.worst_margin {
+                    compliance.worst_margin = margin;
+                    compliance.worst_frequency = frequency;
+                }
+            }
+        }
+        compliance
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::SampleRate;
+    use approx::assert_abs_diff_eq;
+
+    const MASK: [LimitPoint; 4] = [
+        LimitPoint { frequency: Hertz(100.0), level: -20.0 },
+        LimitPoint { frequency: Hertz(200.0), level: -40.0 },
+        LimitPoint { frequency: Hertz(200.0), level: -30.0 },
+        LimitPoint { frequency: Hertz(400.0), level: -30.0 }
+    ];
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_limit() {
+        assert_eq!(LimitLine::new(&[], LimitKind::Upper), Err(Error::InvalidLength));
+        let reversed = [MASK[3], MASK[0]];
+        assert_eq!(LimitLine::new(&reversed, LimitKind::Upper), Err(Error::InvalidParameter));
+        let zero = [LimitPoint { frequency: Hertz(0.0), level: 0.0 }];
+        assert_eq!(LimitLine::logarithmic(&zero, LimitKind::Upper), Err(Error::InvalidParameter));
+
+        let upper = LimitLine::new(&MASK, LimitKind::Upper).unwrap();
+        assert_eq!(upper.limit(Hertz(99.0)), None);
+        assert_eq!(upper.limit(Hertz(150.0)), Some(-30.0));
+        assert_eq!(upper.limit(Hertz(200.0)), Some(-40.0));
+        assert_eq!(upper.limit(Hertz(400.0)), Some(-30.0));
+        assert_eq!(upper.margin(Hertz(300.0), -25.0), Some(-5.0));
+
+        // On a log scale, the middle of the first segment is at √2 × 100 Hz
+        let lower = LimitLine::logarithmic(&MASK, LimitKind::Lower).unwrap();
+        assert_abs_diff_eq!(lower.limit(Hertz(core::f64::consts::SQRT_2 * 100.0)).unwrap(), -30.0, epsilon = 1e-9);
+        assert_eq!(lower.limit(Hertz(200.0)), Some(-30.0));
+        assert_eq!(lower.margin(Hertz(300.0), -25.0), Some(5.0));
+    }
+
+    #[test]
+    fn test_check() {
+        let line = LimitLine::new(&MASK, LimitKind::Upper).unwrap();
+        let frequencies = [Hertz(50.0), Hertz(120.0), Hertz(180.0), Hertz(250.0), Hertz(300.0)];
+        let levels = [0.0, -30.0, -33.0, -31.0, -29.0];
+        assert_eq!(line.check(&frequencies, &levels[1..]), Err(Error::InvalidLength));
+
+        let compliance = line.check(&frequencies, &levels).unwrap();
+        assert_eq!(compliance.checked, 4);
+        assert_eq!(compliance.violations, 2);
+        assert!(!compliance.passed());
+        assert_abs_diff_eq!(compliance.worst_margin, -3.0, epsilon = 1e-12);
+        assert_eq!(compliance.worst_frequency, Hertz(180.0));
+
+        let mut violations = line.violations(&frequencies, &levels);
+        assert_eq!(violations.next().map(|v| v.frequency), Some(Hertz(180.0)));
+        assert_eq!(violations.next(), Some(Violation { frequency: Hertz(300.0), level: -29.0, limit: -30.0, margin: -1.0 }));
+        assert_eq!(violations.next(), None);
+    }
+
+    #[test]
+    fn test_spectrum() {
+        // A full-scale tone at 250 Hz reads 0 dBFS, the other bins -inf dBFS
+        let mut data = [(0.0f64, 0.0); 16];
+        data[5] = (8.0, 0.0);
+        let view = SpectrumView::new(&data, SampleRate::new(800.0).unwrap());
+        let compliance = LimitLine::new(&MASK, LimitKind::Upper).unwrap().check_spectrum(&view);
+        assert_eq!(compliance.checked, 7);
+        assert_eq!(compliance.violations, 1);
+        assert_eq!(compliance.worst_frequency, Hertz(250.0));
+        assert_abs_diff_eq!(compliance.worst_margin, -30.0, epsilon = 1e-9);
+    }
+}
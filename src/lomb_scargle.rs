@@ -0,0 +1,142 @@
+/* embfft | lomb_scargle.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Lomb–Scargle periodogram of unevenly sampled data
+//!
+//! Duty-cycled sensors do not sample at a constant rate, which rules out the FFT. The Lomb–Scargle
+//! periodogram fits a sine wave to the samples at each frequency of interest, whatever their timestamps.
+//! For example:
+//! ```
+//! use embfft::lomb_scargle;
+//! use embfft::units::Hertz;
+//!
+//! let times = [0.0, 0.1, 0.35, 0.4, 0.7, 0.9, 1.15, 1.3, 1.6, 1.65];
+//! let values = times.map(|t: f64| embfft::cordic::sin(2.0 * core::f64::consts::PI * 2.0 * t));
+//! let frequencies = [Hertz(1.0), Hertz(2.0), Hertz(3.0)];
+//! let mut power = [0.0; 3];
+//! lomb_scargle::periodogram(&times, &values, &frequencies, &mut power).unwrap();
+//! assert!(power[1] > power[0] && power[1] > power[2]);
+//! ```
+
+/******************************************************************************/
+
+use crate::error::Error;
+use crate::math;
+use crate::units::Hertz;
+
+/******************************************************************************/
+
+/// Computes the normalized Lomb–Scargle periodogram
+///
+/// `times` are the timestamps of the `values`, in seconds, in any order, and `frequencies` the
+/// frequencies of interest. Each power is normalized by the variance of the values: a pure sine
+/// wave reaches about half the number of samples at its frequency, while white noise averages 1.
+/// Fails with [`Error::InvalidLength`] if the lengths of `times` and `values`, or of `frequencies` and
+/// `power` differ, or if there are fewer than 3 samples, and with [`Error::InvalidParameter`] if a
+/// frequency is not positive or if the values are constant.
+pub fn periodogram(times: &[f64], values: &[f64], frequencies: &[Hertz], power: &mut [f64]) -> Result<(), Error> {
+    if times.len() != values.len() || frequencies.len() != power.len() || values.len() < 3 {
+        return Err(Error::InvalidLength);
+    }
+    if frequencies.iter().any(|f| f.0.is_nan() || f.0 <= 0.0) {
+        return Err(Error::InvalidParameter);
+    }
+
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter().map(|y| (y - mean) * (y - mean)).sum::<f64>() / (count - 1.0);
+    if variance.is_nan() || variance <= 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    for (f, p) in frequencies.iter().zip(power.iter_mut()) {
+        let omega = 2.0 * core::f64::consts::PI * f.0;
+        let (mut yc, mut ys, mut cc, mut ss, mut cs) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (t, y) in times.iter().zip(values.iter()) {
+            let (s, c) = math::sin_cos(omega * t);
+            let y = y - mean;
+            yc += y * c;
+            ys += y * s;
+            cc += c * c;
+            ss += s * s;
+            cs += c * s;
+        }
+
+        // The time offset τ makes the sine and cosine terms orthogonal: tan(2ωτ) = 2 CS / (CC - SS)
+        let (s, c) = math::sin_cos(0.5 * math::atan2(2.0 * cs, cc - ss));
+        let yc_tau = c * yc + s * ys;
+        let ys_tau = c * ys - s * yc;
+        let cc_tau = c * c * cc + 2.0 * c * s * cs + s * s * ss;
+        let ss_tau = c * c * ss - 2.0 * c * s * cs + s * s * cc;
+
+        let mut sum = 0.0;
+        if cc_tau > 0.0 {
+            sum += yc_tau * yc_tau / cc_tau;
+        }
+        if ss_tau > 0.0 {
+            sum += ys_tau * ys_tau / ss_tau;
+        }
+        *p = sum / (2.0 * variance);
+    }
+    Ok(())
+}
+
+/// Fills `frequencies` with evenly spaced frequencies, from `f_max / len` to `f_max`
+///
+/// The average sample rate of the data gives a sensible `f_max`, and a frequency step lower than the
+/// inverse of the time span does not bring any more resolution.
+/// Fails with [`Error::InvalidParameter`] if `f_max` is not positive.
+pub fn frequency_grid(f_max: Hertz, frequencies: &mut [Hertz]) -> Result<(), Error> {
+    if f_max.0.is_nan() || f_max.0 <= 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+    let step = f_max / frequencies.len() as f64;
+    for (i, f) in frequencies.iter_mut().enumerate() {
+        *f = step * (i + 1) as f64;
+    }
+    Ok(())
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth::Lfsr;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_periodogram() {
+        // 200 samples over 20 s, with random gaps
+        let mut lfsr = Lfsr::new(0x1234_5678);
+        let mut times = [0.0; 200];
+        for i in 1..times.len() {
+            times[i] = times[i - 1] + 0.1 * (1.0 + lfsr.uniform());
+        }
+        let values = times.map(|t| 0.3 + (2.0 * core::f64::consts::PI * 1.3 * t + 0.5).sin());
+
+        let mut frequencies = [Hertz(0.0); 100];
+        frequency_grid(Hertz(5.0), &mut frequencies).unwrap();
+        assert_eq!(frequencies[0], Hertz(0.05));
+        assert_eq!(frequencies[99], Hertz(5.0));
+        let mut power = [0.0; 100];
+        periodogram(&times, &values, &frequencies, &mut power).unwrap();
+
+        let peak = (0..power.len()).max_by(|a, b| power[*a].total_cmp(&power[*b])).unwrap();
+        assert_eq!(frequencies[peak], Hertz(1.3));
+        assert_abs_diff_eq!(power[peak], 100.0, epsilon = 1.0);
+        assert!(power.iter().enumerate().all(|(i, p)| i == peak || *p < 10.0));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut power = [0.0; 2];
+        assert_eq!(periodogram(&[0.0, 1.0], &[1.0, 2.0], &[Hertz(1.0), Hertz(2.0)], &mut power), Err(Error::InvalidLength));
+        assert_eq!(periodogram(&[0.0, 1.0, 2.0], &[1.0, 2.0], &[Hertz(1.0), Hertz(2.0)], &mut power), Err(Error::InvalidLength));
+        assert_eq!(periodogram(&[0.0, 1.0, 2.0], &[1.0; 3], &[Hertz(1.0), Hertz(2.0)], &mut power), Err(Error::InvalidParameter));
+        assert_eq!(periodogram(&[0.0, 1.0, 2.0], &[1.0, 2.0, 0.0], &[Hertz(0.0), Hertz(2.0)], &mut power), Err(Error::InvalidParameter));
+        assert_eq!(frequency_grid(Hertz(0.0), &mut [Hertz(0.0); 2]), Err(Error::InvalidParameter));
+    }
+}
@@ -0,0 +1,147 @@
+/* embfft | mask.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Exclusion of known spurious bins
+//!
+//! Clock harmonics, switching regulators and other hardware-specific spurs show up at fixed frequencies.
+//! Registering them once in an [`ExclusionMask`] and passing it to the analysis stages, such as
+//! [`CarrierDetector::with_mask()`](crate::detector::CarrierDetector::with_mask),
+//! [`WienerFilter::with_mask()`](crate::wiener::WienerFilter::with_mask) or
+//! [`band_power_excluding()`](crate::band::band_power_excluding), keeps them from being reported as
+//! signals or counted in the noise floor.
+
+/******************************************************************************/
+
+use crate::error::Error;
+use crate::units::{Hertz, SampleRate};
+
+/******************************************************************************/
+
+/// Set of bins to ignore, in an N-point spectrum
+///
+/// For example:
+/// ```
+/// use embfft::mask::ExclusionMask;
+/// use embfft::units::{Hertz, SampleRate};
+///
+/// // 8 MHz clock spur, sampled at 48 kHz: aliased to 16 kHz
+/// let mut mask = ExclusionMask::<256>::new();
+/// mask.exclude_frequency(Hertz::mhz(8.0), SampleRate::new(48000.0).unwrap(), 1).unwrap();
+/// assert!(mask.is_excluded(85) && mask.is_excluded(171));
+/// assert_eq!(mask.count(), 6);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExclusionMask<const N: usize> {
+    bins: [bool; N]
+}
+
+impl<const N: usize> ExclusionMask<N> {
+    /// Initializes an empty mask
+    pub const fn new() -> Self {
+        Self { bins: [false; N] }
+    }
+
+    /// Removes all the bins from the mask
+    pub fn clear(&mut self) {
+        self.bins = [false; N];
+    }
+
+    /// Excludes the bin k
+    ///
+    /// Fails with [`Error::OutOfRange`] if k is not below N.
+    pub fn exclude(&mut self, k: usize) -> Result<(), Error> {
+        *self.bins.get_mut(k).ok_or(Error::OutOfRange)? = true;
+        Ok(())
+    }
+
+    /// Excludes the bins `first` to `last`, inclusive
+    ///
+    /// Fails with [`Error::OutOfRange`] if `last` is not below N, or if `first` is greater than `last`.
+    pub fn exclude_range(&mut self, first: usize, last: usize) -> Result<(), Error> {
+        if first > last || last >= N {
+            return Err(Error::OutOfRange);
+        }
+        self.bins[first..=last].fill(true);
+        Ok(())
+    }
+
+    /// Excludes the bins around a frequency, and their mirror image in the spectrum of a real signal
+    ///
+    /// The nearest bin is excluded, along with `half_width` bins on each side. Frequencies above the
+    /// Nyquist frequency are first folded back, as they would be by the sampling.
+    /// Fails with [`Error::InvalidParameter`] if the frequency is negative.
+    pub fn exclude_frequency(&mut self, frequency: Hertz, sample_rate: SampleRate, half_width: usize) -> Result<(), Error> {
+        if frequency.0.is_nan() || frequency.0 < 0.0 {
+            return Err(Error::InvalidParameter);
+        }
+        let k = (sample_rate.to_bin::<N>(frequency) + 0.5) as usize % N;
+        for i in 0..=2 * half_width.min(N / 2) {
+            let bin = (k + N - half_width.min(N / 2) + i) % N;
+            self.bins[bin] = true;
+            self.bins[(N - bin) % N] = true;
+        }
+        Ok(())
+    }
+
+    /// Includes the bin k again
+    ///
+    /// Fails with [`Error::OutOfRange`] if k is not below N.
+    pub fn include(&mut self, k: usize) -> Result<(), Error> {
+        *self.bins.get_mut(k).ok_or(Error::OutOfRange)? = false;
+        Ok(())
+    }
+
+    /// Checks if the bin k is excluded, the bins out of range never being
+    pub fn is_excluded(&self, k: usize) -> bool {
+        self.bins.get(k).copied().unwrap_or(false)
+    }
+
+    /// Returns the number of excluded bins
+    pub fn count(&self) -> usize {
+        self.bins.iter().filter(|&&x| x).count()
+    }
+}
+
+impl<const N: usize> Default for ExclusionMask<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask() {
+        let mut mask = ExclusionMask::<16>::default();
+        assert_eq!(mask.count(), 0);
+        assert_eq!(mask.exclude(16), Err(Error::OutOfRange));
+        assert_eq!(mask.exclude_range(4, 3), Err(Error::OutOfRange));
+        assert_eq!(mask.exclude_range(4, 16), Err(Error::OutOfRange));
+
+        mask.exclude(2).unwrap();
+        mask.exclude_range(5, 7).unwrap();
+        mask.include(6).unwrap();
+        assert!((0..17).all(|k| mask.is_excluded(k) == [2, 5, 7].contains(&k)));
+        mask.clear();
+        assert_eq!(mask, ExclusionMask::new());
+    }
+
+    #[test]
+    fn test_frequency() {
+        let fs = SampleRate::new(1600.0).unwrap();
+        let mut mask = ExclusionMask::<16>::new();
+        assert_eq!(mask.exclude_frequency(Hertz(-1.0), fs, 0), Err(Error::InvalidParameter));
+
+        // Bin 0 has no mirror image, and 2100 Hz aliases to 500 Hz, i.e. bin 5
+        mask.exclude_frequency(Hertz(30.0), fs, 1).unwrap();
+        mask.exclude_frequency(Hertz(2100.0), fs, 0).unwrap();
+        let expected = [0, 1, 5, 11, 15];
+        assert!((0..16).all(|k| mask.is_excluded(k) == expected.contains(&k)));
+    }
+}
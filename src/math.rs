@@ -0,0 +1,282 @@
+/* embfft | math.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Fast mathematical functions, for the post-processing of the spectra
+//!
+//! These approximations do not depend on libm, and are suitable for small microcontrollers.
+//! The post-processing modules call the run-time functions of this module, such as [`sqrt()`] and
+//! [`ln()`], which rely on these approximations and on the [`cordic`](crate::cordic) module by default,
+//! on the `micromath` crate if that feature is enabled, or on the exact functions of the `libm` crate if
+//! that feature is enabled, which takes precedence.
+//! For example:
+//! ```
+//! use embfft::math::FastMath;
+//!
+//! let x = 4.0f32.rsqrt();
+//! assert!((x - 0.5).abs() < 1e-5);
+//! ```
+
+/******************************************************************************/
+
+use core::ops::{Add, Mul};
+
+/******************************************************************************/
+
+/// Fast approximations, for the floating-point types
+pub trait FastMath: Copy + Add<Output = Self> + Mul<Output = Self> {
+    /// Computes 1 / √x, with a single Newton step
+    ///
+    /// The relative error is below 2e-3. `x` must be positive.
+    fn rsqrt_fast(self) -> Self;
+
+    /// Computes 1 / √x, with two Newton steps
+    ///
+    /// The relative error is below 5e-6. `x` must be positive.
+    fn rsqrt(self) -> Self;
+
+    /// Computes √x, with four Newton steps
+    ///
+    /// The result is accurate to the last few bits. NaN is returned for negative numbers.
+    fn fast_sqrt(self) -> Self;
+}
+
+macro_rules! gen_fast_math_impl {
+    ($T: ty, $magic: literal) => {
+        impl FastMath for $T {
+            fn rsqrt_fast(self) -> Self {
+                // The exponent is halved and negated by the integer arithmetic
+                let y = <$T>::from_bits($magic - (self.to_bits() >> 1));
+                y * (1.5 - 0.5 * self * y * y)
+            }
+
+            fn rsqrt(self) -> Self {
+                let y = self.rsqrt_fast();
+                y * (1.5 - 0.5 * self * y * y)
+            }
+
+            fn fast_sqrt(self) -> Self {
+                if self == 0.0 {
+                    return 0.0;
+                } else if self < 0.0 {
+                    return <$T>::NAN;
+                } else if self < <$T>::MIN_POSITIVE {
+                    // The seed is wrong for the subnormals, which are scaled by 2^108 first
+                    return (self * (1u128 << 108) as $T).fast_sqrt() / (1u64 << 54) as $T;
+                }
+                let y = self.rsqrt();
+                let y = y * (1.5 - 0.5 * self * y * y);
+                let y = y * (1.5 - 0.5 * self * y * y);
+                self * y
+            }
+        }
+    };
+}
+
+gen_fast_math_impl!(f32, 0x5F37_59DF);
+gen_fast_math_impl!(f64, 0x5FE6_EB50_C7B5_37A9);
+
+/// Normalizes a complex number to unit magnitude, keeping its phase
+///
+/// Returns zero for a null input.
+pub fn unit_vector<T: FastMath + Default + PartialEq>(x: (T, T)) -> (T, T) {
+    let m2 = x.0 * x.0 + x.1 * x.1;
+    if m2 == T::default() {
+        return x;
+    }
+    let k = m2.rsqrt();
+    (x.0 * k, x.1 * k)
+}
+
+/******************************************************************************/
+
+/// Computes √x at run time
+///
+/// Returns NaN for negative numbers.
+pub fn sqrt(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    } else if x == 0.0 || x.is_infinite() {
+        return x;
+    }
+    #[cfg(feature = "libm")]
+    {
+        libm::sqrt(x)
+    }
+    #[cfg(all(feature = "micromath", not(feature = "libm")))]
+    {
+        micromath::F32Ext::sqrt(x as f32) as f64
+    }
+    #[cfg(not(any(feature = "micromath", feature = "libm")))]
+    {
+        x.fast_sqrt()
+    }
+}
+
+/// Computes the natural logarithm of x at run time
+///
+/// Returns NaN for negative numbers and -∞ for zero.
+pub fn ln(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    } else if x == 0.0 {
+        return f64::NEG_INFINITY;
+    } else if x.is_infinite() {
+        return x;
+    }
+    #[cfg(feature = "libm")]
+    {
+        libm::log(x)
+    }
+    #[cfg(all(feature = "micromath", not(feature = "libm")))]
+    {
+        micromath::F32Ext::ln(x as f32) as f64
+    }
+    #[cfg(not(any(feature = "micromath", feature = "libm")))]
+    {
+        crate::cordic::ln(x)
+    }
+}
+
+/// Computes e^x at run time
+pub fn exp(x: f64) -> f64 {
+    #[cfg(feature = "libm")]
+    {
+        libm::exp(x)
+    }
+    #[cfg(all(feature = "micromath", not(feature = "libm")))]
+    {
+        micromath::F32Ext::exp(x as f32) as f64
+    }
+    #[cfg(not(any(feature = "micromath", feature = "libm")))]
+    {
+        crate::cordic::exp(x)
+    }
+}
+
+/// Computes the angle of the vector (x, y) at run time, in radians between -π and +π
+pub fn atan2(y: f64, x: f64) -> f64 {
+    #[cfg(feature = "libm")]
+    {
+        libm::atan2(y, x)
+    }
+    #[cfg(all(feature = "micromath", not(feature = "libm")))]
+    {
+        micromath::F32Ext::atan2(y as f32, x as f32) as f64
+    }
+    #[cfg(not(any(feature = "micromath", feature = "libm")))]
+    {
+        crate::cordic::atan2(y, x)
+    }
+}
+
+/// Computes the sine and cosine of an angle at run time, in radians
+pub fn sin_cos(x: f64) -> (f64, f64) {
+    #[cfg(feature = "libm")]
+    {
+        libm::sincos(x)
+    }
+    #[cfg(all(feature = "micromath", not(feature = "libm")))]
+    {
+        // Reduce to [-π, π], where the approximation is accurate
+        const TAU: f64 = core::f64::consts::TAU;
+        let x = x - TAU * ((x / TAU) as i64 as f64);
+        let x = if x > core::f64::consts::PI { x - TAU } else if x < -core::f64::consts::PI { x + TAU } else { x };
+        let (s, c) = micromath::F32Ext::sin_cos(x as f32);
+        (s as f64, c as f64)
+    }
+    #[cfg(not(any(feature = "micromath", feature = "libm")))]
+    {
+        crate::cordic::sin_cos(x)
+    }
+}
+
+/// Converts a power ratio to decibels at run time, 10 × log10(x)
+///
+/// Returns -∞ for zero and negative numbers.
+pub fn power_to_db(x: f64) -> f64 {
+    if x <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    // 10 / ln(10)
+    4.342944819032518 * ln(x)
+}
+
+/// Converts an amplitude ratio to decibels at run time, 20 × log10(x)
+///
+/// Returns -∞ for zero and negative numbers.
+pub fn amplitude_to_db(x: f64) -> f64 {
+    if x <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    // 20 / ln(10)
+    8.685889638065037 * ln(x)
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::{assert_abs_diff_eq, assert_relative_eq};
+
+    #[test]
+    fn test_rsqrt() {
+        for x in [1e-6f32, 0.01, 0.5, 1.0, 2.0, 3.0, 1000.0, 1e9] {
+            assert_relative_eq!((x * x).rsqrt_fast(), 1.0 / x, max_relative = 2e-3);
+            assert_relative_eq!((x * x).rsqrt(), 1.0 / x, max_relative = 5e-6);
+        }
+        for x in [1e-100f64, 0.3, 7.0, 1e100] {
+            assert_relative_eq!((x * x).rsqrt(), 1.0 / x, max_relative = 5e-6);
+        }
+    }
+
+    #[test]
+    fn test_fast_sqrt() {
+        assert_eq!(0.0f32.fast_sqrt(), 0.0);
+        assert!((-1e-20f64).fast_sqrt().is_nan());
+        assert!((-4.0f32).fast_sqrt().is_nan());
+        assert!(f64::NEG_INFINITY.fast_sqrt().is_nan());
+        assert_relative_eq!(2.0f64.fast_sqrt(), core::f64::consts::SQRT_2, max_relative = 1e-15);
+        assert_relative_eq!(1e-20f64.fast_sqrt(), 1e-10, max_relative = 1e-15);
+        assert_relative_eq!(9.0f32.fast_sqrt(), 3.0, max_relative = 1e-7);
+
+        // Subnormals, whose representation is itself inexact
+        assert_relative_eq!(1e-320f64.fast_sqrt(), 1e-160, max_relative = 1e-3);
+        assert_relative_eq!(1e-40f32.fast_sqrt(), 1e-20, max_relative = 1e-3);
+        assert_relative_eq!(f64::from_bits(1).fast_sqrt(), 2.2227587494850775e-162, max_relative = 1e-15);
+    }
+
+    #[test]
+    fn test_runtime() {
+        let tolerance = if cfg!(all(feature = "micromath", not(feature = "libm"))) { 0.1 } else { 1e-6 };
+        assert_relative_eq!(sqrt(2.0), core::f64::consts::SQRT_2, max_relative = tolerance);
+        assert_eq!(sqrt(0.0), 0.0);
+        assert!(sqrt(-1.0).is_nan());
+        if !cfg!(all(feature = "micromath", not(feature = "libm"))) {
+            assert_relative_eq!(sqrt(1e-320), 1e-160, max_relative = 1e-3);
+        }
+        assert_relative_eq!(ln(10.0), core::f64::consts::LN_10, max_relative = tolerance);
+        assert_eq!(ln(0.0), f64::NEG_INFINITY);
+        assert!(ln(-1.0).is_nan());
+        assert_relative_eq!(exp(-2.5), 0.0820849986238988, max_relative = tolerance);
+        assert_abs_diff_eq!(atan2(1.0, -1.0), 3.0 * core::f64::consts::FRAC_PI_4, epsilon = tolerance);
+        let (s, c) = sin_cos(100.0);
+        assert_abs_diff_eq!(s, -0.5063656411097588, epsilon = tolerance);
+        assert_abs_diff_eq!(c, 0.8623188722876839, epsilon = tolerance);
+        assert_abs_diff_eq!(power_to_db(100.0), 20.0, epsilon = 20.0 * tolerance);
+        assert_abs_diff_eq!(amplitude_to_db(0.1), -20.0, epsilon = 20.0 * tolerance);
+        assert_eq!(power_to_db(0.0), f64::NEG_INFINITY);
+        assert_eq!(amplitude_to_db(-1.0), f64::NEG_INFINITY);
+        assert!(power_to_db(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_unit_vector() {
+        let (re, im) = unit_vector((3.0f64, -4.0));
+        assert_relative_eq!(re, 0.6, max_relative = 5e-6);
+        assert_relative_eq!(im, -0.8, max_relative = 5e-6);
+        assert_eq!(unit_vector((0.0f32, 0.0)), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,26 @@
+/* embfft | metadata.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+/******************************************************************************/
+
+/// Direction of a transform
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// Time domain to frequency domain, with the twiddle factors e^(-j2πkn / N)
+    Forward,
+    /// Frequency domain to time domain, with the twiddle factors e^(+j2πkn / N)
+    Inverse
+}
+
+/// Scaling of the output of a transform
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Scaling {
+    /// The output is not scaled, its magnitude grows with N
+    Unscaled,
+    /// The output is multiplied by 1 / N
+    InverseN
+}
@@ -0,0 +1,111 @@
+/* embfft | metrics.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Error metrics between two spectra or frames
+//!
+//! The metrics are computed in double precision, and a NaN in either buffer yields a NaN result.
+//! For example:
+//! ```
+//! use embfft::metrics;
+//!
+//! let mut data: [(f32, f32); 16] = core::array::from_fn(|i| (i as f32, 1.0));
+//! let reference = data;
+//! embfft::EmbFft::new(&mut data).fft();
+//! embfft::EmbIfft::new(&mut data).ifft();
+//! assert!(metrics::max_ulp_error(&data, &reference) < 50.0);
+//! assert!(metrics::rms_relative_error(&data, &reference) < 1e-6);
+//! ```
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::math;
+
+/******************************************************************************/
+
+/// Returns the largest of two values, propagating NaN
+fn max(a: f64, b: f64) -> f64 {
+    if b > a || b.is_nan() { b } else { a }
+}
+
+/// Returns the real and imaginary errors of each value, with the corresponding reference
+fn errors<'a, T: Float<N>, const N: usize>(actual: &'a [(T, T); N], reference: &'a [(T, T); N]) -> impl Iterator<Item = (f64, T)> + 'a {
+    core::iter::zip(actual, reference).flat_map(|(x, y)| {
+        [(x.0.to_f64() - y.0.to_f64(), y.0), (x.1.to_f64() - y.1.to_f64(), y.1)]
+    })
+}
+
+/// Returns the largest absolute difference between the real or imaginary parts
+pub fn max_abs_error<T: Float<N>, const N: usize>(actual: &[(T, T); N], reference: &[(T, T); N]) -> f64 {
+    errors(actual, reference).fold(0.0, |m, (e, _)| max(m, if e < 0.0 { -e } else { e }))
+}
+
+/// Returns the largest difference between the real or imaginary parts, in units in the last place of the
+/// reference values
+///
+/// This is strict for the values close to zero, where floating-point types have a fine resolution.
+pub fn max_ulp_error<T: Float<N>, const N: usize>(actual: &[(T, T); N], reference: &[(T, T); N]) -> f64 {
+    errors(actual, reference).fold(0.0, |m, (e, y)| max(m, if e < 0.0 { -e } else { e } / y.ulp()))
+}
+
+/// Returns the RMS error, relative to the RMS value of the reference
+///
+/// Returns 0 if both buffers are null, and infinity if only the reference is.
+pub fn rms_relative_error<T: Float<N>, const N: usize>(actual: &[(T, T); N], reference: &[(T, T); N]) -> f64 {
+    let mut error = 0.0;
+    let mut power = 0.0;
+    for (e, y) in errors(actual, reference) {
+        let y = y.to_f64();
+        error += e * e;
+        power += y * y;
+    }
+    if error == 0.0 {
+        0.0
+    } else if power == 0.0 {
+        f64::INFINITY
+    } else {
+        math::sqrt(error / power)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::Q15;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_metrics() {
+        let reference = [(1.0f32, 0.0), (-2.0, 0.5), (0.0, 0.0), (4.0, -1.0)];
+        let mut actual = reference;
+        assert_eq!(max_abs_error(&actual, &reference), 0.0);
+        assert_eq!(max_ulp_error(&actual, &reference), 0.0);
+        assert_eq!(rms_relative_error(&actual, &reference), 0.0);
+
+        actual[1].0 = f32::from_bits((-2.0f32).to_bits() + 3);
+        assert_eq!(max_ulp_error(&actual, &reference), 3.0);
+        actual[3].1 = -1.5;
+        assert_eq!(max_abs_error(&actual, &reference), 0.5);
+        assert_abs_diff_eq!(rms_relative_error(&actual, &reference), 0.5 / 22.25f64.sqrt(), epsilon = 1e-6);
+
+        actual[2].1 = f32::NAN;
+        assert!(max_abs_error(&actual, &reference).is_nan());
+        assert!(max_ulp_error(&actual, &reference).is_nan());
+        assert!(rms_relative_error(&actual, &reference).is_nan());
+        assert_eq!(rms_relative_error(&[(1.0, 0.0); 4], &[(0.0, 0.0); 4]), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let reference: [(Q15, Q15); 4] = [(Q15::from_f64(0.25), Q15::from_bits(0)); 4];
+        let mut actual = reference;
+        actual[2].1 = Q15::from_bits(-2);
+        assert_eq!(max_ulp_error(&actual, &reference), 2.0);
+        assert_eq!(max_abs_error(&actual, &reference), 2.0 / 32768.0);
+    }
+}
@@ -0,0 +1,145 @@
+/* embfft | notch.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Frequency-domain interference notching
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::math;
+use crate::error::Error;
+
+/******************************************************************************/
+
+/// Notch removing a band of a spectrum, applied between an FFT and an IFFT
+///
+/// The frequencies are given in bins, i.e. `frequency * N / sample_rate`, and may be fractional. The
+/// notch is symmetric: the negative frequencies of real signals are also attenuated. Its edges can be
+/// tapered with a raised cosine, which limits the ringing in the time domain.
+///
+/// For example:
+/// ```
+/// use embfft::notch::Notch;
+///
+/// // 50 Hz mains hum and its harmonics, at 1.6 kHz with 64 bins
+/// let notch = Notch::new(2.0, 0.5).unwrap().with_taper(0.5);
+/// let mut data = [(1.0f32, 0.0); 64];
+/// notch.apply_harmonics(&mut data, 3);
+/// assert_eq!(data[4], (0.0, 0.0));
+/// assert_eq!(data[62], (0.0, 0.0));
+/// assert_eq!(data[8], (1.0, 0.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Notch {
+    center: f64,
+    half_width: f64,
+    taper: f64,
+    gain: f64
+}
+
+impl Notch {
+    /// Initializes a notch that zeroes the bins within `half_width` of `center`
+    ///
+    /// Fails with [`Error::InvalidParameter`] if the center or the half width is negative.
+    pub fn new(center: f64, half_width: f64) -> Result<Self, Error> {
+        if center < 0.0 || half_width < 0.0 {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { center, half_width, taper: 0.0, gain: 0.0 })
+    }
+
+    /// Sets the width of the raised cosine transitions on each side of the notch, in bins
+    pub fn with_taper(mut self, taper: f64) -> Self {
+        self.taper = if taper > 0.0 { taper } else { 0.0 };
+        self
+    }
+
+    /// Sets the gain inside the notch, between 0 and 1, to attenuate the bins instead of zeroing them
+    pub fn with_gain(mut self, gain: f64) -> Self {
+        self.gain = gain.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns the gain of the notch at the given distance from its center, in bins
+    fn gain_at(&self, distance: f64) -> f64 {
+        let distance = if distance < 0.0 { -distance } else { distance };
+        if distance <= self.half_width {
+            self.gain
+        } else if distance < self.half_width + self.taper {
+            let x = (distance - self.half_width) / self.taper;
+            self.gain + (1.0 - self.gain) * (1.0 - math::sin_cos(PI * x).1) / 2.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the gain of the notch centered on `center` for bin k
+    fn bin_gain<const N: usize>(&self, center: f64, k: usize) -> f64 {
+        let f = if 2 * k > N { k as f64 - N as f64 } else { k as f64 };
+        let a = self.gain_at(f - center);
+        let b = self.gain_at(f + center);
+        if a < b { a } else { b }
+    }
+
+    /// Attenuates a spectrum in natural order, in place
+    pub fn apply<T: Float<N>, const N: usize>(&self, data: &mut [(T, T); N]) {
+        self.apply_at(data, self.center);
+    }
+
+    /// Attenuates a spectrum in natural order at the center frequency and its first harmonics, in place
+    ///
+    /// `count` includes the fundamental, and all the notches have the same width.
+    pub fn apply_harmonics<T: Float<N>, const N: usize>(&self, data: &mut [(T, T); N], count: usize) {
+        for h in 1..=count {
+            self.apply_at(data, self.center * h as f64);
+        }
+    }
+
+    fn apply_at<T: Float<N>, const N: usize>(&self, data: &mut [(T, T); N], center: f64) {
+        for (k, x) in data.iter_mut().enumerate() {
+            let gain = self.bin_gain::<N>(center, k);
+            if gain < 1.0 {
+                let gain = T::from_f64(gain);
+                *x = (x.0 * gain, x.1 * gain);
+            }
+        }
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_notch() {
+        assert!(Notch::new(-1.0, 1.0).is_err());
+        assert!(Notch::new(1.0, -1.0).is_err());
+
+        let notch = Notch::new(4.0, 1.0).unwrap().with_taper(2.0).with_gain(0.1);
+        let mut data = [(1.0, -2.0); 16];
+        notch.apply(&mut data);
+        let expected = [1.0, 1.0, 0.55, 0.1, 0.1, 0.1, 0.55, 1.0, 1.0];
+        for (k, &g) in expected.iter().enumerate() {
+            assert_abs_diff_eq!(data[k].0, g, epsilon = 1e-12);
+            assert_abs_diff_eq!(data[k].1, -2.0 * g, epsilon = 1e-12);
+            assert_abs_diff_eq!(data[(16 - k) % 16].0, g, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_harmonics() {
+        let mut data = [(1.0, 0.0); 32];
+        Notch::new(2.5, 0.5).unwrap().apply_harmonics(&mut data, 4);
+        for (k, x) in data.iter().enumerate().take(17) {
+            let removed = [2, 3, 5, 7, 8, 10].contains(&k);
+            assert_eq!(x.0, if removed { 0.0 } else { 1.0 });
+        }
+    }
+}
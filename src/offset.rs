@@ -0,0 +1,113 @@
+/* embfft | offset.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Carrier frequency offset estimation
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::fft::EmbFft;
+use crate::math;
+
+/******************************************************************************/
+
+/// Estimated carrier frequency offset
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrequencyOffset {
+    /// Signed index of the strongest bin, between -N/2 and N/2 - 1
+    pub bin: isize,
+    /// Fractional part of the offset, in bins, normally between -0.5 and 0.5
+    pub fraction: f64,
+    /// Size of the transform
+    pub n: usize
+}
+
+impl FrequencyOffset {
+    /// Returns the offset in bins
+    pub fn bins(&self) -> f64 {
+        self.bin as f64 + self.fraction
+    }
+
+    /// Returns the offset in cycles per sample, between -0.5 and 0.5
+    pub fn cycles_per_sample(&self) -> f64 {
+        self.bins() / self.n as f64
+    }
+}
+
+/// Estimates the frequency offset of the strongest carrier in a frame of complex baseband samples
+///
+/// The coarse estimate is the strongest bin of the spectrum. It is refined with the phase rotation of this
+/// bin between the first and second halves of the frame, which is unambiguous within ±1 bin.
+///
+/// For example:
+/// ```
+/// // Carrier at 2.25 bins
+/// let data: [(f64, f64); 64] = core::array::from_fn(|i| {
+///     let phase = 2.0 * core::f64::consts::PI * 2.25 * i as f64 / 64.0;
+///     (phase.cos(), phase.sin())
+/// });
+/// let offset = embfft::offset::estimate(&data);
+/// assert_eq!(offset.bin, 2);
+/// # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+/// assert!((offset.bins() - 2.25).abs() < 1e-6);
+/// ```
+pub fn estimate<T: Float<N>, const N: usize>(data: &[(T, T); N]) -> FrequencyOffset {
+    // Coarse estimate
+    let mut spectrum = *data;
+    EmbFft::new(&mut spectrum).fft();
+    let mut peak = 0;
+    let mut peak_power = T::ZERO;
+    for (k, x) in spectrum.iter().enumerate() {
+        let power = x.0 * x.0 + x.1 * x.1;
+        if power > peak_power {
+            peak = k;
+            peak_power = power;
+        }
+    }
+
+    // Correlate each half of the frame with the coarse carrier
+    let (sin, cos) = math::sin_cos(-2.0 * core::f64::consts::PI * peak as f64 / N as f64);
+    let mut w = (1.0, 0.0);
+    let mut halves = [(0.0, 0.0); 2];
+    for (i, x) in data.iter().enumerate() {
+        let x = (x.0.to_f64(), x.1.to_f64());
+        let h = &mut halves[2 * i / N];
+        h.0 += x.0 * w.0 - x.1 * w.1;
+        h.1 += x.0 * w.1 + x.1 * w.0;
+        w = (w.0 * cos - w.1 * sin, w.0 * sin + w.1 * cos);
+    }
+
+    // Phase rotation over N / 2 samples
+    let (a, b) = (halves[0], halves[1]);
+    let phi = math::atan2(a.0 * b.1 - a.1 * b.0, a.0 * b.0 + a.1 * b.1);
+    let fraction = phi / core::f64::consts::PI;
+
+    let bin = if 2 * peak >= N { peak as isize - N as isize } else { peak as isize };
+    FrequencyOffset { bin, fraction, n: N }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_estimate() {
+        for f in [-5.4, -0.2, 0.0, 3.5, 7.49] {
+            let data: [(f32, f32); 32] = core::array::from_fn(|i| {
+                let (s, c) = cordic::sin_cos(2.0 * core::f64::consts::PI * f * i as f64 / 32.0);
+                (0.5 * c as f32, 0.5 * s as f32)
+            });
+            let offset = estimate(&data);
+            assert_abs_diff_eq!(offset.bins(), f, epsilon = 1e-3);
+            assert_abs_diff_eq!(offset.cycles_per_sample(), f / 32.0, epsilon = 1e-4);
+        }
+    }
+}
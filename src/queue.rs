@@ -0,0 +1,212 @@
+/* embfft | queue.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Sample queue, from an interrupt handler to the processing loop
+
+/******************************************************************************/
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::framer::Framer;
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Lock-free single-producer single-consumer sample queue
+///
+/// The queue is split into a [`Producer`], typically used in the ADC interrupt handler, and a
+/// [`Consumer`], that feeds the [`Framer`] in the processing loop. Only atomic loads and stores are used,
+/// so that it also works on cores without compare-and-swap instructions. The samples that do not fit in
+/// the queue are discarded and counted, so that overruns can be detected.
+///
+/// For example:
+/// ```
+/// use embfft::queue::SampleQueue;
+///
+/// let mut queue = SampleQueue::<f32, 16>::new();
+/// let (mut producer, mut consumer) = queue.split();
+/// let mut framer = embfft::framer::Framer::<f32, 8>::new(8).unwrap();
+///
+/// for i in 0..8 {
+///     producer.enqueue(i as f32).unwrap();
+/// }
+/// assert!(consumer.feed(&mut framer));
+/// assert!(!consumer.take_overrun());
+/// ```
+pub struct SampleQueue<T, const N: usize> {
+    buffer: UnsafeCell<[MaybeUninit<T>; N]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicUsize
+}
+
+// The producer and the consumer never access the same slot at the same time
+unsafe impl<T: Send, const N: usize> Sync for SampleQueue<T, N> {}
+
+/// Writing end of a [`SampleQueue`]
+pub struct Producer<'a, T, const N: usize> {
+    queue: &'a SampleQueue<T, N>
+}
+
+/// Reading end of a [`SampleQueue`]
+pub struct Consumer<'a, T, const N: usize> {
+    queue: &'a SampleQueue<T, N>,
+    dropped: usize
+}
+
+impl<T: Copy, const N: usize> SampleQueue<T, N> {
+    /// Initializes an empty queue, that can hold up to N samples
+    pub const fn new() -> Self {
+        Self {
+            buffer: UnsafeCell::new([const { MaybeUninit::uninit() }; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0)
+        }
+    }
+
+    /// Splits the queue into its writing and reading ends
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        (Producer { queue: self }, Consumer { queue: self, dropped })
+    }
+
+    fn len(&self) -> usize {
+        self.tail.load(Ordering::Acquire).wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+}
+
+impl<T: Copy, const N: usize> Default for SampleQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, const N: usize> Producer<'_, T, N> {
+    /// Adds a sample to the queue
+    ///
+    /// Fails with [`Error::Overrun`] if the queue is full, the sample is then discarded.
+    pub fn enqueue(&mut self, x: T) -> Result<(), Error> {
+        let queue = self.queue;
+        let tail = queue.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(queue.head.load(Ordering::Acquire)) >= N {
+            // Only the producer writes this counter
+            queue.dropped.store(queue.dropped.load(Ordering::Relaxed).wrapping_add(1), Ordering::Release);
+            return Err(Error::Overrun);
+        }
+        // The consumer does not read this slot until the tail is updated
+        unsafe { (*queue.buffer.get())[tail % N] = MaybeUninit::new(x) };
+        queue.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Checks if the queue is full
+    pub fn is_full(&self) -> bool {
+        self.queue.len() >= N
+    }
+}
+
+impl<T: Copy, const N: usize> Consumer<'_, T, N> {
+    /// Removes the oldest sample from the queue
+    pub fn dequeue(&mut self) -> Option<T> {
+        let queue = self.queue;
+        let head = queue.head.load(Ordering::Relaxed);
+        if queue.tail.load(Ordering::Acquire) == head {
+            return None;
+        }
+        // The producer does not write this slot until the head is updated
+        let x = unsafe { (*queue.buffer.get())[head % N].assume_init() };
+        queue.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(x)
+    }
+
+    /// Returns the number of samples waiting in the queue
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks if samples were discarded since the last call
+    pub fn take_overrun(&mut self) -> bool {
+        let dropped = self.queue.dropped.load(Ordering::Acquire);
+        let overrun = dropped != self.dropped;
+        self.dropped = dropped;
+        overrun
+    }
+
+    /// Moves samples from the queue to a frame manager, until a frame is available or the queue is empty
+    ///
+    /// Returns `true` if a frame is available.
+    pub fn feed<const M: usize, W: Window<T, M>>(&mut self, framer: &mut Framer<T, M, W>) -> bool
+    where
+        T: Float<M>
+    {
+        while !framer.is_ready() {
+            match self.dequeue() {
+                Some(x) => { framer.push(x); },
+                None => { break; }
+            }
+        }
+        framer.is_ready()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue() {
+        let mut queue = SampleQueue::<u16, 4>::new();
+        let (mut producer, mut consumer) = queue.split();
+        assert_eq!(consumer.dequeue(), None);
+
+        for i in 0..4 {
+            producer.enqueue(i).unwrap();
+        }
+        assert!(producer.is_full());
+        assert_eq!(producer.enqueue(4), Err(Error::Overrun));
+        assert!(consumer.take_overrun());
+        assert!(!consumer.take_overrun());
+
+        // Wrap around the end of the buffer
+        assert_eq!(consumer.dequeue(), Some(0));
+        assert_eq!(consumer.dequeue(), Some(1));
+        producer.enqueue(5).unwrap();
+        assert_eq!(consumer.len(), 3);
+        for i in [2, 3, 5] {
+            assert_eq!(consumer.dequeue(), Some(i));
+        }
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_feed() {
+        let mut queue = SampleQueue::<f32, 8>::new();
+        let (mut producer, mut consumer) = queue.split();
+        let mut framer = Framer::<f32, 4>::new(2).unwrap();
+        for i in 0..6 {
+            producer.enqueue(i as f32).unwrap();
+        }
+
+        let mut data = [(0.0, 0.0); 4];
+        assert!(consumer.feed(&mut framer));
+        framer.read(&mut data).unwrap();
+        assert!(consumer.feed(&mut framer));
+        framer.read(&mut data).unwrap();
+        assert_eq!(data, [(2.0, 0.0), (3.0, 0.0), (4.0, 0.0), (5.0, 0.0)]);
+        assert!(!consumer.feed(&mut framer));
+    }
+}
@@ -0,0 +1,125 @@
+/* embfft | real.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Transforms of real signals
+//!
+//! The spectrum of a real signal is conjugate-symmetric, so that an N-point transform can be computed with
+//! an N / 2-point complex transform and an additional pass. The size of that transform must be given as
+//! a second parameter M, equal to N / 2.
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::status::Status;
+use crate::EmbIfft;
+
+/******************************************************************************/
+
+/// Returns the twiddle factor e^(+j2πk / N), with 0 <= k < N / 2, from the sine table
+pub(crate) fn twiddle<T: Float<N>, const N: usize>(k: usize) -> (T, T) {
+    let table = &T::SINE_TABLE;
+    if k == 0 {
+        (T::ONE, T::ZERO)
+    } else if 4 * k < N {
+        (table[N / 4 - k], table[k])
+    } else if 4 * k == N {
+        (T::ZERO, T::ONE)
+    } else {
+        (-table[k - N / 4], table[N / 2 - k])
+    }
+}
+
+/// Computes the inverse transform of a conjugate-symmetric spectrum, i.e. the spectrum of a real signal
+///
+/// The even and odd samples of the output are computed together by an M-point IFFT, with M = N / 2,
+/// which halves the work. The imaginary parts of the output are exactly zero, and the output is
+/// multiplied by 1 / N, like with [`EmbIfft`]. The symmetry of the input is not checked: the imaginary
+/// part of a non-symmetric spectrum is discarded.
+/// This is a blocking function, which returns the status flags of the conversion.
+/// For example:
+/// ```
+/// let mut data = [(0.0f32, 0.0); 8];
+/// data[1] = (2.0, 0.0);
+/// data[7] = (2.0, 0.0);
+/// embfft::real::symmetric_ifft::<f32, 8, 4>(&mut data);
+/// assert_eq!(data[0], (0.5, 0.0));
+/// assert_eq!(data[2].1, 0.0);
+/// ```
+pub fn symmetric_ifft<T: Float<N> + Float<M>, const N: usize, const M: usize>(data: &mut [(T, T); N]) -> Status {
+    const { assert!(2 * M == N, "M must be equal to N / 2") };
+
+    // Z[k] = A[k] + jB[k], with A and B the spectra of the even and odd samples
+    let half = <T as Float<N>>::from_f64(0.5);
+    for k in 0..M {
+        let (x, y) = (data[k], data[k + M]);
+        let a = ((x.0 + y.0) * half, (x.1 + y.1) * half);
+        let d = ((x.0 - y.0) * half, (x.1 - y.1) * half);
+        let (c, s) = twiddle::<T, N>(k);
+        let b = (d.0 * c - d.1 * s, d.0 * s + d.1 * c);
+        data[k] = (a.0 - b.1, a.1 + b.0);
+    }
+
+    let mut status = Status::OK;
+    if let Some((lower, _)) = data.split_first_chunk_mut::<M>() {
+        let mut ifft = EmbIfft::<T, M>::new(lower);
+        ifft.ifft();
+        status = ifft.status();
+    }
+
+    // z[n] = x[2n] + jx[2n + 1], unpacked from the end so that no sample is overwritten before being read
+    for n in (0..M).rev() {
+        let z = data[n];
+        data[2 * n + 1] = (z.1, <T as Float<N>>::ZERO);
+        data[2 * n] = (z.0, <T as Float<N>>::ZERO);
+    }
+    status
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::Q31;
+    use crate::EmbFft;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_symmetric_ifft() {
+        let mut data = [(0.0f64, 0.0); 64];
+        for (n, x) in data.iter_mut().enumerate() {
+            x.0 = ((n * 7) % 13) as f64 - 6.0;
+        }
+        let signal = data;
+        EmbFft::new(&mut data).fft();
+        let mut reference = data;
+        EmbIfft::new(&mut reference).ifft();
+
+        let status = symmetric_ifft::<f64, 64, 32>(&mut data);
+        assert!(!status.is_corrupted());
+        for ((x, y), s) in data.iter().zip(reference.iter()).zip(signal.iter()) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.0, s.0, epsilon = 1e-12);
+            assert_eq!(x.1, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        // Cosine at bin 3 and sine at bin 5
+        let mut data = [(Q31::from_f64(0.0), Q31::from_f64(0.0)); 16];
+        data[3] = (Q31::from_f64(0.25), Q31::from_f64(0.0));
+        data[13] = (Q31::from_f64(0.25), Q31::from_f64(0.0));
+        data[5] = (Q31::from_f64(0.0), Q31::from_f64(-0.25));
+        data[11] = (Q31::from_f64(0.0), Q31::from_f64(0.25));
+        symmetric_ifft::<Q31, 16, 8>(&mut data);
+        for (n, x) in data.iter().enumerate() {
+            let t = 2.0 * core::f64::consts::PI * n as f64 / 16.0;
+            let expected = ((3.0 * t).cos() + (5.0 * t).sin()) / 32.0;
+            assert_abs_diff_eq!(x.0.to_f64(), expected, epsilon = 1e-8);
+            assert_eq!(x.1.to_f64(), 0.0);
+        }
+    }
+}
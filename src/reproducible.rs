@@ -0,0 +1,110 @@
+/* embfft | reproducible.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Bit-exact reproducibility across targets
+//!
+//! The transforms only use additions, subtractions and multiplications in a fixed order, with twiddle
+//! factors computed at compile time, and Rust never contracts a multiplication and an addition into a
+//! fused multiply-add on its own. On every target with IEEE 754 single-precision arithmetic, such as the
+//! Cortex-M4, the Cortex-M7, the Cortex-A and the usual hosts, the f32 results are therefore identical to
+//! the last bit. The `bit-exact` feature refuses to build for the targets where this does not hold, such
+//! as the x86 targets without SSE2, whose x87 unit rounds to extended precision.
+//!
+//! This module provides a fingerprint of the spectra, and a reference vector whose fingerprint is known,
+//! so that a device can check at run time that it produces the same results as the host.
+//! For example:
+//! ```
+//! assert!(embfft::reproducible::check_golden_vector());
+//! ```
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::synth::Lfsr;
+use crate::{EmbFft, EmbIfft};
+
+/******************************************************************************/
+
+/// Length of the reference vector
+pub const GOLDEN_LENGTH: usize = 256;
+
+/// Fingerprint of the f32 spectrum of the reference vector
+pub const GOLDEN_SPECTRUM: u64 = 0x102B_C8F8_8B6B_1A70;
+
+/// Fingerprint of the f32 reference vector after an FFT and an IFFT
+pub const GOLDEN_ROUND_TRIP: u64 = 0x8076_6DCA_038A_7447;
+
+/// Computes a 64-bit fingerprint of a buffer, from the exact bit patterns of its values
+///
+/// Two buffers only have the same fingerprint if their values are identical, barring a collision: even
+/// the sign of zero and the payload of NaNs are taken into account. This is a 64-bit FNV-1a hash, which
+/// is not meant to be cryptographically secure.
+pub fn fingerprint<T: Float<N>, const N: usize>(data: &[(T, T); N]) -> u64 {
+    let mut hash = 0xCBF2_9CE4_8422_2325u64;
+    for x in data.iter() {
+        for bits in [x.0.to_f64().to_bits(), x.1.to_f64().to_bits()] {
+            for byte in bits.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01B3);
+            }
+        }
+    }
+    hash
+}
+
+/// Writes the reference vector, pseudo-random complex samples between -1 and 1
+pub fn golden_vector<T: Float<N>, const N: usize>(data: &mut [(T, T); N]) {
+    let mut lfsr = Lfsr::new(0x5EED_F00D);
+    for x in data.iter_mut() {
+        *x = (T::from_f64(lfsr.uniform()), T::from_f64(lfsr.uniform()));
+    }
+}
+
+/// Transforms the reference vector back and forth in single precision, and checks the fingerprints of
+/// the results against those obtained on the host
+///
+/// Returns `false` if the results of this target differ from the host ones, even by a single bit.
+/// This is a blocking function, which needs a buffer of [`GOLDEN_LENGTH`] samples on the stack.
+pub fn check_golden_vector() -> bool {
+    let mut data = [(0.0f32, 0.0); GOLDEN_LENGTH];
+    golden_vector(&mut data);
+    EmbFft::new(&mut data).fft();
+    let spectrum = fingerprint(&data);
+    EmbIfft::new(&mut data).ifft();
+    spectrum == GOLDEN_SPECTRUM && fingerprint(&data) == GOLDEN_ROUND_TRIP
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let mut data = [(0.0f32, 0.0); 8];
+        let reference = fingerprint(&data);
+        data[3].1 = -0.0;
+        assert_ne!(fingerprint(&data), reference);
+        data[3].1 = 0.0;
+        assert_eq!(fingerprint(&data), reference);
+        data[7].0 = f32::from_bits(1);
+        assert_ne!(fingerprint(&data), reference);
+    }
+
+    #[test]
+    fn test_golden_vector() {
+        assert!(check_golden_vector());
+
+        // The non-blocking API goes through the same operations
+        let mut data = [(0.0f32, 0.0); GOLDEN_LENGTH];
+        golden_vector(&mut data);
+        let mut fft = EmbFft::new(&mut data);
+        while !fft.is_done() {
+            fft.fft_iterate();
+        }
+        assert_eq!(fingerprint(&data), GOLDEN_SPECTRUM);
+    }
+}
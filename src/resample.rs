@@ -0,0 +1,190 @@
+/* embfft | resample.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Resampling of spectra onto arbitrary frequency grids
+//!
+//! The bins of a transform are evenly spaced, while standards specify their limits at given frequencies,
+//! such as the third-octave centers. These functions interpolate the magnitude or the phase of the
+//! spectrum of a real signal at any frequency between DC and the Nyquist frequency.
+//! For example:
+//! ```
+//! use embfft::resample::{resample_magnitude, Resampling};
+//! use embfft::units::{Hertz, SampleRate};
+//!
+//! let mut data = [(0.0f32, 0.0); 64];
+//! data[0] = (1.0, 0.0);
+//! embfft::EmbFft::new(&mut data).fft();
+//!
+//! let frequencies = [Hertz(31.5), Hertz(63.0), Hertz(125.0), Hertz(250.0)];
+//! let mut magnitude = [0.0; 4];
+//! let fs = SampleRate::new(1000.0).unwrap();
+//! resample_magnitude(&data, fs, &frequencies, Resampling::Cubic, &mut magnitude).unwrap();
+//! assert!(magnitude.iter().all(|m| (m - 1.0).abs() < 1e-6));
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::{PI, TAU};
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+use crate::units::{Hertz, SampleRate};
+
+/******************************************************************************/
+
+/// Interpolation between the bins
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Resampling {
+    /// Straight line between the two nearest bins
+    Linear,
+    /// Catmull-Rom spline through the four nearest bins
+    ///
+    /// Smoother than the linear interpolation, but can overshoot near sharp peaks.
+    Cubic
+}
+
+impl Resampling {
+    /// Interpolates between `p[1]` at t = 0 and `p[2]` at t = 1
+    fn interpolate(self, p: [f64; 4], t: f64) -> f64 {
+        match self {
+            Resampling::Linear => p[1] + t * (p[2] - p[1]),
+            Resampling::Cubic => {
+                let a = -0.5 * p[0] + 1.5 * p[1] - 1.5 * p[2] + 0.5 * p[3];
+                let b = p[0] - 2.5 * p[1] + 2.0 * p[2] - 0.5 * p[3];
+                let c = 0.5 * (p[2] - p[0]);
+                ((a * t + b) * t + c) * t + p[1]
+            }
+        }
+    }
+}
+
+/// Wraps an angle between -π and +π
+fn wrap(x: f64) -> f64 {
+    let x = x - TAU * ((x / TAU) as i64 as f64);
+    if x > PI { x - TAU } else if x < -PI { x + TAU } else { x }
+}
+
+/// Calls `f` with the four bins around each frequency, in natural order and circular, and the fraction t
+fn resample<T: Float<N>, const N: usize>(
+    spectrum: &[(T, T); N],
+    sample_rate: SampleRate,
+    frequencies: &[Hertz],
+    output: &mut [f64],
+    mut f: impl FnMut([(f64, f64); 4], f64) -> f64
+) -> Result<(), Error> {
+    if frequencies.len() != output.len() {
+        return Err(Error::InvalidLength);
+    }
+    if frequencies.iter().any(|f| f.0.is_nan() || f.0 < 0.0 || *f > sample_rate.nyquist()) {
+        return Err(Error::OutOfRange);
+    }
+    for (frequency, y) in frequencies.iter().zip(output.iter_mut()) {
+        let bin = sample_rate.to_bin::<N>(*frequency);
+        let k = (bin as usize).min(N / 2);
+        let get = |i: usize| {
+            let x = spectrum[(k + N + i - 1) % N];
+            (x.0.to_f64(), x.1.to_f64())
+        };
+        *y = f([get(0), get(1), get(2), get(3)], bin - k as f64);
+    }
+    Ok(())
+}
+
+/// Interpolates the magnitude of the spectrum of a real signal, in natural order, at the given frequencies
+///
+/// Fails with [`Error::InvalidLength`] if `frequencies` and `magnitude` have different lengths, and with
+/// [`Error::OutOfRange`] if a frequency is negative or above the Nyquist frequency.
+pub fn resample_magnitude<T: Float<N>, const N: usize>(
+    spectrum: &[(T, T); N],
+    sample_rate: SampleRate,
+    frequencies: &[Hertz],
+    method: Resampling,
+    magnitude: &mut [f64]
+) -> Result<(), Error> {
+    resample(spectrum, sample_rate, frequencies, magnitude, |bins, t| {
+        let p = bins.map(|x| math::sqrt(x.0 * x.0 + x.1 * x.1));
+        method.interpolate(p, t).max(0.0)
+    })
+}
+
+/// Interpolates the phase of the spectrum of a real signal, in natural order, at the given frequencies
+///
+/// The phases of the neighbouring bins are unwrapped before the interpolation, and the result is between
+/// -π and +π.
+/// Fails with [`Error::InvalidLength`] if `frequencies` and `phase` have different lengths, and with
+/// [`Error::OutOfRange`] if a frequency is negative or above the Nyquist frequency.
+pub fn resample_phase<T: Float<N>, const N: usize>(
+    spectrum: &[(T, T); N],
+    sample_rate: SampleRate,
+    frequencies: &[Hertz],
+    method: Resampling,
+    phase: &mut [f64]
+) -> Result<(), Error> {
+    resample(spectrum, sample_rate, frequencies, phase, |bins, t| {
+        let mut p = bins.map(|x| math::atan2(x.1, x.0));
+        for i in [0, 2, 3] {
+            let reference = if i == 3 { p[2] } else { p[1] };
+            p[i] = reference + wrap(p[i] - reference);
+        }
+        wrap(method.interpolate(p, t))
+    })
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_wrap() {
+        for (x, expected) in [(0.5, 0.5), (4.0, 4.0 - TAU), (-4.0, TAU - 4.0), (7.0, 7.0 - TAU), (-10.0, 4.0 * PI - 10.0)] {
+            assert_abs_diff_eq!(wrap(x), expected, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_magnitude() {
+        let fs = SampleRate::new(64.0).unwrap();
+        let mut data = [(0.0f64, 0.0); 64];
+        for (k, x) in data.iter_mut().enumerate() {
+            let f = k.min(64 - k) as f64;
+            *x = (f * f, 0.0);
+        }
+        let mut magnitude = [0.0; 2];
+        assert_eq!(resample_magnitude(&data, fs, &[Hertz(1.0)], Resampling::Linear, &mut magnitude), Err(Error::InvalidLength));
+        assert_eq!(resample_magnitude(&data, fs, &[Hertz(1.0), Hertz(32.5)], Resampling::Linear, &mut magnitude), Err(Error::OutOfRange));
+
+        // The spline is exact for a parabola, the linear interpolation is not
+        resample_magnitude(&data, fs, &[Hertz(3.5), Hertz(32.0)], Resampling::Cubic, &mut magnitude).unwrap();
+        assert_abs_diff_eq!(magnitude[0], 12.25, epsilon = 1e-9);
+        assert_abs_diff_eq!(magnitude[1], 1024.0, epsilon = 1e-9);
+        resample_magnitude(&data, fs, &[Hertz(3.5), Hertz(0.5)], Resampling::Linear, &mut magnitude).unwrap();
+        assert_abs_diff_eq!(magnitude[0], 12.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(magnitude[1], 0.5, epsilon = 1e-9);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_phase() {
+        // A delay of 10 samples gives a linear phase, wrapped many times
+        let fs = SampleRate::new(1.0).unwrap();
+        let mut data = [(0.0f64, 0.0); 64];
+        data[10] = (1.0, 0.0);
+        crate::EmbFft::new(&mut data).fft();
+        let frequencies: [Hertz; 8] = core::array::from_fn(|i| Hertz(0.06 * i as f64 + 0.01));
+        let mut phase = [0.0; 8];
+        for method in [Resampling::Linear, Resampling::Cubic] {
+            resample_phase(&data, fs, &frequencies, method, &mut phase).unwrap();
+            for (f, p) in frequencies.iter().zip(phase.iter()) {
+                assert_abs_diff_eq!(wrap(p + TAU * 10.0 * f.0), 0.0, epsilon = 1e-6);
+            }
+        }
+    }
+}
@@ -0,0 +1,423 @@
+/* embfft | rfft.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::kernel::Kernel;
+use crate::real::twiddle;
+use crate::status::Status;
+use crate::window::Rectangular;
+
+/******************************************************************************/
+
+/// Fast Fourier transform of a real signal
+///
+/// The N real samples are packed by pairs into M = N / 2 complex values, transformed in place by an
+/// M-point FFT, and separated by a final pass into the N / 2 + 1 unique bins of the spectrum. This halves
+/// the memory and the work compared to an [`EmbFft`](crate::EmbFft) of the same signal. The size M must
+/// be given as a second parameter, like in the [`real`](crate::real) module.
+/// The bins are stored in the sample buffer, the real parts of the DC and Nyquist bins, whose imaginary
+/// parts are zero, sharing the first two samples: use [`EmbRfft::get_bin()`] or [`EmbRfft::unpack()`]
+/// to read them.
+/// For example:
+/// ```
+/// let mut data = [0.0f32, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, -1.0];
+/// let mut rfft = embfft::EmbRfft::<f32, 8, 4>::new(&mut data);
+/// rfft.fft();
+/// assert_eq!(rfft.get_bin(0), Ok((0.0, 0.0)));
+/// assert_eq!(rfft.get_bin(2), Ok((0.0, -4.0)));
+/// ```
+pub struct EmbRfft<'a, T, const N: usize, const M: usize> {
+    kernel: Kernel<'a, T, M, false, Rectangular, [T; N]>,
+    k: usize,
+    status: Status
+}
+
+impl<'a, T: Float<N> + Float<M>, const N: usize, const M: usize> EmbRfft<'a, T, N, M> {
+    /// Initializes a new conversion
+    ///
+    /// Use this function whenever a new conversion is required.
+    pub fn new(data: &'a mut [T; N]) -> Self {
+        const { assert!(2 * M == N, "M must be equal to N / 2") };
+        Self {
+            kernel: Kernel::new(data, true, false),
+            k: 0,
+            status: Status::OK
+        }
+    }
+
+    /// Initializes a new conversion from a slice
+    ///
+    /// Fails with [`Error::InvalidLength`] if the slice length is not N.
+    pub fn try_from_slice(data: &'a mut [T]) -> Result<Self, Error> {
+        data.try_into().map(Self::new).map_err(|_| Error::InvalidLength)
+    }
+
+    /// Stores a computed value, keeping track of abnormal values
+    fn store(&mut self, idx: usize, x: T) {
+        self.status |= <T as Float<N>>::status(x);
+        self.kernel.data_mut()[idx] = x;
+    }
+
+    /// Separates the bins k and M - k, with 0 <= k <= M / 2
+    fn separate(&mut self, k: usize) {
+        let data = self.kernel.data();
+        if k == 0 {
+            // DC and Nyquist bins, both real
+            let z = (data[0], data[1]);
+            self.store(0, z.0 + z.1);
+            self.store(1, z.0 - z.1);
+            return;
+        }
+
+        // Z[k] = E[k] + jO[k], with E and O the spectra of the even and odd samples
+        let half = <T as Float<N>>::from_f64(0.5);
+        let j = M - k;
+        let (zk, zj) = ((data[2 * k], data[2 * k + 1]), (data[2 * j], data[2 * j + 1]));
+        let e = ((zk.0 + zj.0) * half, (zk.1 - zj.1) * half);
+        let o = ((zk.1 + zj.1) * half, (zj.0 - zk.0) * half);
+
+        // X[k] = E[k] + W^k O[k] and X[M - k] = conj(E[k] - W^k O[k]), with W = e^(-j2π / N)
+        let (c, s) = twiddle::<T, N>(k);
+        let p = (o.0 * c + o.1 * s, o.1 * c - o.0 * s);
+        self.store(2 * j, e.0 - p.0);
+        self.store(2 * j + 1, p.1 - e.1);
+        self.store(2 * k, e.0 + p.0);
+        self.store(2 * k + 1, e.1 + p.1);
+    }
+
+    /// Non-blocking FFT computation
+    ///
+    /// Use this together with the [`EmbRfft::is_done()`] function.
+    pub fn fft_iterate(&mut self) {
+        if !self.kernel.is_done() {
+            self.kernel.iterate();
+        } else if self.k <= M / 2 {
+            self.separate(self.k);
+            self.k += 1;
+        }
+    }
+
+    /// Blocking FFT computation
+    pub fn fft(&mut self) {
+        while !self.is_done() {
+            self.fft_iterate();
+        }
+    }
+
+    /// Checks if the conversion is complete
+    ///
+    /// Use this together with the [`EmbRfft::fft_iterate()`] function.
+    pub fn is_done(&self) -> bool {
+        self.kernel.is_done() && self.k > M / 2
+    }
+
+    /// Returns the number of real samples of the transform
+    pub const fn len() -> usize {
+        N
+    }
+
+    /// Returns the status flags accumulated so far
+    ///
+    /// Use this to detect overflows or invalid values, and discard the corrupted results.
+    pub fn status(&self) -> Status {
+        self.kernel.status() | self.status
+    }
+
+    /// Returns the bin at index `k`, for 0 <= k <= N / 2
+    ///
+    /// The other bins are the complex conjugates of the bins N - k.
+    /// Fails with [`Error::NotDone`] until the conversion is complete, or with [`Error::OutOfRange`]
+    /// if `k` is greater than N / 2.
+    pub fn get_bin(&self, k: usize) -> Result<(T, T), Error> {
+        if !self.is_done() {
+            return Err(Error::NotDone);
+        }
+        let data = self.kernel.data();
+        let zero = <T as Float<N>>::ZERO;
+        match k {
+            0 => Ok((data[0], zero)),
+            k if k == M => Ok((data[1], zero)),
+            k if k < M => Ok((data[2 * k], data[2 * k + 1])),
+            _ => Err(Error::OutOfRange)
+        }
+    }
+
+    /// Copies the N / 2 + 1 unique bins, sorted by frequency
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete, or with [`Error::InvalidLength`]
+    /// if the length of `bins` is not N / 2 + 1.
+    pub fn unpack(&self, bins: &mut [(T, T)]) -> Result<(), Error> {
+        if !self.is_done() {
+            return Err(Error::NotDone);
+        }
+        if bins.len() != M + 1 {
+            return Err(Error::InvalidLength);
+        }
+        for (k, bin) in bins.iter_mut().enumerate() {
+            *bin = self.get_bin(k)?;
+        }
+        Ok(())
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    ///
+    /// If the conversion is complete, the buffer contains the packed bins, see [`EmbRfft`].
+    pub fn into_data(self) -> &'a mut [T; N] {
+        self.kernel.into_data()
+    }
+}
+
+/******************************************************************************/
+
+/// Inverse fast Fourier transform of a conjugate-symmetric spectrum, i.e. the spectrum of a real signal
+///
+/// The N / 2 + 1 unique bins are stored in the sample buffer with the same packing as the output of
+/// [`EmbRfft`], and are combined by a first pass into M = N / 2 complex values, whose M-point IFFT gives
+/// the N real samples directly, in place. The output is multiplied by 1 / N, like with
+/// [`EmbIfft`](crate::EmbIfft).
+/// For example:
+/// ```
+/// let mut data = [0.0f32; 8];
+/// let mut irfft = embfft::EmbIrfft::<f32, 8, 4>::from_bins(&mut data, &[
+///     (0.0, 0.0), (0.0, 0.0), (0.0, -4.0), (0.0, 0.0), (0.0, 0.0)
+/// ]).unwrap();
+/// irfft.ifft();
+/// assert_eq!(irfft.output(), Ok(&[0.0, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, -1.0]));
+/// ```
+pub struct EmbIrfft<'a, T, const N: usize, const M: usize> {
+    kernel: Kernel<'a, T, M, true, Rectangular, [T; N]>,
+    k: usize,
+    status: Status
+}
+
+impl<'a, T: Float<N> + Float<M>, const N: usize, const M: usize> EmbIrfft<'a, T, N, M> {
+    /// Initializes a new conversion of packed bins, see [`EmbRfft`]
+    ///
+    /// Use this function whenever a new conversion is required.
+    pub fn new(data: &'a mut [T; N]) -> Self {
+        const { assert!(2 * M == N, "M must be equal to N / 2") };
+        Self {
+            kernel: Kernel::new_dit(data, true),
+            k: 0,
+            status: Status::OK
+        }
+    }
+
+    /// Initializes a new conversion from a slice of packed bins
+    ///
+    /// Fails with [`Error::InvalidLength`] if the slice length is not N.
+    pub fn try_from_slice(data: &'a mut [T]) -> Result<Self, Error> {
+        data.try_into().map(Self::new).map_err(|_| Error::InvalidLength)
+    }
+
+    /// Initializes a new conversion, packing the N / 2 + 1 unique bins, sorted by frequency, into `data`
+    ///
+    /// The imaginary parts of the DC and Nyquist bins are ignored.
+    /// Fails with [`Error::InvalidLength`] if the length of `bins` is not N / 2 + 1.
+    pub fn from_bins(data: &'a mut [T; N], bins: &[(T, T)]) -> Result<Self, Error> {
+        if bins.len() != M + 1 {
+            return Err(Error::InvalidLength);
+        }
+        data[0] = bins[0].0;
+        data[1] = bins[M].0;
+        for (k, bin) in bins.iter().enumerate().take(M).skip(1) {
+            data[2 * k] = bin.0;
+            data[2 * k + 1] = bin.1;
+        }
+        Ok(Self::new(data))
+    }
+
+    /// Stores a computed value, keeping track of abnormal values
+    fn store(&mut self, idx: usize, x: T) {
+        self.status |= <T as Float<N>>::status(x);
+        self.kernel.data_mut()[idx] = x;
+    }
+
+    /// Combines the bins k and M - k, with 0 <= k <= M / 2
+    fn combine(&mut self, k: usize) {
+        let data = self.kernel.data();
+        let half = <T as Float<N>>::from_f64(0.5);
+        if k == 0 {
+            // DC and Nyquist bins, both real
+            let x = (data[0], data[1]);
+            self.store(0, (x.0 + x.1) * half);
+            self.store(1, (x.0 - x.1) * half);
+            return;
+        }
+
+        // E[k] = (X[k] + conj(X[M - k])) / 2 and W^k O[k] = (X[k] - conj(X[M - k])) / 2, with W = e^(-j2π / N)
+        let j = M - k;
+        let (xk, xj) = ((data[2 * k], data[2 * k + 1]), (data[2 * j], data[2 * j + 1]));
+        let e = ((xk.0 + xj.0) * half, (xk.1 - xj.1) * half);
+        let d = ((xk.0 - xj.0) * half, (xk.1 + xj.1) * half);
+        let (c, s) = twiddle::<T, N>(k);
+        let o = (d.0 * c - d.1 * s, d.0 * s + d.1 * c);
+
+        // Z[k] = E[k] + jO[k] and Z[M - k] = conj(E[k]) + j conj(O[k])
+        self.store(2 * j, e.0 + o.1);
+        self.store(2 * j + 1, o.0 - e.1);
+        self.store(2 * k, e.0 - o.1);
+        self.store(2 * k + 1, e.1 + o.0);
+    }
+
+    /// Non-blocking IFFT computation
+    ///
+    /// Use this together with the [`EmbIrfft::is_done()`] function.
+    pub fn ifft_iterate(&mut self) {
+        if self.k <= M / 2 {
+            self.combine(self.k);
+            self.k += 1;
+        } else {
+            self.kernel.iterate();
+        }
+    }
+
+    /// Blocking IFFT computation
+    pub fn ifft(&mut self) {
+        while !self.is_done() {
+            self.ifft_iterate();
+        }
+    }
+
+    /// Checks if the conversion is complete
+    ///
+    /// Use this together with the [`EmbIrfft::ifft_iterate()`] function.
+    pub fn is_done(&self) -> bool {
+        self.k > M / 2 && self.kernel.is_done()
+    }
+
+    /// Returns the number of real samples of the transform
+    pub const fn len() -> usize {
+        N
+    }
+
+    /// Returns the status flags accumulated so far
+    ///
+    /// Use this to detect overflows or invalid values, and discard the corrupted results.
+    pub fn status(&self) -> Status {
+        self.kernel.status() | self.status
+    }
+
+    /// Returns a view of the real samples
+    ///
+    /// Fails with [`Error::NotDone`] until the conversion is complete.
+    pub fn output(&self) -> Result<&[T; N], Error> {
+        if self.is_done() {
+            Ok(self.kernel.data())
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Consumes the conversion and hands back the data buffer
+    ///
+    /// If the conversion is not complete, the buffer contains partially transformed data.
+    pub fn into_data(self) -> &'a mut [T; N] {
+        self.kernel.into_data()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::{Q15, Q31, Saturating};
+    use crate::EmbFft;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_rfft() {
+        let signal: [f64; 64] = core::array::from_fn(|n| ((n * 7) % 13) as f64 - 6.0);
+        let mut reference = signal.map(|x| (x, 0.0));
+        EmbFft::new(&mut reference).fft();
+
+        let mut data = signal;
+        let mut rfft = EmbRfft::<f64, 64, 32>::new(&mut data);
+        assert_eq!(rfft.get_bin(0), Err(Error::NotDone));
+        let mut iterations = 0;
+        while !rfft.is_done() {
+            rfft.fft_iterate();
+            iterations += 1;
+        }
+        assert!(!rfft.status().is_corrupted());
+        assert_eq!(rfft.get_bin(33), Err(Error::OutOfRange));
+
+        let mut bins = [(0.0, 0.0); 33];
+        assert_eq!(rfft.unpack(&mut bins[..32]), Err(Error::InvalidLength));
+        rfft.unpack(&mut bins).unwrap();
+        for (x, y) in bins.iter().zip(reference.iter()) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-9);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-9);
+        }
+
+        // About half the iterations of the complex transform
+        let mut complex = signal.map(|x| (x, 0.0));
+        let mut fft = EmbFft::new(&mut complex);
+        let mut complex_iterations = 0;
+        while !fft.is_done() {
+            fft.fft_iterate();
+            complex_iterations += 1;
+        }
+        assert!(2 * iterations < complex_iterations + complex_iterations / 10);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        // Cosine at bin 3, scaled down to avoid any overflow
+        let mut data: [Q15<Saturating>; 16] = core::array::from_fn(|n| {
+            Q15::from_f64(crate::cordic::cos(2.0 * core::f64::consts::PI * 3.0 * n as f64 / 16.0) / 16.0)
+        });
+        assert!(EmbRfft::<Q15<Saturating>, 16, 8>::try_from_slice(&mut data[..8]).is_err());
+        let mut rfft = EmbRfft::<_, 16, 8>::try_from_slice(&mut data).unwrap();
+        rfft.fft();
+        assert!(!rfft.status().is_corrupted());
+        for k in 0..=8 {
+            let bin = rfft.get_bin(k).unwrap();
+            assert_abs_diff_eq!(bin.0.to_f64(), if k == 3 { 0.5 } else { 0.0 }, epsilon = 1e-3);
+            assert_abs_diff_eq!(bin.1.to_f64(), 0.0, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_irfft() {
+        let signal: [f64; 64] = core::array::from_fn(|n| ((n * 5) % 11) as f64 - 5.0);
+        let mut data = signal;
+        EmbRfft::<f64, 64, 32>::new(&mut data).fft();
+        let mut irfft = EmbIrfft::<f64, 64, 32>::new(&mut data);
+        assert_eq!(irfft.output(), Err(Error::NotDone));
+        irfft.ifft();
+        assert!(irfft.status().contains(Status::SCALED));
+        assert!(!irfft.status().is_corrupted());
+        for (x, y) in irfft.output().unwrap().iter().zip(signal.iter()) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-12);
+        }
+
+        // Same result as the complex transform
+        let mut spectrum = signal.map(|x| (x, 0.0));
+        EmbFft::new(&mut spectrum).fft();
+        assert_eq!(EmbIrfft::<f64, 64, 32>::from_bins(&mut data, &spectrum[..32]).err(), Some(Error::InvalidLength));
+        let mut irfft = EmbIrfft::<f64, 64, 32>::from_bins(&mut data, &spectrum[..33]).unwrap();
+        irfft.ifft();
+        for (x, y) in irfft.into_data().iter().zip(signal.iter()) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_fixed_point() {
+        let signal: [Q31; 32] = core::array::from_fn(|n| Q31::from_f64(((n * 5) % 11) as f64 / 1024.0 - 0.005));
+        let mut data = signal;
+        EmbRfft::<Q31, 32, 16>::new(&mut data).fft();
+        let mut irfft = EmbIrfft::<Q31, 32, 16>::try_from_slice(&mut data).unwrap();
+        irfft.ifft();
+        for (x, y) in irfft.output().unwrap().iter().zip(signal.iter()) {
+            assert_abs_diff_eq!(x.to_f64(), y.to_f64(), epsilon = 1e-8);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+/* embfft | selftest.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Power-on self-test
+//!
+//! A known pseudo-random vector is transformed back and forth, which exercises the arithmetic, the
+//! twiddle tables in flash and the data buffer in RAM.
+//! For example:
+//! ```
+//! let result = embfft::selftest::self_test::<f32, 256>();
+//! assert!(result.passed);
+//! ```
+
+/******************************************************************************/
+
+use crate::common::{Base, Float};
+use crate::metrics;
+use crate::status::Status;
+use crate::synth::{self, Lfsr};
+use crate::{EmbFft, EmbIfft};
+
+/******************************************************************************/
+
+/// Outcome of the self-test
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTest {
+    /// `true` if the error is within the expected bound
+    pub passed: bool,
+    /// RMS round-trip error, relative to the RMS value of the test vector
+    pub error: f64,
+    /// Expected bound of the relative error
+    pub bound: f64
+}
+
+/// Largest relative error accepted, whatever the resolution of the type, i.e. a signal to noise ratio of
+/// 12 dB
+///
+/// A null or corrupted output has a relative error of about 1, and must never pass.
+const MAX_ERROR: f64 = 0.25;
+
+/// Runs a known vector through an FFT and an IFFT, and checks the round-trip error
+///
+/// The amplitude A of the vector is 0.5 / N, low enough to avoid any overflow with the fixed-point types.
+/// The bound of the relative error grows with the square of the number of stages, in units in the last
+/// place of A relative to A, and never exceeds 0.25. The fixed-point types have few bits left for such a
+/// signal as N grows, e.g. Q15 fails beyond 256 points, which is the actual accuracy of their transforms.
+/// This is a blocking function, which needs a buffer of N samples on the stack.
+pub fn self_test<T: Float<N>, const N: usize>() -> SelfTest {
+    let amplitude = 0.5 / N as f64;
+    let mut lfsr = Lfsr::new(0x5EED_F00D);
+    let mut data = [(T::ZERO, T::ZERO); N];
+    synth::noise(&mut data, &mut lfsr, amplitude);
+    for x in data.iter_mut() {
+        x.1 = T::from_f64(amplitude * lfsr.uniform());
+    }
+    let reference = data;
+
+    let mut fft = EmbFft::new(&mut data);
+    fft.fft();
+    let mut status = fft.status();
+    let mut ifft = EmbIfft::new(&mut data);
+    ifft.ifft();
+    status |= ifft.status();
+
+    evaluate(&data, &reference, amplitude, status)
+}
+
+/// Compares the output of the round trip to its input, of the given amplitude
+fn evaluate<T: Float<N>, const N: usize>(output: &[(T, T); N], reference: &[(T, T); N], amplitude: f64, status: Status) -> SelfTest {
+    let error = metrics::rms_relative_error(output, reference);
+    let stages = (Base::<N>::LOG2_N + 1) as f64;
+    let bound = (stages * stages * T::from_f64(amplitude).ulp() / amplitude).min(MAX_ERROR);
+    SelfTest { passed: !status.is_corrupted() && error <= bound, error, bound }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::{Saturating, Q15, Q31};
+
+    #[test]
+    fn test_self_test() {
+        for result in [self_test::<f32, 8>(), self_test::<f32, 1024>(), self_test::<f64, 4096>()] {
+            assert!(result.passed);
+            assert!(result.error < result.bound);
+        }
+        assert!(self_test::<Q15, 256>().passed);
+        assert!(self_test::<Q31, 4096>().passed);
+
+        // Too few bits are left for the signal
+        let result = self_test::<Q15<Saturating>, 1024>();
+        assert!(!result.passed);
+        assert_eq!(result.bound, MAX_ERROR);
+    }
+
+    #[test]
+    fn test_corruption() {
+        let reference: [(f32, f32); 64] = core::array::from_fn(|i| ((i as f32 - 31.5) / 8192.0, 0.001 - (i % 7) as f32 / 8192.0));
+        assert!(evaluate(&reference, &reference, 0.5 / 64.0, Status::OK).passed);
+
+        // Null output, a single wrong sample, or an overflow
+        assert!(!evaluate(&[(0.0, 0.0); 64], &reference, 0.5 / 64.0, Status::OK).passed);
+        let mut output = reference;
+        output[17].0 = -output[17].0;
+        assert!(!evaluate(&output, &reference, 0.5 / 64.0, Status::OK).passed);
+        assert!(!evaluate(&reference, &reference, 0.5 / 64.0, Status::OVERFLOW).passed);
+
+        // Same with the fixed-point types, whose bound is the loosest
+        let reference = reference.map(|x| (Q15::<Saturating>::from_f32(x.0), Q15::from_f32(x.1)));
+        assert!(!evaluate(&[(Q15::from_f32(0.0), Q15::from_f32(0.0)); 64], &reference, 0.5 / 64.0, Status::OK).passed);
+    }
+}
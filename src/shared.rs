@@ -0,0 +1,232 @@
+/* embfft | shared.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Transform shared between two execution contexts
+//!
+//! An [`EmbFft`] must not be polled with [`EmbFft::is_done()`] from an interrupt handler while the main
+//! loop drives it with [`EmbFft::fft_iterate()`], or the reverse: one context would read the state while
+//! the other one modifies it, which is undefined behavior, even if the race looks harmless.
+//! A [`SharedFft`] makes this sound, by splitting the transform into a single [`FftDriver`], which owns
+//! the right to iterate, and any number of [`FftMonitor`]s, which only read an atomic completion flag.
+//! Like the [`SampleQueue`](crate::queue::SampleQueue), only atomic loads and stores are used, so that it
+//! also works on cores without compare-and-swap instructions.
+//! For example:
+//! ```
+//! use embfft::shared::SharedFft;
+//!
+//! let mut data = [(1.0f32, 0.0); 8];
+//! let mut shared = SharedFft::new(embfft::EmbFft::new(&mut data));
+//! let (mut driver, monitor) = shared.split();
+//!
+//! // The monitor can be copied to another context, e.g. an interrupt handler
+//! while !monitor.is_done() {
+//!     driver.iterate();
+//! }
+//! assert_eq!(monitor.get_bin(0), Ok(&(8.0, 0.0)));
+//! ```
+
+/******************************************************************************/
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::status::Status;
+use crate::window::{Rectangular, Window};
+
+/******************************************************************************/
+
+/// Transform that can be driven from one context and polled from another one
+///
+/// The completion flag is set with release ordering after the last write of the transform, and read with
+/// acquire ordering, so that a context which observes the completion also observes the final output. Once
+/// complete, the transform is never modified again, and the monitors can read its output and status.
+/// Before that, they can only read the flag, which may still be `false` for a short while after the last
+/// iteration of the driver, but never becomes `true` too early.
+pub struct SharedFft<'a, T, const N: usize, W = Rectangular> {
+    fft: UnsafeCell<EmbFft<'a, T, N, W>>,
+    done: AtomicBool
+}
+
+// The driver is the only one to modify the transform, and only until the completion flag is set
+unsafe impl<T: Send + Sync, const N: usize, W> Sync for SharedFft<'_, T, N, W> {}
+
+/// Driving end of a [`SharedFft`], which performs the iterations
+pub struct FftDriver<'s, 'a, T, const N: usize, W = Rectangular> {
+    shared: &'s SharedFft<'a, T, N, W>
+}
+
+/// Polling end of a [`SharedFft`], which checks the completion and reads the output
+pub struct FftMonitor<'s, 'a, T, const N: usize, W = Rectangular> {
+    shared: &'s SharedFft<'a, T, N, W>
+}
+
+impl<T, const N: usize, W> Clone for FftMonitor<'_, '_, T, N, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const N: usize, W> Copy for FftMonitor<'_, '_, T, N, W> {}
+
+impl<'a, T: Float<N>, const N: usize, W: Window<T, N>> SharedFft<'a, T, N, W> {
+    /// Wraps a transform, which may already be partially computed
+    pub fn new(fft: EmbFft<'a, T, N, W>) -> Self {
+        let done = fft.is_done();
+        Self {
+            fft: UnsafeCell::new(fft),
+            done: AtomicBool::new(done)
+        }
+    }
+
+    /// Splits the transform into its driving and polling ends
+    ///
+    /// The exclusive borrow guarantees that there is a single driver at a time.
+    pub fn split(&mut self) -> (FftDriver<'_, 'a, T, N, W>, FftMonitor<'_, 'a, T, N, W>) {
+        (FftDriver { shared: self }, FftMonitor { shared: self })
+    }
+
+    /// Unwraps the transform, once both ends are dropped
+    pub fn into_inner(self) -> EmbFft<'a, T, N, W> {
+        self.fft.into_inner()
+    }
+}
+
+impl<T: Float<N>, const N: usize, W: Window<T, N>> FftDriver<'_, '_, T, N, W> {
+    /// Performs a single iteration, or nothing if the transform is complete
+    pub fn iterate(&mut self) {
+        let shared = self.shared;
+        if shared.done.load(Ordering::Relaxed) {
+            return;
+        }
+        // The monitors do not access the transform until the completion flag is set
+        let fft = unsafe { &mut *shared.fft.get() };
+        fft.fft_iterate();
+        if fft.is_done() {
+            shared.done.store(true, Ordering::Release);
+        }
+    }
+
+    /// Performs all the remaining iterations
+    pub fn run(&mut self) {
+        while !self.is_done() {
+            self.iterate();
+        }
+    }
+
+    /// Checks if the transform is complete
+    pub fn is_done(&self) -> bool {
+        self.shared.done.load(Ordering::Relaxed)
+    }
+}
+
+impl<'s, 'a, T: Float<N>, const N: usize, W: Window<T, N>> FftMonitor<'s, 'a, T, N, W> {
+    /// Checks if the transform is complete
+    pub fn is_done(&self) -> bool {
+        self.shared.done.load(Ordering::Acquire)
+    }
+
+    /// Returns the transform, if it is complete
+    fn fft(&self) -> Result<&'s EmbFft<'a, T, N, W>, Error> {
+        if self.is_done() {
+            // The driver does not modify the transform once the completion flag is set
+            Ok(unsafe { &*self.shared.fft.get() })
+        } else {
+            Err(Error::NotDone)
+        }
+    }
+
+    /// Returns the bin at index `k`, sorted by frequency
+    ///
+    /// Fails with [`Error::NotDone`] until the transform is complete, or with [`Error::OutOfRange`] if `k`
+    /// is not lower than N.
+    pub fn get_bin(&self, k: usize) -> Result<&'s (T, T), Error> {
+        self.fft()?.get_bin(k)
+    }
+
+    /// Returns a view of the transformed data
+    ///
+    /// Fails with [`Error::NotDone`] until the transform is complete.
+    pub fn output(&self) -> Result<&'s [(T, T); N], Error> {
+        self.fft()?.output()
+    }
+
+    /// Returns the status flags of the transform
+    ///
+    /// Fails with [`Error::NotDone`] until the transform is complete.
+    pub fn status(&self) -> Result<Status, Error> {
+        Ok(self.fft()?.status())
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    fn input() -> [(f64, f64); 64] {
+        core::array::from_fn(|i| (i as f64, 1.0 - i as f64 / 8.0))
+    }
+
+    #[test]
+    fn test_split() {
+        let mut data = input();
+        let mut shared = SharedFft::new(EmbFft::new(&mut data));
+        let (mut driver, monitor) = shared.split();
+        assert_eq!(monitor.output(), Err(Error::NotDone));
+        assert_eq!(monitor.status(), Err(Error::NotDone));
+        driver.iterate();
+        assert!(!monitor.is_done() && !driver.is_done());
+        driver.run();
+        assert!(monitor.is_done() && driver.is_done());
+        assert_eq!(monitor.status(), Ok(Status::OK));
+        assert_eq!(monitor.get_bin(64), Err(Error::OutOfRange));
+
+        // Further iterations leave the output untouched
+        let output = *monitor.output().unwrap();
+        driver.iterate();
+        assert_eq!(monitor.output(), Ok(&output));
+
+        let mut expected = input();
+        EmbFft::new(&mut expected).fft();
+        assert_eq!(output, expected);
+        assert_eq!(shared.into_inner().output(), Ok(&expected));
+
+        // Already complete when wrapped
+        let mut data = input();
+        let mut fft = EmbFft::new(&mut data);
+        fft.fft();
+        let mut shared = SharedFft::new(fft);
+        assert!(shared.split().1.is_done());
+    }
+
+    #[test]
+    fn test_threads() {
+        let mut expected = input();
+        EmbFft::new(&mut expected).fft();
+
+        for _ in 0..20 {
+            let mut data = input();
+            let mut shared = SharedFft::new(EmbFft::new(&mut data));
+            let (mut driver, monitor) = shared.split();
+            std::thread::scope(|s| {
+                let poller = s.spawn(move || {
+                    while !monitor.is_done() {
+                        std::hint::spin_loop();
+                    }
+                    // Observing the completion implies observing the final output
+                    assert_eq!(monitor.output(), Ok(&expected));
+                });
+                driver.run();
+                poller.join().unwrap();
+            });
+        }
+    }
+}
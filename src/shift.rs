@@ -0,0 +1,138 @@
+/* embfft | shift.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Circular time shifts of the frames
+//!
+//! Frames must be aligned on a common reference before being averaged coherently, and the channels of a
+//! beamformer must be delayed by a fraction of a sample. A delay of d samples multiplies the bin k by
+//! e^(-2jπ·k·d / N), which is exact for any fractional d as long as the frame is periodic, the samples
+//! leaving at one end of the frame entering again at the other end.
+//! For example:
+//! ```
+//! use embfft::shift;
+//!
+//! let mut data = [(0.0f32, 0.0); 8];
+//! data[2] = (1.0, 0.0);
+//! shift::rotate(&mut data, 3);
+//! assert_eq!(data[5], (1.0, 0.0));
+//!
+//! // Half a sample back, between the samples 4 and 5
+//! shift::delay(&mut data, -0.5);
+//! # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+//! assert!((data[4].0 - data[5].0).abs() < 1e-6);
+//! assert!(data[4].0 > 0.6);
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::fft::EmbFft;
+use crate::ifft::EmbIfft;
+use crate::math;
+use crate::status::Status;
+
+/******************************************************************************/
+
+/// Delays a frame by an integer number of samples, circularly
+///
+/// The sample i is moved to i + samples, modulo N. A negative number of samples advances the frame.
+pub fn rotate<T, const N: usize>(data: &mut [T; N], samples: isize) {
+    if N > 0 {
+        data.rotate_right(samples.rem_euclid(N as isize) as usize);
+    }
+}
+
+/// Delays a spectrum by a number of samples, which may be fractional, multiplying each bin by a phase ramp
+///
+/// The bins above N / 2 are the negative frequencies. The Nyquist bin, which is shared by both, is only
+/// multiplied by the real part of its phase factor, so that the spectrum of a real frame stays that of a
+/// real frame.
+pub fn delay_spectrum<T: Float<N>, const N: usize>(spectrum: &mut [(T, T); N], samples: f64) {
+    for (k, x) in spectrum.iter_mut().enumerate() {
+        let f = if 2 * k < N { k as f64 } else { k as f64 - N as f64 };
+        // Keep the argument small, to preserve the accuracy
+        let cycles = -f * samples / N as f64;
+        let (sin, cos) = math::sin_cos(2.0 * PI * (cycles - cycles as i64 as f64));
+        let (sin, cos) = if 2 * k == N { (0.0, cos) } else { (sin, cos) };
+        let (re, im) = (x.0.to_f64(), x.1.to_f64());
+        *x = (T::from_f64(re * cos - im * sin), T::from_f64(re * sin + im * cos));
+    }
+}
+
+/// Delays a frame by a number of samples, which may be fractional, circularly
+///
+/// The frame is transformed, delayed by [`delay_spectrum()`], and transformed back. An integer delay is
+/// equivalent to [`rotate()`], which is much cheaper.
+/// This is a blocking function, which performs an FFT and an IFFT, and returns the status flags of both.
+pub fn delay<T: Float<N>, const N: usize>(data: &mut [(T, T); N], samples: f64) -> Status {
+    let mut fft = EmbFft::new(data);
+    fft.fft();
+    let status = fft.status();
+    delay_spectrum(data, samples);
+    let mut ifft = EmbIfft::new(data);
+    ifft.ifft();
+    status | ifft.status()
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use approx::assert_abs_diff_eq;
+
+    /// Periodic test frame, made of two tones
+    fn frame(delay: f64) -> [(f64, f64); 64] {
+        core::array::from_fn(|i| {
+            let t = i as f64 - delay;
+            (cordic::cos(2.0 * PI * 3.0 * t / 64.0) + 0.5 * cordic::sin(2.0 * PI * 7.0 * t / 64.0), 0.2 * cordic::cos(2.0 * PI * 5.0 * t / 64.0))
+        })
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut data = [0, 1, 2, 3, 4];
+        rotate(&mut data, 2);
+        assert_eq!(data, [3, 4, 0, 1, 2]);
+        rotate(&mut data, -7);
+        assert_eq!(data, [0, 1, 2, 3, 4]);
+        rotate(&mut [0u8; 0], 3);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_delay() {
+        for samples in [0.0, 1.0, 2.5, -0.3, 17.75, -100.2] {
+            let mut data = frame(0.0);
+            let status = delay(&mut data, samples);
+            assert!(!status.is_corrupted());
+            for (x, y) in data.iter().zip(frame(samples).iter()) {
+                assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-9);
+                assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-9);
+            }
+        }
+
+        // Same as the rotation for an integer delay
+        let mut data = frame(0.0);
+        let mut rotated = data;
+        delay(&mut data, -5.0);
+        rotate(&mut rotated, -5);
+        for (x, y) in data.iter().zip(rotated.iter()) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_real_frame() {
+        // A real frame with energy at Nyquist stays real
+        let mut data: [(f64, f64); 16] = core::array::from_fn(|i| (if i % 2 == 0 { 1.0 } else { -1.0 } + i as f64 / 16.0, 0.0));
+        delay(&mut data, 0.37);
+        assert!(data.iter().all(|x| x.1.abs() < 1e-12));
+    }
+}
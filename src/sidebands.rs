@@ -0,0 +1,208 @@
+/* embfft | sidebands.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Sideband and subharmonic analysis
+//!
+//! A modulated carrier, such as a gear mesh tone modulated by the rotation of a worn gear, shows pairs of
+//! sidebands spaced by the modulation frequency. Mechanical looseness and rubbing show subharmonics of the
+//! running speed instead. These helpers look for both among the peaks found by
+//! [`find_peaks()`](crate::containers::find_peaks).
+//!
+//! Requires the `heapless` feature.
+//! For example:
+//! ```
+//! use embfft::containers::find_peaks;
+//! use embfft::sidebands::find_sidebands;
+//!
+//! // Carrier at bin 20, modulated at 3 bins
+//! let mut spectrum = [0.0f32; 64];
+//! spectrum[20] = 1.0;
+//! (spectrum[17], spectrum[23]) = (0.3, 0.2);
+//! (spectrum[14], spectrum[26]) = (0.1, 0.1);
+//!
+//! let peaks = find_peaks::<f32, 64, 8>(&spectrum, 0.05);
+//! let sidebands = find_sidebands::<f32, 64, 4>(&spectrum, &peaks, 20, 0.5).unwrap();
+//! assert_eq!(sidebands.spacing, 3);
+//! assert_eq!(sidebands.pairs.len(), 2);
+//! assert!(sidebands.asymmetry < 0.0);
+//! ```
+
+/******************************************************************************/
+
+use heapless::Vec;
+
+use crate::common::Float;
+use crate::containers::Peak;
+use crate::error::Error;
+
+/******************************************************************************/
+
+/// Sidebands of a given order, on both sides of the carrier
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SidebandPair<T> {
+    /// Order of the sidebands, their distance to the carrier in multiples of the spacing
+    pub order: usize,
+    /// Sideband below the carrier, if found
+    pub lower: Option<Peak<T>>,
+    /// Sideband above the carrier, if found
+    pub upper: Option<Peak<T>>
+}
+
+/// Sidebands around a carrier
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sidebands<T, const M: usize> {
+    /// Carrier
+    pub carrier: Peak<T>,
+    /// Spacing of the sidebands in bins, i.e. the modulation frequency, 0 if no sidebands were found
+    pub spacing: usize,
+    /// Sidebands of the successive orders, from 1 to the last order where at least one was found
+    pub pairs: Vec<SidebandPair<T>, M>,
+    /// Balance of the sidebands, from -1 when only the lower ones are present to +1 when only the upper
+    /// ones are
+    pub asymmetry: f64
+}
+
+/// Subharmonic of a fundamental
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Subharmonic<T> {
+    /// Numerator of the order
+    pub numerator: usize,
+    /// Denominator of the order, the subharmonic frequency being numerator / denominator × the fundamental
+    pub denominator: usize,
+    /// Peak found at that frequency
+    pub peak: Peak<T>
+}
+
+/// Returns the peak closest to a fractional bin, within a tolerance
+fn nearest<T: Copy>(peaks: &[Peak<T>], bin: f64, tolerance: f64) -> Option<Peak<T>> {
+    let distance = |p: &Peak<T>| if p.bin as f64 > bin { p.bin as f64 - bin } else { bin - p.bin as f64 };
+    peaks.iter().filter(|p| distance(p) <= tolerance).fold(None, |best: Option<Peak<T>>, p| match best {
+        Some(q) if distance(&q) <= distance(p) => Some(q),
+        _ => Some(*p)
+    })
+}
+
+/// Finds the sidebands of up to M orders around the carrier at bin `carrier`
+///
+/// `spectrum` holds the magnitudes in which the `peaks` were found. The spacing is the distance to the
+/// carrier of the strongest peak that is mirrored on the other side of the carrier, within `tolerance`
+/// bins; the sidebands of the higher orders are searched at multiples of this spacing.
+/// Fails with [`Error::OutOfRange`] if `carrier` is not lower than N.
+pub fn find_sidebands<T: Float<N>, const N: usize, const M: usize>(spectrum: &[T; N], peaks: &[Peak<T>], carrier: usize, tolerance: f64) -> Result<Sidebands<T, M>, Error> {
+    if carrier >= N {
+        return Err(Error::OutOfRange);
+    }
+    let mut sidebands = Sidebands { carrier: Peak { bin: carrier, value: spectrum[carrier] }, spacing: 0, pairs: Vec::new(), asymmetry: 0.0 };
+
+    // The peaks are sorted strongest first
+    let mirrored = peaks.iter().filter(|p| p.bin != carrier && p.bin < 2 * carrier).find(|p| {
+        nearest(peaks, 2.0 * carrier as f64 - p.bin as f64, tolerance).is_some_and(|q| q.bin != carrier)
+    });
+    let Some(first) = mirrored else {
+        return Ok(sidebands);
+    };
+    let spacing = first.bin.abs_diff(carrier);
+    sidebands.spacing = spacing;
+
+    let (mut lower_sum, mut upper_sum) = (0.0, 0.0);
+    for order in 1..=M {
+        let offset = (order * spacing) as f64;
+        let lower = if offset <= carrier as f64 { nearest(peaks, carrier as f64 - offset, tolerance) } else { None };
+        let upper = nearest(peaks, carrier as f64 + offset, tolerance);
+        if lower.is_none() && upper.is_none() {
+            break;
+        }
+        lower_sum += lower.map_or(0.0, |p| p.value.to_f64());
+        upper_sum += upper.map_or(0.0, |p| p.value.to_f64());
+        let _ = sidebands.pairs.push(SidebandPair { order, lower, upper });
+    }
+    if lower_sum + upper_sum > 0.0 {
+        sidebands.asymmetry = (upper_sum - lower_sum) / (upper_sum + lower_sum);
+    }
+    Ok(sidebands)
+}
+
+/// Finds up to M subharmonics of the fundamental at bin `fundamental`
+///
+/// The subharmonics are searched among the `peaks` at the fractions n / d of the fundamental, with d up
+/// to `max_denominator` and n lower than d, within `tolerance` bins. They are listed by increasing
+/// denominator, then numerator: 1/2, 1/3, 2/3, 1/4, 3/4...
+pub fn find_subharmonics<T: Copy, const M: usize>(peaks: &[Peak<T>], fundamental: usize, max_denominator: usize, tolerance: f64) -> Vec<Subharmonic<T>, M> {
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    let mut subharmonics = Vec::new();
+    for denominator in 2..=max_denominator {
+        for numerator in (1..denominator).filter(|&n| gcd(n, denominator) == 1) {
+            let bin = (fundamental * numerator) as f64 / denominator as f64;
+            if let Some(peak) = nearest(peaks, bin, tolerance) {
+                if subharmonics.push(Subharmonic { numerator, denominator, peak }).is_err() {
+                    return subharmonics;
+                }
+            }
+        }
+    }
+    subharmonics
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::containers::find_peaks;
+
+    #[test]
+    fn test_sidebands() {
+        let mut spectrum = [0.0f64; 64];
+        spectrum[30] = 1.0;
+        // Unrelated tone, stronger than the sidebands but not mirrored
+        spectrum[50] = 0.5;
+        (spectrum[26], spectrum[34]) = (0.2, 0.2);
+        (spectrum[21], spectrum[39]) = (0.1, 0.1);
+        spectrum[42] = 0.05;
+        let peaks = find_peaks::<f64, 64, 8>(&spectrum, 0.01);
+        assert_eq!(find_sidebands::<f64, 64, 4>(&spectrum, &peaks, 64, 0.5), Err(Error::OutOfRange));
+
+        // The second-order sidebands are one bin off, the third order only has an upper sideband
+        let sidebands = find_sidebands::<f64, 64, 4>(&spectrum, &peaks, 30, 1.0).unwrap();
+        assert_eq!(sidebands.carrier, Peak { bin: 30, value: 1.0 });
+        assert_eq!(sidebands.spacing, 4);
+        assert_eq!(sidebands.pairs.len(), 3);
+        assert_eq!(sidebands.pairs[1].lower.map(|p| p.bin), Some(21));
+        assert_eq!(sidebands.pairs[1].upper.map(|p| p.bin), Some(39));
+        assert_eq!(sidebands.pairs[2], SidebandPair { order: 3, lower: None, upper: Some(Peak { bin: 42, value: 0.05 }) });
+        assert!((sidebands.asymmetry - 0.05 / 0.65).abs() < 1e-12);
+
+        // Within a tighter tolerance, the search stops at the first order
+        let sidebands = find_sidebands::<f64, 64, 4>(&spectrum, &peaks, 30, 0.5).unwrap();
+        assert_eq!(sidebands.pairs.len(), 1);
+        assert_eq!(sidebands.asymmetry, 0.0);
+
+        // A lone carrier has no sidebands
+        let sidebands = find_sidebands::<f64, 64, 4>(&spectrum, &peaks[..1], 30, 0.5).unwrap();
+        assert_eq!((sidebands.spacing, sidebands.pairs.len()), (0, 0));
+    }
+
+    #[test]
+    fn test_subharmonics() {
+        let mut spectrum = [0.0f32; 64];
+        spectrum[24] = 1.0;
+        spectrum[12] = 0.3;
+        spectrum[16] = 0.2;
+        spectrum[18] = 0.1;
+        let peaks = find_peaks::<f32, 64, 8>(&spectrum, 0.05);
+
+        let subharmonics = find_subharmonics::<f32, 4>(&peaks, 24, 4, 0.5);
+        let orders: Vec<_, 4> = subharmonics.iter().map(|s| (s.numerator, s.denominator, s.peak.bin)).collect();
+        assert_eq!(orders, [(1, 2, 12), (2, 3, 16), (3, 4, 18)]);
+        assert_eq!(find_subharmonics::<f32, 1>(&peaks, 24, 4, 0.5).len(), 1);
+    }
+}
@@ -0,0 +1,192 @@
+/* embfft | sine_fit.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Sine wave fitting, as described by IEEE 1057
+//!
+//! A least-squares fit estimates the amplitude, the phase and the frequency of a tone much more precisely
+//! than the FFT bins, which is required to test ADCs: the residual of the fit contains the noise and the
+//! distortion of the converter.
+//! For example:
+//! ```
+//! let samples: [f64; 128] = core::array::from_fn(|i| {
+//!     0.5 * (2.0 * core::f64::consts::PI * 0.0731 * i as f64 + 1.0).cos() + 0.1
+//! });
+//! let fit = embfft::sine_fit::sine_fit(&samples, 10).unwrap();
+//! # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+//! assert!((fit.frequency - 0.0731).abs() < 1e-9);
+//! # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+//! assert!((fit.amplitude - 0.5).abs() < 1e-9);
+//! # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+//! assert!((fit.phase - 1.0).abs() < 1e-9);
+//! # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+//! assert!((fit.offset - 0.1).abs() < 1e-9);
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::interpolate::{refine_peak, Interpolation};
+use crate::math;
+
+/******************************************************************************/
+
+/// Parameters of the fitted sine wave, a · cos(2π · f · n + φ) + c
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SineFit {
+    /// Peak amplitude a
+    pub amplitude: f64,
+    /// Phase φ at the first sample, in radians between -π and +π
+    pub phase: f64,
+    /// Frequency f, normalized to the sample rate
+    pub frequency: f64,
+    /// DC offset c
+    pub offset: f64,
+    /// RMS value of the residual, i.e. of the noise and distortion
+    pub residual: f64,
+    /// Number of iterations performed
+    pub iterations: usize
+}
+
+/// Solves a linear system by Gaussian elimination with partial pivoting
+fn solve<const M: usize>(mut a: [[f64; M]; M], mut b: [f64; M]) -> Option<[f64; M]> {
+    for col in 0..M {
+        let pivot = (col..M).fold(col, |p, r| if a[r][col].abs() > a[p][col].abs() { r } else { p });
+        if a[pivot][col] == 0.0 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..M {
+            let k = a[row][col] / a[col][col];
+            let pivot = a[col];
+            for (x, p) in a[row].iter_mut().zip(pivot).skip(col) {
+                *x -= k * p;
+            }
+            b[row] -= k * b[col];
+        }
+    }
+    let mut x = [0.0; M];
+    for row in (0..M).rev() {
+        let sum = (row + 1..M).fold(b[row], |s, i| s - a[row][i] * x[i]);
+        x[row] = sum / a[row][row];
+    }
+    Some(x)
+}
+
+/// Fits a sine wave to real samples, with the four-parameter method of IEEE 1057
+///
+/// The initial frequency is the strongest peak of the spectrum, refined between the bins. Each iteration
+/// then solves the least-squares problem linearized around the current frequency, until the frequency
+/// stops changing or `max_iterations` is reached. The samples should span several periods of the tone.
+/// This is a blocking function, which performs an FFT and needs a buffer of N samples on the stack.
+/// Fails with [`Error::InvalidParameter`] if the samples do not contain any tone.
+pub fn sine_fit<T: Float<N>, const N: usize>(samples: &[T; N], max_iterations: usize) -> Result<SineFit, Error> {
+    // Scaled by 1 / N to avoid any overflow with the fixed-point types
+    let mut data = samples.map(|x| (T::from_f64(x.to_f64() / N as f64), T::ZERO));
+    EmbFft::new(&mut data).fft();
+    let power = |k: usize| {
+        let (re, im) = (data[k].0.to_f64(), data[k].1.to_f64());
+        re * re + im * im
+    };
+    let peak = (1..=N / 2).fold(1, |p, k| if power(k) > power(p) { k } else { p });
+    if power(peak) == 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+    let mut omega = 2.0 * PI * refine_peak(&data, peak, Interpolation::Quinn)? / N as f64;
+
+    let mut params = [0.0; 4];
+    let mut iterations = 0;
+    loop {
+        // Columns: cos(ωn), sin(ωn), 1, and the derivative with respect to ω
+        let (mut ata, mut atb) = ([[0.0; 4]; 4], [0.0; 4]);
+        for (n, y) in samples.iter().enumerate() {
+            let (s, c) = math::sin_cos(omega * n as f64);
+            let row = [c, s, 1.0, n as f64 * (params[1] * c - params[0] * s)];
+            for i in 0..4 {
+                for j in 0..4 {
+                    ata[i][j] += row[i] * row[j];
+                }
+                atb[i] += row[i] * y.to_f64();
+            }
+        }
+
+        // The first pass is the three-parameter fit at the initial frequency
+        let (x, step) = if iterations == 0 {
+            let reduced = core::array::from_fn(|i| core::array::from_fn(|j| ata[i][j]));
+            let x: [f64; 3] = solve(reduced, [atb[0], atb[1], atb[2]]).ok_or(Error::InvalidParameter)?;
+            ([x[0], x[1], x[2], 0.0], 0.0)
+        } else {
+            let x = solve(ata, atb).ok_or(Error::InvalidParameter)?;
+            (x, x[3])
+        };
+        params = x;
+        omega += step;
+        iterations += 1;
+        if iterations > max_iterations || (iterations > 1 && step.abs() < 1e-15 * omega.abs()) {
+            break;
+        }
+    }
+
+    // Residual of the final model
+    let mut sum = 0.0;
+    for (n, y) in samples.iter().enumerate() {
+        let (s, c) = math::sin_cos(omega * n as f64);
+        let e = y.to_f64() - params[0] * c - params[1] * s - params[2];
+        sum += e * e;
+    }
+    Ok(SineFit {
+        amplitude: math::sqrt(params[0] * params[0] + params[1] * params[1]),
+        phase: math::atan2(-params[1], params[0]),
+        frequency: omega / (2.0 * PI),
+        offset: params[2],
+        residual: math::sqrt(sum / N as f64),
+        iterations
+    })
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::Q15;
+    use crate::synth::Lfsr;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_sine_fit() {
+        assert_eq!(sine_fit(&[0.5f64; 16], 10), Err(Error::InvalidParameter));
+
+        // Noisy tone between two bins
+        let mut lfsr = Lfsr::new(0x1057);
+        let samples: [f64; 512] = core::array::from_fn(|i| {
+            0.8 * crate::cordic::cos(2.0 * PI * 0.1237 * i as f64 - 2.0) - 0.2 + 1e-3 * lfsr.uniform()
+        });
+        let fit = sine_fit(&samples, 20).unwrap();
+        assert!(fit.iterations < 20);
+        assert_abs_diff_eq!(fit.frequency, 0.1237, epsilon = 1e-7);
+        assert_abs_diff_eq!(fit.amplitude, 0.8, epsilon = 1e-4);
+        assert_abs_diff_eq!(fit.phase, -2.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(fit.offset, -0.2, epsilon = 1e-4);
+        assert_abs_diff_eq!(fit.residual, 1e-3 / 3.0f64.sqrt(), epsilon = 1e-4);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_adc() {
+        // The residual of a 16-bit converter is its quantization noise, 1 / √12 LSB
+        let samples: [Q15; 1024] = core::array::from_fn(|i| Q15::from_f64(0.9 * crate::cordic::sin(2.0 * PI * 0.0411 * i as f64)));
+        let fit = sine_fit(&samples, 20).unwrap();
+        assert_abs_diff_eq!(fit.frequency, 0.0411, epsilon = 1e-8);
+        let lsb = 1.0 / 32768.0;
+        assert!(fit.residual > 0.25 * lsb && fit.residual < 0.35 * lsb);
+    }
+}
@@ -0,0 +1,195 @@
+/* embfft | spectrum.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Magnitude and power of the spectra
+//!
+//! Most applications only need the magnitude |X\[k\]| of the bins computed by the transforms. The square
+//! root is computed with [`math::sqrt()`], i.e. with Newton iterations when neither the `libm` nor the
+//! `micromath` feature is enabled, so that it does not depend on any external library. The squares are
+//! summed in double precision, which cannot overflow for the fixed-point types.
+//! For example:
+//! ```
+//! let mut data = [(1.0f32, 0.0); 8];
+//! embfft::EmbFft::new(&mut data).fft();
+//!
+//! let mut magnitude = [0.0; 8];
+//! embfft::spectrum::magnitude(&data, &mut magnitude);
+//! assert_eq!(magnitude, [8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+//! ```
+//!
+//! The power spectrum and the power spectral density (PSD) of real signals are one-sided: the power of
+//! the negative frequencies is folded onto the positive ones, so that they hold the N / 2 + 1 bins from
+//! DC to the Nyquist frequency. The power spectrum is scaled for the tones, by 1 / (N × coherent gain)²,
+//! so that a sine wave of amplitude A reads A² / 2 at its bin whatever the window. The PSD is scaled for
+//! the noise, by dividing it by the equivalent noise bandwidth of the window, in hertz, so that its
+//! integral over the frequencies is the mean square value of the signal.
+//! For example:
+//! ```
+//! use embfft::units::SampleRate;
+//! use embfft::window::Hann;
+//!
+//! // Sine wave of amplitude 2 at bin 4
+//! let mut data: [(f32, f32); 32] = core::array::from_fn(|i| {
+//!     (2.0 * (2.0 * core::f32::consts::PI * 4.0 * i as f32 / 32.0).cos(), 0.0)
+//! });
+//! embfft::EmbFft::<f32, 32, Hann>::windowed(&mut data).fft();
+//!
+//! let mut power = [0.0; 17];
+//! embfft::spectrum::power_spectrum::<f32, Hann, 32>(&data, &mut power).unwrap();
+//! assert!((power[4] - 2.0).abs() < 1e-5);
+//!
+//! // In V²/Hz, for samples in volts taken at 1 kHz
+//! let fs = SampleRate::new(1000.0).unwrap();
+//! let mut psd = [0.0; 17];
+//! embfft::spectrum::psd::<f32, Hann, 32>(&data, fs, &mut psd).unwrap();
+//! assert!((psd[4] - 2.0 / (1.5 * 1000.0 / 32.0)).abs() < 1e-5);
+//! ```
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+use crate::units::SampleRate;
+use crate::window::Window;
+
+/******************************************************************************/
+
+/// Returns the magnitude of a complex value
+fn abs<T: Float<N>, const N: usize>(x: (T, T)) -> T {
+    let (re, im) = (x.0.to_f64(), x.1.to_f64());
+    T::from_f64(math::sqrt(re * re + im * im))
+}
+
+/// Computes the magnitude of each bin of a spectrum
+///
+/// The magnitudes of the fixed-point types saturate at their maximum value.
+pub fn magnitude<T: Float<N>, const N: usize>(spectrum: &[(T, T); N], output: &mut [T; N]) {
+    for (y, &x) in output.iter_mut().zip(spectrum.iter()) {
+        *y = abs(x);
+    }
+}
+
+/// Replaces each bin of a spectrum by its magnitude, in the real part, clearing the imaginary part
+///
+/// This saves the output buffer of [`magnitude()`].
+pub fn magnitude_in_place<T: Float<N>, const N: usize>(spectrum: &mut [(T, T); N]) {
+    for x in spectrum.iter_mut() {
+        *x = (abs(*x), T::ZERO);
+    }
+}
+
+/// Writes the one-sided squared magnitudes of the bins of a real signal, multiplied by `scale`
+fn one_sided<T: Float<N>, const N: usize>(spectrum: &[(T, T); N], output: &mut [T], scale: f64) -> Result<(), Error> {
+    if output.len() != N / 2 + 1 {
+        return Err(Error::InvalidLength);
+    }
+    for (k, y) in output.iter_mut().enumerate() {
+        let (re, im) = (spectrum[k].0.to_f64(), spectrum[k].1.to_f64());
+        let power = (re * re + im * im) * scale;
+        *y = T::from_f64(if k == 0 || 2 * k == N { power } else { 2.0 * power });
+    }
+    Ok(())
+}
+
+/// Computes the one-sided power spectrum of a real signal, transformed with the window `W`
+///
+/// `output` receives the N / 2 + 1 bins from DC to the Nyquist frequency, in the squared unit of the
+/// samples. A sine wave of amplitude A centered on a bin reads A² / 2, but a tone between two bins reads
+/// less, by the scalloping loss of the window, and the noise reads more with wider windows.
+/// Fails with [`Error::InvalidLength`] if the length of `output` is not N / 2 + 1.
+pub fn power_spectrum<T: Float<N>, W: Window<T, N>, const N: usize>(spectrum: &[(T, T); N], output: &mut [T]) -> Result<(), Error> {
+    let sum = N as f64 * W::COHERENT_GAIN;
+    one_sided(spectrum, output, 1.0 / (sum * sum))
+}
+
+/// Computes the one-sided power spectral density of a real signal, transformed with the window `W`
+///
+/// `output` receives the N / 2 + 1 bins from DC to the Nyquist frequency, in the squared unit of the
+/// samples per hertz. This is the power spectrum divided by the equivalent noise bandwidth of the window
+/// in hertz, i.e. [`Window::ENBW`] bins of fs / N, so that the sum of the bins multiplied by fs / N is the
+/// mean square value of the signal.
+/// Fails with [`Error::InvalidLength`] if the length of `output` is not N / 2 + 1.
+pub fn psd<T: Float<N>, W: Window<T, N>, const N: usize>(spectrum: &[(T, T); N], sample_rate: SampleRate, output: &mut [T]) -> Result<(), Error> {
+    let sum = N as f64 * W::COHERENT_GAIN;
+    let enbw = W::ENBW * sample_rate.bin_width::<N>().0;
+    one_sided(spectrum, output, 1.0 / (sum * sum * enbw))
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::Q15;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_magnitude() {
+        let tolerance = if cfg!(all(feature = "micromath", not(feature = "libm"))) { 0.1 } else { 1e-12 };
+        let spectrum = [(3.0, -4.0), (0.0, 0.0), (-1e-3, 0.0), (1e6, 1e6)];
+        let mut output = [1.0; 4];
+        magnitude(&spectrum, &mut output);
+        assert_relative_eq!(output[0], 5.0, max_relative = tolerance);
+        assert_eq!(output[1], 0.0);
+        assert_relative_eq!(output[2], 1e-3, max_relative = tolerance);
+        assert_relative_eq!(output[3], 1e6 * core::f64::consts::SQRT_2, max_relative = tolerance);
+
+        let mut data = spectrum;
+        magnitude_in_place(&mut data);
+        assert_eq!(data.map(|x| x.0), output);
+        assert!(data.iter().all(|x| x.1 == 0.0));
+    }
+
+    #[test]
+    fn test_power() {
+        use crate::window::{Blackman, Hann, Rectangular};
+
+        let mut output = [0.0; 32];
+        assert_eq!(power_spectrum::<f64, Hann, 64>(&[(0.0, 0.0); 64], &mut output), Err(Error::InvalidLength));
+
+        // Sine wave, DC and Nyquist components
+        let frame: [(f64, f64); 64] = core::array::from_fn(|i| {
+            let x = 3.0 * crate::cordic::cos(2.0 * core::f64::consts::PI * 10.0 * i as f64 / 64.0);
+            (x + 0.5 + if i % 2 == 0 { 0.25 } else { -0.25 }, 0.0)
+        });
+        let mut data = frame;
+        crate::EmbFft::new(&mut data).fft();
+        let mut power = [0.0; 33];
+        power_spectrum::<f64, Rectangular, 64>(&data, &mut power).unwrap();
+        for (k, &p) in power.iter().enumerate() {
+            let expected = match k { 0 => 0.25, 10 => 4.5, 32 => 0.0625, _ => 0.0 };
+            approx::assert_abs_diff_eq!(p, expected, epsilon = 1e-12);
+        }
+
+        // The PSD integrates to the mean square value, for any window
+        let mean_square = frame.iter().map(|x| x.0 * x.0).sum::<f64>() / 64.0;
+        let fs = crate::units::SampleRate::new(2000.0).unwrap();
+        let mut data = frame;
+        crate::EmbFft::<f64, 64, Rectangular>::windowed(&mut data).fft();
+        psd::<f64, Rectangular, 64>(&data, fs, &mut power).unwrap();
+        assert_relative_eq!(power.iter().sum::<f64>() * 2000.0 / 64.0, mean_square, max_relative = 1e-12);
+
+        // With a window, a tone spreads over a few bins, whose sum is the power of the tone times the ENBW
+        let mut data = frame;
+        crate::EmbFft::<f64, 64, Blackman>::windowed(&mut data).fft();
+        power_spectrum::<f64, Blackman, 64>(&data, &mut power).unwrap();
+        assert_relative_eq!(power[10], 4.5, max_relative = 1e-9);
+        let enbw = <Blackman as crate::window::Window<f64, 64>>::ENBW;
+        assert_relative_eq!(power[7..=13].iter().sum::<f64>(), 4.5 * enbw, max_relative = 1e-9);
+        psd::<f64, Blackman, 64>(&data, fs, &mut power).unwrap();
+        assert_relative_eq!(power[7..=13].iter().sum::<f64>() * 2000.0 / 64.0, 4.5, max_relative = 1e-9);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let tolerance = if cfg!(all(feature = "micromath", not(feature = "libm"))) { 0.1 } else { 1e-3 };
+        let mut data: [(Q15, Q15); 2] = [(Q15::from_f64(0.3), Q15::from_f64(-0.4)), (Q15::from_f64(-0.9), Q15::from_f64(0.9))];
+        magnitude_in_place(&mut data);
+        assert_relative_eq!(data[0].0.to_f64(), 0.5, max_relative = tolerance);
+        assert_eq!(data[0].1, Q15::from_f64(0.0));
+        assert_eq!(data[1].0, Q15::MAX);
+    }
+}
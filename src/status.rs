@@ -0,0 +1,62 @@
+/* embfft | status.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+/******************************************************************************/
+
+use core::ops::{BitOr, BitOrAssign};
+
+/******************************************************************************/
+
+/// Status flags of a conversion
+///
+/// The flags are accumulated over all the values computed during the conversion, so that corrupted
+/// results can be detected and discarded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status(u8);
+
+impl Status {
+    /// No flag set
+    pub const OK: Self = Self(0);
+    /// A floating-point value was infinite, or a saturating fixed-point value reached its limits
+    pub const OVERFLOW: Self = Self(1 << 0);
+    /// A floating-point value was not a number (NaN)
+    pub const INVALID: Self = Self(1 << 1);
+    /// A floating-point value was denormal, which may reduce the accuracy and the performance
+    pub const DENORMAL: Self = Self(1 << 2);
+    /// The results were multiplied by 1 / N
+    pub const SCALED: Self = Self(1 << 3);
+    /// The input contained substitutes for dropped samples, see [`Framer::conceal()`](crate::framer::Framer::conceal)
+    pub const DEGRADED: Self = Self(1 << 4);
+
+    /// Returns the raw flags
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Checks if all the flags of `other` are set
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Checks if the results should be considered corrupted, i.e. an overflow or an invalid value occurred
+    pub const fn is_corrupted(self) -> bool {
+        self.0 & (Self::OVERFLOW.0 | Self::INVALID.0) != 0
+    }
+}
+
+impl BitOr for Status {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Status {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
@@ -0,0 +1,169 @@
+/* embfft | stereo.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Spectra of interleaved stereo buffers
+//!
+//! Audio codecs usually deliver the left and right samples interleaved in a single DMA buffer. Both
+//! channels are real, so that they can be transformed at once by packing them in the real and imaginary
+//! parts of a single complex FFT, their spectra being separated when read.
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::convert::ConvertFrom;
+use crate::cross_spectrum::separate;
+use crate::error::Error;
+use crate::fft::EmbFft;
+use crate::status::Status;
+use crate::window::{Rectangular, Window};
+
+/******************************************************************************/
+
+/// Spectra of both channels of an interleaved stereo buffer
+///
+/// The spectra are computed with a single N-point FFT, the frames being weighted by the window `W`.
+/// The samples are converted like in the [`convert`](crate::convert) module, the integers being scaled to
+/// the -1 to 1 range. Since the inputs are real, only the bins up to N / 2 are meaningful, the others are
+/// their complex conjugate.
+///
+/// For example:
+/// ```
+/// let mut stereo = embfft::stereo::StereoFft::<f32, 8>::new();
+/// let buffer: [i16; 16] = [16384, 0, 0, 8192, -16384, 0, 0, -8192, 16384, 0, 0, 8192, -16384, 0, 0, -8192];
+/// stereo.process(&buffer).unwrap();
+/// assert_eq!(stereo.left(2), Ok((2.0, 0.0)));
+/// assert_eq!(stereo.right(2), Ok((0.0, -1.0)));
+/// ```
+pub struct StereoFft<T, const N: usize, W = Rectangular> {
+    data: [(T, T); N],
+    window: core::marker::PhantomData<W>
+}
+
+impl<T: Float<N>, const N: usize> StereoFft<T, N> {
+    /// Initializes the transform, without any window
+    pub fn new() -> Self {
+        Self::windowed()
+    }
+}
+
+impl<T: Float<N>, const N: usize, W: Window<T, N>> StereoFft<T, N, W> {
+    /// Initializes the transform, the frames being weighted by the window `W`
+    pub fn windowed() -> Self {
+        Self {
+            data: [(T::ZERO, T::ZERO); N],
+            window: core::marker::PhantomData
+        }
+    }
+
+    /// Deinterleaves a frame of N left and N right samples, starting with the left one, and transforms it
+    ///
+    /// This is a blocking function, which returns the status flags of the conversion.
+    /// Fails with [`Error::InvalidLength`] if the buffer does not hold exactly 2N samples.
+    pub fn process<S: Copy>(&mut self, interleaved: &[S]) -> Result<Status, Error>
+    where
+        T: ConvertFrom<S>
+    {
+        if interleaved.len() != 2 * N {
+            return Err(Error::InvalidLength);
+        }
+        for (z, pair) in self.data.iter_mut().zip(interleaved.chunks_exact(2)) {
+            *z = (T::convert_from(pair[0]), T::convert_from(pair[1]));
+        }
+        let mut fft = EmbFft::<T, N, W>::windowed(&mut self.data);
+        fft.fft();
+        Ok(fft.status())
+    }
+
+    /// Returns the bin k of the left channel
+    ///
+    /// Fails with [`Error::OutOfRange`] if k is not below N.
+    pub fn left(&self, k: usize) -> Result<(T, T), Error> {
+        self.channels(k).map(|[left, _]| left)
+    }
+
+    /// Returns the bin k of the right channel
+    ///
+    /// Fails with [`Error::OutOfRange`] if k is not below N.
+    pub fn right(&self, k: usize) -> Result<(T, T), Error> {
+        self.channels(k).map(|[_, right]| right)
+    }
+
+    /// Writes the full spectra of both channels
+    pub fn spectra(&self, left: &mut [(T, T); N], right: &mut [(T, T); N]) {
+        for k in 0..N {
+            if let Ok([l, r]) = self.channels(k) {
+                left[k] = l;
+                right[k] = r;
+            }
+        }
+    }
+
+    fn channels(&self, k: usize) -> Result<[(T, T); 2], Error> {
+        if k >= N {
+            return Err(Error::OutOfRange);
+        }
+        let (l, r) = separate(&self.data, k);
+        let half = T::from_f64(0.5);
+        Ok([(l.0 * half, l.1 * half), (r.0 * half, r.1 * half)])
+    }
+}
+
+impl<T: Float<N>, const N: usize, W: Window<T, N>> Default for StereoFft<T, N, W> {
+    fn default() -> Self {
+        Self::windowed()
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixed_point::Q15;
+    use crate::window::Hann;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_stereo() {
+        let left: [f32; 16] = core::array::from_fn(|i| ((i * 5) % 7) as f32 - 3.0);
+        let right: [f32; 16] = core::array::from_fn(|i| ((i * 3) % 11) as f32 * 0.5);
+        let mut interleaved = [0.0f32; 32];
+        for i in 0..16 {
+            interleaved[2 * i] = left[i];
+            interleaved[2 * i + 1] = right[i];
+        }
+
+        let mut stereo = StereoFft::<f32, 16, Hann>::windowed();
+        assert_eq!(stereo.process(&interleaved[1..]), Err(Error::InvalidLength));
+        assert!(!stereo.process(&interleaved).unwrap().is_corrupted());
+        assert_eq!(stereo.left(16), Err(Error::OutOfRange));
+        let (mut l, mut r) = ([(0.0, 0.0); 16], [(0.0, 0.0); 16]);
+        stereo.spectra(&mut l, &mut r);
+
+        for (spectrum, signal) in [(l, left), (r, right)] {
+            let mut reference = signal.map(|x| (x, 0.0));
+            EmbFft::<f32, 16, Hann>::windowed(&mut reference).fft();
+            for (x, y) in spectrum.iter().zip(reference.iter()) {
+                assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-5);
+                assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_i16() {
+        let mut stereo = StereoFft::<f64, 4>::new();
+        stereo.process(&[i16::MIN, 0, 0, 0, 0, 0, 0, 16384i16]).unwrap();
+        assert_eq!(stereo.left(0), Ok((-1.0, 0.0)));
+        assert_eq!(stereo.right(0), Ok((0.5, 0.0)));
+        assert_eq!(stereo.right(1), Ok((0.0, 0.5)));
+
+        // The i16 samples are taken as Q15 without any rounding
+        let mut stereo = StereoFft::<Q15, 4>::new();
+        stereo.process(&[-8192i16, 0, 0, 0, 0, 0, 0, 4096]).unwrap();
+        assert_eq!(stereo.left(0).unwrap().0.to_bits(), -8192);
+        assert_eq!(stereo.right(0).unwrap().0.to_bits(), 4096);
+    }
+}
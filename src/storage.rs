@@ -0,0 +1,484 @@
+/* embfft | storage.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Persistence of spectra in NOR flash memory
+//!
+//! The records are appended to a region of the flash, one slot after the other, so that the erase cycles
+//! are spread over all its pages. Each record starts with a header containing a sequence number and a
+//! CRC, and the header is written last, so that a record interrupted by a power loss is ignored.
+//! Any [`Record`] can be stored, such as a spectrum or a power spectral density.
+
+/******************************************************************************/
+
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::error::Error;
+use crate::fixed_point::{Q15, Q31};
+
+/******************************************************************************/
+
+/// Value that can be serialized to bytes, in little-endian order
+pub trait Record {
+    /// Size of the serialized value, in bytes
+    const SIZE: usize;
+
+    /// Passes the serialized bytes to `put`, in one or several calls
+    fn encode(&self, put: &mut dyn FnMut(&[u8]));
+
+    /// Deserializes a value, filling the buffers passed to `take`
+    fn decode(take: &mut dyn FnMut(&mut [u8])) -> Self;
+}
+
+macro_rules! gen_record_impl {
+    ($T: ty, $size: expr, $to: expr, $from: expr) => {
+        impl Record for $T {
+            const SIZE: usize = $size;
+
+            fn encode(&self, put: &mut dyn FnMut(&[u8])) {
+                put(&$to(*self).to_le_bytes());
+            }
+
+            fn decode(take: &mut dyn FnMut(&mut [u8])) -> Self {
+                let mut bytes = [0; $size];
+                take(&mut bytes);
+                $from(bytes)
+            }
+        }
+    };
+}
+
+gen_record_impl!(f32, 4, f32::to_bits, |b| f32::from_bits(u32::from_le_bytes(b)));
+gen_record_impl!(f64, 8, f64::to_bits, |b| f64::from_bits(u64::from_le_bytes(b)));
+
+impl<O: Copy, R: Copy> Record for Q15<O, R> {
+    const SIZE: usize = 2;
+
+    fn encode(&self, put: &mut dyn FnMut(&[u8])) {
+        put(&self.to_bits().to_le_bytes());
+    }
+
+    fn decode(take: &mut dyn FnMut(&mut [u8])) -> Self {
+        let mut bytes = [0; 2];
+        take(&mut bytes);
+        Self::from_bits(i16::from_le_bytes(bytes))
+    }
+}
+
+impl<O: Copy, R: Copy> Record for Q31<O, R> {
+    const SIZE: usize = 4;
+
+    fn encode(&self, put: &mut dyn FnMut(&[u8])) {
+        put(&self.to_bits().to_le_bytes());
+    }
+
+    fn decode(take: &mut dyn FnMut(&mut [u8])) -> Self {
+        let mut bytes = [0; 4];
+        take(&mut bytes);
+        Self::from_bits(i32::from_le_bytes(bytes))
+    }
+}
+
+impl<A: Record, B: Record> Record for (A, B) {
+    const SIZE: usize = A::SIZE + B::SIZE;
+
+    fn encode(&self, put: &mut dyn FnMut(&[u8])) {
+        self.0.encode(put);
+        self.1.encode(put);
+    }
+
+    fn decode(take: &mut dyn FnMut(&mut [u8])) -> Self {
+        let a = A::decode(take);
+        (a, B::decode(take))
+    }
+}
+
+impl<R: Record, const N: usize> Record for [R; N] {
+    const SIZE: usize = R::SIZE * N;
+
+    fn encode(&self, put: &mut dyn FnMut(&[u8])) {
+        for x in self {
+            x.encode(put);
+        }
+    }
+
+    fn decode(take: &mut dyn FnMut(&mut [u8])) -> Self {
+        core::array::from_fn(|_| R::decode(take))
+    }
+}
+
+/******************************************************************************/
+
+/// Errors returned by the [`SpectrumLog`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageError<E> {
+    /// The flash memory reported an error
+    Flash(E),
+    /// The log reported an error, see [`SpectrumLog`]
+    Log(Error)
+}
+
+impl<E> From<Error> for StorageError<E> {
+    fn from(error: Error) -> Self {
+        StorageError::Log(error)
+    }
+}
+
+/// Size of the buffer used to transfer the records to and from the flash
+const CHUNK: usize = 256;
+/// Marker of a valid header, "EFFT"
+const MAGIC: u32 = 0x5446_4645;
+/// Size of the header: magic, sequence number, record size and CRC
+const HEADER: usize = 16;
+
+/// Updates a CRC-32 (IEEE 802.3) with some bytes
+fn crc32(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// Rounds x up to a multiple of m
+const fn align(x: usize, m: usize) -> usize {
+    x.div_ceil(m) * m
+}
+
+/// Header of a record
+struct Header {
+    sequence: u32,
+    crc: u32
+}
+
+/// Log of records in a region of NOR flash memory
+///
+/// The region is divided into slots of identical size, which never span two erase pages. A page is only
+/// erased when the log reaches it, so that at least one page of previous records is always kept.
+///
+/// For example:
+/// ```
+/// use embedded_storage::nor_flash::NorFlash;
+/// use embfft::storage::SpectrumLog;
+///
+/// fn log_psd<F: NorFlash>(flash: F, psd: &[f32; 512]) {
+///     let mut log = SpectrumLog::<_, [f32; 512]>::new(flash, 0x40000, 0x80000).unwrap();
+///     log.store(psd).unwrap();
+///     assert_eq!(&log.load(0).unwrap(), psd);
+/// }
+/// ```
+pub struct SpectrumLog<F, R> {
+    flash: F,
+    start: u32,
+    slots_per_page: usize,
+    slots: usize,
+    latest: Option<(usize, u32)>,
+    next: usize,
+    record: core::marker::PhantomData<R>
+}
+
+impl<F: NorFlash, R: Record> SpectrumLog<F, R> {
+    /// Size of the header, padded to the write size
+    const HEADER_SIZE: usize = align(HEADER, F::WRITE_SIZE);
+    /// Size of a slot, padded to the write size
+    pub const SLOT_SIZE: usize = Self::HEADER_SIZE + align(R::SIZE, F::WRITE_SIZE);
+
+    /// Opens the log stored between the `start` and `end` addresses, and finds its latest record
+    ///
+    /// Fails with [`Error::InvalidParameter`] if the region is not aligned to the erase size, if it is
+    /// smaller than two pages, if a page cannot contain a record, or if the read and write sizes are not
+    /// divisors of 256 bytes.
+    pub fn new(flash: F, start: u32, end: u32) -> Result<Self, StorageError<F::Error>> {
+        let page = F::ERASE_SIZE;
+        if !(start as usize).is_multiple_of(page) || !(end as usize).is_multiple_of(page) || end <= start || ((end - start) as usize) < 2 * page
+            || !CHUNK.is_multiple_of(F::WRITE_SIZE) || !CHUNK.is_multiple_of(F::READ_SIZE) || Self::SLOT_SIZE > page {
+            return Err(Error::InvalidParameter.into());
+        }
+        let slots_per_page = page / Self::SLOT_SIZE;
+        let mut log = Self {
+            flash,
+            start,
+            slots_per_page,
+            slots: (end - start) as usize / page * slots_per_page,
+            latest: None,
+            next: 0,
+            record: core::marker::PhantomData
+        };
+
+        for slot in 0..log.slots {
+            if let Some(header) = log.read_header(slot)? {
+                if log.latest.is_none_or(|(_, sequence)| header.sequence > sequence) {
+                    log.latest = Some((slot, header.sequence));
+                }
+            }
+        }
+        if let Some((slot, _)) = log.latest {
+            log.next = (slot + 1) % log.slots;
+            // A slot left dirty by a power loss cannot be written, the log restarts on the next page
+            if !log.next.is_multiple_of(slots_per_page) && !log.is_blank(log.next)? {
+                log.next = (log.next / slots_per_page + 1) * slots_per_page % log.slots;
+            }
+        }
+        Ok(log)
+    }
+
+    /// Releases the flash memory
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    /// Returns the sequence number of the latest record, if any
+    pub fn sequence(&self) -> Option<u32> {
+        self.latest.map(|(_, sequence)| sequence)
+    }
+
+    fn address(&self, slot: usize) -> u32 {
+        self.start + ((slot / self.slots_per_page) * F::ERASE_SIZE + (slot % self.slots_per_page) * Self::SLOT_SIZE) as u32
+    }
+
+    /// Reads `len` bytes from the flash, in chunks, and passes them to `f`
+    fn read_chunks(&mut self, mut address: u32, mut len: usize, mut f: impl FnMut(&[u8])) -> Result<(), StorageError<F::Error>> {
+        let mut buffer = [0; CHUNK];
+        while len > 0 {
+            let n = if len < CHUNK { align(len, F::READ_SIZE) } else { CHUNK };
+            self.flash.read(address, &mut buffer[..n]).map_err(StorageError::Flash)?;
+            f(&buffer[..if len < n { len } else { n }]);
+            address += n as u32;
+            len -= if len < n { len } else { n };
+        }
+        Ok(())
+    }
+
+    fn is_blank(&mut self, slot: usize) -> Result<bool, StorageError<F::Error>> {
+        let mut blank = true;
+        self.read_chunks(self.address(slot), Self::SLOT_SIZE, |bytes| blank &= bytes.iter().all(|&b| b == 0xFF))?;
+        Ok(blank)
+    }
+
+    /// Reads the header of a slot, and checks the CRC of its record
+    fn read_header(&mut self, slot: usize) -> Result<Option<Header>, StorageError<F::Error>> {
+        let address = self.address(slot);
+        let mut bytes = [0; HEADER];
+        self.read_chunks(address, HEADER, |b| bytes.copy_from_slice(b))?;
+        let word = |i: usize| u32::from_le_bytes([bytes[4 * i], bytes[4 * i + 1], bytes[4 * i + 2], bytes[4 * i + 3]]);
+        if word(0) != MAGIC || word(2) as usize != R::SIZE {
+            return Ok(None);
+        }
+
+        let mut crc = crc32(!0, &bytes[4..12]);
+        self.read_chunks(address + Self::HEADER_SIZE as u32, R::SIZE, |b| crc = crc32(crc, b))?;
+        Ok(if !crc == word(3) { Some(Header { sequence: word(1), crc: word(3) }) } else { None })
+    }
+
+    /// Appends a record to the log, erasing the next page first if needed
+    ///
+    /// Returns the sequence number of the record.
+    pub fn store(&mut self, record: &R) -> Result<u32, StorageError<F::Error>> {
+        let slot = self.next;
+        let address = self.address(slot);
+        if slot.is_multiple_of(self.slots_per_page) {
+            self.flash.erase(address, address + F::ERASE_SIZE as u32).map_err(StorageError::Flash)?;
+        }
+        let sequence = self.latest.map_or(0, |(_, sequence)| sequence.wrapping_add(1));
+        let mut header = [0xFF; HEADER];
+        header[4..8].copy_from_slice(&sequence.to_le_bytes());
+        header[8..12].copy_from_slice(&(R::SIZE as u32).to_le_bytes());
+
+        // The record is written first, in chunks
+        let mut crc = crc32(!0, &header[4..12]);
+        let mut buffer = [0xFF; CHUNK];
+        let mut len = 0;
+        let mut offset = address + Self::HEADER_SIZE as u32;
+        let mut result = Ok(());
+        record.encode(&mut |mut bytes: &[u8]| {
+            crc = crc32(crc, bytes);
+            while !bytes.is_empty() && result.is_ok() {
+                let n = if bytes.len() < CHUNK - len { bytes.len() } else { CHUNK - len };
+                buffer[len..len + n].copy_from_slice(&bytes[..n]);
+                len += n;
+                bytes = &bytes[n..];
+                if len == CHUNK {
+                    result = self.flash.write(offset, &buffer);
+                    offset += CHUNK as u32;
+                    len = 0;
+                }
+            }
+        });
+        result.map_err(StorageError::Flash)?;
+        if len > 0 {
+            buffer[len..].fill(0xFF);
+            self.flash.write(offset, &buffer[..align(len, F::WRITE_SIZE)]).map_err(StorageError::Flash)?;
+        }
+
+        // The header validates the record
+        header[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+        header[12..16].copy_from_slice(&(!crc).to_le_bytes());
+        let mut padded = [0xFF; CHUNK];
+        padded[..HEADER].copy_from_slice(&header);
+        self.flash.write(address, &padded[..Self::HEADER_SIZE]).map_err(StorageError::Flash)?;
+
+        self.latest = Some((slot, sequence));
+        self.next = (slot + 1) % self.slots;
+        Ok(sequence)
+    }
+
+    /// Loads a record, 0 being the latest one, 1 the previous one, and so on
+    ///
+    /// Fails with [`Error::NotDone`] if the log is empty, and with [`Error::OutOfRange`] if the record
+    /// does not exist, or was overwritten or corrupted.
+    pub fn load(&mut self, age: usize) -> Result<R, StorageError<F::Error>> {
+        let (latest, sequence) = self.latest.ok_or(Error::NotDone)?;
+        if age >= self.slots {
+            return Err(Error::OutOfRange.into());
+        }
+        let slot = (latest + self.slots - age) % self.slots;
+        let header = self.read_header(slot)?.ok_or(Error::OutOfRange)?;
+        if header.sequence != sequence.wrapping_sub(age as u32) {
+            return Err(Error::OutOfRange.into());
+        }
+
+        // The record is decoded from chunks, and checked again in case the flash is failing
+        let address = self.address(slot) + Self::HEADER_SIZE as u32;
+        let mut buffer = [0; CHUNK];
+        let (mut pos, mut available, mut offset) = (0, 0, 0);
+        let mut result = Ok(());
+        let mut crc = crc32(crc32(!0, &header.sequence.to_le_bytes()), &(R::SIZE as u32).to_le_bytes());
+        let record = R::decode(&mut |bytes: &mut [u8]| {
+            let mut done = 0;
+            while done < bytes.len() {
+                if pos == available {
+                    let n = if R::SIZE - offset < CHUNK { align(R::SIZE - offset, F::READ_SIZE) } else { CHUNK };
+                    if result.is_ok() {
+                        result = self.flash.read(address + offset as u32, &mut buffer[..n]);
+                    }
+                    available = if R::SIZE - offset < n { R::SIZE - offset } else { n };
+                    offset += available;
+                    pos = 0;
+                }
+                let n = if bytes.len() - done < available - pos { bytes.len() - done } else { available - pos };
+                bytes[done..done + n].copy_from_slice(&buffer[pos..pos + n]);
+                pos += n;
+                done += n;
+            }
+            crc = crc32(crc, bytes);
+        });
+        result.map_err(StorageError::Flash)?;
+        if !crc != header.crc {
+            return Err(Error::OutOfRange.into());
+        }
+        Ok(record)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_storage::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+
+    /// NOR flash emulation, where writing can only clear bits
+    struct Flash([u8; 2048]);
+
+    impl ErrorType for Flash {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for Flash {
+        const READ_SIZE: usize = 4;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            if !(offset as usize).is_multiple_of(4) || !bytes.len().is_multiple_of(4) {
+                return Err(NorFlashErrorKind::NotAligned);
+            }
+            bytes.copy_from_slice(&self.0[offset as usize..offset as usize + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl NorFlash for Flash {
+        const WRITE_SIZE: usize = 8;
+        const ERASE_SIZE: usize = 512;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.0[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            if !(offset as usize).is_multiple_of(8) || !bytes.len().is_multiple_of(8) {
+                return Err(NorFlashErrorKind::NotAligned);
+            }
+            for (x, &b) in self.0[offset as usize..].iter_mut().zip(bytes) {
+                *x &= b;
+            }
+            Ok(())
+        }
+    }
+
+    type Spectrum = [(f32, f32); 16];
+
+    fn spectrum(i: usize) -> Spectrum {
+        core::array::from_fn(|k| (i as f32, -(k as f32)))
+    }
+
+    #[test]
+    fn test_store_load() {
+        let flash = Flash([0; 2048]);
+        assert!(SpectrumLog::<_, Spectrum>::new(Flash([0; 2048]), 0, 512).is_err());
+        assert!(SpectrumLog::<_, [f64; 128]>::new(Flash([0; 2048]), 0, 2048).is_err());
+
+        // 144-byte slots, 3 per page
+        let mut log = SpectrumLog::<_, Spectrum>::new(flash, 512, 2048).unwrap();
+        assert_eq!(log.load(0), Err(StorageError::Log(Error::NotDone)));
+        for i in 0..11 {
+            assert_eq!(log.store(&spectrum(i)).unwrap(), i as u32);
+        }
+        assert_eq!(log.load(0).unwrap(), spectrum(10));
+        assert_eq!(log.load(7).unwrap(), spectrum(3));
+        // The first page was erased
+        assert_eq!(log.load(8), Err(StorageError::Log(Error::OutOfRange)));
+        assert_eq!(log.load(9), Err(StorageError::Log(Error::OutOfRange)));
+
+        // The log is found again after a reset
+        let mut log = SpectrumLog::<_, Spectrum>::new(log.release(), 512, 2048).unwrap();
+        assert_eq!(log.sequence(), Some(10));
+        assert_eq!(log.load(1).unwrap(), spectrum(9));
+        log.store(&spectrum(11)).unwrap();
+        assert_eq!(log.load(0).unwrap(), spectrum(11));
+    }
+
+    #[test]
+    fn test_corruption() {
+        let mut log = SpectrumLog::<_, Spectrum>::new(Flash([0xFF; 2048]), 0, 1024).unwrap();
+        log.store(&spectrum(1)).unwrap();
+        log.store(&spectrum(2)).unwrap();
+
+        // A flipped bit invalidates the record, the previous one is then the latest
+        let mut flash = log.release();
+        flash.0[144 + 40] ^= 0x10;
+        let mut log = SpectrumLog::<_, Spectrum>::new(flash, 0, 1024).unwrap();
+        assert_eq!(log.sequence(), Some(0));
+        assert_eq!(log.load(0).unwrap(), spectrum(1));
+
+        // The dirty slot is skipped
+        assert_eq!(log.store(&spectrum(3)).unwrap(), 1);
+        assert_eq!(log.load(0).unwrap(), spectrum(3));
+        assert_eq!(log.release().0[512..516], MAGIC.to_le_bytes());
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let data: [Q15; 4] = core::array::from_fn(|i| Q15::from_bits(i as i16 - 2));
+        let mut log = SpectrumLog::<_, [Q15; 4]>::new(Flash([0xFF; 2048]), 0, 1024).unwrap();
+        log.store(&data).unwrap();
+        assert_eq!(log.load(0).unwrap(), data);
+    }
+}
@@ -0,0 +1,431 @@
+/* embfft | synth.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Test signal synthesis
+//!
+//! The signals are written directly into FFT buffers, as real samples. The frequencies are normalized to
+//! the sample rate, between 0 and 0.5.
+//! For example:
+//! ```
+//! use embfft::synth::{self, Tone};
+//!
+//! let mut data = [(0.0f32, 0.0); 16];
+//! synth::multitone(&mut data, &[Tone::new(0.125, 1.0), Tone::new(0.25, 0.5)]);
+//! embfft::EmbFft::new(&mut data).fft();
+//! # #[cfg(not(all(feature = "micromath", not(feature = "libm"))))]
+//! assert!((data[2].0 - 8.0).abs() < 1e-4);
+//! assert!((data[4].0 - 4.0).abs() < 1e-4);
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::convolve::FftKernel;
+use crate::error::Error;
+use crate::math;
+use crate::status::Status;
+use crate::{EmbFft, EmbIfft};
+
+/******************************************************************************/
+
+/// Sinusoidal component of a multi-tone signal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tone {
+    /// Frequency, normalized to the sample rate
+    pub frequency: f64,
+    /// Peak amplitude
+    pub amplitude: f64,
+    /// Phase at the first sample, in radians
+    pub phase: f64
+}
+
+impl Tone {
+    /// Creates a cosine tone
+    pub fn new(frequency: f64, amplitude: f64) -> Self {
+        Self { frequency, amplitude, phase: 0.0 }
+    }
+
+    /// Sets the phase at the first sample
+    pub fn with_phase(mut self, phase: f64) -> Self {
+        self.phase = phase;
+        self
+    }
+}
+
+/// Writes the sum of several tones
+pub fn multitone<T: Float<N>, const N: usize>(data: &mut [(T, T); N], tones: &[Tone]) {
+    for (i, y) in data.iter_mut().enumerate() {
+        let mut x = 0.0;
+        for tone in tones {
+            // Keep the argument small, to preserve the accuracy
+            let cycles = tone.frequency * i as f64;
+            x += tone.amplitude * math::sin_cos(2.0 * PI * (cycles - cycles as i64 as f64) + tone.phase).1;
+        }
+        *y = (T::from_f64(x), T::ZERO);
+    }
+}
+
+/// Writes a cosine whose frequency increases linearly from `f0` to `f1` over the buffer
+pub fn linear_chirp<T: Float<N>, const N: usize>(data: &mut [(T, T); N], f0: f64, f1: f64, amplitude: f64) {
+    let rate = (f1 - f0) / N as f64;
+    for (i, y) in data.iter_mut().enumerate() {
+        let n = i as f64;
+        let cycles = f0 * n + rate * n * n / 2.0;
+        let x = amplitude * math::sin_cos(2.0 * PI * (cycles - cycles as i64 as f64)).1;
+        *y = (T::from_f64(x), T::ZERO);
+    }
+}
+
+/// Writes a cosine whose frequency increases exponentially from `f0` to `f1` over the buffer
+///
+/// Both frequencies must be positive.
+pub fn log_chirp<T: Float<N>, const N: usize>(data: &mut [(T, T); N], f0: f64, f1: f64, amplitude: f64) {
+    // f(n) = f0 * k^(n / N), with k = f1 / f0
+    let ln_k = math::ln(f1 / f0);
+    for (i, y) in data.iter_mut().enumerate() {
+        let cycles = if ln_k == 0.0 {
+            f0 * i as f64
+        } else {
+            f0 * N as f64 / ln_k * (math::exp(ln_k * i as f64 / N as f64) - 1.0)
+        };
+        let x = amplitude * math::sin_cos(2.0 * PI * (cycles - cycles as i64 as f64)).1;
+        *y = (T::from_f64(x), T::ZERO);
+    }
+}
+
+/// Pseudo-random generator, based on a 32-bit linear feedback shift register
+///
+/// The sequence is deterministic and has a period of 2^32 - 1, which makes the tests reproducible.
+#[derive(Clone, Debug)]
+pub struct Lfsr {
+    state: u32
+}
+
+impl Lfsr {
+    /// Initializes the generator, a null seed being replaced by 1
+    pub fn new(seed: u32) -> Self {
+        Self { state: if seed == 0 { 1 } else { seed } }
+    }
+
+    /// Returns the next bit of the sequence
+    pub fn next_bit(&mut self) -> bool {
+        // Galois form, x^32 + x^22 + x^2 + x + 1
+        let bit = self.state & 1 != 0;
+        self.state >>= 1;
+        if bit {
+            self.state ^= 0x8020_0003;
+        }
+        bit
+    }
+
+    /// Returns the next 32 bits of the sequence
+    pub fn next_u32(&mut self) -> u32 {
+        (0..32).fold(0, |x, _| (x << 1) | self.next_bit() as u32)
+    }
+
+    /// Returns a uniformly distributed number, between -1 and 1
+    pub fn uniform(&mut self) -> f64 {
+        self.next_u32() as i32 as f64 / 2147483648.0
+    }
+}
+
+/// Writes uniformly distributed white noise, between -amplitude and amplitude
+pub fn noise<T: Float<N>, const N: usize>(data: &mut [(T, T); N], lfsr: &mut Lfsr, amplitude: f64) {
+    for y in data.iter_mut() {
+        *y = (T::from_f64(amplitude * lfsr.uniform()), T::ZERO);
+    }
+}
+
+/// Spectral shape of a noise signal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NoiseColor {
+    /// Flat power spectral density
+    White,
+    /// Power spectral density in 1 / f, i.e. equal power in each octave
+    Pink,
+    /// Power spectral density in 1 / f², i.e. integrated white noise
+    Brown
+}
+
+/// Writes noise of the given color, with on average the same RMS value as white noise between -amplitude
+/// and amplitude
+///
+/// White noise is shaped in the frequency domain, through an FFT and an IFFT, which makes the signal
+/// periodic over the N samples without any DC component. The peaks can exceed the amplitude, which
+/// must leave some headroom with the fixed-point types.
+/// This is a blocking function, which returns the status flags of the conversions.
+/// For example:
+/// ```
+/// use embfft::synth::{self, Lfsr, NoiseColor};
+///
+/// let mut data = [(0.0f32, 0.0); 256];
+/// synth::shaped_noise(&mut data, &mut Lfsr::new(1), NoiseColor::Pink, 0.5);
+/// ```
+pub fn shaped_noise<T: Float<N>, const N: usize>(data: &mut [(T, T); N], lfsr: &mut Lfsr, color: NoiseColor, amplitude: f64) -> Status {
+    noise(data, lfsr, amplitude);
+    let gain = |k: usize| {
+        let f = k.min(N - k) as f64;
+        match color {
+            NoiseColor::White => 1.0,
+            NoiseColor::Pink => 1.0 / math::sqrt(f),
+            NoiseColor::Brown => 1.0 / f
+        }
+    };
+    if color == NoiseColor::White {
+        return Status::OK;
+    }
+
+    // The gains are normalized to keep the total power
+    let power: f64 = (1..N).map(|k| gain(k) * gain(k)).sum();
+    let norm = math::sqrt(N as f64 / power);
+
+    let mut fft = EmbFft::new(data);
+    fft.fft();
+    let mut status = fft.status();
+    data[0] = (T::ZERO, T::ZERO);
+    for (k, x) in data.iter_mut().enumerate().skip(1) {
+        let g = T::from_f64(gain(k) * norm);
+        *x = (x.0 * g, x.1 * g);
+    }
+    let mut ifft = EmbIfft::new(data);
+    ifft.ifft();
+    status |= ifft.status();
+
+    // The imaginary parts are only rounding errors
+    for x in data.iter_mut() {
+        x.1 = T::ZERO;
+    }
+    status
+}
+
+/// Maximum length sequence generator, for transfer function measurements
+///
+/// The sequence of order m has a period of 2^m - 1 and a nearly flat spectrum. Its response can be
+/// deconvolved with a kernel from [`deconvolution_kernel()`].
+/// For example:
+/// ```
+/// use embfft::convolve::FftConvolver;
+/// use embfft::synth::{self, Mls};
+///
+/// let mut excitation = [(0.0f32, 0.0); 64];
+/// synth::mls(&mut excitation, &mut Mls::new(6).unwrap(), 1.0);
+/// let kernel = synth::deconvolution_kernel(&excitation).unwrap();
+///
+/// // Periodic response of the system under test, here a simple delay
+/// let mut response: [(f32, f32); 64] = core::array::from_fn(|i| excitation[(i + 61) % 64]);
+/// FftConvolver::new(&kernel, &mut response).convolve();
+/// assert!((response[3].0 - 1.0).abs() < 1e-4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Mls {
+    state: u32,
+    taps: u32,
+    order: u32
+}
+
+impl Mls {
+    /// Feedback taps of maximal length registers, from order 2 to 32
+    const TAPS: [u32; 31] = [
+        0x3, 0x6, 0xC, 0x14, 0x30, 0x60, 0xB8, 0x110, 0x240, 0x500, 0x829, 0x100D, 0x2015, 0x6000, 0xD008,
+        0x12000, 0x20400, 0x40023, 0x90000, 0x140000, 0x300000, 0x420000, 0xE10000, 0x1200000, 0x2000023,
+        0x4000013, 0x9000000, 0x14000000, 0x20000029, 0x48000000, 0x80200003
+    ];
+
+    /// Initializes a generator of the given order
+    ///
+    /// Fails with [`Error::InvalidParameter`] if the order is not between 2 and 32.
+    pub fn new(order: u32) -> Result<Self, Error> {
+        if !(2..=32).contains(&order) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { state: 1, taps: Self::TAPS[order as usize - 2], order })
+    }
+
+    /// Returns the period of the sequence
+    pub fn period(&self) -> u64 {
+        (1 << self.order) - 1
+    }
+
+    /// Returns the next bit of the sequence
+    pub fn next_bit(&mut self) -> bool {
+        let bit = self.state & 1 != 0;
+        self.state >>= 1;
+        if bit {
+            self.state ^= self.taps;
+        }
+        bit
+    }
+}
+
+/// Writes a maximum length sequence, with values of -amplitude and amplitude
+pub fn mls<T: Float<N>, const N: usize>(data: &mut [(T, T); N], mls: &mut Mls, amplitude: f64) {
+    for y in data.iter_mut() {
+        let x = if mls.next_bit() { -amplitude } else { amplitude };
+        *y = (T::from_f64(x), T::ZERO);
+    }
+}
+
+/// Computes the kernel that recovers the impulse response of a system from its response to an excitation
+///
+/// The excitation must be played periodically, and one period of the response must be recorded once it
+/// has settled. Convolving that period with this kernel gives the circular impulse response, whose FFT
+/// is the frequency response. The bins where the excitation has no energy are set to zero.
+/// This is a blocking function, which performs an FFT.
+/// Fails with [`Error::InvalidParameter`] if the excitation is null.
+pub fn deconvolution_kernel<T: Float<N>, const N: usize>(excitation: &[(T, T); N]) -> Result<FftKernel<T, N>, Error> {
+    let mut spectrum = *excitation;
+    EmbFft::new(&mut spectrum).fft();
+
+    let mut energy = 0.0;
+    for x in spectrum.iter() {
+        let (re, im) = (x.0.to_f64(), x.1.to_f64());
+        energy += re * re + im * im;
+    }
+    if energy == 0.0 {
+        return Err(Error::InvalidParameter);
+    }
+
+    // 1 / X = conj(X) / |X|², ignoring the bins that are more than 60 dB below the average
+    let threshold = 1e-6 * energy / N as f64;
+    for x in spectrum.iter_mut() {
+        let (re, im) = (x.0.to_f64(), x.1.to_f64());
+        let m2 = re * re + im * im;
+        *x = if m2 > threshold {
+            (T::from_f64(re / m2), T::from_f64(-im / m2))
+        } else {
+            (T::ZERO, T::ZERO)
+        };
+    }
+    Ok(FftKernel::from_spectrum(&spectrum))
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_multitone() {
+        let mut data = [(0.0, 0.0); 32];
+        multitone(&mut data, &[Tone::new(0.0, 0.5), Tone::new(3.0 / 32.0, 2.0).with_phase(PI / 2.0)]);
+        crate::EmbFft::new(&mut data).fft();
+        for (k, x) in data.iter().enumerate() {
+            let expected = match k {
+                0 => (16.0, 0.0),
+                3 => (0.0, 32.0),
+                29 => (0.0, -32.0),
+                _ => (0.0, 0.0)
+            };
+            assert_abs_diff_eq!(x.0, expected.0, epsilon = 1e-10);
+            assert_abs_diff_eq!(x.1, expected.1, epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_shaped_noise() {
+        let octave = |data: &[(f64, f64); 1024], k: usize| (k..2 * k).map(|k| data[k].0 * data[k].0 + data[k].1 * data[k].1).sum::<f64>();
+        let mut lfsr = Lfsr::new(0xC0FF_EE00);
+        // The lowest bins dominate brown noise, whose RMS value varies the most
+        for (color, ratio, tolerance) in [(NoiseColor::White, 4.0, 0.01), (NoiseColor::Pink, 1.0, 0.03), (NoiseColor::Brown, 0.25, 0.15)] {
+            let mut data = [(0.0, 0.0); 1024];
+            assert!(!shaped_noise(&mut data, &mut lfsr, color, 0.5).is_corrupted());
+            let rms = math::sqrt(data.iter().map(|x| x.0 * x.0).sum::<f64>() / 1024.0);
+            assert_abs_diff_eq!(rms, 0.5 / 3.0f64.sqrt(), epsilon = tolerance);
+            assert!(data.iter().all(|x| x.1 == 0.0));
+
+            // Power of the octave [128, 256) relative to [32, 64)
+            crate::EmbFft::new(&mut data).fft();
+            let measured = octave(&data, 128) / octave(&data, 32);
+            assert!(measured > 0.5 * ratio && measured < 2.0 * ratio);
+        }
+    }
+
+    #[test]
+    fn test_chirps() {
+        // The sign changes of the signal give its instantaneous frequency
+        let mut data = [(0.0, 0.0); 1024];
+        for chirp in [linear_chirp::<f64, 1024>, log_chirp::<f64, 1024>] {
+            chirp(&mut data, 0.01, 0.2, 1.0);
+            assert_abs_diff_eq!(data[0].0, 1.0, epsilon = 1e-12);
+            let crossings = |range: core::ops::Range<usize>| {
+                range.filter(|&i| (data[i].0 < 0.0) != (data[i + 1].0 < 0.0)).count()
+            };
+            let (start, end) = (crossings(0..128), crossings(895..1023));
+            assert!(start < end / 4);
+        }
+
+        // With equal frequencies, both are a plain tone
+        let mut a = [(0.0, 0.0); 64];
+        let mut b = [(0.0, 0.0); 64];
+        linear_chirp(&mut a, 0.1, 0.1, 1.0);
+        log_chirp(&mut b, 0.1, 0.1, 1.0);
+        for (x, y) in core::iter::zip(a, b) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_noise() {
+        let mut lfsr = Lfsr::new(0);
+        let mut data = [(0.0f32, 0.0); 4096];
+        noise(&mut data, &mut lfsr, 0.5);
+        let mean = data.iter().map(|x| x.0 as f64).sum::<f64>() / 4096.0;
+        let power = data.iter().map(|x| (x.0 * x.0) as f64).sum::<f64>() / 4096.0;
+        assert!(data.iter().all(|x| x.0 >= -0.5 && x.0 < 0.5));
+        assert_abs_diff_eq!(mean, 0.0, epsilon = 0.02);
+        assert_abs_diff_eq!(power, 0.25 / 3.0, epsilon = 0.005);
+
+        // The sequence is reproducible
+        let mut a = Lfsr::new(1234);
+        let mut b = Lfsr::new(1234);
+        assert_eq!(a.next_u32(), b.next_u32());
+    }
+
+    #[test]
+    fn test_mls() {
+        assert!(Mls::new(1).is_err());
+        assert!(Mls::new(33).is_err());
+        for order in 2..=16 {
+            let mut mls = Mls::new(order).unwrap();
+            let initial = mls.state;
+            let mut ones = 0;
+            for i in 1..=mls.period() {
+                ones += mls.next_bit() as u64;
+                assert!(mls.state != initial || i == mls.period());
+            }
+            assert_eq!(mls.state, initial);
+            assert_eq!(ones, 1 << (order - 1));
+        }
+    }
+
+    #[test]
+    fn test_deconvolution() {
+        assert!(deconvolution_kernel(&[(0.0f64, 0.0); 8]).is_err());
+
+        let mut excitation = [(0.0, 0.0); 256];
+        mls(&mut excitation, &mut Mls::new(8).unwrap(), 0.5);
+        let mut impulse = [(0.0, 0.0); 256];
+        impulse[0] = (0.5, 0.0);
+        impulse[1] = (0.25, 0.0);
+        impulse[5] = (-0.125, 0.0);
+
+        // Periodic response of the system
+        let mut response = excitation;
+        crate::convolve::FftConvolver::new(&FftKernel::new(&impulse), &mut response).convolve();
+
+        let kernel = deconvolution_kernel(&excitation).unwrap();
+        crate::convolve::FftConvolver::new(&kernel, &mut response).convolve();
+        for (x, y) in core::iter::zip(response, impulse) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-9);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-9);
+        }
+    }
+}
@@ -0,0 +1,440 @@
+/* embfft | telemetry.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Compact encoding of spectra, for low-bandwidth links
+//!
+//! The levels are quantized in dB to 8-bit or 16-bit codes. A key frame contains the raw codes, and the
+//! following frames only contain the differences with the previous one, as variable-length integers, with
+//! runs of unchanged bins collapsed. A stable spectrum thus takes a few bytes per frame. A frame can also
+//! carry a timestamp, such as the one of the [`Framer`](crate::framer::Framer) the spectrum comes from.
+//! For example:
+//! ```
+//! use embfft::telemetry::{CodeWidth, Quantizer, SpectrumDecoder, SpectrumEncoder};
+//!
+//! let quantizer = Quantizer::new(-120.0, 0.5, CodeWidth::U8).unwrap();
+//! let mut encoder = SpectrumEncoder::<64>::new(quantizer);
+//! let mut decoder = SpectrumDecoder::<64>::new(quantizer);
+//! let mut frame = [0; SpectrumEncoder::<64>::MAX_FRAME_SIZE];
+//! let mut levels = [0.0; 64];
+//!
+//! let len = encoder.encode(&[-60.0; 64], &mut frame).unwrap();
+//! assert_eq!(len, 65);
+//! decoder.decode(&frame[..len], &mut levels).unwrap();
+//!
+//! let len = encoder.encode(&[-60.0; 64], &mut frame).unwrap();
+//! assert_eq!(len, 3);
+//! decoder.decode(&frame[..len], &mut levels).unwrap();
+//! assert_eq!(levels, [-60.0; 64]);
+//! ```
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+
+/******************************************************************************/
+
+/// Size of the quantized codes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CodeWidth {
+    /// 8-bit codes, 256 levels
+    U8,
+    /// 16-bit codes, 65536 levels
+    U16
+}
+
+impl CodeWidth {
+    fn max(self) -> u16 {
+        match self {
+            CodeWidth::U8 => u8::MAX as u16,
+            CodeWidth::U16 => u16::MAX
+        }
+    }
+}
+
+/// Quantization of the levels in dB, shared by the encoder and the decoder
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantizer {
+    floor: f64,
+    step: f64,
+    width: CodeWidth
+}
+
+impl Quantizer {
+    /// Initializes the quantization, where code c stands for `floor + c * step` dB
+    ///
+    /// Fails with [`Error::InvalidParameter`] if the step is not positive.
+    pub fn new(floor: f64, step: f64, width: CodeWidth) -> Result<Self, Error> {
+        if step <= 0.0 {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { floor, step, width })
+    }
+
+    /// Converts a level to the nearest code, the levels out of range being clamped
+    pub fn quantize(&self, level: f64) -> u16 {
+        let x = (level - self.floor) / self.step + 0.5;
+        let max = self.width.max();
+        // NaN and -inf are clamped to the floor
+        if x >= max as f64 {
+            max
+        } else if x >= 0.0 {
+            x as u16
+        } else {
+            0
+        }
+    }
+
+    /// Converts a code back to a level
+    pub fn level(&self, code: u16) -> f64 {
+        self.floor + code as f64 * self.step
+    }
+}
+
+/// Frame type markers
+const KEY_FRAME: u8 = 0;
+const DELTA_FRAME: u8 = 1;
+/// Flag of the frames followed by a timestamp
+const STAMPED: u8 = 0x80;
+
+fn zigzag(x: i32) -> u32 {
+    ((x << 1) ^ (x >> 31)) as u32
+}
+
+fn unzigzag(x: u32) -> i32 {
+    (x >> 1) as i32 ^ -((x & 1) as i32)
+}
+
+/// Writes a variable-length integer, 7 bits per byte, and returns the updated position
+fn put_varint(output: &mut [u8], mut pos: usize, mut x: u32) -> Result<usize, Error> {
+    loop {
+        let byte = output.get_mut(pos).ok_or(Error::InvalidLength)?;
+        pos += 1;
+        if x < 0x80 {
+            *byte = x as u8;
+            return Ok(pos);
+        }
+        *byte = (x as u8 & 0x7F) | 0x80;
+        x >>= 7;
+    }
+}
+
+/// Reads a variable-length integer, and returns it with the updated position
+fn get_varint(input: &[u8], mut pos: usize) -> Result<(u32, usize), Error> {
+    let mut x = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *input.get(pos).ok_or(Error::InvalidLength)?;
+        pos += 1;
+        x |= ((byte & 0x7F) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((x, pos));
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err(Error::InvalidLength);
+        }
+    }
+}
+
+/// Encoder of successive spectra of M bins
+pub struct SpectrumEncoder<const M: usize> {
+    quantizer: Quantizer,
+    previous: [u16; M],
+    key_interval: usize,
+    count: usize
+}
+
+impl<const M: usize> SpectrumEncoder<M> {
+    /// Largest size of an encoded frame, in bytes, timestamp included
+    pub const MAX_FRAME_SIZE: usize = 9 + 3 * M;
+
+    /// Initializes an encoder, whose first frame is a key frame
+    pub fn new(quantizer: Quantizer) -> Self {
+        Self { quantizer, previous: [0; M], key_interval: 0, count: 0 }
+    }
+
+    /// Sends a key frame every `interval` frames, so that a receiver can recover from lost frames
+    ///
+    /// With an interval of 0, only the first frame is a key frame.
+    pub fn with_key_interval(mut self, interval: usize) -> Self {
+        self.key_interval = interval;
+        self
+    }
+
+    /// Forces the next frame to be a key frame
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+
+    /// Encodes levels in dB, and returns the length of the frame
+    ///
+    /// Fails with [`Error::InvalidLength`] if the output is too small for the frame, see
+    /// [`SpectrumEncoder::MAX_FRAME_SIZE`]. The state of the encoder is then unchanged.
+    pub fn encode(&mut self, levels: &[f64; M], output: &mut [u8]) -> Result<usize, Error> {
+        self.encode_frame(levels, None, output)
+    }
+
+    /// Encodes levels in dB along with a timestamp, in user ticks, and returns the length of the frame
+    ///
+    /// The timestamp takes 8 more bytes. See [`SpectrumEncoder::encode()`].
+    pub fn encode_stamped(&mut self, levels: &[f64; M], ticks: u64, output: &mut [u8]) -> Result<usize, Error> {
+        self.encode_frame(levels, Some(ticks), output)
+    }
+
+    fn encode_frame(&mut self, levels: &[f64; M], ticks: Option<u64>, output: &mut [u8]) -> Result<usize, Error> {
+        let codes: [u16; M] = core::array::from_fn(|k| self.quantizer.quantize(levels[k]));
+        let key = self.count == 0 || (self.key_interval > 0 && self.count.is_multiple_of(self.key_interval));
+        let mut pos = 1;
+        if let Some(ticks) = ticks {
+            output.get_mut(1..9).ok_or(Error::InvalidLength)?.copy_from_slice(&ticks.to_le_bytes());
+            pos = 9;
+        }
+        let flag = if ticks.is_some() { STAMPED } else { 0 };
+        if key {
+            *output.first_mut().ok_or(Error::InvalidLength)? = KEY_FRAME | flag;
+            for &c in &codes {
+                let bytes = c.to_le_bytes();
+                let bytes = if self.quantizer.width == CodeWidth::U8 { &bytes[..1] } else { &bytes[..] };
+                output.get_mut(pos..pos + bytes.len()).ok_or(Error::InvalidLength)?.copy_from_slice(bytes);
+                pos += bytes.len();
+            }
+        } else {
+            *output.first_mut().ok_or(Error::InvalidLength)? = DELTA_FRAME | flag;
+            let mut k = 0;
+            while k < M {
+                let delta = codes[k] as i32 - self.previous[k] as i32;
+                pos = put_varint(output, pos, zigzag(delta))?;
+                k += 1;
+                if delta == 0 {
+                    // Run of unchanged bins
+                    let mut run = 0;
+                    while k < M && codes[k] == self.previous[k] {
+                        run += 1;
+                        k += 1;
+                    }
+                    pos = put_varint(output, pos, run)?;
+                }
+            }
+        }
+        self.previous = codes;
+        self.count += 1;
+        Ok(pos)
+    }
+
+    /// Encodes the power of each bin of a spectrum, relative to the magnitude `reference`
+    ///
+    /// See [`SpectrumEncoder::encode()`].
+    pub fn encode_spectrum<T: Float<M>>(&mut self, spectrum: &[(T, T); M], reference: f64, output: &mut [u8]) -> Result<usize, Error> {
+        let levels = core::array::from_fn(|k| {
+            let (re, im) = (spectrum[k].0.to_f64(), spectrum[k].1.to_f64());
+            math::power_to_db((re * re + im * im) / (reference * reference))
+        });
+        self.encode(&levels, output)
+    }
+}
+
+/// Decoder of successive spectra of M bins
+pub struct SpectrumDecoder<const M: usize> {
+    quantizer: Quantizer,
+    previous: [u16; M],
+    synchronized: bool,
+    ticks: Option<u64>
+}
+
+impl<const M: usize> SpectrumDecoder<M> {
+    /// Initializes a decoder, which waits for a key frame
+    pub fn new(quantizer: Quantizer) -> Self {
+        Self { quantizer, previous: [0; M], synchronized: false, ticks: None }
+    }
+
+    /// Waits for the next key frame, after a frame was lost
+    pub fn reset(&mut self) {
+        self.synchronized = false;
+    }
+
+    /// Returns the timestamp of the last decoded frame, or `None` if it had none
+    pub fn timestamp(&self) -> Option<u64> {
+        self.ticks
+    }
+
+    /// Decodes a frame into levels in dB, and returns the number of bytes used
+    ///
+    /// Fails with [`Error::NotDone`] if a delta frame is received before any key frame, and with
+    /// [`Error::InvalidLength`] if the frame is truncated or malformed.
+    pub fn decode(&mut self, input: &[u8], levels: &mut [f64; M]) -> Result<usize, Error> {
+        let mut codes = self.previous;
+        let mut pos = 1;
+        let header = input.first().copied();
+        let ticks = if header.is_some_and(|h| h & STAMPED != 0) {
+            let bytes = input.get(1..9).ok_or(Error::InvalidLength)?;
+            pos = 9;
+            Some(u64::from_le_bytes(core::array::from_fn(|i| bytes[i])))
+        } else {
+            None
+        };
+        match header.map(|h| h & !STAMPED).as_ref() {
+            Some(&KEY_FRAME) => {
+                let size = if self.quantizer.width == CodeWidth::U8 { 1 } else { 2 };
+                for c in codes.iter_mut() {
+                    let bytes = input.get(pos..pos + size).ok_or(Error::InvalidLength)?;
+                    *c = if size == 1 { bytes[0] as u16 } else { u16::from_le_bytes([bytes[0], bytes[1]]) };
+                    pos += size;
+                }
+            },
+            Some(&DELTA_FRAME) => {
+                if !self.synchronized {
+                    return Err(Error::NotDone);
+                }
+                let mut k = 0;
+                while k < M {
+                    let (x, next) = get_varint(input, pos)?;
+                    pos = next;
+                    let delta = unzigzag(x);
+                    let code = codes[k] as i32 + delta;
+                    if code < 0 || code > self.quantizer.width.max() as i32 {
+                        return Err(Error::InvalidLength);
+                    }
+                    codes[k] = code as u16;
+                    k += 1;
+                    if delta == 0 {
+                        let (run, next) = get_varint(input, pos)?;
+                        pos = next;
+                        k += run as usize;
+                    }
+                }
+                if k > M {
+                    return Err(Error::InvalidLength);
+                }
+            },
+            _ => {
+                return Err(Error::InvalidLength);
+            }
+        }
+        self.previous = codes;
+        self.synchronized = true;
+        self.ticks = ticks;
+        for (y, &c) in levels.iter_mut().zip(&codes) {
+            *y = self.quantizer.level(c);
+        }
+        Ok(pos)
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantizer() {
+        assert!(Quantizer::new(-100.0, 0.0, CodeWidth::U8).is_err());
+        let q = Quantizer::new(-100.0, 0.25, CodeWidth::U8).unwrap();
+        assert_eq!(q.quantize(-100.0), 0);
+        assert_eq!(q.quantize(-99.9), 0);
+        assert_eq!(q.quantize(-99.8), 1);
+        assert_eq!(q.quantize(f64::NEG_INFINITY), 0);
+        assert_eq!(q.quantize(f64::NAN), 0);
+        assert_eq!(q.quantize(0.0), 255);
+        assert_eq!(q.level(3), -99.25);
+
+        let q = Quantizer::new(-100.0, 0.01, CodeWidth::U16).unwrap();
+        assert_eq!(q.quantize(0.0), 10000);
+        assert_eq!(q.quantize(1e9), u16::MAX);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for width in [CodeWidth::U8, CodeWidth::U16] {
+            let q = Quantizer::new(-100.0, 0.5, width).unwrap();
+            let mut encoder = SpectrumEncoder::<8>::new(q).with_key_interval(3);
+            let mut decoder = SpectrumDecoder::<8>::new(q);
+            let mut frame = [0; SpectrumEncoder::<8>::MAX_FRAME_SIZE];
+            let mut levels = [0.0; 8];
+
+            let frames = [
+                [-10.0, -20.0, -30.0, -40.0, -50.0, -60.0, -70.0, -80.0],
+                [-10.0, -20.0, -30.0, -40.0, -50.0, -60.0, -70.0, -80.0],
+                [-10.0, -20.5, -30.0, -40.0, 0.0, -60.0, -70.0, -79.0],
+                [-10.0, -20.0, -30.0, -40.0, -50.0, -60.0, -70.0, -80.0]
+            ];
+            let lengths = [if width == CodeWidth::U8 { 9 } else { 17 }, 3, 11, 0];
+            for (i, f) in frames.iter().enumerate() {
+                let len = encoder.encode(f, &mut frame).unwrap();
+                if lengths[i] > 0 {
+                    assert_eq!(len, lengths[i]);
+                } else {
+                    assert_eq!(frame[0], KEY_FRAME);
+                }
+                if i == 1 {
+                    // The decoder needs a key frame first
+                    let mut lost = SpectrumDecoder::<8>::new(q);
+                    assert_eq!(lost.decode(&frame[..len], &mut levels), Err(Error::NotDone));
+                }
+                assert_eq!(decoder.decode(&frame[..len], &mut levels), Ok(len));
+                assert_eq!(&levels, f);
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let q = Quantizer::new(-100.0, 1.0, CodeWidth::U16).unwrap();
+        let mut encoder = SpectrumEncoder::<4>::new(q);
+        let mut decoder = SpectrumDecoder::<4>::new(q);
+        let mut frame = [0; 16];
+        let mut levels = [0.0; 4];
+        assert_eq!(encoder.encode(&[0.0; 4], &mut frame[..8]), Err(Error::InvalidLength));
+        let len = encoder.encode(&[0.0; 4], &mut frame).unwrap();
+        assert_eq!(decoder.decode(&frame[..len - 1], &mut levels), Err(Error::InvalidLength));
+        assert_eq!(decoder.decode(&[7], &mut levels), Err(Error::InvalidLength));
+        decoder.decode(&frame[..len], &mut levels).unwrap();
+
+        // Run longer than the spectrum
+        assert_eq!(decoder.decode(&[DELTA_FRAME, 0, 9], &mut levels), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_encode_spectrum() {
+        let q = Quantizer::new(-60.0, 1.0, CodeWidth::U8).unwrap();
+        let mut encoder = SpectrumEncoder::<4>::new(q);
+        let mut decoder = SpectrumDecoder::<4>::new(q);
+        let mut frame = [0; 16];
+        let mut levels = [0.0; 4];
+        let len = encoder.encode_spectrum(&[(2.0f32, 0.0), (0.0, 0.2), (0.0, 0.0), (0.02, 0.0)], 2.0, &mut frame).unwrap();
+        decoder.decode(&frame[..len], &mut levels).unwrap();
+        assert_eq!(levels, [0.0, -20.0, -60.0, -40.0]);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let q = Quantizer::new(-100.0, 1.0, CodeWidth::U8).unwrap();
+        let mut encoder = SpectrumEncoder::<4>::new(q);
+        let mut decoder = SpectrumDecoder::<4>::new(q);
+        let mut frame = [0; SpectrumEncoder::<4>::MAX_FRAME_SIZE];
+        let mut levels = [0.0; 4];
+        assert_eq!(encoder.encode_stamped(&[-10.0; 4], 1, &mut frame[..8]), Err(Error::InvalidLength));
+
+        let ticks = 0x0123_4567_89AB_CDEF;
+        let len = encoder.encode_stamped(&[-10.0; 4], ticks, &mut frame).unwrap();
+        assert_eq!(len, 13);
+        assert_eq!(decoder.decode(&frame[..8], &mut levels), Err(Error::InvalidLength));
+        decoder.decode(&frame[..len], &mut levels).unwrap();
+        assert_eq!(decoder.timestamp(), Some(ticks));
+
+        // Delta frames can be stamped too
+        let len = encoder.encode_stamped(&[-10.0; 4], ticks + 1, &mut frame).unwrap();
+        assert_eq!(len, 11);
+        decoder.decode(&frame[..len], &mut levels).unwrap();
+        assert_eq!(decoder.timestamp(), Some(ticks + 1));
+        let len = encoder.encode(&[-20.0; 4], &mut frame).unwrap();
+        decoder.decode(&frame[..len], &mut levels).unwrap();
+        assert_eq!(decoder.timestamp(), None);
+        assert_eq!(levels, [-20.0; 4]);
+    }
+}
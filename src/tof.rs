@@ -0,0 +1,285 @@
+/* embfft | tof.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Ultrasonic time-of-flight measurement
+//!
+//! Ultrasonic rangefinders and flow meters transmit a burst, and time its echo or its arrival at a second
+//! transducer. A chirp, rather than a tone burst, is compressed by its matched filter into a short peak,
+//! which stands out of the noise and can be timed to a fraction of a sample. A [`TofMeasurement`] chains
+//! the generation of the chirp, the matched filtering, the extraction of the envelope and the
+//! interpolation of its peak.
+//! For example:
+//! ```
+//! use embfft::tof::TofMeasurement;
+//! use embfft::units::{Hertz, SampleRate};
+//!
+//! let fs = SampleRate::new(200000.0).unwrap();
+//! let tof = TofMeasurement::<f32, 256>::new(fs, Hertz::khz(30.0), Hertz::khz(50.0), 64).unwrap();
+//!
+//! // Echo of the chirp, attenuated by half and received 100 samples after its transmission
+//! let mut chirp = [(0.0f32, 0.0); 256];
+//! tof.chirp(&mut chirp);
+//! let mut data = [(0.0f32, 0.0); 256];
+//! for (y, x) in data[100..].iter_mut().zip(chirp.iter()) {
+//!     *y = (0.5 * x.0, 0.0);
+//! }
+//!
+//! let echo = tof.measure(&mut data).unwrap();
+//! assert!((echo.delay - 100.0).abs() < 0.1);
+//! assert!((echo.amplitude - 0.5).abs() < 0.05);
+//! // 500 µs, i.e. 8.6 cm in air
+//! assert!((echo.distance(343.0) - 0.08575).abs() < 1e-4);
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+use crate::convolve::{FftConvolver, FftKernel};
+use crate::error::Error;
+use crate::math;
+use crate::status::Status;
+use crate::units::{Hertz, SampleRate};
+use crate::EmbFft;
+
+/******************************************************************************/
+
+/// Echo found by a time-of-flight measurement
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Echo {
+    /// Delay of the echo from the start of the frame, in samples, interpolated between the samples
+    pub delay: f64,
+    /// Delay of the echo from the start of the frame, in seconds
+    pub time: f64,
+    /// Amplitude of the echo, relative to that of the transmitted chirp
+    pub amplitude: f64,
+    /// Status flags of the processing
+    pub status: Status
+}
+
+impl Echo {
+    /// Returns the distance to the reflector, for a pulse-echo measurement at the given speed of sound,
+    /// in meters per second
+    ///
+    /// The sound travels the distance twice, to the reflector and back.
+    pub fn distance(&self, speed_of_sound: f64) -> f64 {
+        speed_of_sound * self.time / 2.0
+    }
+}
+
+/// Time-of-flight measurement with a linear chirp of up to N samples, in frames of N samples
+///
+/// The frames start at the transmission of the chirp, and must hold the whole echo, so that the delays
+/// range from 0 to N minus the length of the chirp. The matched filter only keeps the positive
+/// frequencies, so that its output is the analytic correlation, whose magnitude is the envelope.
+pub struct TofMeasurement<T, const N: usize> {
+    kernel: FftKernel<T, N>,
+    sample_rate: SampleRate,
+    f0: f64,
+    f1: f64,
+    length: usize,
+    energy: f64,
+    blanking: usize,
+    threshold: f64
+}
+
+impl<T: Float<N>, const N: usize> TofMeasurement<T, N> {
+    /// Initializes a measurement with a chirp of `length` samples, from `f0` to `f1`
+    ///
+    /// This is a blocking function, which performs an FFT.
+    /// Fails with [`Error::InvalidLength`] if the length is 0 or greater than N, and with
+    /// [`Error::InvalidParameter`] if a frequency is not between 0 and the Nyquist frequency.
+    pub fn new(sample_rate: SampleRate, f0: Hertz, f1: Hertz, length: usize) -> Result<Self, Error> {
+        if length == 0 || length > N {
+            return Err(Error::InvalidLength);
+        }
+        let nyquist = sample_rate.nyquist();
+        if [f0, f1].iter().any(|f| f.0.is_nan() || f.0 <= 0.0 || f.0 > nyquist.0) {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut tof = Self {
+            kernel: FftKernel::from_spectrum(&[(T::ZERO, T::ZERO); N]),
+            sample_rate,
+            f0: sample_rate.normalize(f0),
+            f1: sample_rate.normalize(f1),
+            length,
+            energy: 0.0,
+            blanking: 0,
+            threshold: 0.0
+        };
+        let mut spectrum = [(T::ZERO, T::ZERO); N];
+        tof.chirp(&mut spectrum);
+        tof.energy = spectrum.iter().map(|x| x.0.to_f64() * x.0.to_f64()).sum();
+
+        // Conjugate of the chirp spectrum, with the negative frequencies folded onto the positive ones
+        EmbFft::new(&mut spectrum).fft();
+        for (k, x) in spectrum.iter_mut().enumerate() {
+            *x = if k == 0 || 2 * k == N {
+                (x.0, T::ZERO - x.1)
+            } else if 2 * k < N {
+                (x.0 + x.0, T::ZERO - x.1 - x.1)
+            } else {
+                (T::ZERO, T::ZERO)
+            };
+        }
+        tof.kernel = FftKernel::from_spectrum(&spectrum);
+        Ok(tof)
+    }
+
+    /// Ignores the echoes received during the first samples of the frame, e.g. while the transducer rings
+    /// after the transmission
+    pub fn with_blanking(mut self, samples: usize) -> Self {
+        self.blanking = samples;
+        self
+    }
+
+    /// Ignores the echoes whose amplitude, relative to that of the transmitted chirp, is not above a
+    /// threshold
+    pub fn with_threshold(mut self, amplitude: f64) -> Self {
+        self.threshold = amplitude;
+        self
+    }
+
+    /// Writes the chirp to transmit, of unit amplitude, followed by zeros
+    pub fn chirp(&self, data: &mut [(T, T); N]) {
+        let rate = (self.f1 - self.f0) / self.length as f64;
+        for (i, y) in data.iter_mut().enumerate() {
+            *y = if i < self.length {
+                let n = i as f64;
+                // Keep the argument small, to preserve the accuracy
+                let cycles = self.f0 * n + rate * n * n / 2.0;
+                (T::from_f64(math::sin_cos(2.0 * PI * (cycles - cycles as i64 as f64)).1), T::ZERO)
+            } else {
+                (T::ZERO, T::ZERO)
+            };
+        }
+    }
+
+    /// Finds the strongest echo of the chirp in a frame of received samples
+    ///
+    /// The frame is replaced by the analytic output of the matched filter, whose magnitude is the envelope
+    /// of the correlation. The peak of the envelope is then refined by a parabola through its neighbours.
+    /// Returns `None` if no echo rises above the threshold after the blanking period.
+    /// This is a blocking function, which performs an FFT and an IFFT.
+    pub fn measure(&self, data: &mut [(T, T); N]) -> Option<Echo> {
+        let mut conv = FftConvolver::new(&self.kernel, data);
+        conv.convolve();
+        let status = conv.status();
+        let data = conv.into_data();
+
+        let envelope = |i: usize| {
+            let x = data[i % N];
+            let (re, im) = (x.0.to_f64(), x.1.to_f64());
+            math::sqrt(re * re + im * im)
+        };
+        let mut peak = None;
+        for i in self.blanking..=N - self.length {
+            let e = envelope(i);
+            if e > self.threshold * self.energy && peak.is_none_or(|(_, p)| e > p) {
+                peak = Some((i, e));
+            }
+        }
+        let (i, b) = peak?;
+
+        let (a, c) = (envelope(i + N - 1), envelope(i + 1));
+        let d = 2.0 * (2.0 * b - a - c);
+        let delay = i as f64 + if d == 0.0 { 0.0 } else { ((c - a) / d).clamp(-0.5, 0.5) };
+        Some(Echo {
+            delay,
+            time: delay / self.sample_rate.hertz().0,
+            amplitude: b / self.energy,
+            status
+        })
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+    use crate::synth::Lfsr;
+    use crate::EmbIfft;
+    use approx::assert_abs_diff_eq;
+
+    /// Chirp from 0.15 to 0.25 cycles per sample over 64 samples
+    fn measurement() -> TofMeasurement<f64, 512> {
+        let fs = SampleRate::new(1.0).unwrap();
+        TofMeasurement::new(fs, Hertz(0.15), Hertz(0.25), 64).unwrap()
+    }
+
+    /// Echo of the chirp, delayed by a fractional number of samples through a phase ramp of its spectrum
+    fn echo(tof: &TofMeasurement<f64, 512>, delay: f64, amplitude: f64) -> [(f64, f64); 512] {
+        let mut data = [(0.0, 0.0); 512];
+        tof.chirp(&mut data);
+        EmbFft::new(&mut data).fft();
+        for (k, x) in data.iter_mut().enumerate() {
+            let f = if 2 * k <= 512 { k as f64 } else { k as f64 - 512.0 };
+            let (sin, cos) = cordic::sin_cos(-2.0 * PI * f * delay / 512.0);
+            *x = (amplitude * (x.0 * cos - x.1 * sin), amplitude * (x.0 * sin + x.1 * cos));
+        }
+        EmbIfft::new(&mut data).ifft();
+        data.map(|x| (x.0, 0.0))
+    }
+
+    #[test]
+    fn test_errors() {
+        let fs = SampleRate::new(1000.0).unwrap();
+        let new = |f0, f1, length| TofMeasurement::<f32, 64>::new(fs, Hertz(f0), Hertz(f1), length).err();
+        assert_eq!(new(100.0, 200.0, 0), Some(Error::InvalidLength));
+        assert_eq!(new(100.0, 200.0, 65), Some(Error::InvalidLength));
+        assert_eq!(new(0.0, 200.0, 32), Some(Error::InvalidParameter));
+        assert_eq!(new(100.0, 501.0, 32), Some(Error::InvalidParameter));
+        assert_eq!(new(100.0, f64::NAN, 32), Some(Error::InvalidParameter));
+        assert_eq!(new(400.0, 100.0, 64), None);
+
+        // Nothing to find in a null frame
+        assert_eq!(measurement().measure(&mut [(0.0, 0.0); 512]), None);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_sub_sample() {
+        let tof = measurement();
+        let mut chirp = [(1.0, 1.0); 512];
+        tof.chirp(&mut chirp);
+        assert_eq!(chirp[0], (1.0, 0.0));
+        assert_abs_diff_eq!(chirp[63].0, cordic::cos(2.0 * PI * (0.15 * 63.0 + 0.1 / 128.0 * 63.0 * 63.0)), epsilon = 1e-12);
+        assert!(chirp[64..].iter().all(|&x| x == (0.0, 0.0)));
+
+        for delay in [0.0, 17.1, 200.25, 301.5, 448.0] {
+            let mut data = echo(&tof, delay, 0.1);
+            let echo = tof.measure(&mut data).unwrap();
+            assert_abs_diff_eq!(echo.delay, delay, epsilon = 0.02);
+            assert_abs_diff_eq!(echo.time, delay, epsilon = 0.02);
+            assert_abs_diff_eq!(echo.amplitude, 0.1, epsilon = 0.002);
+            assert!(!echo.status.is_corrupted());
+        }
+    }
+
+    #[test]
+    fn test_noise() {
+        // Ringing of the transducer, then a weak echo in noise
+        let tof = measurement();
+        let ringing = echo(&tof, 0.0, 1.0);
+        let mut data = echo(&tof, 150.7, 0.2);
+        let mut lfsr = Lfsr::new(3);
+        for (x, r) in data.iter_mut().zip(ringing.iter()) {
+            x.0 += r.0 + 0.1 * lfsr.uniform();
+        }
+
+        let mut frame = data;
+        let echo = tof.measure(&mut frame).unwrap();
+        assert_eq!(echo.delay.round(), 0.0);
+        let mut frame = data;
+        let echo = measurement().with_blanking(100).with_threshold(0.1).measure(&mut frame).unwrap();
+        assert_abs_diff_eq!(echo.delay, 150.7, epsilon = 0.3);
+        assert_eq!(measurement().with_blanking(100).with_threshold(0.5).measure(&mut data), None);
+    }
+}
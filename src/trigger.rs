@@ -0,0 +1,202 @@
+/* embfft | trigger.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Triggered acquisition
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+
+/******************************************************************************/
+
+/// Trigger condition
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition<T> {
+    /// Fires as soon as a sample is greater than or equal to the level
+    Above(T),
+    /// Fires as soon as a sample is lower than or equal to the level
+    Below(T),
+    /// Fires when the signal crosses the level upwards
+    RisingEdge(T),
+    /// Fires when the signal crosses the level downwards
+    FallingEdge(T)
+}
+
+/// Acquisition state
+#[derive(PartialEq)]
+enum State {
+    Armed,
+    Triggered,
+    Ready
+}
+
+/// Trigger engine
+///
+/// The samples are kept in a ring buffer until the trigger condition fires, so that the released frame
+/// contains the requested amount of pre-trigger samples, followed by the trigger sample and the samples
+/// acquired after it, for a total of exactly N samples.
+///
+/// For example:
+/// ```
+/// use embfft::trigger::{Condition, Trigger};
+///
+/// let mut trigger = Trigger::<f32, 8>::new(Condition::RisingEdge(0.5), 2).unwrap();
+/// let mut ready = false;
+/// for x in [0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0] {
+///     ready = trigger.push(x);
+/// }
+/// assert!(ready);
+///
+/// let mut data = [(0.0, 0.0); 8];
+/// trigger.read(&mut data).unwrap();
+/// embfft::EmbFft::new(&mut data).fft();
+/// ```
+pub struct Trigger<T, const N: usize> {
+    condition: Condition<T>,
+    pre_trigger: usize,
+    buffer: [T; N],
+    state: State,
+    idx: usize,
+    count: usize,
+    last: Option<T>
+}
+
+impl<T: Float<N>, const N: usize> Trigger<T, N> {
+    /// Initializes a new trigger engine
+    ///
+    /// `pre_trigger` is the number of samples preceding the trigger sample in the released frame.
+    /// Fails with [`Error::InvalidParameter`] if it is not lower than N.
+    pub fn new(condition: Condition<T>, pre_trigger: usize) -> Result<Self, Error> {
+        if pre_trigger >= N {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            condition,
+            pre_trigger,
+            buffer: [T::ZERO; N],
+            state: State::Armed,
+            idx: 0,
+            count: 0,
+            last: None
+        })
+    }
+
+    /// Discards the acquired samples and waits for a new trigger
+    pub fn rearm(&mut self) {
+        self.state = State::Armed;
+        self.count = 0;
+        self.last = None;
+    }
+
+    fn fires(&self, x: T) -> bool {
+        match self.condition {
+            Condition::Above(level) => x >= level,
+            Condition::Below(level) => x <= level,
+            Condition::RisingEdge(level) => self.last.is_some_and(|last| last < level) && x >= level,
+            Condition::FallingEdge(level) => self.last.is_some_and(|last| last > level) && x <= level
+        }
+    }
+
+    /// Feeds a new sample
+    ///
+    /// Returns `true` once a complete frame is available. Further samples are ignored until the frame is
+    /// read or the trigger is re-armed.
+    pub fn push(&mut self, x: T) -> bool {
+        match self.state {
+            State::Armed => {
+                // The pre-trigger part of the ring must be filled before the trigger can fire
+                let fires = self.count >= self.pre_trigger && self.fires(x);
+                self.last = Some(x);
+                self.write(x);
+                if fires {
+                    self.count = 1;
+                    self.state = State::Triggered;
+                } else {
+                    self.count = usize::min(self.count + 1, self.pre_trigger);
+                }
+            },
+            State::Triggered => {
+                self.write(x);
+                self.count += 1;
+            },
+            State::Ready => {}
+        }
+        if self.state == State::Triggered && self.count == N - self.pre_trigger {
+            self.state = State::Ready;
+        }
+        self.state == State::Ready
+    }
+
+    fn write(&mut self, x: T) {
+        self.buffer[self.idx] = x;
+        self.idx = (self.idx + 1) % N;
+    }
+
+    /// Checks if a complete frame is available
+    pub fn is_ready(&self) -> bool {
+        self.state == State::Ready
+    }
+
+    /// Copies the acquired frame into an FFT buffer and re-arms the trigger
+    ///
+    /// The oldest sample is placed first, and the imaginary parts are set to zero.
+    /// Fails with [`Error::NotDone`] if no complete frame is available.
+    pub fn read(&mut self, data: &mut [(T, T); N]) -> Result<(), Error> {
+        if !self.is_ready() {
+            return Err(Error::NotDone);
+        }
+        // Once ready, the write index points to the oldest sample of the frame
+        for (i, y) in data.iter_mut().enumerate() {
+            *y = (self.buffer[(self.idx + i) % N], T::ZERO);
+        }
+        self.rearm();
+        Ok(())
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rising_edge() {
+        let mut trigger = Trigger::<f32, 4>::new(Condition::RisingEdge(0.5), 1).unwrap();
+        let mut data = [(0.0, 0.0); 4];
+        assert_eq!(trigger.read(&mut data), Err(Error::NotDone));
+
+        // Starting above the level is not an edge
+        for x in [1.0, 2.0, 0.0, -1.0] {
+            assert!(!trigger.push(x));
+        }
+        for x in [3.0, 4.0] {
+            assert!(!trigger.push(x));
+        }
+        assert!(trigger.push(5.0));
+        assert!(trigger.push(6.0));
+
+        trigger.read(&mut data).unwrap();
+        assert_eq!(data, [(-1.0, 0.0), (3.0, 0.0), (4.0, 0.0), (5.0, 0.0)]);
+        assert!(!trigger.is_ready());
+    }
+
+    #[test]
+    fn test_pre_trigger_fill() {
+        assert_eq!(Trigger::<f32, 4>::new(Condition::Above(0.0), 4).err(), Some(Error::InvalidParameter));
+
+        // The trigger cannot fire before the pre-trigger samples are available
+        let mut trigger = Trigger::<f32, 4>::new(Condition::Above(0.0), 2).unwrap();
+        for x in [1.0, 2.0, 3.0] {
+            assert!(!trigger.push(x));
+        }
+        assert!(trigger.push(4.0));
+
+        let mut data = [(0.0, 0.0); 4];
+        trigger.read(&mut data).unwrap();
+        assert_eq!(data, [(1.0, 0.0), (2.0, 0.0), (3.0, 0.0), (4.0, 0.0)]);
+    }
+}
@@ -0,0 +1,171 @@
+/* embfft | units.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Units of the frequency axis
+//!
+//! Frequencies in hertz, sample rates and bin indices are all plain numbers, which are easily mixed up.
+//! These lightweight wrappers turn such mistakes into compile errors, and the conversions between them
+//! are only provided by the [`SampleRate`].
+//! For example:
+//! ```
+//! use embfft::units::{Hertz, SampleRate};
+//!
+//! let fs = SampleRate::new(48000.0).unwrap();
+//! assert_eq!(fs.bin_width::<1024>(), Hertz(46.875));
+//! assert_eq!(fs.to_bin::<1024>(Hertz::khz(3.0)), 64.0);
+//! assert_eq!(fs.nyquist(), Hertz(24000.0));
+//! ```
+
+/******************************************************************************/
+
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::error::Error;
+
+/******************************************************************************/
+
+/// Frequency, in hertz
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hertz(pub f64);
+
+impl Hertz {
+    /// Creates a frequency from a value in kilohertz
+    pub const fn khz(x: f64) -> Self {
+        Self(x * 1e3)
+    }
+
+    /// Creates a frequency from a value in megahertz
+    pub const fn mhz(x: f64) -> Self {
+        Self(x * 1e6)
+    }
+}
+
+impl Add for Hertz {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Hertz {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Hertz {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Mul<f64> for Hertz {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        Self(self.0 * rhs)
+    }
+}
+
+impl Div<f64> for Hertz {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self {
+        Self(self.0 / rhs)
+    }
+}
+
+/// Ratio of two frequencies
+impl Div for Hertz {
+    type Output = f64;
+
+    fn div(self, rhs: Self) -> f64 {
+        self.0 / rhs.0
+    }
+}
+
+/// Sample rate, in samples per second, which is always positive and finite
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SampleRate(f64);
+
+impl SampleRate {
+    /// Creates a sample rate, in samples per second
+    ///
+    /// Fails with [`Error::InvalidParameter`] if the sample rate is not positive and finite.
+    pub fn new(samples_per_second: f64) -> Result<Self, Error> {
+        if samples_per_second > 0.0 && samples_per_second.is_finite() {
+            Ok(Self(samples_per_second))
+        } else {
+            Err(Error::InvalidParameter)
+        }
+    }
+
+    /// Returns the sample rate as a frequency
+    pub fn hertz(self) -> Hertz {
+        Hertz(self.0)
+    }
+
+    /// Returns the Nyquist frequency, half the sample rate
+    pub fn nyquist(self) -> Hertz {
+        Hertz(self.0 / 2.0)
+    }
+
+    /// Returns the frequency step between two bins of an N-point transform
+    pub fn bin_width<const N: usize>(self) -> Hertz {
+        Hertz(self.0 / N as f64)
+    }
+
+    /// Converts a frequency to a bin index of an N-point transform, which may be fractional
+    pub fn to_bin<const N: usize>(self, frequency: Hertz) -> f64 {
+        frequency.0 * N as f64 / self.0
+    }
+
+    /// Converts a bin index of an N-point transform, which may be fractional, to a frequency
+    pub fn bin_frequency<const N: usize>(self, bin: f64) -> Hertz {
+        Hertz(bin * self.0 / N as f64)
+    }
+
+    /// Converts a frequency to cycles per sample
+    pub fn normalize(self, frequency: Hertz) -> f64 {
+        frequency.0 / self.0
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hertz() {
+        assert_eq!(Hertz::khz(1.5) + Hertz(500.0), Hertz(2000.0));
+        assert_eq!(Hertz::mhz(1.0) - Hertz::khz(1.0), Hertz(999000.0));
+        assert_eq!(-Hertz(3.0) * 2.0, Hertz(-6.0));
+        assert_eq!(Hertz(3.0) / 2.0, Hertz(1.5));
+        assert_eq!(Hertz(3.0) / Hertz(2.0), 1.5);
+        assert!(Hertz(1.0) < Hertz(2.0));
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        assert_eq!(SampleRate::new(0.0), Err(Error::InvalidParameter));
+        assert_eq!(SampleRate::new(f64::NAN), Err(Error::InvalidParameter));
+        assert_eq!(SampleRate::new(f64::INFINITY), Err(Error::InvalidParameter));
+
+        let fs = SampleRate::new(8000.0).unwrap();
+        assert_eq!(fs.hertz(), Hertz(8000.0));
+        assert_eq!(fs.bin_frequency::<64>(-2.5), Hertz(-312.5));
+        assert_eq!(fs.to_bin::<64>(Hertz(312.5)), 2.5);
+        assert_eq!(fs.normalize(Hertz(2000.0)), 0.25);
+    }
+}
@@ -0,0 +1,221 @@
+/* embfft | view.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Spectra in engineering units
+//!
+//! For example:
+//! ```
+//! use embfft::units::SampleRate;
+//! use embfft::view::{SpectrumView, Unit};
+//!
+//! let mut data = [(0.25f32, 0.0); 8];
+//! embfft::EmbFft::new(&mut data).fft();
+//!
+//! let view = SpectrumView::new(&data, SampleRate::new(8000.0).unwrap());
+//! assert_eq!(format!("{}", view.bin(0).unwrap()), "0.0 Hz -6.0 dBFS");
+//! assert_eq!(format!("{}", view.bin(3).unwrap()), "3.000 kHz -inf dBFS");
+//! ```
+
+/******************************************************************************/
+
+use core::fmt;
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::math;
+use crate::units::{Hertz, SampleRate};
+
+/******************************************************************************/
+
+/// Unit of the levels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Unit {
+    /// Ratio to the reference
+    Linear,
+    /// Decibels relative to the reference
+    Db,
+    /// Decibels relative to full scale
+    Dbfs,
+    /// Decibels relative to 1 V
+    Dbv
+}
+
+impl Unit {
+    /// Returns the symbol of the unit
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Linear => "",
+            Unit::Db => "dB",
+            Unit::Dbfs => "dBFS",
+            Unit::Dbv => "dBV"
+        }
+    }
+}
+
+/// Single bin of a spectrum, in engineering units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BinView {
+    /// Frequency
+    pub frequency: Hertz,
+    /// Level, in the given unit
+    pub level: f64,
+    /// Unit of the level
+    pub unit: Unit
+}
+
+impl BinView {
+    /// Returns the frequency scaled with an SI prefix, and the prefix
+    fn scaled_frequency(&self) -> (f64, &'static str) {
+        let frequency = self.frequency.0;
+        let f = if frequency < 0.0 { -frequency } else { frequency };
+        if f >= 1e6 {
+            (frequency / 1e6, "M")
+        } else if f >= 1e3 {
+            (frequency / 1e3, "k")
+        } else {
+            (frequency, "")
+        }
+    }
+}
+
+impl fmt::Display for BinView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (frequency, prefix) = self.scaled_frequency();
+        if prefix.is_empty() {
+            write!(f, "{frequency:.1} Hz")?;
+        } else {
+            write!(f, "{frequency:.3} {prefix}Hz")?;
+        }
+        match self.unit {
+            Unit::Linear => write!(f, " {:.4}", self.level),
+            _ => write!(f, " {:.1} {}", self.level, self.unit.symbol())
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for BinView {
+    fn format(&self, f: defmt::Formatter) {
+        let (frequency, prefix) = self.scaled_frequency();
+        defmt::write!(f, "{=f64} {=str}Hz {=f64} {=str}", frequency, prefix, self.level, self.unit.symbol());
+    }
+}
+
+/// Spectrum with its sample rate, reference level and unit
+///
+/// Displays the bins from DC to the Nyquist frequency, one per line.
+pub struct SpectrumView<'a, T, const N: usize> {
+    data: &'a [(T, T); N],
+    sample_rate: SampleRate,
+    reference: f64,
+    unit: Unit
+}
+
+impl<'a, T: Float<N>, const N: usize> SpectrumView<'a, T, N> {
+    /// Wraps a spectrum in natural order
+    ///
+    /// By default, the levels are in dBFS, a full-scale sine wave of amplitude 1 reading 0 dBFS.
+    pub fn new(data: &'a [(T, T); N], sample_rate: SampleRate) -> Self {
+        Self { data, sample_rate, reference: N as f64 / 2.0, unit: Unit::Dbfs }
+    }
+
+    /// Sets the magnitude of a bin that corresponds to the reference level, and the unit
+    pub fn with_reference(mut self, reference: f64, unit: Unit) -> Self {
+        self.reference = reference;
+        self.unit = unit;
+        self
+    }
+
+    /// Returns bin k in engineering units
+    ///
+    /// The bins above N / 2 have negative frequencies.
+    /// Fails with [`Error::OutOfRange`] if k is not lower than N.
+    pub fn bin(&self, k: usize) -> Result<BinView, Error> {
+        let x = self.data.get(k).ok_or(Error::OutOfRange)?;
+        let bin = if 2 * k > N { k as f64 - N as f64 } else { k as f64 };
+        let (re, im) = (x.0.to_f64(), x.1.to_f64());
+        let ratio = math::sqrt(re * re + im * im) / self.reference;
+        let level = match self.unit {
+            Unit::Linear => ratio,
+            _ => math::amplitude_to_db(ratio)
+        };
+        Ok(BinView { frequency: self.sample_rate.bin_frequency::<N>(bin), level, unit: self.unit })
+    }
+}
+
+impl<T: Float<N>, const N: usize> fmt::Display for SpectrumView<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for k in 0..=N / 2 {
+            if let Ok(bin) = self.bin(k) {
+                writeln!(f, "{bin}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: Float<N>, const N: usize> defmt::Format for SpectrumView<'_, T, N> {
+    fn format(&self, f: defmt::Formatter) {
+        for k in 0..=N / 2 {
+            if let Ok(bin) = self.bin(k) {
+                defmt::write!(f, "{}\n", bin);
+            }
+        }
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    #[cfg_attr(all(feature = "micromath", not(feature = "libm")), ignore = "the micromath approximations are too coarse")]
+    fn test_bin() {
+        let mut data = [(0.0, 0.0); 64];
+        data[1] = (0.0, -0.32);
+        data[63] = (3.2, 0.0);
+        let view = SpectrumView::new(&data, SampleRate::new(48000.0).unwrap());
+        assert_eq!(view.bin(64), Err(Error::OutOfRange));
+
+        let bin = view.bin(1).unwrap();
+        assert_eq!(bin.frequency, Hertz(750.0));
+        assert_abs_diff_eq!(bin.level, -40.0, epsilon = 1e-12);
+        let bin = view.bin(63).unwrap();
+        assert_eq!(bin.frequency, Hertz(-750.0));
+        assert_abs_diff_eq!(bin.level, -20.0, epsilon = 1e-12);
+
+        let view = view.with_reference(0.64, Unit::Linear);
+        assert_eq!(view.bin(1).unwrap().level, 0.5);
+    }
+
+    #[test]
+    fn test_display() {
+        use core::fmt::Write;
+
+        struct Buffer([u8; 64], usize);
+        impl Write for Buffer {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0[self.1..self.1 + s.len()].copy_from_slice(s.as_bytes());
+                self.1 += s.len();
+                Ok(())
+            }
+        }
+
+        let bin = BinView { frequency: Hertz(1203.0), level: -46.24, unit: Unit::Dbfs };
+        let mut buffer = Buffer([0; 64], 0);
+        write!(buffer, "{bin}").unwrap();
+        assert_eq!(&buffer.0[..buffer.1], b"1.203 kHz -46.2 dBFS");
+
+        let bin = BinView { frequency: Hertz::mhz(-2.5), level: 0.25, unit: Unit::Linear };
+        let mut buffer = Buffer([0; 64], 0);
+        write!(buffer, "{bin}").unwrap();
+        assert_eq!(&buffer.0[..buffer.1], b"-2.500 MHz 0.2500");
+    }
+}
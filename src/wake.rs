@@ -0,0 +1,182 @@
+/* embfft | wake.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Always-on acoustic event detection
+//!
+//! A bank of Goertzel filters is much cheaper than an FFT, and is enough to tell whether a sound of
+//! interest may be present. The full FFT path only needs to be woken up when it is, which saves power on
+//! battery-operated devices.
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::detector::CarrierDetector;
+use crate::error::Error;
+use crate::framer::Framer;
+use crate::goertzel::Goertzel;
+use crate::window::{Rectangular, Window};
+
+/******************************************************************************/
+
+/// Wake-on-sound pipeline
+///
+/// While asleep, the samples are fed to a bank of `B` Goertzel filters over blocks of `N` samples. At
+/// the end of each block, the bins are passed to a [`CarrierDetector`], and if it reports a signal, the
+/// decision callback is called with the power of each filter. If the callback returns `true`, the
+/// pipeline wakes up, and the following samples are collected into a frame of `M` samples, weighted by
+/// the window `W`, for the full FFT path. Reading that frame puts the pipeline back to sleep.
+///
+/// For example:
+/// ```
+/// use embfft::detector::CarrierDetector;
+/// use embfft::goertzel::Goertzel;
+/// use embfft::wake::WakeOnSound;
+///
+/// // 1 kHz and 2 kHz filters, over blocks of 32 samples at 8 kHz
+/// let filters = [Goertzel::<f32, 32>::new(4.0), Goertzel::new(8.0)];
+/// let detector = CarrierDetector::new(0, 1, 10.0, 5.0, 0.1).unwrap();
+/// let mut wake = WakeOnSound::<f32, 32, 2, 64, _>::new(filters, detector, |power: &[f32; 2]| power[0] > power[1]).unwrap();
+///
+/// let mut data = [(0.0f32, 0.0); 64];
+/// for i in 0..1024 {
+///     let x = if i < 256 { 0.001 * (i % 3) as f32 } else { (core::f32::consts::PI * i as f32 / 4.0).cos() };
+///     if wake.push(x) {
+///         wake.read_frame(&mut data).unwrap();
+///         embfft::EmbFft::new(&mut data).fft();
+///     }
+/// }
+/// ```
+pub struct WakeOnSound<T, const N: usize, const B: usize, const M: usize, F, W = Rectangular> {
+    filters: [Goertzel<T, N>; B],
+    detector: CarrierDetector<T, B>,
+    decide: F,
+    framer: Framer<T, M, W>,
+    awake: bool
+}
+
+impl<T, const N: usize, const B: usize, const M: usize, F, W> WakeOnSound<T, N, B, M, F, W>
+where
+    T: Float<N> + Float<B> + Float<M>,
+    F: FnMut(&[T; B]) -> bool,
+    W: Window<T, M>
+{
+    /// Initializes a new pipeline, asleep
+    ///
+    /// The detector must cover the bins of interest among the `B` filters, the first block being used as
+    /// its initial noise floor.
+    /// Fails with [`Error::InvalidParameter`] if M is 0.
+    pub fn new(filters: [Goertzel<T, N>; B], detector: CarrierDetector<T, B>, decide: F) -> Result<Self, Error> {
+        Ok(Self {
+            filters,
+            detector,
+            decide,
+            framer: Framer::windowed(M)?,
+            awake: false
+        })
+    }
+
+    /// Puts the pipeline back to sleep, and forgets the noise floor
+    pub fn reset(&mut self) {
+        for filter in self.filters.iter_mut() {
+            filter.reset();
+        }
+        self.detector.reset();
+        self.framer.reset();
+        self.awake = false;
+    }
+
+    /// Checks if the pipeline is collecting a frame for the full FFT path
+    pub fn is_awake(&self) -> bool {
+        self.awake
+    }
+
+    /// Feeds a new sample
+    ///
+    /// Returns `true` once a frame is available, see [`WakeOnSound::read_frame()`].
+    pub fn push(&mut self, x: T) -> bool {
+        if self.awake {
+            return self.framer.push(x);
+        }
+
+        let mut done = false;
+        for filter in self.filters.iter_mut() {
+            done = filter.push(x);
+        }
+        if done {
+            let zero = <T as Float<B>>::ZERO;
+            let bins: [(T, T); B] = core::array::from_fn(|i| self.filters[i].result().unwrap_or((zero, zero)));
+            for filter in self.filters.iter_mut() {
+                filter.reset();
+            }
+            if self.detector.update(&bins) {
+                let power = bins.map(|x| x.0 * x.0 + x.1 * x.1);
+                if (self.decide)(&power) {
+                    self.framer.reset();
+                    self.awake = true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Copies the collected frame into an FFT buffer, and puts the pipeline back to sleep
+    ///
+    /// The imaginary parts are set to zero.
+    /// Fails with [`Error::NotDone`] if no frame is available.
+    pub fn read_frame(&mut self, data: &mut [(T, T); M]) -> Result<(), Error> {
+        self.framer.read(data)?;
+        self.awake = false;
+        Ok(())
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cordic;
+
+    fn tone(i: usize, bin: f64) -> f64 {
+        cordic::cos(2.0 * core::f64::consts::PI * bin * i as f64 / 16.0)
+    }
+
+    #[test]
+    fn test_wake() {
+        let filters = [Goertzel::<f64, 16>::new(2.0), Goertzel::new(5.0)];
+        let detector = CarrierDetector::new(0, 1, 8.0, 4.0, 0.5).unwrap();
+        let mut calls = 0;
+        let mut wake = WakeOnSound::<f64, 16, 2, 8, _>::new(filters, detector, |power: &[f64; 2]| {
+            calls += 1;
+            power[1] > power[0]
+        }).unwrap();
+
+        // Noise, then a tone at bin 2 that is rejected by the callback
+        let mut data = [(0.0, 0.0); 8];
+        assert_eq!(wake.read_frame(&mut data), Err(Error::NotDone));
+        for i in 0..64 {
+            let x = if i < 32 { 0.01 * ((i * 7) % 5) as f64 } else { tone(i, 2.0) };
+            assert!(!wake.push(x));
+        }
+        assert!(!wake.is_awake());
+
+        // A tone at bin 5 is accepted, and the following 8 samples are collected
+        let mut woken = None;
+        for i in 64..128 {
+            if wake.push(tone(i, 5.0)) {
+                woken = Some(i);
+                break;
+            }
+        }
+        assert!(wake.is_awake());
+        assert_eq!(woken, Some(87));
+        wake.read_frame(&mut data).unwrap();
+        assert!(!wake.is_awake());
+        assert_eq!(data[7].0, tone(87, 5.0));
+
+        wake.reset();
+        assert_eq!(calls, 3);
+    }
+}
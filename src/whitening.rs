@@ -0,0 +1,104 @@
+/* embfft | whitening.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Spectral whitening
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::math::FastMath;
+
+/******************************************************************************/
+
+/// Spectral whitening
+///
+/// Flattens the magnitude spectrum by dividing each bin by the magnitude averaged over its neighbours,
+/// while preserving the phase. With a width of 0, every bin is normalized to unit magnitude, as in the
+/// phase transform (PHAT) used before correlations.
+///
+/// For example:
+/// ```
+/// use embfft::whitening::Whitening;
+///
+/// let mut data = [(1.0f32, 0.0); 8];
+/// data[1] = (0.0, 4.0);
+/// Whitening::new(0, 1e-9).apply(&mut data);
+/// assert!((data[1].1 - 1.0).abs() < 1e-4);
+/// ```
+pub struct Whitening<T> {
+    half_width: usize,
+    floor: T
+}
+
+impl<T: Copy + PartialOrd> Whitening<T> {
+    /// Initializes the whitening stage
+    ///
+    /// The magnitude is averaged over `2 * half_width + 1` bins, and is clamped to `floor` to avoid
+    /// amplifying the bins that only contain noise.
+    pub fn new(half_width: usize, floor: T) -> Self {
+        Self { half_width, floor }
+    }
+
+    /// Whitens a spectrum in place
+    ///
+    /// The spectrum is considered circular, the first and last bins are neighbours.
+    pub fn apply<const N: usize>(&self, data: &mut [(T, T); N])
+    where
+        T: Float<N> + FastMath
+    {
+        let mut magnitude = [T::ZERO; N];
+        for (m, x) in core::iter::zip(&mut magnitude, data.iter()) {
+            let m2 = x.0 * x.0 + x.1 * x.1;
+            *m = if m2 > T::ZERO { m2 * m2.rsqrt() } else { T::ZERO };
+        }
+
+        let width = (2 * self.half_width + 1).min(N);
+        let start = N - width / 2;
+        let mut sum = T::ZERO;
+        for i in 0..width {
+            sum = sum + magnitude[(start + i) % N];
+        }
+
+        let count_inv = T::ONE / T::from_usize(width);
+        for (i, x) in data.iter_mut().enumerate() {
+            let average = sum * count_inv;
+            let k = T::ONE / if average > self.floor { average } else { self.floor };
+            *x = (x.0 * k, x.1 * k);
+
+            // Slide the averaging window
+            sum = sum + magnitude[(start + i + width) % N] - magnitude[(start + i) % N];
+        }
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_phat() {
+        let mut data = [(3.0f64, 4.0), (0.0, -2.0), (0.0, 0.0), (-1.0, 0.0)];
+        Whitening::new(0, 1e-12).apply(&mut data);
+        let expected = [(0.6, 0.8), (0.0, -1.0), (0.0, 0.0), (-1.0, 0.0)];
+        for (x, y) in core::iter::zip(data, expected) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-5);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_smoothed() {
+        let mut data = [(1.0f32, 0.0), (2.0, 0.0), (3.0, 0.0), (0.0, 0.0)];
+        Whitening::new(1, 1e-6).apply(&mut data);
+        // Averages are 1, 2, 5/3 and 4/3
+        let expected = [1.0, 1.0, 1.8, 0.0];
+        for (x, y) in core::iter::zip(data, expected) {
+            assert_abs_diff_eq!(x.0, y, epsilon = 1e-4);
+        }
+    }
+}
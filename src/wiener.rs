@@ -0,0 +1,147 @@
+/* embfft | wiener.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Frequency-domain noise suppression
+
+/******************************************************************************/
+
+use crate::common::Float;
+use crate::error::Error;
+use crate::mask::ExclusionMask;
+
+/******************************************************************************/
+
+/// Frequency-domain Wiener filter
+///
+/// Attenuates each bin of successive spectra according to its estimated signal to noise ratio, with the
+/// gain `1 - noise / power`, limited to a floor to avoid musical noise. The noise power is learned from
+/// frames known to only contain noise, and follows its minimum while the filter is applied.
+///
+/// For example:
+/// ```
+/// let mut wiener = embfft::wiener::WienerFilter::<f32, 8>::new(0.1, 0.1).unwrap();
+/// wiener.learn(&[(1.0, 0.0); 8]);
+///
+/// let mut data = [(2.0, 0.0); 8];
+/// wiener.apply(&mut data);
+/// assert_eq!(data[0], (1.5, 0.0));
+/// ```
+pub struct WienerFilter<T, const N: usize> {
+    noise: [T; N],
+    alpha: T,
+    floor: T,
+    mask: ExclusionMask<N>,
+    learned: bool
+}
+
+impl<T: Float<N>, const N: usize> WienerFilter<T, N> {
+    /// Initializes the filter, with an empty noise estimate
+    ///
+    /// `alpha` is the weight of each new frame in the noise estimate, and `floor` the minimum gain, both
+    /// between 0 and 1.
+    pub fn new(alpha: T, floor: T) -> Result<Self, Error> {
+        if alpha <= T::ZERO || alpha > T::ONE || floor < T::ZERO || floor > T::ONE {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self { noise: [T::ZERO; N], alpha, floor, mask: ExclusionMask::new(), learned: false })
+    }
+
+    /// Attenuates the excluded bins by the floor gain, as known spurs, and leaves them out of the noise
+    /// estimate
+    pub fn with_mask(mut self, mask: &ExclusionMask<N>) -> Self {
+        self.mask = *mask;
+        self
+    }
+
+    /// Forgets the noise estimate
+    pub fn reset(&mut self) {
+        self.noise = [T::ZERO; N];
+        self.learned = false;
+    }
+
+    /// Updates the noise estimate with a spectrum that only contains noise
+    pub fn learn(&mut self, data: &[(T, T); N]) {
+        for (k, (n, x)) in core::iter::zip(&mut self.noise, data).enumerate() {
+            if self.mask.is_excluded(k) {
+                continue;
+            }
+            let power = x.0 * x.0 + x.1 * x.1;
+            *n = if self.learned { *n + self.alpha * (power - *n) } else { power };
+        }
+        self.learned = true;
+    }
+
+    /// Returns the estimated noise power of each bin
+    pub fn noise(&self) -> &[T; N] {
+        &self.noise
+    }
+
+    /// Attenuates the noise of a spectrum in place
+    ///
+    /// The bins that are weaker than the noise estimate also lower it.
+    pub fn apply(&mut self, data: &mut [(T, T); N]) {
+        for (k, (n, x)) in core::iter::zip(&mut self.noise, data.iter_mut()).enumerate() {
+            if self.mask.is_excluded(k) {
+                *x = (x.0 * self.floor, x.1 * self.floor);
+                continue;
+            }
+            let power = x.0 * x.0 + x.1 * x.1;
+            if power < *n {
+                *n = *n + self.alpha * (power - *n);
+            }
+
+            let gain = if power > T::ZERO { T::ONE - *n / power } else { T::ZERO };
+            let gain = if gain > self.floor { gain } else { self.floor };
+            *x = (x.0 * gain, x.1 * gain);
+        }
+    }
+}
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_parameters() {
+        assert!(WienerFilter::<f32, 4>::new(0.0, 0.1).is_err());
+        assert!(WienerFilter::<f32, 4>::new(0.5, 1.5).is_err());
+    }
+
+    #[test]
+    fn test_wiener() {
+        let mut wiener = WienerFilter::<f64, 4>::new(0.5, 0.1).unwrap();
+        wiener.learn(&[(1.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.0, 0.0)]);
+        wiener.learn(&[(1.0, 0.0), (0.0, 3.0), (1.0, 1.0), (0.0, 0.0)]);
+        assert_eq!(wiener.noise(), &[1.0, 5.0, 2.0, 0.0]);
+
+        let mut data = [(0.0, 4.0), (1.0, 2.0), (0.0, 1.0), (-2.0, 0.0)];
+        wiener.apply(&mut data);
+        let expected = [(0.0, 3.75), (0.1, 0.2), (0.0, 0.1), (-2.0, 0.0)];
+        for (x, y) in core::iter::zip(data, expected) {
+            assert_abs_diff_eq!(x.0, y.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(x.1, y.1, epsilon = 1e-12);
+        }
+        // The weak bin lowered the noise estimate
+        assert_eq!(wiener.noise(), &[1.0, 5.0, 1.5, 0.0]);
+    }
+
+    #[test]
+    fn test_mask() {
+        let mut mask = ExclusionMask::new();
+        mask.exclude(1).unwrap();
+        let mut wiener = WienerFilter::<f64, 4>::new(0.5, 0.1).unwrap().with_mask(&mask);
+
+        // The spur at bin 1 is neither learned as noise nor passed as a signal
+        wiener.learn(&[(1.0, 0.0), (10.0, 0.0), (1.0, 0.0), (1.0, 0.0)]);
+        assert_eq!(wiener.noise(), &[1.0, 0.0, 1.0, 1.0]);
+        let mut data = [(2.0, 0.0), (10.0, 0.0), (2.0, 0.0), (2.0, 0.0)];
+        wiener.apply(&mut data);
+        assert_eq!(data, [(1.5, 0.0), (1.0, 0.0), (1.5, 0.0), (1.5, 0.0)]);
+        assert_eq!(wiener.noise(), &[1.0, 0.0, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,419 @@
+/* embfft | window.rs
+ * Copyright (c) 2025 L. Sartory
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Window functions
+//!
+//! The windows are selected at compile time, as a type parameter of the transform, and their tables are
+//! computed at compile time for each buffer size.
+//!
+//! A window scales the amplitude of a tone by its coherent gain, and spreads the noise over its equivalent
+//! noise bandwidth (ENBW), both exposed as constants so that amplitude-accurate measurements can correct
+//! their spectra. The [`FlatTop`] window has the flattest main lobe, for an amplitude error below 0.01 dB
+//! wherever the tone falls between two bins.
+//! For example:
+//! ```
+//! use embfft::window::Hann;
+//!
+//! let mut data = [(1.0f32, 0.0); 8];
+//! embfft::EmbFft::<f32, 8, Hann>::windowed(&mut data).fft();
+//! ```
+//!
+//! Other windows are defined by implementing the [`Window`] trait, with a table built at compile time.
+//! The [`coherent_gain()`] and [`equivalent_noise_bandwidth()`] functions compute its constants from the
+//! same coefficients.
+//! For example:
+//! ```
+//! use embfft::window::{coherent_gain, equivalent_noise_bandwidth, Window};
+//!
+//! /// Triangular window
+//! struct Bartlett;
+//!
+//! const fn bartlett<const N: usize>() -> [f64; N] {
+//!     let mut table = [0.0; N];
+//!     let mut i = 0;
+//!     while i < N {
+//!         let x = 2.0 * i as f64 / N as f64;
+//!         table[i] = if x < 1.0 { x } else { 2.0 - x };
+//!         i += 1;
+//!     }
+//!     table
+//! }
+//!
+//! impl<const N: usize> Window<f32, N> for Bartlett {
+//!     const TABLE: [f32; N] = {
+//!         let (table, mut coefficients) = (bartlett::<N>(), [0.0; N]);
+//!         let mut i = 0;
+//!         while i < N {
+//!             coefficients[i] = table[i] as f32;
+//!             i += 1;
+//!         }
+//!         coefficients
+//!     };
+//!     const COHERENT_GAIN: f64 = coherent_gain(&bartlett::<N>());
+//!     const ENBW: f64 = equivalent_noise_bandwidth(&bartlett::<N>());
+//! }
+//!
+//! assert_eq!(<Bartlett as Window<f32, 8>>::coefficient(2), 0.5);
+//! assert_eq!(<Bartlett as Window<f32, 8>>::COHERENT_GAIN, 0.5);
+//!
+//! let mut data = [(1.0f32, 0.0); 8];
+//! embfft::EmbFft::<f32, 8, Bartlett>::windowed(&mut data).fft();
+//! assert_eq!(data[0], (4.0, 0.0));
+//! ```
+
+/******************************************************************************/
+
+use core::f64::consts::PI;
+
+use crate::common::Float;
+#[cfg(feature = "fixed")]
+use crate::fixed_point::fixed_bits;
+use crate::fixed_point::{Overflow, Rounding, Q15, Q31};
+
+/******************************************************************************/
+
+// Rounded half-length window tables, generated by the build script
+include!(concat!(env!("OUT_DIR"), "/fixed_windows.rs"));
+
+/******************************************************************************/
+
+/// A window function, applied to the data before the transform
+pub trait Window<T, const N: usize> {
+    /// Whether the window leaves the data unchanged
+    const IS_RECTANGULAR: bool = false;
+    /// Window coefficients
+    const TABLE: [T; N];
+    /// Coherent gain, i.e. the mean of the coefficients, by which the amplitude of a tone is scaled
+    const COHERENT_GAIN: f64;
+    /// Equivalent noise bandwidth, in bins, i.e. N × the mean of the squared coefficients divided by the
+    /// squared coherent gain
+    const ENBW: f64;
+
+    /// Returns the coefficient i of the window
+    ///
+    /// Panics if i is not lower than N.
+    fn coefficient(i: usize) -> T where T: Copy {
+        Self::TABLE[i]
+    }
+}
+
+/// Rectangular window, i.e. no window
+pub struct Rectangular;
+
+/// Hann window
+pub struct Hann;
+
+/// Hamming window
+pub struct Hamming;
+
+/// Blackman window
+pub struct Blackman;
+
+/// Flat-top window, for amplitude measurements
+pub struct FlatTop;
+
+/******************************************************************************/
+
+/// Compute cos(2π * i / n) at compile time
+const fn cos(i: usize, n: usize) -> f64 {
+    crate::cordic::cos(2.0 * PI * (i % n) as f64 / n as f64)
+}
+
+// Coefficients of the cosine-sum windows, with alternating signs
+const HANN: [f64; 2] = [0.5, 0.5];
+const HAMMING: [f64; 2] = [0.54, 0.46];
+const BLACKMAN: [f64; 3] = [0.42, 0.5, 0.08];
+const FLAT_TOP: [f64; 5] = [0.21557895, 0.41663158, 0.277263158, 0.083578947, 0.006947368];
+
+/// Compute a0 - a1 cos(2π * i / n) + a2 cos(4π * i / n) - ... at compile time
+const fn cosine_sum(i: usize, n: usize, a: &[f64]) -> f64 {
+    let mut y = a[0];
+    let mut k = 1;
+    while k < a.len() {
+        y += if k % 2 == 0 { a[k] } else { -a[k] } * cos(k * i, n);
+        k += 1;
+    }
+    y
+}
+
+/// Compute the ENBW of a cosine-sum window, in bins
+///
+/// The cosines are orthogonal over a period, so this is exact as soon as N is greater than twice the
+/// number of cosines.
+const fn enbw(a: &[f64]) -> f64 {
+    let mut sum = 2.0 * a[0] * a[0];
+    let mut k = 1;
+    while k < a.len() {
+        sum += a[k] * a[k];
+        k += 1;
+    }
+    sum / (2.0 * a[0] * a[0])
+}
+
+/// Returns the coherent gain of a window, i.e. the mean of its coefficients
+///
+/// This is meant to compute the [`Window::COHERENT_GAIN`] of the user-defined windows at compile time.
+pub const fn coherent_gain(table: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut i = 0;
+    while i < table.len() {
+        sum += table[i];
+        i += 1;
+    }
+    sum / table.len() as f64
+}
+
+/// Returns the equivalent noise bandwidth of a window, in bins
+///
+/// This is meant to compute the [`Window::ENBW`] of the user-defined windows at compile time.
+pub const fn equivalent_noise_bandwidth(table: &[f64]) -> f64 {
+    let (mut sum, mut squares) = (0.0, 0.0);
+    let mut i = 0;
+    while i < table.len() {
+        sum += table[i];
+        squares += table[i] * table[i];
+        i += 1;
+    }
+    table.len() as f64 * squares / (sum * sum)
+}
+
+pub(crate) const fn hann(i: usize, n: usize) -> f64 {
+    cosine_sum(i, n, &HANN)
+}
+
+pub(crate) const fn hamming(i: usize, n: usize) -> f64 {
+    cosine_sum(i, n, &HAMMING)
+}
+
+pub(crate) const fn blackman(i: usize, n: usize) -> f64 {
+    cosine_sum(i, n, &BLACKMAN)
+}
+
+pub(crate) const fn flat_top(i: usize, n: usize) -> f64 {
+    cosine_sum(i, n, &FLAT_TOP)
+}
+
+impl<T: Float<N>, const N: usize> Window<T, N> for Rectangular {
+    const IS_RECTANGULAR: bool = true;
+    const TABLE: [T; N] = [T::ONE; N];
+    const COHERENT_GAIN: f64 = 1.0;
+    const ENBW: f64 = 1.0;
+}
+
+macro_rules! gen_window_impl {
+    ($T: ty) => {
+        gen_window_impl!($T, Hann, hann, HANN);
+        gen_window_impl!($T, Hamming, hamming, HAMMING);
+        gen_window_impl!($T, Blackman, blackman, BLACKMAN);
+        gen_window_impl!($T, FlatTop, flat_top, FLAT_TOP);
+    };
+    ($T: ty, $W: ty, $f: ident, $a: ident) => {
+        impl<const N: usize> Window<$T, N> for $W {
+            const COHERENT_GAIN: f64 = $a[0];
+            const ENBW: f64 = enbw(&$a);
+            const TABLE: [$T; N] = {
+                let mut table = [0.0; N];
+                let mut i = 0;
+                while i < N {
+                    table[i] = $f(i, N) as $T;
+                    i += 1;
+                }
+                table
+            };
+        }
+    };
+}
+
+gen_window_impl!(f32);
+gen_window_impl!(f64);
+
+#[cfg(feature = "half")]
+macro_rules! gen_half_window_impl {
+    ($W: ty, $f: ident, $a: ident) => {
+        impl<const N: usize> Window<half::f16, N> for $W {
+            const COHERENT_GAIN: f64 = $a[0];
+            const ENBW: f64 = enbw(&$a);
+            const TABLE: [half::f16; N] = {
+                let mut table = [half::f16::ZERO; N];
+                let mut i = 0;
+                while i < N {
+                    table[i] = half::f16::from_f64_const($f(i, N));
+                    i += 1;
+                }
+                table
+            };
+        }
+    };
+}
+
+#[cfg(feature = "half")]
+gen_half_window_impl!(Hann, hann, HANN);
+#[cfg(feature = "half")]
+gen_half_window_impl!(Hamming, hamming, HAMMING);
+#[cfg(feature = "half")]
+gen_half_window_impl!(Blackman, blackman, BLACKMAN);
+#[cfg(feature = "half")]
+gen_half_window_impl!(FlatTop, flat_top, FLAT_TOP);
+
+macro_rules! gen_fixed_window_impl {
+    ($Q: ident, $hann: ident, $hamming: ident, $blackman: ident, $flat_top: ident) => {
+        gen_fixed_window_impl!($Q, Hann => hann, HANN, $hann);
+        gen_fixed_window_impl!($Q, Hamming => hamming, HAMMING, $hamming);
+        gen_fixed_window_impl!($Q, Blackman => blackman, BLACKMAN, $blackman);
+        gen_fixed_window_impl!($Q, FlatTop => flat_top, FLAT_TOP, $flat_top);
+    };
+    ($Q: ident, $W: ty => $f: ident, $a: ident, $table: ident) => {
+        /// Taken from the generated table when possible, computed with the CORDIC otherwise
+        impl<O: Overflow + Copy, R: Rounding + Copy, const N: usize> Window<$Q<O, R>, N> for $W {
+            const COHERENT_GAIN: f64 = $a[0];
+            const ENBW: f64 = enbw(&$a);
+            const TABLE: [$Q<O, R>; N] = {
+                let mut table = [$Q::from_bits(0); N];
+                let mut i = 0;
+                while i < N {
+                    table[i] = if N <= FIXED_TABLE_N && FIXED_TABLE_N % N == 0 {
+                        // The windows are symmetric, only the first half is stored
+                        let j = i * (FIXED_TABLE_N / N);
+                        $Q::from_bits($table[if 2 * j > FIXED_TABLE_N { FIXED_TABLE_N - j } else { j }])
+                    } else {
+                        $Q::from_f64($f(i, N))
+                    };
+                    i += 1;
+                }
+                table
+            };
+        }
+    };
+}
+
+gen_fixed_window_impl!(Q15, HANN_Q15, HAMMING_Q15, BLACKMAN_Q15, FLAT_TOP_Q15);
+gen_fixed_window_impl!(Q31, HANN_Q31, HAMMING_Q31, BLACKMAN_Q31, FLAT_TOP_Q31);
+
+#[cfg(feature = "fixed")]
+macro_rules! gen_fixed_crate_window_impl {
+    ($F: ident, $I: ty, $LeEqU: ident) => {
+        gen_fixed_crate_window_impl!($F, $I, $LeEqU, Hann => hann, HANN);
+        gen_fixed_crate_window_impl!($F, $I, $LeEqU, Hamming => hamming, HAMMING);
+        gen_fixed_crate_window_impl!($F, $I, $LeEqU, Blackman => blackman, BLACKMAN);
+        gen_fixed_crate_window_impl!($F, $I, $LeEqU, FlatTop => flat_top, FLAT_TOP);
+    };
+    ($F: ident, $I: ty, $LeEqU: ident, $W: ty => $f: ident, $a: ident) => {
+        impl<Frac: fixed::types::extra::$LeEqU, const N: usize> Window<fixed::$F<Frac>, N> for $W {
+            const COHERENT_GAIN: f64 = $a[0];
+            const ENBW: f64 = enbw(&$a);
+            const TABLE: [fixed::$F<Frac>; N] = {
+                let mut table = [fixed::$F::from_bits(0); N];
+                let mut i = 0;
+                while i < N {
+                    table[i] = fixed::$F::from_bits(fixed_bits($f(i, N), fixed::$F::<Frac>::FRAC_NBITS, <$I>::BITS) as $I);
+                    i += 1;
+                }
+                table
+            };
+        }
+    };
+}
+
+#[cfg(feature = "fixed")]
+gen_fixed_crate_window_impl!(FixedI16, i16, LeEqU16);
+#[cfg(feature = "fixed")]
+gen_fixed_crate_window_impl!(FixedI32, i32, LeEqU32);
+#[cfg(feature = "fixed")]
+gen_fixed_crate_window_impl!(FixedI64, i64, LeEqU64);
+
+/******************************************************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use core::f64::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn test_cos() {
+        let expected = [1.0, FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2, -1.0, -FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2];
+        for (i, y) in expected.into_iter().enumerate() {
+            assert_abs_diff_eq!(cos(i, 8), y, epsilon = 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_hann() {
+        let table = <Hann as Window<f64, 8>>::TABLE;
+        let expected = [0.0, 0.146446609406726, 0.5, 0.853553390593274, 1.0, 0.853553390593274, 0.5, 0.146446609406726];
+        for (x, y) in core::iter::zip(table, expected) {
+            assert_abs_diff_eq!(x, y, epsilon = 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_gains() {
+        // Against the mean coefficients of the tables
+        fn check<W: Window<f64, 64>>(enbw: f64) {
+            let sum: f64 = W::TABLE.iter().sum();
+            let squares: f64 = W::TABLE.iter().map(|w| w * w).sum();
+            assert_abs_diff_eq!(W::COHERENT_GAIN, sum / 64.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(W::ENBW, 64.0 * squares / (sum * sum), epsilon = 1e-12);
+            assert_abs_diff_eq!(W::ENBW, enbw, epsilon = 1e-3);
+        }
+        check::<Rectangular>(1.0);
+        check::<Hann>(1.5);
+        check::<Hamming>(1.363);
+        check::<Blackman>(1.727);
+        check::<FlatTop>(3.770);
+    }
+
+    #[test]
+    fn test_flat_top() {
+        // A tone halfway between two bins is measured within 0.01 dB once corrected by the coherent gain
+        let mut data: [(f64, f64); 64] = core::array::from_fn(|i| (2.0 * crate::cordic::cos(2.0 * PI * 10.5 * i as f64 / 64.0), 0.0));
+        crate::EmbFft::<f64, 64, FlatTop>::windowed(&mut data).fft();
+        let peak = data[10].0.hypot(data[10].1).max(data[11].0.hypot(data[11].1));
+        let amplitude = 2.0 * peak / (64.0 * <FlatTop as Window<f64, 64>>::COHERENT_GAIN);
+        assert_abs_diff_eq!(amplitude, 2.0, epsilon = 2.0 * 0.0012);
+    }
+
+    #[test]
+    fn test_user_window() {
+        struct Welch;
+        const fn welch<const N: usize>() -> [f64; N] {
+            let mut table = [0.0; N];
+            let mut i = 0;
+            while i < N {
+                let x = 2.0 * i as f64 / N as f64 - 1.0;
+                table[i] = 1.0 - x * x;
+                i += 1;
+            }
+            table
+        }
+        impl<const N: usize> Window<f64, N> for Welch {
+            const TABLE: [f64; N] = welch::<N>();
+            const COHERENT_GAIN: f64 = coherent_gain(&welch::<N>());
+            const ENBW: f64 = equivalent_noise_bandwidth(&welch::<N>());
+        }
+
+        assert_eq!(<Welch as Window<f64, 8>>::coefficient(4), 1.0);
+        assert_abs_diff_eq!(<Welch as Window<f64, 256>>::COHERENT_GAIN, 2.0 / 3.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(<Welch as Window<f64, 256>>::ENBW, 1.2, epsilon = 1e-3);
+
+        // Applied by the transform like the built-in windows
+        let mut data = [(1.0, 0.0); 8];
+        crate::EmbFft::<f64, 8, Welch>::windowed(&mut data).fft();
+        assert_abs_diff_eq!(data[0].0, 8.0 * <Welch as Window<f64, 8>>::COHERENT_GAIN, epsilon = 1e-12);
+        assert_abs_diff_eq!(coherent_gain(&<Hann as Window<f64, 64>>::TABLE), <Hann as Window<f64, 64>>::COHERENT_GAIN, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_fixed_window() {
+        let table = <Blackman as Window<Q15, 16>>::TABLE;
+        let expected = <Blackman as Window<f64, 16>>::TABLE;
+        for (x, y) in core::iter::zip(table, expected) {
+            assert_eq!(x, Q15::from_f64(y));
+        }
+        let table = <Hann as Window<Q31, 8>>::TABLE;
+        assert_eq!(table[4], Q31::MAX);
+        assert_eq!(table[5], table[3]);
+    }
+}